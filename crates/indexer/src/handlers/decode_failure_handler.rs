@@ -0,0 +1,85 @@
+use crate::handlers::{is_deepbook_tx, parsed_events};
+use crate::DeepbookEnv;
+use async_trait::async_trait;
+use deeplook_schema::models::DecodeFailure;
+use deeplook_schema::schema::decode_failures;
+use diesel_async::RunQueryDsl;
+use move_core_types::language_storage::StructTag;
+use std::sync::Arc;
+use sui_indexer_alt_framework::pipeline::Processor;
+use sui_indexer_alt_framework::postgres::handler::Handler;
+use sui_indexer_alt_framework::postgres::Connection;
+use sui_indexer_alt_framework::types::full_checkpoint_content::Checkpoint;
+use sui_types::effects::TransactionEffectsAPI;
+
+/// Records events of the types the other handlers parse whose contents don't decode, e.g.
+/// after a package upgrade changed an event layout. The handler of such an event fails its
+/// checkpoint, this pipeline keeps the event for debugging.
+pub struct DecodeFailureHandler {
+    env: DeepbookEnv,
+}
+
+impl DecodeFailureHandler {
+    pub fn new(env: DeepbookEnv) -> Self {
+        Self { env }
+    }
+}
+
+/// Error of decoding `contents` as the handler's type of `event_type`, `None` when it decodes
+/// or no handler parses the type
+pub fn decode_error(event_type: &StructTag, contents: &[u8], env: DeepbookEnv) -> Option<String> {
+    parsed_events()
+        .find(|event| (event.matches)(event_type, env))
+        .and_then(|event| (event.decode_error)(contents))
+}
+
+#[async_trait]
+impl Processor for DecodeFailureHandler {
+    const NAME: &'static str = "decode_failures";
+    type Value = DecodeFailure;
+
+    async fn process(&self, checkpoint: &Arc<Checkpoint>) -> anyhow::Result<Vec<Self::Value>> {
+        let checkpoint_seq = checkpoint.summary.sequence_number as i64;
+        let checkpoint_timestamp_ms = checkpoint.summary.timestamp_ms as i64;
+
+        let mut results = vec![];
+        for tx in &checkpoint.transactions {
+            if !is_deepbook_tx(tx, &checkpoint.object_set, self.env) {
+                continue;
+            }
+            let Some(events) = &tx.events else {
+                continue;
+            };
+            let digest = tx.effects.transaction_digest().to_string();
+
+            for (index, ev) in events.data.iter().enumerate() {
+                let Some(error) = decode_error(&ev.type_, &ev.contents, self.env) else {
+                    continue;
+                };
+                results.push(DecodeFailure {
+                    event_digest: format!("{digest}{index}"),
+                    digest: digest.clone(),
+                    checkpoint: checkpoint_seq,
+                    checkpoint_timestamp_ms,
+                    event_type: ev.type_.to_string(),
+                    error,
+                });
+            }
+        }
+        Ok(results)
+    }
+}
+
+#[async_trait]
+impl Handler for DecodeFailureHandler {
+    async fn commit<'a>(
+        values: &[Self::Value],
+        conn: &mut Connection<'a>,
+    ) -> anyhow::Result<usize> {
+        Ok(diesel::insert_into(decode_failures::table)
+            .values(values)
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .await?)
+    }
+}
@@ -1,10 +1,16 @@
+use crate::metrics::IngestMetrics;
+use crate::traits::MoveStruct;
 use crate::DeepbookEnv;
+use move_core_types::language_storage::StructTag;
+use serde::de::DeserializeOwned;
 use std::sync::Arc;
 use sui_indexer_alt_framework::types::full_checkpoint_content::{
     Checkpoint, ExecutedTransaction, ObjectSet,
 };
 use sui_types::effects::TransactionEffectsAPI;
+use sui_types::event::Event;
 use sui_types::transaction::{Command, TransactionDataAPI};
+use tracing::error;
 
 /// Captures common transaction metadata for event processing.
 /// Used by the `define_handler!` macro to avoid repetitive field extraction.
@@ -97,11 +103,19 @@ macro_rules! define_handler {
     } => {
         pub struct $handler {
             env: $crate::DeepbookEnv,
+            metrics: std::sync::Arc<$crate::metrics::IngestMetrics>,
         }
 
         impl $handler {
-            pub fn new(env: $crate::DeepbookEnv) -> Self {
-                Self { env }
+            /// Event types the handler parses
+            pub const EVENTS: &'static [$crate::handlers::ParsedEvent] =
+                &[$crate::handlers::ParsedEvent::of::<$event>()];
+
+            pub fn new(
+                env: $crate::DeepbookEnv,
+                metrics: std::sync::Arc<$crate::metrics::IngestMetrics>,
+            ) -> Self {
+                Self { env, metrics }
             }
         }
 
@@ -114,7 +128,7 @@ macro_rules! define_handler {
                 &self,
                 checkpoint: &std::sync::Arc<sui_indexer_alt_framework::types::full_checkpoint_content::Checkpoint>,
             ) -> anyhow::Result<Vec<Self::Value>> {
-                use $crate::handlers::{is_deepbook_tx, EventMeta};
+                use $crate::handlers::{decode_event, is_deepbook_tx, EventMeta};
                 use $crate::traits::MoveStruct;
 
                 let mut results = vec![];
@@ -128,7 +142,13 @@ macro_rules! define_handler {
 
                     for (index, ev) in events.data.iter().enumerate() {
                         if <$event>::matches_event_type(&ev.type_, self.env) {
-                            let $ev: $event = bcs::from_bytes(&ev.contents)?;
+                            let $ev: $event = decode_event(
+                                &self.metrics,
+                                Self::NAME,
+                                ev,
+                                &base_meta.digest(),
+                                base_meta.checkpoint(),
+                            )?;
                             let $meta = base_meta.with_index(index);
                             results.push($body);
                             tracing::debug!("Observed {} event", $proc_name);
@@ -157,6 +177,7 @@ macro_rules! define_handler {
 }
 
 pub mod balances_handler;
+pub mod decode_failure_handler;
 pub mod flash_loan_handler;
 pub mod order_fill_handler;
 pub mod order_update_handler;
@@ -165,8 +186,48 @@ pub mod proposals_handler;
 pub mod rebates_handler;
 pub mod stakes_handler;
 pub mod trade_params_update_handler;
+pub mod unknown_event_handler;
 pub mod vote_handler;
 
+/// Matcher and decoder of an event type a handler parses
+#[derive(Clone, Copy)]
+pub struct ParsedEvent {
+    pub matches: fn(&StructTag, DeepbookEnv) -> bool,
+    /// Error of decoding the contents as the event, `None` when they decode
+    pub decode_error: fn(&[u8]) -> Option<String>,
+}
+
+impl ParsedEvent {
+    pub const fn of<T: MoveStruct + DeserializeOwned>() -> Self {
+        Self {
+            matches: T::matches_event_type,
+            decode_error: decode_error_of::<T>,
+        }
+    }
+}
+
+fn decode_error_of<T: DeserializeOwned>(contents: &[u8]) -> Option<String> {
+    bcs::from_bytes::<T>(contents).err().map(|e| e.to_string())
+}
+
+/// Event types of all handlers, from their `EVENTS`
+pub fn parsed_events() -> impl Iterator<Item = &'static ParsedEvent> {
+    [
+        balances_handler::BalancesHandler::EVENTS,
+        flash_loan_handler::FlashLoanHandler::EVENTS,
+        order_fill_handler::OrderFillHandler::EVENTS,
+        order_update_handler::OrderUpdateHandler::EVENTS,
+        pool_price_handler::PoolPriceHandler::EVENTS,
+        proposals_handler::ProposalsHandler::EVENTS,
+        rebates_handler::RebatesHandler::EVENTS,
+        stakes_handler::StakesHandler::EVENTS,
+        trade_params_update_handler::TradeParamsUpdateHandler::EVENTS,
+        vote_handler::VotesHandler::EVENTS,
+    ]
+    .into_iter()
+    .flatten()
+}
+
 pub(crate) fn is_deepbook_tx(
     tx: &ExecutedTransaction,
     checkpoint_objects: &ObjectSet,
@@ -214,6 +275,31 @@ pub(crate) fn is_deepbook_tx(
     has_deepbook_call
 }
 
+/// Decodes an event of a parsed type, logging its type, transaction and checkpoint when the
+/// contents don't match the expected layout. The error still fails the checkpoint, so no
+/// event is dropped.
+pub(crate) fn decode_event<T: DeserializeOwned>(
+    metrics: &IngestMetrics,
+    handler: &str,
+    ev: &Event,
+    digest: &str,
+    checkpoint: i64,
+) -> anyhow::Result<T> {
+    metrics.decode(handler, &ev.contents).map_err(|e| {
+        error!(
+            handler,
+            event_type = %ev.type_,
+            digest,
+            checkpoint,
+            "Failed to decode event: {e}"
+        );
+        anyhow::anyhow!(
+            "{handler} failed to decode {} of transaction {digest} in checkpoint {checkpoint}: {e}",
+            ev.type_
+        )
+    })
+}
+
 pub(crate) fn try_extract_move_call_package(tx: &ExecutedTransaction) -> Option<String> {
     let txn_kind = tx.transaction.kind();
     let first_command = txn_kind.iter_commands().next()?;
@@ -1,4 +1,5 @@
-use crate::handlers::{is_deepbook_tx, try_extract_move_call_package};
+use crate::handlers::{decode_event, is_deepbook_tx, try_extract_move_call_package, ParsedEvent};
+use crate::metrics::IngestMetrics;
 use crate::models::deepbook::order::{OrderCanceled, OrderModified};
 use crate::models::deepbook::order_info::{OrderExpired, OrderPlaced};
 use crate::traits::MoveStruct;
@@ -20,11 +21,20 @@ type TransactionMetadata = (String, u64, u64, String, String);
 
 pub struct OrderUpdateHandler {
     env: DeepbookEnv,
+    metrics: Arc<IngestMetrics>,
 }
 
 impl OrderUpdateHandler {
-    pub fn new(env: DeepbookEnv) -> Self {
-        Self { env }
+    /// Event types the handler parses, as dispatched in `process`
+    pub const EVENTS: &'static [ParsedEvent] = &[
+        ParsedEvent::of::<OrderPlaced>(),
+        ParsedEvent::of::<OrderModified>(),
+        ParsedEvent::of::<OrderCanceled>(),
+        ParsedEvent::of::<OrderExpired>(),
+    ];
+
+    pub fn new(env: DeepbookEnv, metrics: Arc<IngestMetrics>) -> Self {
+        Self { env, metrics }
     }
 }
 
@@ -45,29 +55,35 @@ impl Processor for OrderUpdateHandler {
             };
 
             let package = try_extract_move_call_package(tx).unwrap_or_default();
+            let digest = tx.transaction.digest().to_string();
+            let checkpoint_seq = checkpoint.summary.sequence_number as i64;
             let metadata = (
                 tx.transaction.sender().to_string(),
                 checkpoint.summary.sequence_number,
                 checkpoint.summary.timestamp_ms,
-                tx.transaction.digest().to_string(),
+                digest.clone(),
                 package.clone(),
             );
 
             for (index, ev) in events.data.iter().enumerate() {
                 if OrderPlaced::matches_event_type(&ev.type_, self.env) {
-                    let event = bcs::from_bytes(&ev.contents)?;
+                    let event =
+                        decode_event(&self.metrics, Self::NAME, ev, &digest, checkpoint_seq)?;
                     results.push(process_order_placed(event, metadata.clone(), index));
                     debug!("Observed Deepbook Order Placed {:?}", tx);
                 } else if OrderModified::matches_event_type(&ev.type_, self.env) {
-                    let event = bcs::from_bytes(&ev.contents)?;
+                    let event =
+                        decode_event(&self.metrics, Self::NAME, ev, &digest, checkpoint_seq)?;
                     results.push(process_order_modified(event, metadata.clone(), index));
                     debug!("Observed Deepbook Order Modified {:?}", tx);
                 } else if OrderCanceled::matches_event_type(&ev.type_, self.env) {
-                    let event = bcs::from_bytes(&ev.contents)?;
+                    let event =
+                        decode_event(&self.metrics, Self::NAME, ev, &digest, checkpoint_seq)?;
                     results.push(process_order_canceled(event, metadata.clone(), index));
                     debug!("Observed Deepbook Order Canceled {:?}", tx);
                 } else if OrderExpired::matches_event_type(&ev.type_, self.env) {
-                    let event = bcs::from_bytes(&ev.contents)?;
+                    let event =
+                        decode_event(&self.metrics, Self::NAME, ev, &digest, checkpoint_seq)?;
                     results.push(process_order_expired(event, metadata.clone(), index));
                     debug!("Observed Deepbook Order Expired {:?}", tx);
                 }
@@ -1,4 +1,5 @@
-use crate::handlers::{is_deepbook_tx, try_extract_move_call_package};
+use crate::handlers::{decode_event, is_deepbook_tx, try_extract_move_call_package, ParsedEvent};
+use crate::metrics::IngestMetrics;
 use crate::models::deepbook::governance::TradeParamsUpdateEvent;
 use crate::traits::MoveStruct;
 use crate::utils::ms_to_secs;
@@ -17,11 +18,15 @@ use tracing::debug;
 
 pub struct TradeParamsUpdateHandler {
     env: DeepbookEnv,
+    metrics: Arc<IngestMetrics>,
 }
 
 impl TradeParamsUpdateHandler {
-    pub fn new(env: DeepbookEnv) -> Self {
-        Self { env }
+    /// Event types the handler parses
+    pub const EVENTS: &'static [ParsedEvent] = &[ParsedEvent::of::<TradeParamsUpdateEvent>()];
+
+    pub fn new(env: DeepbookEnv, metrics: Arc<IngestMetrics>) -> Self {
+        Self { env, metrics }
     }
 }
 
@@ -60,7 +65,13 @@ impl Processor for TradeParamsUpdateHandler {
                 if !TradeParamsUpdateEvent::matches_event_type(&ev.type_, self.env) {
                     continue;
                 }
-                let event: TradeParamsUpdateEvent = bcs::from_bytes(&ev.contents)?;
+                let event: TradeParamsUpdateEvent = decode_event(
+                    &self.metrics,
+                    Self::NAME,
+                    ev,
+                    &digest.to_string(),
+                    checkpoint_seq,
+                )?;
                 let data = TradeParamsUpdate {
                     digest: digest.to_string(),
                     event_digest: format!("{digest}{index}"),
@@ -73,6 +84,7 @@ impl Processor for TradeParamsUpdateHandler {
                     taker_fee: event.taker_fee as i64,
                     maker_fee: event.maker_fee as i64,
                     stake_required: event.stake_required as i64,
+                    epoch: Some(checkpoint.summary.epoch as i64),
                 };
                 debug!("Observed Deepbook Trade Params Update Event {:?}", data);
                 results.push(data);
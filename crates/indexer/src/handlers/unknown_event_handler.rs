@@ -0,0 +1,153 @@
+use crate::handlers::{is_deepbook_tx, parsed_events};
+use crate::{get_core_package_addresses, DeepbookEnv};
+use async_trait::async_trait;
+use deeplook_schema::models::UnknownEvent;
+use deeplook_schema::schema::unknown_events;
+use diesel::dsl::sql;
+use diesel::sql_types::{BigInt, Text};
+use diesel::upsert::excluded;
+use diesel::ExpressionMethods;
+use diesel_async::RunQueryDsl;
+use move_core_types::account_address::AccountAddress;
+use move_core_types::language_storage::StructTag;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use sui_indexer_alt_framework::pipeline::Processor;
+use sui_indexer_alt_framework::postgres::handler::Handler;
+use sui_indexer_alt_framework::postgres::Connection;
+use sui_indexer_alt_framework::types::full_checkpoint_content::Checkpoint;
+use sui_types::effects::TransactionEffectsAPI;
+use tracing::warn;
+
+/// Records event types of the DeepBook core package that no other handler parses, so new
+/// events of a package upgrade are noticed. Occurrences may be overcounted when checkpoints
+/// are reprocessed after a restart.
+pub struct UnknownEventHandler {
+    env: DeepbookEnv,
+}
+
+impl UnknownEventHandler {
+    pub fn new(env: DeepbookEnv) -> Self {
+        Self { env }
+    }
+}
+
+/// Whether any of the event handlers parses events of this type
+pub fn is_known_event(event_type: &StructTag, env: DeepbookEnv) -> bool {
+    parsed_events().any(|event| (event.matches)(event_type, env))
+}
+
+/// `address::module::name` of the event type, type parameters are dropped so generic
+/// events of different pools count as one type
+pub fn event_type_name(event_type: &StructTag) -> String {
+    format!(
+        "{}::{}::{}",
+        event_type.address.to_hex_literal(),
+        event_type.module,
+        event_type.name
+    )
+}
+
+/// Merges rows of the same event type, keeping the earliest first and the latest last occurrence
+pub fn merge_unknown_events(values: &[UnknownEvent]) -> Vec<UnknownEvent> {
+    let mut merged: BTreeMap<&str, UnknownEvent> = BTreeMap::new();
+    for value in values {
+        match merged.get_mut(value.event_type.as_str()) {
+            Some(existing) => {
+                if value.first_checkpoint < existing.first_checkpoint {
+                    existing.first_checkpoint = value.first_checkpoint;
+                    existing.first_digest = value.first_digest.clone();
+                }
+                existing.last_checkpoint = existing.last_checkpoint.max(value.last_checkpoint);
+                existing.occurrences += value.occurrences;
+            }
+            None => {
+                merged.insert(&value.event_type, value.clone());
+            }
+        }
+    }
+    merged.into_values().collect()
+}
+
+#[async_trait]
+impl Processor for UnknownEventHandler {
+    const NAME: &'static str = "unknown_events";
+    type Value = UnknownEvent;
+
+    async fn process(&self, checkpoint: &Arc<Checkpoint>) -> anyhow::Result<Vec<Self::Value>> {
+        let core_addresses: Vec<AccountAddress> = get_core_package_addresses(self.env)
+            .iter()
+            .map(|pkg| AccountAddress::from_str(pkg))
+            .collect::<Result<_, _>>()?;
+        let checkpoint_seq = checkpoint.summary.sequence_number as i64;
+
+        let mut results = vec![];
+        for tx in &checkpoint.transactions {
+            if !is_deepbook_tx(tx, &checkpoint.object_set, self.env) {
+                continue;
+            }
+            let Some(events) = &tx.events else {
+                continue;
+            };
+            let digest = tx.effects.transaction_digest().to_string();
+
+            for ev in &events.data {
+                if !core_addresses.contains(&ev.type_.address)
+                    || is_known_event(&ev.type_, self.env)
+                {
+                    continue;
+                }
+                results.push(UnknownEvent {
+                    event_type: event_type_name(&ev.type_),
+                    first_checkpoint: checkpoint_seq,
+                    first_digest: digest.clone(),
+                    last_checkpoint: checkpoint_seq,
+                    occurrences: 1,
+                });
+            }
+        }
+
+        let results = merge_unknown_events(&results);
+        for event in &results {
+            warn!(
+                event_type = %event.event_type,
+                checkpoint = checkpoint_seq,
+                occurrences = event.occurrences,
+                "Unknown DeepBook event"
+            );
+        }
+        Ok(results)
+    }
+}
+
+#[async_trait]
+impl Handler for UnknownEventHandler {
+    async fn commit<'a>(
+        values: &[Self::Value],
+        conn: &mut Connection<'a>,
+    ) -> anyhow::Result<usize> {
+        // A batch may hold several checkpoints, one upsert can't touch a row twice
+        let values = merge_unknown_events(values);
+        Ok(diesel::insert_into(unknown_events::table)
+            .values(&values)
+            .on_conflict(unknown_events::event_type)
+            .do_update()
+            .set((
+                unknown_events::first_checkpoint.eq(sql::<BigInt>(
+                    "LEAST(unknown_events.first_checkpoint, excluded.first_checkpoint)",
+                )),
+                unknown_events::first_digest.eq(sql::<Text>(
+                    "CASE WHEN excluded.first_checkpoint < unknown_events.first_checkpoint \
+                     THEN excluded.first_digest ELSE unknown_events.first_digest END",
+                )),
+                unknown_events::last_checkpoint.eq(sql::<BigInt>(
+                    "GREATEST(unknown_events.last_checkpoint, excluded.last_checkpoint)",
+                )),
+                unknown_events::occurrences
+                    .eq(unknown_events::occurrences + excluded(unknown_events::occurrences)),
+            ))
+            .execute(conn)
+            .await?)
+    }
+}
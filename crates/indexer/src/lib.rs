@@ -1,6 +1,7 @@
 use url::Url;
 
 pub mod handlers;
+pub mod metrics;
 pub mod models;
 pub mod traits;
 pub mod utils;
@@ -1,6 +1,7 @@
 use anyhow::Context;
 use clap::Parser;
 use deeplook_indexer::handlers::balances_handler::BalancesHandler;
+use deeplook_indexer::handlers::decode_failure_handler::DecodeFailureHandler;
 use deeplook_indexer::handlers::flash_loan_handler::FlashLoanHandler;
 use deeplook_indexer::handlers::order_fill_handler::OrderFillHandler;
 use deeplook_indexer::handlers::order_update_handler::OrderUpdateHandler;
@@ -9,8 +10,10 @@ use deeplook_indexer::handlers::proposals_handler::ProposalsHandler;
 use deeplook_indexer::handlers::rebates_handler::RebatesHandler;
 use deeplook_indexer::handlers::stakes_handler::StakesHandler;
 use deeplook_indexer::handlers::trade_params_update_handler::TradeParamsUpdateHandler;
+use deeplook_indexer::handlers::unknown_event_handler::UnknownEventHandler;
 use deeplook_indexer::handlers::vote_handler::VotesHandler;
 
+use deeplook_indexer::metrics::IngestMetrics;
 use deeplook_indexer::DeepbookEnv;
 use deeplook_schema::MIGRATIONS;
 use prometheus::Registry;
@@ -56,6 +59,19 @@ struct Args {
     /// Packages to index events for (can specify multiple)
     #[clap(long, value_enum, default_values = ["deepbook", "deepbook-margin"])]
     packages: Vec<Package>,
+    /// Record DeepBook event types no handler parses in the `unknown_events` table
+    #[clap(env, long)]
+    unknown_events: bool,
+    /// Record events of parsed types whose contents fail to decode in the `decode_failures` table
+    #[clap(env, long)]
+    decode_failures: bool,
+    /// Checkpoints fetched ahead of the pipelines, the framework default when unset. Larger
+    /// buffers speed up backfills at the cost of memory.
+    #[clap(env, long)]
+    checkpoint_buffer_size: Option<usize>,
+    /// Checkpoints fetched concurrently, the framework default when unset
+    #[clap(env, long)]
+    ingest_concurrency: Option<usize>,
 }
 
 #[tokio::main]
@@ -72,11 +88,16 @@ async fn main() -> Result<(), anyhow::Error> {
         database_url,
         env,
         packages,
+        unknown_events,
+        decode_failures,
+        checkpoint_buffer_size,
+        ingest_concurrency,
     } = Args::parse();
 
     let registry = Registry::new_custom(Some("deeplook".into()), None)
         .context("Failed to create Prometheus registry.")?;
     let metrics = MetricsService::new(MetricsArgs { metrics_address }, registry.clone());
+    let ingest_metrics = IngestMetrics::new(&registry);
 
     // Prepare the store for the indexer
     let store = Db::for_write(database_url, db_args)
@@ -93,6 +114,14 @@ async fn main() -> Result<(), anyhow::Error> {
         store.clone(),
     )))?;
 
+    let mut ingestion_config = IngestionConfig::default();
+    if let Some(checkpoint_buffer_size) = checkpoint_buffer_size {
+        ingestion_config.checkpoint_buffer_size = checkpoint_buffer_size;
+    }
+    if let Some(ingest_concurrency) = ingest_concurrency {
+        ingestion_config.ingest_concurrency = ingest_concurrency;
+    }
+
     let mut indexer = Indexer::new(
         store,
         indexer_args,
@@ -106,7 +135,7 @@ async fn main() -> Result<(), anyhow::Error> {
             },
             streaming: streaming_args,
         },
-        IngestionConfig::default(),
+        ingestion_config,
         None,
         metrics.registry(),
     )
@@ -118,35 +147,75 @@ async fn main() -> Result<(), anyhow::Error> {
             Package::Deepbook => {
                 // DeepBook core event handlers
                 indexer
-                    .concurrent_pipeline(BalancesHandler::new(env), Default::default())
+                    .concurrent_pipeline(
+                        BalancesHandler::new(env, ingest_metrics.clone()),
+                        Default::default(),
+                    )
                     .await?;
                 indexer
-                    .concurrent_pipeline(FlashLoanHandler::new(env), Default::default())
+                    .concurrent_pipeline(
+                        FlashLoanHandler::new(env, ingest_metrics.clone()),
+                        Default::default(),
+                    )
                     .await?;
                 indexer
-                    .concurrent_pipeline(OrderFillHandler::new(env), Default::default())
+                    .concurrent_pipeline(
+                        OrderFillHandler::new(env, ingest_metrics.clone()),
+                        Default::default(),
+                    )
                     .await?;
                 indexer
-                    .concurrent_pipeline(OrderUpdateHandler::new(env), Default::default())
+                    .concurrent_pipeline(
+                        OrderUpdateHandler::new(env, ingest_metrics.clone()),
+                        Default::default(),
+                    )
                     .await?;
                 indexer
-                    .concurrent_pipeline(PoolPriceHandler::new(env), Default::default())
+                    .concurrent_pipeline(
+                        PoolPriceHandler::new(env, ingest_metrics.clone()),
+                        Default::default(),
+                    )
                     .await?;
                 indexer
-                    .concurrent_pipeline(ProposalsHandler::new(env), Default::default())
+                    .concurrent_pipeline(
+                        ProposalsHandler::new(env, ingest_metrics.clone()),
+                        Default::default(),
+                    )
                     .await?;
                 indexer
-                    .concurrent_pipeline(RebatesHandler::new(env), Default::default())
+                    .concurrent_pipeline(
+                        RebatesHandler::new(env, ingest_metrics.clone()),
+                        Default::default(),
+                    )
                     .await?;
                 indexer
-                    .concurrent_pipeline(StakesHandler::new(env), Default::default())
+                    .concurrent_pipeline(
+                        StakesHandler::new(env, ingest_metrics.clone()),
+                        Default::default(),
+                    )
                     .await?;
                 indexer
-                    .concurrent_pipeline(TradeParamsUpdateHandler::new(env), Default::default())
+                    .concurrent_pipeline(
+                        TradeParamsUpdateHandler::new(env, ingest_metrics.clone()),
+                        Default::default(),
+                    )
                     .await?;
                 indexer
-                    .concurrent_pipeline(VotesHandler::new(env), Default::default())
+                    .concurrent_pipeline(
+                        VotesHandler::new(env, ingest_metrics.clone()),
+                        Default::default(),
+                    )
                     .await?;
+                if unknown_events {
+                    indexer
+                        .concurrent_pipeline(UnknownEventHandler::new(env), Default::default())
+                        .await?;
+                }
+                if decode_failures {
+                    indexer
+                        .concurrent_pipeline(DecodeFailureHandler::new(env), Default::default())
+                        .await?;
+                }
             }
             Package::DeepbookMargin => {}
         }
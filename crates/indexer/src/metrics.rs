@@ -0,0 +1,54 @@
+use prometheus::{register_int_counter_vec_with_registry, IntCounterVec, Registry};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+
+/// Counters of the events decoded by each handler, labeled by the handler's pipeline name
+#[derive(Clone)]
+pub struct IngestMetrics {
+    pub events_parsed: IntCounterVec,
+    pub events_failed: IntCounterVec,
+    pub event_bytes: IntCounterVec,
+}
+
+impl IngestMetrics {
+    pub fn new(registry: &Registry) -> Arc<Self> {
+        Arc::new(Self {
+            events_parsed: register_int_counter_vec_with_registry!(
+                "indexer_events_parsed",
+                "Number of events decoded by each indexer handler",
+                &["handler"],
+                registry
+            )
+            .unwrap(),
+            events_failed: register_int_counter_vec_with_registry!(
+                "indexer_events_failed",
+                "Number of events each indexer handler failed to decode",
+                &["handler"],
+                registry
+            )
+            .unwrap(),
+            event_bytes: register_int_counter_vec_with_registry!(
+                "indexer_event_bytes",
+                "BCS bytes of the events decoded by each indexer handler",
+                &["handler"],
+                registry
+            )
+            .unwrap(),
+        })
+    }
+
+    /// Decodes the BCS contents of an event and counts the outcome for `handler`
+    pub fn decode<T: DeserializeOwned>(&self, handler: &str, contents: &[u8]) -> bcs::Result<T> {
+        let decoded = bcs::from_bytes(contents);
+        match &decoded {
+            Ok(_) => {
+                self.events_parsed.with_label_values(&[handler]).inc();
+                self.event_bytes
+                    .with_label_values(&[handler])
+                    .inc_by(contents.len() as u64);
+            }
+            Err(_) => self.events_failed.with_label_values(&[handler]).inc(),
+        }
+        decoded
+    }
+}
@@ -0,0 +1,72 @@
+use deeplook_indexer::handlers::decode_failure_handler::decode_error;
+use deeplook_indexer::models::deepbook::balance_manager::BalanceEvent;
+use deeplook_indexer::DeepbookEnv;
+use move_core_types::account_address::AccountAddress;
+use move_core_types::identifier::Identifier;
+use move_core_types::language_storage::StructTag;
+use std::str::FromStr;
+use sui_types::base_types::ObjectID;
+
+const PACKAGE: &str = "0x2c8d603bc51326b8c13cef9dd07031a408a48dddb541963357661df5d3204809";
+
+fn event_type(module: &str, name: &str) -> StructTag {
+    StructTag {
+        address: AccountAddress::from_str(PACKAGE).unwrap(),
+        module: Identifier::new(module).unwrap(),
+        name: Identifier::new(name).unwrap(),
+        type_params: vec![],
+    }
+}
+
+fn balance_event() -> Vec<u8> {
+    bcs::to_bytes(&BalanceEvent {
+        balance_manager_id: ObjectID::ZERO,
+        asset: "0x2::sui::SUI".to_string(),
+        amount: 100,
+        deposit: true,
+    })
+    .unwrap()
+}
+
+#[test]
+fn decodable_events_are_not_failures() {
+    let balance = event_type("balance_manager", "BalanceEvent");
+    assert_eq!(
+        decode_error(&balance, &balance_event(), DeepbookEnv::Mainnet),
+        None
+    );
+}
+
+#[test]
+fn changed_layouts_are_failures() {
+    let balance = event_type("balance_manager", "BalanceEvent");
+    let mut truncated = balance_event();
+    truncated.pop();
+    assert!(decode_error(&balance, &truncated, DeepbookEnv::Mainnet).is_some());
+
+    // a BalanceEvent doesn't decode as the much larger OrderFilled
+    let filled = event_type("order_info", "OrderFilled");
+    assert!(decode_error(&filled, &balance_event(), DeepbookEnv::Mainnet).is_some());
+}
+
+#[test]
+fn unparsed_types_are_not_failures() {
+    let burned = event_type("pool", "DeepBurned");
+    assert_eq!(
+        decode_error(&burned, &[1, 2, 3], DeepbookEnv::Mainnet),
+        None
+    );
+}
+
+#[test]
+fn every_parsed_type_is_checked() {
+    for (module, name) in [
+        ("order_info", "OrderPlaced"),
+        ("order", "OrderCanceled"),
+        ("governance", "TradeParamsUpdateEvent"),
+        ("state", "VoteEvent"),
+    ] {
+        let parsed = event_type(module, name);
+        assert!(decode_error(&parsed, &[1, 2, 3], DeepbookEnv::Mainnet).is_some());
+    }
+}
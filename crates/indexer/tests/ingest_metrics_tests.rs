@@ -0,0 +1,51 @@
+use deeplook_indexer::metrics::IngestMetrics;
+use prometheus::Registry;
+
+#[test]
+fn decode_counts_parsed_events_and_bytes() {
+    let metrics = IngestMetrics::new(&Registry::new());
+    let contents = bcs::to_bytes(&42u64).unwrap();
+
+    let value: u64 = metrics.decode("balances", &contents).unwrap();
+
+    assert_eq!(value, 42);
+    assert_eq!(
+        metrics.events_parsed.with_label_values(&["balances"]).get(),
+        1
+    );
+    assert_eq!(
+        metrics.event_bytes.with_label_values(&["balances"]).get(),
+        8
+    );
+    assert_eq!(
+        metrics.events_failed.with_label_values(&["balances"]).get(),
+        0
+    );
+}
+
+#[test]
+fn decode_counts_failures_per_handler() {
+    let metrics = IngestMetrics::new(&Registry::new());
+
+    let result = metrics.decode::<u64>("order_fill", &[1, 2, 3]);
+
+    assert!(result.is_err());
+    assert_eq!(
+        metrics
+            .events_failed
+            .with_label_values(&["order_fill"])
+            .get(),
+        1
+    );
+    assert_eq!(
+        metrics
+            .events_parsed
+            .with_label_values(&["order_fill"])
+            .get(),
+        0
+    );
+    assert_eq!(
+        metrics.events_failed.with_label_values(&["balances"]).get(),
+        0
+    );
+}
@@ -5,10 +5,12 @@ use deeplook_indexer::handlers::order_fill_handler::OrderFillHandler;
 use deeplook_indexer::handlers::order_update_handler::OrderUpdateHandler;
 use deeplook_indexer::handlers::pool_price_handler::PoolPriceHandler;
 
+use deeplook_indexer::metrics::IngestMetrics;
 use deeplook_indexer::DeepbookEnv;
 use deeplook_schema::MIGRATIONS;
 use fastcrypto::hash::{HashFunction, Sha256};
 use insta::assert_json_snapshot;
+use prometheus::Registry;
 use serde_json::Value;
 use sqlx::{types::BigDecimal, Column, PgPool, Row, ValueRef};
 use std::env;
@@ -28,34 +30,34 @@ use sui_types::full_checkpoint_content::CheckpointData;
 
 #[tokio::test]
 async fn balances_test() -> Result<(), anyhow::Error> {
-    let handler = BalancesHandler::new(DeepbookEnv::Mainnet);
+    let handler = BalancesHandler::new(DeepbookEnv::Mainnet, ingest_metrics());
     data_test("balances", handler, ["balances"]).await?;
     Ok(())
 }
 
 #[tokio::test]
 async fn flash_loan_test() -> Result<(), anyhow::Error> {
-    let handler = FlashLoanHandler::new(DeepbookEnv::Mainnet);
+    let handler = FlashLoanHandler::new(DeepbookEnv::Mainnet, ingest_metrics());
     data_test("flash_loans", handler, ["flashloans"]).await?;
     Ok(())
 }
 
 #[tokio::test]
 async fn order_fill_test() -> Result<(), anyhow::Error> {
-    let handler = OrderFillHandler::new(DeepbookEnv::Mainnet);
+    let handler = OrderFillHandler::new(DeepbookEnv::Mainnet, ingest_metrics());
     data_test("order_fill", handler, ["order_fills"]).await?;
     Ok(())
 }
 #[tokio::test]
 async fn order_update_test() -> Result<(), anyhow::Error> {
-    let handler = OrderUpdateHandler::new(DeepbookEnv::Mainnet);
+    let handler = OrderUpdateHandler::new(DeepbookEnv::Mainnet, ingest_metrics());
     data_test("order_update", handler, ["order_updates"]).await?;
     Ok(())
 }
 
 #[tokio::test]
 async fn pool_price_test() -> Result<(), anyhow::Error> {
-    let handler = PoolPriceHandler::new(DeepbookEnv::Mainnet);
+    let handler = PoolPriceHandler::new(DeepbookEnv::Mainnet, ingest_metrics());
     data_test("pool_price", handler, ["pool_prices"]).await?;
     Ok(())
 }
@@ -64,11 +66,15 @@ async fn pool_price_test() -> Result<(), anyhow::Error> {
 async fn balances_indirect_interaction_test() -> Result<(), anyhow::Error> {
     // Test that balance events from transactions that interact with DeepBook
     // indirectly (through other protocols) are still captured
-    let handler = BalancesHandler::new(DeepbookEnv::Mainnet);
+    let handler = BalancesHandler::new(DeepbookEnv::Mainnet, ingest_metrics());
     data_test("balances_indirect", handler, ["balances"]).await?;
     Ok(())
 }
 
+fn ingest_metrics() -> Arc<IngestMetrics> {
+    IngestMetrics::new(&Registry::new())
+}
+
 async fn data_test<H, I>(
     test_name: &str,
     handler: H,
@@ -0,0 +1,72 @@
+use deeplook_indexer::handlers::unknown_event_handler::{
+    event_type_name, is_known_event, merge_unknown_events,
+};
+use deeplook_indexer::DeepbookEnv;
+use deeplook_schema::models::UnknownEvent;
+use move_core_types::account_address::AccountAddress;
+use move_core_types::identifier::Identifier;
+use move_core_types::language_storage::{StructTag, TypeTag};
+use std::str::FromStr;
+
+const PACKAGE: &str = "0x2c8d603bc51326b8c13cef9dd07031a408a48dddb541963357661df5d3204809";
+
+fn event_type(module: &str, name: &str, type_params: Vec<TypeTag>) -> StructTag {
+    StructTag {
+        address: AccountAddress::from_str(PACKAGE).unwrap(),
+        module: Identifier::new(module).unwrap(),
+        name: Identifier::new(name).unwrap(),
+        type_params,
+    }
+}
+
+fn unknown(event_type: &str, checkpoint: i64, digest: &str) -> UnknownEvent {
+    UnknownEvent {
+        event_type: event_type.to_string(),
+        first_checkpoint: checkpoint,
+        first_digest: digest.to_string(),
+        last_checkpoint: checkpoint,
+        occurrences: 1,
+    }
+}
+
+#[test]
+fn parsed_events_are_known() {
+    let filled = event_type("order_info", "OrderFilled", vec![]);
+    assert!(is_known_event(&filled, DeepbookEnv::Mainnet));
+
+    let burned = event_type("pool", "DeepBurned", vec![TypeTag::U64, TypeTag::U64]);
+    assert!(!is_known_event(&burned, DeepbookEnv::Mainnet));
+}
+
+#[test]
+fn type_params_are_dropped_from_names() {
+    let burned = event_type("pool", "DeepBurned", vec![TypeTag::U64, TypeTag::U64]);
+    assert_eq!(
+        event_type_name(&burned),
+        format!("{PACKAGE}::pool::DeepBurned")
+    );
+}
+
+#[test]
+fn rows_of_one_type_are_merged() {
+    let merged = merge_unknown_events(&[
+        unknown("0x1::pool::B", 12, "d12"),
+        unknown("0x1::pool::A", 11, "d11"),
+        unknown("0x1::pool::B", 10, "d10"),
+        unknown("0x1::pool::B", 14, "d14"),
+    ]);
+
+    assert_eq!(
+        merged,
+        vec![
+            unknown("0x1::pool::A", 11, "d11"),
+            UnknownEvent {
+                event_type: "0x1::pool::B".to_string(),
+                first_checkpoint: 10,
+                first_digest: "d10".to_string(),
+                last_checkpoint: 14,
+                occurrences: 3,
+            },
+        ]
+    );
+}
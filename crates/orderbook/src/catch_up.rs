@@ -1,7 +1,7 @@
-use std::{net::SocketAddr, sync::Arc};
+use std::net::SocketAddr;
 
 use anyhow::Context;
-use deeplook_indexer::{DeepbookEnv, MAINNET_REMOTE_STORE_URL};
+use deeplook_indexer::{DeepbookEnv, metrics::IngestMetrics};
 use prometheus::Registry;
 use sui_indexer_alt_framework::{
     Indexer, IndexerArgs, TaskArgs,
@@ -15,7 +15,9 @@ use sui_indexer_alt_metrics::{MetricsArgs, MetricsService, db::DbConnectionStats
 use url::Url;
 
 use crate::{
-    OrderbookManagerMap, handlers::orderbook_order_update_handler::OrderbookOrderUpdateHandler,
+    SharedOrderbookManagerMap,
+    handlers::orderbook_order_update_handler::OrderbookOrderUpdateHandler,
+    metrics::OrderbookMetrics,
 };
 
 /// Takes orderbook managers and quickly catches up to the latest checkpoint
@@ -23,7 +25,10 @@ use crate::{
 pub async fn catch_up(
     database_url: Url,
     metrics_address: SocketAddr,
-    orderbook_managers: Arc<OrderbookManagerMap>,
+    env: DeepbookEnv,
+    remote_store_url: Url,
+    orderbook_managers: SharedOrderbookManagerMap,
+    orderbook_metrics: &OrderbookMetrics,
     end: u64,
 ) -> Result<(), anyhow::Error> {
     let registry = Registry::new_custom(Some("deeplook".into()), None)
@@ -39,8 +44,11 @@ pub async fn catch_up(
         Some("deepbook_indexer_db"),
         store.clone(),
     )))?;
+    orderbook_metrics.register(&registry)?;
 
     let lowest_checkpoint = orderbook_managers
+        .read()
+        .map_err(|_| anyhow::anyhow!("orderbook managers lock poisoned"))?
         .values()
         .filter_map(|arc_mutex| {
             arc_mutex
@@ -61,7 +69,7 @@ pub async fn catch_up(
         },
         ClientArgs {
             ingestion: IngestionClientArgs {
-                remote_store_url: Some(Url::parse(MAINNET_REMOTE_STORE_URL).unwrap()),
+                remote_store_url: Some(remote_store_url),
                 local_ingestion_path: None,
                 rpc_api_url: None,
                 rpc_username: None,
@@ -77,7 +85,11 @@ pub async fn catch_up(
 
     indexer
         .concurrent_pipeline(
-            OrderbookOrderUpdateHandler::new(DeepbookEnv::Mainnet, orderbook_managers),
+            OrderbookOrderUpdateHandler::new(
+                env,
+                orderbook_managers,
+                IngestMetrics::new(&registry),
+            ),
             Default::default(),
         )
         .await?;
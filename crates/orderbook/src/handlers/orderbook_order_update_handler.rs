@@ -1,29 +1,249 @@
-use crate::OrderbookManagerMap;
+use crate::SharedOrderbookManagerMap;
+use crate::historic_orderbook::prune_snapshots_except;
+use crate::orderbook::{BookEvent, fetch_onchain_orderbook_at_tip, interleave};
 
 use async_trait::async_trait;
 use deeplook_indexer::DeepbookEnv;
 use deeplook_indexer::handlers::order_fill_handler::OrderFillHandler;
 use deeplook_indexer::handlers::order_update_handler::OrderUpdateHandler;
+use deeplook_indexer::metrics::IngestMetrics;
 use deeplook_schema::models::{OrderFill, OrderUpdate};
-use std::collections::HashMap;
-use std::sync::Arc;
+use diesel::{Connection as _, PgConnection};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use sui_indexer_alt_framework::pipeline::Processor;
 use sui_indexer_alt_framework::postgres::Connection;
 use sui_indexer_alt_framework::postgres::handler::Handler as PgHandler;
 use sui_indexer_alt_framework::types::full_checkpoint_content::Checkpoint;
+use sui_types::effects::TransactionEffectsAPI;
+use tracing::{error, info};
+use url::Url;
+
+/// Where and how often the books are stored as snapshots
+struct SnapshotSchedule {
+    database_url: Url,
+    interval: Duration,
+    /// Snapshots kept per pool, older ones are deleted after storing a new one
+    keep_last: Option<usize>,
+    last_persisted: Mutex<Instant>,
+    /// Checkpoints processed ahead of the next one to apply
+    queue: Mutex<CheckpointQueue>,
+    /// Held while applying queued checkpoints, so they are applied one at a time
+    applying: tokio::sync::Mutex<()>,
+}
+
+/// Events of a checkpoint by pool, in emission order
+type CheckpointEvents = Vec<(String, Vec<BookEvent>)>;
+
+struct CheckpointQueue {
+    next: i64,
+    pending: BTreeMap<i64, CheckpointEvents>,
+}
+
+impl SnapshotSchedule {
+    fn is_due(&self) -> bool {
+        let Ok(mut last_persisted) = self.last_persisted.lock() else {
+            return false;
+        };
+        if last_persisted.elapsed() < self.interval {
+            return false;
+        }
+        *last_persisted = Instant::now();
+        true
+    }
+}
 
 pub struct OrderbookOrderUpdateHandler {
     update_handler: OrderUpdateHandler,
     fill_handler: OrderFillHandler,
-    orderbook_managers: Arc<OrderbookManagerMap>,
+    orderbook_managers: SharedOrderbookManagerMap,
+    snapshots: Option<SnapshotSchedule>,
 }
 
 impl OrderbookOrderUpdateHandler {
-    pub fn new(env: DeepbookEnv, orderbook_managers: Arc<OrderbookManagerMap>) -> Self {
+    pub fn new(
+        env: DeepbookEnv,
+        orderbook_managers: SharedOrderbookManagerMap,
+        ingest_metrics: Arc<IngestMetrics>,
+    ) -> Self {
         Self {
-            update_handler: OrderUpdateHandler::new(env),
-            fill_handler: OrderFillHandler::new(env),
+            update_handler: OrderUpdateHandler::new(env, ingest_metrics.clone()),
+            fill_handler: OrderFillHandler::new(env, ingest_metrics),
             orderbook_managers,
+            snapshots: None,
+        }
+    }
+
+    /// Stores all books as snapshots at most once per `interval`. The pipeline processes
+    /// checkpoints concurrently, so from then on checkpoints are applied to the books in order
+    /// starting at `first_checkpoint`, and a snapshot is labeled with the last applied one.
+    /// With `keep_last` only that many snapshots of each pool are kept, plus the one its
+    /// book started from.
+    pub fn with_snapshots(
+        mut self,
+        database_url: Url,
+        interval: Duration,
+        keep_last: Option<usize>,
+        first_checkpoint: u64,
+    ) -> Self {
+        self.snapshots = Some(SnapshotSchedule {
+            database_url,
+            interval,
+            keep_last,
+            last_persisted: Mutex::new(Instant::now()),
+            queue: Mutex::new(CheckpointQueue {
+                next: first_checkpoint as i64,
+                pending: BTreeMap::new(),
+            }),
+            applying: tokio::sync::Mutex::new(()),
+        });
+        self
+    }
+
+    /// Applies the events of the checkpoint to the books, resyncing the strict ones that
+    /// became invalid
+    async fn apply_checkpoint(&self, checkpoint: i64, events: CheckpointEvents) {
+        for (pool_id, events) in events {
+            // clone the manager out, so the map isn't locked while handling the batch
+            let ob_m = self
+                .orderbook_managers
+                .read()
+                .ok()
+                .and_then(|managers| managers.get(&pool_id).cloned());
+            let Some(ob_m) = ob_m else {
+                continue;
+            };
+            let resync = match ob_m.lock() {
+                Ok(mut locked) => {
+                    locked.handle_batch(events);
+                    locked
+                        .needs_resync()
+                        .then(|| (locked.sui_client.clone(), locked.pool.clone()))
+                }
+                Err(_) => None,
+            };
+            // the onchain book is read without holding the manager lock
+            if let Some((sui_client, pool)) = resync {
+                let onchain = fetch_onchain_orderbook_at_tip(&sui_client, &pool).await;
+                if let Ok(mut locked) = ob_m.lock() {
+                    locked.finish_resync(onchain, checkpoint);
+                }
+            }
+        }
+    }
+
+    /// Queues the checkpoint and applies every queued one that follows the last applied
+    /// checkpoint, storing snapshots when they are due
+    async fn apply_in_order(
+        &self,
+        snapshots: &SnapshotSchedule,
+        checkpoint: i64,
+        events: CheckpointEvents,
+    ) {
+        if let Ok(mut queue) = snapshots.queue.lock() {
+            queue.pending.insert(checkpoint, events);
+        }
+
+        let _applying = snapshots.applying.lock().await;
+        loop {
+            let next = match snapshots.queue.lock() {
+                Ok(mut queue) => {
+                    let next = queue.next;
+                    queue.pending.remove(&next).map(|events| {
+                        queue.next += 1;
+                        (next, events)
+                    })
+                }
+                Err(_) => None,
+            };
+            let Some((checkpoint, events)) = next else {
+                break;
+            };
+
+            self.apply_checkpoint(checkpoint, events).await;
+            if snapshots.is_due() {
+                self.persist_snapshots(
+                    snapshots.database_url.clone(),
+                    checkpoint,
+                    snapshots.keep_last,
+                )
+                .await;
+            }
+        }
+    }
+
+    async fn persist_snapshots(
+        &self,
+        database_url: Url,
+        checkpoint: i64,
+        keep_last: Option<usize>,
+    ) {
+        // every manager is registered under both pool name and pool id
+        let managers: Vec<_> = match self.orderbook_managers.read() {
+            Ok(managers) => managers
+                .iter()
+                .filter(|(key, ob_m)| {
+                    ob_m.lock()
+                        .map(|locked| &locked.pool.pool_id == *key)
+                        .unwrap_or(false)
+                })
+                .map(|(_, ob_m)| ob_m.clone())
+                .collect(),
+            Err(_) => return,
+        };
+
+        let result = tokio::task::spawn_blocking(move || {
+            let mut conn = PgConnection::establish(database_url.as_str())?;
+            let mut persisted = 0;
+            for ob_m in managers {
+                let Ok(locked) = ob_m.lock() else {
+                    continue;
+                };
+                match locked.persist_snapshot(&mut conn, checkpoint) {
+                    Ok(true) => {
+                        persisted += 1;
+                        let Some(keep_last) = keep_last else {
+                            continue;
+                        };
+                        match prune_snapshots_except(
+                            &mut conn,
+                            &locked.pool.pool_id,
+                            keep_last,
+                            Some(locked.initial_checkpoint),
+                        ) {
+                            Ok(0) => {}
+                            Ok(pruned) => info!(
+                                "Pruned {} snapshots of pool {}",
+                                pruned, locked.pool.pool_name
+                            ),
+                            Err(e) => error!(
+                                "Failed pruning snapshots of pool {}: {:?}",
+                                locked.pool.pool_name, e
+                            ),
+                        }
+                    }
+                    Ok(false) => info!(
+                        "Skipped snapshot of pool {} at checkpoint {}",
+                        locked.pool.pool_name, checkpoint
+                    ),
+                    Err(e) => error!(
+                        "Failed storing snapshot of pool {}: {:?}",
+                        locked.pool.pool_name, e
+                    ),
+                }
+            }
+            Ok::<_, anyhow::Error>(persisted)
+        })
+        .await;
+
+        match result {
+            Ok(Ok(persisted)) => info!(
+                "Stored {} orderbook snapshots at checkpoint {}",
+                persisted, checkpoint
+            ),
+            Ok(Err(e)) => error!("Failed storing orderbook snapshots: {:?}", e),
+            Err(e) => error!("Storing orderbook snapshots panicked: {:?}", e),
         }
     }
 }
@@ -53,6 +273,14 @@ impl Processor for OrderbookOrderUpdateHandler {
                 .push(fill);
         }
 
+        // position of each transaction, updates and fills are applied in emission order
+        let tx_order: HashMap<String, usize> = checkpoint
+            .transactions
+            .iter()
+            .enumerate()
+            .map(|(position, tx)| (tx.effects.transaction_digest().to_string(), position))
+            .collect();
+
         let mut all_pool_ids: Vec<String> = updates_by_pool
             .keys()
             .chain(fills_by_pool.keys())
@@ -61,15 +289,20 @@ impl Processor for OrderbookOrderUpdateHandler {
         all_pool_ids.sort();
         all_pool_ids.dedup();
 
-        for pool_id in all_pool_ids {
-            let updates: Vec<OrderUpdate> =
-                updates_by_pool.remove(&pool_id).unwrap_or_else(Vec::new);
-            let fills: Vec<OrderFill> = fills_by_pool.remove(&pool_id).unwrap_or_else(Vec::new);
-            if let Some(ob_m) = self.orderbook_managers.get(&pool_id) {
-                if let Ok(mut locked) = ob_m.lock() {
-                    locked.handle_batch(updates, fills);
-                }
-            }
+        let events: CheckpointEvents = all_pool_ids
+            .into_iter()
+            .map(|pool_id| {
+                let updates = updates_by_pool.remove(&pool_id).unwrap_or_else(Vec::new);
+                let fills = fills_by_pool.remove(&pool_id).unwrap_or_else(Vec::new);
+                let events = interleave(updates, fills, &tx_order);
+                (pool_id, events)
+            })
+            .collect();
+
+        let checkpoint = checkpoint.summary.sequence_number as i64;
+        match &self.snapshots {
+            Some(snapshots) => self.apply_in_order(snapshots, checkpoint, events).await,
+            None => self.apply_checkpoint(checkpoint, events).await,
         }
 
         Ok(vec![])
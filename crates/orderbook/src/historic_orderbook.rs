@@ -1,12 +1,18 @@
 use std::collections::HashMap;
+use std::time::Instant;
 
 use chrono::NaiveDateTime;
 use clap::Parser;
 use diesel::{Connection, PgConnection, Queryable};
+use prometheus::IntGauge;
+use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 use url::Url;
 
-use deeplook_schema::{models::OrderbookSnapshot, schema};
+use deeplook_schema::{
+    models::{OrderFill, OrderUpdate, OrderbookSnapshot},
+    schema,
+};
 use diesel::prelude::*;
 
 #[derive(Parser)]
@@ -40,23 +46,120 @@ struct OrderFillSummary {
     pub taker_is_bid: bool,
 }
 
-#[derive(Debug)]
-enum Op {
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Op {
     Add,
     Subtract,
 }
 
-#[derive(Debug)]
-struct OrderStep {
+/// Single change of a price level, order updates and fills are both replayed as steps
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrderStep {
     pub price: i64,
     pub quantity: i64,
     pub op: Op,
-    #[allow(dead_code)]
     pub checkpoint: i64,
     pub is_bid: bool,
     pub timestamp: NaiveDateTime,
 }
 
+impl OrderStep {
+    /// Step of a fill, it takes `base_quantity` from the maker's resting order at the fill
+    /// price. The taker's unfilled rest isn't part of the fill, it rests on the book only with
+    /// the `OrderPlaced` emitted after the fills, whose `placed_quantity` is that rest.
+    pub fn from_fill(fill: &OrderFill) -> Self {
+        OrderStep {
+            price: fill.price,
+            quantity: fill.base_quantity,
+            op: Op::Subtract,
+            checkpoint: fill.checkpoint,
+            is_bid: !fill.taker_is_bid,
+            timestamp: fill.timestamp,
+        }
+    }
+
+    /// Step of an order update, the same as replayed from stored updates
+    pub fn from_update(update: &OrderUpdate) -> Self {
+        let status = update.status.as_ref();
+        OrderStep {
+            price: update.price,
+            quantity: update_quantity(status, update.original_quantity, update.quantity),
+            op: status_to_operation(status),
+            checkpoint: update.checkpoint,
+            is_bid: update.is_bid,
+            timestamp: update.timestamp,
+        }
+    }
+}
+
+/// Checkpoints between progress logs of a replay
+pub const REPLAY_PROGRESS_CHECKPOINTS: i64 = 100_000;
+
+/// Progress of a replay, logged every `interval` checkpoints with the steps applied and the
+/// time taken so far. `checkpoint` is set to the last applied checkpoint at every log.
+pub struct ReplayProgress {
+    label: String,
+    interval: i64,
+    end_checkpoint: i64,
+    started: Instant,
+    next_log: Option<i64>,
+    steps: usize,
+    checkpoint: Option<IntGauge>,
+}
+
+impl ReplayProgress {
+    /// `interval` of 0 disables the logs
+    pub fn new(label: &str, interval: i64, end_checkpoint: i64) -> Self {
+        Self {
+            label: label.to_string(),
+            interval,
+            end_checkpoint,
+            started: Instant::now(),
+            next_log: None,
+            steps: 0,
+            checkpoint: None,
+        }
+    }
+
+    pub fn with_checkpoint_gauge(mut self, gauge: IntGauge) -> Self {
+        self.checkpoint = Some(gauge);
+        self
+    }
+
+    /// Records a step of `checkpoint`, steps must come in checkpoint order
+    pub fn record(&mut self, checkpoint: i64) {
+        self.steps += 1;
+        let due = self.next_log.is_none_or(|next| checkpoint >= next);
+        if self.interval > 0 && due {
+            self.log(checkpoint);
+            self.next_log = Some(checkpoint + self.interval);
+        }
+    }
+
+    /// Logs the end of the replay
+    pub fn finish(&self) {
+        self.log(self.end_checkpoint);
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    fn log(&self, checkpoint: i64) {
+        if let Some(gauge) = &self.checkpoint {
+            gauge.set(checkpoint);
+        }
+        info!(
+            replay = %self.label,
+            checkpoint,
+            end_checkpoint = self.end_checkpoint,
+            steps = self.steps,
+            elapsed_secs = self.started.elapsed().as_secs(),
+            "Replay progress"
+        );
+    }
+}
+
 #[derive(Debug)]
 pub enum HistoricOrderbookError {
     StartGreaterThanEnd,
@@ -67,6 +170,15 @@ pub enum HistoricOrderbookError {
     FailedReadingFromDatabase(diesel::result::Error),
 }
 
+/// Size an update adds or removes, modifies carry the order's total before and after
+fn update_quantity(status: &str, original_quantity: i64, quantity: i64) -> i64 {
+    if status == "Modified" {
+        modified_size_change(original_quantity, quantity)
+    } else {
+        quantity
+    }
+}
+
 fn status_to_operation(status: &str) -> Op {
     match status {
         "Placed" => Op::Add,
@@ -79,11 +191,19 @@ fn status_to_operation(status: &str) -> Op {
     }
 }
 
-fn get_txs(
+/// Size a modify removes from the order's price level. `OrderModified` carries the order's
+/// total quantity before and after the modify, which fills don't change, so the remaining
+/// size drops by the same amount. Fills are subtracted by their own fill events, subtracting
+/// `filled_quantity` here too would count them twice.
+pub fn modified_size_change(previous_quantity: i64, new_quantity: i64) -> i64 {
+    previous_quantity - new_quantity
+}
+
+pub fn get_txs(
     pool_id: &str,
     start_checkpoint: i64,
     end_checkpoint: i64,
-    mut conn: PgConnection,
+    conn: &mut PgConnection,
 ) -> Result<(Vec<OrderStep>, Option<NaiveDateTime>), HistoricOrderbookError> {
     let updates: Vec<OrderStep> = schema::order_updates::table
         .filter(schema::order_updates::pool_id.eq(&pool_id))
@@ -98,16 +218,12 @@ fn get_txs(
             schema::order_updates::checkpoint,
             schema::order_updates::is_bid,
         ))
-        .load::<OrderUpdateSummary>(&mut conn)
+        .load::<OrderUpdateSummary>(conn)
         .map_err(|e| HistoricOrderbookError::FailedReadingFromDatabase(e))?
         .into_iter()
         .map(|u| OrderStep {
             price: u.price,
-            quantity: if u.status == "Modified" {
-                u.original_quantity - u.quantity
-            } else {
-                u.quantity
-            },
+            quantity: update_quantity(u.status.as_str(), u.original_quantity, u.quantity),
             op: status_to_operation(u.status.as_str()),
             checkpoint: u.checkpoint,
             is_bid: u.is_bid,
@@ -125,7 +241,7 @@ fn get_txs(
             schema::order_fills::checkpoint,
             schema::order_fills::taker_is_bid,
         ))
-        .load::<OrderFillSummary>(&mut conn)
+        .load::<OrderFillSummary>(conn)
         .map_err(|e| HistoricOrderbookError::FailedReadingFromDatabase(e))?
         .into_iter()
         .map(|u| OrderStep {
@@ -202,37 +318,85 @@ pub fn get_latest_snapshot(
     }
 }
 
-pub fn get_historic_orderbook(
-    database_url: Url,
-    pool_id: &str,
-    end_checkpoint: i64,
-) -> Result<OrderbookSnapshot, HistoricOrderbookError> {
-    let mut conn = PgConnection::establish(&database_url.as_str()).expect("Error connecting to DB");
-
-    let last_snapshot =
-        get_latest_snapshot(&mut conn, pool_id).expect("failed getting last snapshot");
+/// Inserts `snapshots` in one transaction, either all of them are stored or none
+pub fn insert_snapshots(
+    conn: &mut PgConnection,
+    snapshots: &[OrderbookSnapshot],
+) -> Result<usize, diesel::result::Error> {
+    conn.transaction(|conn| {
+        let mut inserted = 0;
+        for snapshot in snapshots {
+            inserted += diesel::insert_into(schema::orderbook_snapshots::table)
+                .values(snapshot)
+                .execute(conn)?;
+        }
+        Ok(inserted)
+    })
+}
 
-    let (current_time, start_checkpoint, mut asks, mut bids) =
-        values_from_orderbook_option(last_snapshot);
+/// Deletes all but the `keep_last` latest snapshots of the pool, the latest one is kept even
+/// when `keep_last` is 0. Returns the number of deleted snapshots.
+pub fn prune_snapshots(
+    conn: &mut PgConnection,
+    target_pool_id: &str,
+    keep_last: usize,
+) -> Result<usize, diesel::result::Error> {
+    prune_snapshots_except(conn, target_pool_id, keep_last, None)
+}
 
-    if start_checkpoint >= end_checkpoint {
-        return Err(HistoricOrderbookError::StartGreaterThanEnd);
-    }
+/// [`prune_snapshots`] that also keeps the snapshot at `keep_checkpoint`, e.g. the one a live
+/// book started from
+pub fn prune_snapshots_except(
+    conn: &mut PgConnection,
+    target_pool_id: &str,
+    keep_last: usize,
+    keep_checkpoint: Option<i64>,
+) -> Result<usize, diesel::result::Error> {
+    // checkpoint of the oldest snapshot kept
+    let oldest_kept: Option<i64> = schema::orderbook_snapshots::table
+        .filter(schema::orderbook_snapshots::pool_id.eq(target_pool_id))
+        .select(schema::orderbook_snapshots::checkpoint)
+        .order(schema::orderbook_snapshots::checkpoint.desc())
+        .offset(keep_last.max(1) as i64 - 1)
+        .first(conn)
+        .optional()?;
+    let Some(oldest_kept) = oldest_kept else {
+        return Ok(0);
+    };
 
-    let (orders, ts) = get_txs(
-        pool_id,
-        start_checkpoint,
-        end_checkpoint,
-        PgConnection::establish(&database_url.as_str()).expect("Error connecting to DB"),
-    )?;
+    diesel::delete(
+        schema::orderbook_snapshots::table
+            .filter(schema::orderbook_snapshots::pool_id.eq(target_pool_id))
+            .filter(schema::orderbook_snapshots::checkpoint.lt(oldest_kept))
+            .filter(schema::orderbook_snapshots::checkpoint.ne_all(keep_checkpoint)),
+    )
+    .execute(conn)
+}
 
-    let timestamp = match ts {
-        Some(t) => t,
-        None => current_time,
-    };
+/// Applies `steps` to both sides in order, fails when the result has a negative
+/// price level or bids overlapping asks
+pub fn apply_steps(
+    asks: &mut HashMap<i64, i64>,
+    bids: &mut HashMap<i64, i64>,
+    steps: &[OrderStep],
+    pool_id: &str,
+) -> Result<(), HistoricOrderbookError> {
+    apply_steps_with_progress(asks, bids, steps, pool_id, None)
+}
 
-    for order in orders {
-        let side = if order.is_bid { &mut bids } else { &mut asks };
+/// [`apply_steps`] reporting every step to `progress`, steps must be in checkpoint order then
+pub fn apply_steps_with_progress(
+    asks: &mut HashMap<i64, i64>,
+    bids: &mut HashMap<i64, i64>,
+    steps: &[OrderStep],
+    pool_id: &str,
+    mut progress: Option<&mut ReplayProgress>,
+) -> Result<(), HistoricOrderbookError> {
+    for order in steps {
+        if let Some(progress) = progress.as_deref_mut() {
+            progress.record(order.checkpoint);
+        }
+        let side = if order.is_bid { &mut *bids } else { &mut *asks };
 
         side.entry(order.price)
             .and_modify(|q| match order.op {
@@ -264,11 +428,54 @@ pub fn get_historic_orderbook(
         return Err(HistoricOrderbookError::NegativeOrder);
     }
 
-    if has_overlap(&asks, &bids) {
+    if has_overlap(asks, bids) {
         warn!("Orderbook {} has overlap", pool_id);
         return Err(HistoricOrderbookError::Overlap);
     }
 
+    Ok(())
+}
+
+pub fn get_historic_orderbook(
+    database_url: Url,
+    pool_id: &str,
+    end_checkpoint: i64,
+) -> Result<OrderbookSnapshot, HistoricOrderbookError> {
+    let progress = ReplayProgress::new(pool_id, REPLAY_PROGRESS_CHECKPOINTS, end_checkpoint);
+    replay_historic_orderbook(database_url, pool_id, end_checkpoint, progress)
+}
+
+/// [`get_historic_orderbook`] reporting its progress to `progress`
+pub fn replay_historic_orderbook(
+    database_url: Url,
+    pool_id: &str,
+    end_checkpoint: i64,
+    mut progress: ReplayProgress,
+) -> Result<OrderbookSnapshot, HistoricOrderbookError> {
+    let mut conn = PgConnection::establish(&database_url.as_str()).expect("Error connecting to DB");
+
+    let last_snapshot =
+        get_latest_snapshot(&mut conn, pool_id).expect("failed getting last snapshot");
+
+    let (current_time, start_checkpoint, mut asks, mut bids) =
+        values_from_orderbook_option(last_snapshot);
+
+    if start_checkpoint >= end_checkpoint {
+        return Err(HistoricOrderbookError::StartGreaterThanEnd);
+    }
+
+    let (mut orders, ts) = get_txs(pool_id, start_checkpoint, end_checkpoint, &mut conn)?;
+
+    let timestamp = match ts {
+        Some(t) => t,
+        None => current_time,
+    };
+
+    // updates come before fills, the sums are the same in checkpoint order
+    orders.sort_by_key(|step| step.checkpoint);
+    apply_steps_with_progress(&mut asks, &mut bids, &orders, pool_id, Some(&mut progress))?;
+    progress.finish();
+
     let asks_serde = match serde_json::to_value(&asks) {
         Ok(v) => v,
         Err(_) => return Err(HistoricOrderbookError::FailedSerializeSide),
@@ -1,7 +1,7 @@
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, time::Duration};
 
 use anyhow::Context;
-use deeplook_indexer::{DeepbookEnv, MAINNET_REMOTE_STORE_URL};
+use deeplook_indexer::{DeepbookEnv, metrics::IngestMetrics};
 use prometheus::Registry;
 use sui_indexer_alt_framework::{
     Indexer, IndexerArgs, TaskArgs,
@@ -16,24 +16,33 @@ use tracing::info;
 use url::Url;
 
 use crate::{
-    OrderbookManagerMap, handlers::orderbook_order_update_handler::OrderbookOrderUpdateHandler,
+    SharedOrderbookManagerMap,
+    handlers::orderbook_order_update_handler::OrderbookOrderUpdateHandler,
+    metrics::OrderbookMetrics,
 };
 
 /// Takes orderbook managers, that are caught up, and keeps them
-/// up to date indexing checkpoints one at a time to make sure
-/// orderbooks are always correct.
+/// up to date applying checkpoints in order to make sure
+/// orderbooks are always correct. Books are stored as snapshots every
+/// `snapshot_interval`, so a restart doesn't replay from old snapshots, and
+/// only the `snapshot_keep_last` latest snapshots of each pool are kept when set.
 pub async fn keep_up(
     database_url: Url,
     metrics_address: SocketAddr,
-    orderbook_managers: Arc<OrderbookManagerMap>,
+    env: DeepbookEnv,
+    remote_store_url: Url,
+    orderbook_managers: SharedOrderbookManagerMap,
+    orderbook_metrics: &OrderbookMetrics,
     start: u64,
+    snapshot_interval: Duration,
+    snapshot_keep_last: Option<usize>,
 ) -> Result<(), anyhow::Error> {
     let registry = Registry::new_custom(Some("deeplook".into()), None)
         .context("Failed to create Prometheus registry.")?;
     let metrics = MetricsService::new(MetricsArgs { metrics_address }, registry.clone());
 
     // Prepare the store for the indexer
-    let store = Db::for_write(database_url, DbArgs::default())
+    let store = Db::for_write(database_url.clone(), DbArgs::default())
         .await
         .context("Failed to connect to database")?;
 
@@ -41,6 +50,7 @@ pub async fn keep_up(
         Some("deepbook_indexer_db"),
         store.clone(),
     )))?;
+    orderbook_metrics.register(&registry)?;
 
     let mut indexer = Indexer::new(
         store,
@@ -52,7 +62,7 @@ pub async fn keep_up(
         },
         ClientArgs {
             ingestion: IngestionClientArgs {
-                remote_store_url: Some(Url::parse(MAINNET_REMOTE_STORE_URL).unwrap()),
+                remote_store_url: Some(remote_store_url),
                 local_ingestion_path: None,
                 rpc_api_url: None,
                 rpc_username: None,
@@ -68,7 +78,12 @@ pub async fn keep_up(
 
     indexer
         .concurrent_pipeline(
-            OrderbookOrderUpdateHandler::new(DeepbookEnv::Mainnet, orderbook_managers),
+            OrderbookOrderUpdateHandler::new(
+                env,
+                orderbook_managers,
+                IngestMetrics::new(&registry),
+            )
+            .with_snapshots(database_url, snapshot_interval, snapshot_keep_last, start),
             Default::default(),
         )
         .await?;
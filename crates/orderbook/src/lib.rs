@@ -1,23 +1,65 @@
 use std::{
     collections::HashMap,
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, RwLock},
 };
 
 use sui_sdk::rpc_types::{SuiMoveStruct, SuiMoveValue, SuiParsedData};
+use tracing::warn;
 
 use crate::orderbook::OrderbookManager;
 
 pub mod catch_up;
-pub mod checkpoint;
 pub mod error;
 pub mod handlers;
 pub mod historic_orderbook;
 pub mod keep_up;
+pub mod log_sampler;
+pub mod metrics;
 pub mod orderbook;
+pub mod pool_watcher;
+pub mod readiness;
+pub mod replay_fixture;
+pub mod seed_pools;
 
 /// Get orderbook manager by pool_id or pool_name
 pub type OrderbookManagerMap = HashMap<String, Arc<Mutex<OrderbookManager>>>;
 
+/// Orderbook managers shared by the indexer handlers and the pool watcher,
+/// which registers pools created while the service runs
+pub type SharedOrderbookManagerMap = Arc<RwLock<OrderbookManagerMap>>;
+
+/// Inserts orderbook manager under both pool name and pool id, see [`insert_by_id_and_name`]
+pub fn register_orderbook_manager(map: &mut OrderbookManagerMap, ob_manager: OrderbookManager) {
+    let name = ob_manager.pool.pool_name.clone();
+    let id = ob_manager.pool.pool_id.clone();
+    if !insert_by_id_and_name(map, &id, &name, Arc::new(Mutex::new(ob_manager))) {
+        warn!(
+            "Pool name {} is already used by another pool, pool {} is reachable only by id",
+            name, id
+        );
+    }
+}
+
+/// Inserts `value` under the pool id, which is authoritative, and under the pool name unless
+/// the name belongs to another pool. Returns whether the name entry was inserted.
+pub fn insert_by_id_and_name<T>(
+    map: &mut HashMap<String, Arc<T>>,
+    pool_id: &str,
+    pool_name: &str,
+    value: Arc<T>,
+) -> bool {
+    let name_taken = match (map.get(pool_name), map.get(pool_id)) {
+        (Some(by_name), Some(by_id)) => !Arc::ptr_eq(by_name, by_id),
+        (Some(_), None) => true,
+        (None, _) => false,
+    };
+    if !name_taken {
+        map.insert(pool_name.to_string(), value.clone());
+    }
+    map.insert(pool_id.to_string(), value);
+    !name_taken
+}
+
 pub fn extract_timestamp(input: &Option<SuiParsedData>) -> Result<u64, ()> {
     if let Some(SuiParsedData::MoveObject(obj)) = input {
         if let SuiMoveStruct::WithFields(btree) = &obj.fields {
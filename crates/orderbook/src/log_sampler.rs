@@ -0,0 +1,35 @@
+use std::time::{Duration, Instant};
+
+/// Lets through at most one log line per `interval` and counts the suppressed ones.
+/// Zero interval logs everything.
+#[derive(Debug)]
+pub struct LogSampler {
+    interval: Duration,
+    last_logged: Option<Instant>,
+    suppressed: u64,
+}
+
+impl LogSampler {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_logged: None,
+            suppressed: 0,
+        }
+    }
+
+    /// Returns the number of lines suppressed since the last logged one
+    /// when the line should be logged, None when it should be dropped
+    pub fn sample(&mut self, now: Instant) -> Option<u64> {
+        let due = self
+            .last_logged
+            .is_none_or(|last| now.duration_since(last) >= self.interval);
+        if !due {
+            self.suppressed += 1;
+            return None;
+        }
+
+        self.last_logged = Some(now);
+        Some(std::mem::take(&mut self.suppressed))
+    }
+}
@@ -1,19 +1,26 @@
 use clap::Parser;
 use deeplook_indexer::DeepbookEnv;
-use deeplook_orderbook::OrderbookManagerMap;
 use deeplook_orderbook::catch_up::catch_up;
-use deeplook_orderbook::checkpoint::CheckpointDigest;
 use deeplook_orderbook::keep_up::keep_up;
-use deeplook_orderbook::orderbook::OrderbookManager;
-use deeplook_utils::cache::Cache;
-use deeplook_utils::logging::setup_logging;
+use deeplook_orderbook::metrics::OrderbookMetrics;
+use deeplook_orderbook::orderbook::{
+    DEFAULT_VALIDITY_LOG_INTERVAL, InitMode, ManagerConfig, OrderbookManager,
+};
+use deeplook_orderbook::pool_watcher::watch_new_pools;
+use deeplook_orderbook::readiness::{Readiness, serve_readiness};
+use deeplook_orderbook::{
+    OrderbookManagerMap, SharedOrderbookManagerMap, register_orderbook_manager,
+};
+use deeplook_utils::cache::{Cache, Codec, LATEST_TRADE_SIZE};
+use deeplook_utils::checkpoint::CheckpointDigest;
+use deeplook_utils::logging::setup_logging_with;
 use diesel::{Connection, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
-use tracing::{error, info};
+use tracing::{Level, error, info};
 
 use std::collections::HashMap;
 use std::net::SocketAddr;
-use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 use sui_sdk::SuiClientBuilder;
 use url::Url;
 
@@ -25,6 +32,9 @@ use deeplook_schema::schema::pools;
 struct Args {
     #[clap(env, long, default_value = "0.0.0.0:9184")]
     metrics_address: SocketAddr,
+    /// Address of `/readyz`, which returns 200 once all books are synced and caught up
+    #[clap(env, long, default_value = "0.0.0.0:9185")]
+    readyz_address: SocketAddr,
     #[clap(
         env,
         long,
@@ -38,27 +48,102 @@ struct Args {
     /// Deeplook environment, defaulted to SUI mainnet.
     #[clap(env, long)]
     env: DeepbookEnv,
+    /// Checkpoint store to index from, defaults to the store of `env`
+    #[clap(env, long)]
+    remote_store_url: Option<Url>,
+    /// Number of latest trades kept in Redis per pool
+    #[clap(env, long, default_value_t = LATEST_TRADE_SIZE)]
+    latest_trades_size: usize,
+    /// Encoding of orderbooks and trades in Redis, `json` or `msgpack`, must match the API
+    #[clap(env, long, default_value_t = Codec::Json)]
+    cache_codec: Codec,
+    /// Seconds between checks for pools created while running
+    #[clap(env, long, default_value_t = 60)]
+    pool_poll_interval_secs: u64,
+    /// Validity transitions of a pool are logged at most once per this many seconds
+    #[clap(env, long, default_value_t = DEFAULT_VALIDITY_LOG_INTERVAL.as_secs())]
+    validity_log_interval_secs: u64,
+    /// Seconds between snapshots of all orderbooks stored while keeping up
+    #[clap(env, long, default_value_t = 3600)]
+    snapshot_interval_secs: u64,
+    /// Snapshots kept per pool, older ones are deleted whenever snapshots are stored. The
+    /// snapshot a book started from is kept too, all snapshots are kept when unset.
+    #[clap(env, long)]
+    snapshot_keep_last: Option<usize>,
+    /// Drop subtractions from missing price levels with a warning instead of
+    /// creating negative levels
+    #[clap(env, long)]
+    strict_levels: bool,
+    /// Round readable prices to the pool's tick size and sizes to its lot size
+    #[clap(env, long)]
+    round_to_ticks: bool,
+    /// Resync books that stop being valid with the chain instead of publishing them, pools
+    /// whose resync fails aren't published until a later one succeeds
+    #[clap(env, long)]
+    strict: bool,
+    /// Publish only changed price levels to `orderbook_diff::{pool}` on every batch and store
+    /// the full books every this many seconds, full books are stored on every batch when unset
+    #[clap(env, long)]
+    full_publish_interval_secs: Option<u64>,
+    /// Start books from the latest stored snapshots or from the live onchain books
+    #[clap(env, long, value_enum, default_value_t = InitMode::Snapshot)]
+    init_mode: InitMode,
+    /// Lowest level logged, `RUST_LOG` directives take precedence
+    #[clap(env, long, default_value_t = Level::INFO)]
+    log_level: Level,
+    /// Log one JSON object per line instead of compact text
+    #[clap(env, long)]
+    log_json: bool,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
     let Args {
         metrics_address,
+        readyz_address,
         database_url,
         redis_url,
         rpc_url,
-        env: _,
+        env,
+        remote_store_url,
+        latest_trades_size,
+        cache_codec,
+        pool_poll_interval_secs,
+        validity_log_interval_secs,
+        snapshot_interval_secs,
+        snapshot_keep_last,
+        strict_levels,
+        round_to_ticks,
+        strict,
+        full_publish_interval_secs,
+        init_mode,
+        log_level,
+        log_json,
     } = Args::parse();
-    setup_logging();
+    let manager_config = ManagerConfig {
+        validity_log_interval: Duration::from_secs(validity_log_interval_secs),
+        strict_levels,
+        round_to_ticks,
+        strict,
+        full_publish_interval: full_publish_interval_secs.map(Duration::from_secs),
+        metrics: Arc::new(OrderbookMetrics::new()),
+    };
+    setup_logging_with(log_level, log_json);
+    let remote_store_url = remote_store_url.unwrap_or_else(|| env.remote_store_url());
+
+    let readiness = Readiness::new();
+    serve_readiness(readyz_address, readiness.clone()).await?;
 
     let mut db_connection =
         PgConnection::establish(&database_url.as_str()).expect("Error connecting to DB");
-    let sui_client = SuiClientBuilder::default()
-        .build(rpc_url.as_str())
-        .await
-        .expect("Failed building sui client");
-
-    let mut cache = Cache::new(redis_url);
+    let sui_client = Arc::new(
+        SuiClientBuilder::default()
+            .build(rpc_url.as_str())
+            .await
+            .expect("Failed building sui client"),
+    );
+
+    let mut cache = Cache::with_options(redis_url, latest_trades_size, cache_codec);
     let deleted = cache
         .delete_by_prefixes(&["orderbook::", "latest_trades::"])
         .map_err(|e| anyhow::anyhow!("failed clearing redis startup keys: {:?}", e))?;
@@ -67,9 +152,9 @@ async fn main() -> Result<(), anyhow::Error> {
     // if None index all pools, if Some index only pool names in the list
     let whitelisted_pools: Option<Vec<&'static str>> = None;
 
-    let pools = match whitelisted_pools {
+    let pools = match &whitelisted_pools {
         Some(white_list) => pools::table
-            .filter(pools::pool_name.eq_any(&white_list))
+            .filter(pools::pool_name.eq_any(white_list))
             .load::<Pool>(&mut db_connection)
             .expect("Failed getting pools from db"),
         None => pools::table
@@ -80,30 +165,44 @@ async fn main() -> Result<(), anyhow::Error> {
     let mut ob_manager_map: OrderbookManagerMap = HashMap::new();
 
     for pool in pools {
-        let name = pool.pool_name.to_string();
-        let id = pool.pool_id.to_string();
-        let ob_manager = OrderbookManager::new(
+        let pool_name = pool.pool_name.clone();
+        let mut ob_manager = OrderbookManager::new(
             pool,
-            sui_client.clone().into(),
+            sui_client.clone(),
             Mutex::new(cache.clone()),
             database_url.clone(),
-        );
-        let arc = Arc::new(Mutex::new(ob_manager));
-        ob_manager_map.insert(name, arc.clone());
-        ob_manager_map.insert(id, arc);
+            init_mode,
+        )
+        .map_err(|e| anyhow::anyhow!("failed creating orderbook of {}: {:?}", pool_name, e))?
+        .with_config(&manager_config);
+        // books not loaded from a snapshot start from the onchain book instead of replaying from genesis
+        if !ob_manager.from_snapshot() {
+            // the tip is read per pool right after its book, so catching up doesn't apply events
+            // between both reads twice
+            let checkpoint = ob_manager
+                .sync_with_chain_at_tip()
+                .await
+                .map_err(|e| anyhow::anyhow!("failed syncing {} with chain: {:?}", pool_name, e))?;
+            info!("Pool {} starts from checkpoint {}", pool_name, checkpoint);
+        }
+        register_orderbook_manager(&mut ob_manager_map, ob_manager);
     }
 
-    let orderbook_managers = Arc::new(ob_manager_map);
-
-    let latest_checkpoint = CheckpointDigest::get_sequence_number(Arc::new(sui_client))
+    // read after syncing, so catching up covers every pool's starting checkpoint
+    let latest_checkpoint = CheckpointDigest::get_sequence_number(sui_client.as_ref())
         .await
         .expect("failed getting latest checkpoint");
 
+    let orderbook_managers: SharedOrderbookManagerMap = Arc::new(RwLock::new(ob_manager_map));
+
     let start = Instant::now();
     let catch_up_result = catch_up(
         database_url.clone(),
         metrics_address,
+        env,
+        remote_store_url.clone(),
         orderbook_managers.clone(),
+        &manager_config.metrics,
         latest_checkpoint,
     )
     .await;
@@ -111,18 +210,38 @@ async fn main() -> Result<(), anyhow::Error> {
     let duration = start.elapsed();
 
     match catch_up_result {
-        Ok(_) => info!("Orderbooks caught up in {}s", duration.as_secs()),
+        Ok(_) => {
+            info!("Orderbooks caught up in {}s", duration.as_secs());
+            readiness.set_ready();
+        }
         Err(e) => {
             error!("catching up failed: {:#?}", e);
             return Err(e);
         }
     }
 
+    // whitelisted deployments track only the listed pools
+    if whitelisted_pools.is_none() {
+        tokio::spawn(watch_new_pools(
+            database_url.clone(),
+            sui_client.clone(),
+            cache.clone(),
+            orderbook_managers.clone(),
+            Duration::from_secs(pool_poll_interval_secs),
+            manager_config.clone(),
+        ));
+    }
+
     keep_up(
         database_url,
         metrics_address,
+        env,
+        remote_store_url,
         orderbook_managers,
+        &manager_config.metrics,
         latest_checkpoint + 1,
+        Duration::from_secs(snapshot_interval_secs),
+        snapshot_keep_last,
     )
     .await
 }
@@ -0,0 +1,63 @@
+use prometheus::{IntCounterVec, IntGaugeVec, Opts, Registry};
+
+/// Book anomalies of the orderbook managers. Catch up and keep up run their own
+/// registries, so the counters are created once and registered in each of them.
+#[derive(Clone)]
+pub struct OrderbookMetrics {
+    pub phantom_levels: IntCounterVec,
+    pub validity_transitions: IntCounterVec,
+    pub unhealthy_pools: IntGaugeVec,
+    pub replay_checkpoint: IntGaugeVec,
+}
+
+impl OrderbookMetrics {
+    pub fn new() -> Self {
+        Self {
+            phantom_levels: IntCounterVec::new(
+                Opts::new(
+                    "orderbook_phantom_levels",
+                    "Number of subtractions from price levels missing in the book, by pool",
+                ),
+                &["pool_name"],
+            )
+            .unwrap(),
+            validity_transitions: IntCounterVec::new(
+                Opts::new(
+                    "orderbook_validity_transitions",
+                    "Number of times a book became valid or stopped being valid, by pool",
+                ),
+                &["pool_name", "transition"],
+            )
+            .unwrap(),
+            unhealthy_pools: IntGaugeVec::new(
+                Opts::new(
+                    "orderbook_unhealthy",
+                    "1 when the invalid book of the pool couldn't be resynced and isn't published",
+                ),
+                &["pool_name"],
+            )
+            .unwrap(),
+            replay_checkpoint: IntGaugeVec::new(
+                Opts::new(
+                    "orderbook_replay_checkpoint",
+                    "Checkpoint reached while replaying stored events of the pool",
+                ),
+                &["pool_name"],
+            )
+            .unwrap(),
+        }
+    }
+
+    pub fn register(&self, registry: &Registry) -> Result<(), prometheus::Error> {
+        registry.register(Box::new(self.phantom_levels.clone()))?;
+        registry.register(Box::new(self.validity_transitions.clone()))?;
+        registry.register(Box::new(self.unhealthy_pools.clone()))?;
+        registry.register(Box::new(self.replay_checkpoint.clone()))
+    }
+}
+
+impl Default for OrderbookMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
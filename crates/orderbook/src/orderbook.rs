@@ -1,15 +1,21 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     str::FromStr,
     sync::{Arc, Mutex},
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use chrono::NaiveDateTime;
-use deeplook_schema::models::{OrderFill, OrderUpdate, OrderUpdateStatus, Pool};
-use deeplook_utils::cache::Cache;
-use diesel::{Connection, PgConnection};
-use serde::Serialize;
+use deeplook_schema::{
+    models::{LatestTrade, OrderFill, OrderUpdate, OrderbookSnapshot, Pool},
+    schema::orderbook_snapshots,
+};
+use deeplook_server::orderbook_diff::{
+    LevelChange, OrderbookDiff, orderbook_diff_book_key, orderbook_diff_channel,
+};
+use deeplook_server::server::price_scaling_factor;
+use deeplook_utils::{cache::Cache, rounding::to_readable};
+use diesel::{Connection, PgConnection, RunQueryDsl};
+use serde::{Serialize, Serializer, ser::SerializeStruct};
 use sui_sdk::{
     SuiClient,
     rpc_types::{SuiObjectData, SuiObjectDataOptions, SuiObjectResponse},
@@ -21,11 +27,15 @@ use sui_types::{
     transaction::{Argument, CallArg, Command, ObjectArg, ProgrammableMoveCall, TransactionKind},
     type_input::TypeInput,
 };
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use url::Url;
 
 use crate::{
-    error::DeepLookOrderbookError, extract_timestamp, historic_orderbook::get_latest_snapshot,
+    error::DeepLookOrderbookError,
+    extract_timestamp,
+    historic_orderbook::{Op, OrderStep, get_latest_snapshot},
+    log_sampler::LogSampler,
+    metrics::OrderbookMetrics,
 };
 
 pub const DEEPBOOK_PACKAGE_ID: &str =
@@ -34,6 +44,7 @@ pub const DEEP_TOKEN_PACKAGE_ID: &str =
     "0xdeeb7a4662eec9f2f3def03fb937a663dddaa2e215b8078a284d026b7946c270";
 pub const LEVEL2_MODULE: &str = "pool";
 pub const LEVEL2_FUNCTION: &str = "get_level2_ticks_from_mid";
+pub const DEFAULT_VALIDITY_LOG_INTERVAL: Duration = Duration::from_secs(60);
 
 #[derive(Debug, Serialize, Clone, Copy)]
 pub struct Order {
@@ -41,10 +52,62 @@ pub struct Order {
     pub price: i64,
 }
 
-#[derive(Debug, Serialize, Clone)]
+/// Price levels of both sides, price -> size
+#[derive(Debug, Clone, Default)]
 pub struct Orderbook {
-    pub asks: Vec<Order>,
-    pub bids: Vec<Order>,
+    pub asks: BTreeMap<i64, i64>,
+    pub bids: BTreeMap<i64, i64>,
+}
+
+impl Orderbook {
+    /// Asks from the lowest price
+    pub fn asks_iter(&self) -> impl Iterator<Item = Order> + '_ {
+        self.asks
+            .iter()
+            .map(|(&price, &size)| Order { price, size })
+    }
+
+    /// Bids from the highest price
+    pub fn bids_iter(&self) -> impl Iterator<Item = Order> + '_ {
+        self.bids
+            .iter()
+            .rev()
+            .map(|(&price, &size)| Order { price, size })
+    }
+
+    pub fn best_ask(&self) -> Option<Order> {
+        self.asks_iter().next()
+    }
+
+    pub fn best_bid(&self) -> Option<Order> {
+        self.bids_iter().next()
+    }
+
+    /// No negative sizes and the best ask above the best bid, a book with an empty side isn't crossed
+    pub fn is_valid(&self) -> bool {
+        let all_sizes_valid = self
+            .asks
+            .values()
+            .chain(self.bids.values())
+            .all(|&size| size >= 0);
+
+        let prices_ok = match (self.best_ask(), self.best_bid()) {
+            (Some(ask), Some(bid)) => ask.price > bid.price,
+            _ => true,
+        };
+
+        all_sizes_valid && prices_ok
+    }
+}
+
+// Serialized as sorted lists of orders, same as before the map representation
+impl Serialize for Orderbook {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Orderbook", 2)?;
+        state.serialize_field("asks", &self.asks_iter().collect::<Vec<_>>())?;
+        state.serialize_field("bids", &self.bids_iter().collect::<Vec<_>>())?;
+        state.end()
+    }
 }
 
 #[derive(Debug, Serialize, Clone, Copy)]
@@ -57,274 +120,344 @@ pub struct OrderReadable {
 pub struct OrderbookReadable {
     pub asks: Vec<OrderReadable>,
     pub bids: Vec<OrderReadable>,
+    /// Position in the diff sequence, only set when diffs are published
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
 }
 
-#[derive(Serialize)]
-struct Trade {
-    pub digest: String,
-    pub checkpoint: i64,
-    pub timestamp: NaiveDateTime,
-    pub price: i64,
-    pub base_quantity: i64,
-    pub quote_quantity: i64,
-    pub taker_is_bid: bool,
+/// Options shared by the orderbook managers of the service
+#[derive(Clone)]
+pub struct ManagerConfig {
+    pub validity_log_interval: Duration,
+    /// Refuse subtractions from missing price levels instead of creating negative ones
+    pub strict_levels: bool,
+    /// Round readable prices to the pool's tick size and sizes to its lot size
+    pub round_to_ticks: bool,
+    /// Resync invalid books with the chain instead of publishing them, see [`BookHealth`]
+    pub strict: bool,
+    /// Publish changed levels to `orderbook_diff::{pool}` on every batch and the full book
+    /// only this often, `None` publishes the full book on every batch
+    pub full_publish_interval: Option<Duration>,
+    pub metrics: Arc<OrderbookMetrics>,
 }
 
-impl From<&OrderFill> for Trade {
-    fn from(fill: &OrderFill) -> Self {
-        Trade {
-            digest: fill.digest.clone(),
-            checkpoint: fill.checkpoint,
-            timestamp: fill.timestamp,
-            price: fill.price,
-            base_quantity: fill.base_quantity,
-            quote_quantity: fill.quote_quantity,
-            taker_is_bid: fill.taker_is_bid,
+impl Default for ManagerConfig {
+    fn default() -> Self {
+        Self {
+            validity_log_interval: DEFAULT_VALIDITY_LOG_INTERVAL,
+            strict_levels: false,
+            round_to_ticks: false,
+            strict: false,
+            full_publish_interval: None,
+            metrics: Arc::new(OrderbookMetrics::new()),
         }
     }
 }
 
+/// Whether a manager publishes its book. Lenient managers publish every book, strict ones
+/// resync a book that stopped being valid and publish only valid books. A strict pool whose
+/// resync failed is unhealthy until a later resync succeeds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookHealth {
+    strict: bool,
+    healthy: bool,
+}
+
+impl BookHealth {
+    pub fn new(strict: bool) -> Self {
+        Self {
+            strict,
+            healthy: true,
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.healthy
+    }
+
+    pub fn needs_resync(&self, orderbook: &Orderbook) -> bool {
+        self.strict && !orderbook.is_valid()
+    }
+
+    pub fn should_publish(&self, orderbook: &Orderbook) -> bool {
+        !self.strict || (self.healthy && orderbook.is_valid())
+    }
+
+    pub fn resynced(&mut self) {
+        self.healthy = true;
+    }
+
+    pub fn resync_failed(&mut self) {
+        self.healthy = false;
+    }
+}
+
 pub struct OrderbookManager {
     pub pool: Pool,
     pub orderbook: Orderbook,
     pub initial_checkpoint: i64,
     pub sui_client: Arc<SuiClient>,
     cache: Mutex<Cache>,
-    price_factor: u64,
-    size_factor: u64,
+    price_factor: f64,
+    size_factor: f64,
+    validity_log_sampler: LogSampler,
+    strict_levels: bool,
+    round_to_ticks: bool,
+    health: BookHealth,
+    metrics: Arc<OrderbookMetrics>,
+    from_snapshot: bool,
+    diffs: Option<DiffPublisher>,
+}
+
+/// Diff sequence of a manager publishing diffs, see [`ManagerConfig::full_publish_interval`]
+struct DiffPublisher {
+    full_publish_interval: Duration,
+    epoch: i64,
+    seq: u64,
+    /// Levels as of the last publish
+    published: Orderbook,
+    last_full_publish: Option<Instant>,
+}
+
+impl DiffPublisher {
+    fn new(full_publish_interval: Duration) -> Self {
+        let epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or_default();
+        Self {
+            full_publish_interval,
+            epoch,
+            seq: 0,
+            published: Orderbook::default(),
+            last_full_publish: None,
+        }
+    }
+
+    fn full_publish_due(&self) -> bool {
+        self.last_full_publish
+            .is_none_or(|last| last.elapsed() >= self.full_publish_interval)
+    }
+}
+
+/// Levels of `new` whose size differs from `old`, removed levels have size 0
+pub fn level_changes(old: &BTreeMap<i64, i64>, new: &BTreeMap<i64, i64>) -> Vec<Order> {
+    let removed = old
+        .keys()
+        .filter(|price| !new.contains_key(price))
+        .map(|&price| Order { price, size: 0 });
+    let changed = new
+        .iter()
+        .filter(|(price, size)| old.get(price) != Some(size))
+        .map(|(&price, &size)| Order { price, size });
+    let mut changes: Vec<Order> = removed.chain(changed).collect();
+    changes.sort_by_key(|order| order.price);
+    changes
+}
+
+/// Where orderbook managers take their starting book from
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum InitMode {
+    /// Latest stored snapshot, replayed up to the tip
+    #[default]
+    Snapshot,
+    /// Live onchain book at the tip, stored snapshots are ignored
+    Onchain,
+}
+
+impl InitMode {
+    /// Stored book the manager starts from, `None` when it has to be synced with the chain
+    pub fn initial_orderbook(
+        self,
+        database_url: &Url,
+        pool_id: &str,
+    ) -> Result<Option<(Orderbook, i64)>, DeepLookOrderbookError> {
+        match self {
+            InitMode::Snapshot => {
+                let mut conn = PgConnection::establish(database_url.as_str())?;
+                load_initial_orderbook(&mut conn, pool_id)
+            }
+            InitMode::Onchain => Ok(None),
+        }
+    }
+}
+
+/// Latest stored snapshot of the pool and its checkpoint, `None` when the pool has none yet
+pub fn load_initial_orderbook(
+    conn: &mut PgConnection,
+    pool_id: &str,
+) -> Result<Option<(Orderbook, i64)>, DeepLookOrderbookError> {
+    let Some(snapshot) = get_latest_snapshot(conn, pool_id)? else {
+        return Ok(None);
+    };
+    let orderbook = Orderbook {
+        asks: serde_json::from_value(snapshot.asks)?,
+        bids: serde_json::from_value(snapshot.bids)?,
+    };
+    Ok(Some((orderbook, snapshot.checkpoint)))
 }
 
 impl OrderbookManager {
+    /// Starts from the latest snapshot of the pool in `Snapshot` mode. Pools without
+    /// a snapshot and all pools in `Onchain` mode start from an empty book at checkpoint 0,
+    /// `from_snapshot` is false and the book should be synced with the chain before use.
     pub fn new(
         pool: Pool,
         sui_client: Arc<SuiClient>,
         cache: Mutex<Cache>,
         database_url: Url,
-    ) -> Self {
-        let base_decimals = pool.base_asset_decimals as u32;
-        let quote_decimals = pool.quote_asset_decimals as u32;
-        let price_factor = (10u64).pow(9 - base_decimals + quote_decimals);
-        let size_factor = (10u64).pow(base_decimals);
-
-        let snapshot_option = get_latest_snapshot(
-            &mut PgConnection::establish(&database_url.as_str()).expect("Error connecting to DB"),
-            pool.pool_id.as_str(),
+        init_mode: InitMode,
+    ) -> Result<Self, DeepLookOrderbookError> {
+        let price_factor = price_scaling_factor(
+            pool.base_asset_decimals,
+            pool.quote_asset_decimals,
         )
-        // TODO: handle this better
-        .expect("failed getting snapshot");
-
-        let (asks, bids, initial_checkpoint) = match snapshot_option {
-            Some(snapshot) => {
-                let asks_map: HashMap<i64, i64> =
-                    serde_json::from_value(snapshot.asks).expect("failed parsing asks");
-                let bids_map: HashMap<i64, i64> =
-                    serde_json::from_value(snapshot.bids).expect("failed parsing bids");
-
-                let asks: Vec<Order> = asks_map
-                    .iter()
-                    .map(|(&price, &size)| Order {
-                        price: price,
-                        size: size,
-                    })
-                    .collect();
-                let bids: Vec<Order> = bids_map
-                    .iter()
-                    .map(|(&price, &size)| Order {
-                        price: price,
-                        size: size,
-                    })
-                    .collect();
-                (asks, bids, snapshot.checkpoint)
-            }
-            None => (vec![], vec![], 0),
-        };
+        .map_err(|e| {
+            DeepLookOrderbookError::InternalError(format!("{}: {}", pool.pool_name, e.message()))
+        })?;
+        let size_factor = 10f64.powi(pool.base_asset_decimals as i32);
+
+        let initial = init_mode.initial_orderbook(&database_url, &pool.pool_id)?;
+        let from_snapshot = initial.is_some();
+        if !from_snapshot && init_mode == InitMode::Snapshot {
+            warn!("No snapshot of pool {}, starting empty", pool.pool_name);
+        }
+        let (orderbook, initial_checkpoint) = initial.unwrap_or_default();
 
-        OrderbookManager {
+        Ok(OrderbookManager {
             pool,
             initial_checkpoint,
             sui_client,
-            orderbook: Orderbook { asks, bids },
+            orderbook,
             cache,
             price_factor,
             size_factor,
-        }
+            validity_log_sampler: LogSampler::new(DEFAULT_VALIDITY_LOG_INTERVAL),
+            strict_levels: false,
+            round_to_ticks: false,
+            health: BookHealth::new(false),
+            metrics: Arc::new(OrderbookMetrics::new()),
+            from_snapshot,
+            diffs: None,
+        })
     }
 
-    pub async fn get_onchain_orderbook(&self) -> Result<(Orderbook, u64), DeepLookOrderbookError> {
-        let pool_id = &self.pool.pool_id;
-        let pool_name = &self.pool.pool_name;
-        let base_asset_id = &self.pool.base_asset_id;
-        let quote_asset_id = &self.pool.quote_asset_id;
-        let ticks_from_mid = u64::MAX;
-        let pool_address = ObjectID::from_hex_literal(pool_id)?;
-
-        let mut ptb = ProgrammableTransactionBuilder::new();
-
-        let pool_object: SuiObjectResponse = self
-            .sui_client
-            .read_api()
-            .get_object_with_options(pool_address, SuiObjectDataOptions::full_content())
-            .await?;
-        let pool_data: &SuiObjectData =
-            pool_object
-                .data
-                .as_ref()
-                .ok_or(DeepLookOrderbookError::InternalError(format!(
-                    "Missing data in pool object response for '{}'",
-                    pool_name
-                )))?;
-        let pool_object_ref: ObjectRef = (pool_data.object_id, pool_data.version, pool_data.digest);
-
-        let pool_input = CallArg::Object(ObjectArg::ImmOrOwnedObject(pool_object_ref));
-        ptb.input(pool_input)?;
-
-        let input_argument = CallArg::Pure(bcs::to_bytes(&ticks_from_mid).map_err(|_| {
-            DeepLookOrderbookError::InternalError("Failed to serialize ticks_from_mid".to_string())
-        })?);
-        ptb.input(input_argument)?;
-
-        let sui_clock_object_id = ObjectID::from_hex_literal(
-            "0x0000000000000000000000000000000000000000000000000000000000000006",
-        )?;
-        let sui_clock_object: SuiObjectResponse = self
-            .sui_client
-            .read_api()
-            .get_object_with_options(sui_clock_object_id, SuiObjectDataOptions::full_content())
-            .await?;
-        let clock_data: &SuiObjectData =
-            sui_clock_object
-                .data
-                .as_ref()
-                .ok_or(DeepLookOrderbookError::InternalError(
-                    "Missing data in clock object response".to_string(),
-                ))?;
-
-        let sui_clock_object_ref: ObjectRef =
-            (clock_data.object_id, clock_data.version, clock_data.digest);
-
-        let clock_input = CallArg::Object(ObjectArg::ImmOrOwnedObject(sui_clock_object_ref));
-        ptb.input(clock_input)?;
-
-        let base_coin_type = parse_type_input(&base_asset_id)?;
-        let quote_coin_type = parse_type_input(&quote_asset_id)?;
-
-        let package = ObjectID::from_hex_literal(DEEPBOOK_PACKAGE_ID).map_err(|e| {
-            DeepLookOrderbookError::InternalError(format!("Invalid pool ID: {}", e))
-        })?;
-        let module = LEVEL2_MODULE.to_string();
-        let function = LEVEL2_FUNCTION.to_string();
-
-        ptb.command(Command::MoveCall(Box::new(ProgrammableMoveCall {
-            package,
-            module,
-            function,
-            type_arguments: vec![base_coin_type, quote_coin_type],
-            arguments: vec![Argument::Input(0), Argument::Input(1), Argument::Input(2)],
-        })));
-
-        let builder = ptb.finish();
-        let tx = TransactionKind::ProgrammableTransaction(builder);
-
-        let result = self
-            .sui_client
-            .read_api()
-            .dev_inspect_transaction_block(SuiAddress::default(), tx, None, None, None)
-            .await?;
-
-        let _sui_clock_ts =
-            extract_timestamp(&clock_data.content).expect("Failed to parse timestamp");
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards")
-            .as_millis() as u64;
+    /// Whether the book was loaded from a stored snapshot
+    pub fn from_snapshot(&self) -> bool {
+        self.from_snapshot
+    }
 
-        let mut binding: Vec<sui_sdk::rpc_types::SuiExecutionResult> =
-            result.results.ok_or(DeepLookOrderbookError::InternalError(
-                "No results from dev_inspect_transaction_block".to_string(),
-            ))?;
-        let bid_prices = &binding
-            .first_mut()
-            .ok_or(DeepLookOrderbookError::InternalError(
-                "No return values for bid prices".to_string(),
-            ))?
-            .return_values
-            .first_mut()
-            .ok_or(DeepLookOrderbookError::InternalError(
-                "No bid price data found".to_string(),
-            ))?
-            .0;
-        let bid_parsed_prices: Vec<u64> = bcs::from_bytes(bid_prices).map_err(|_| {
-            DeepLookOrderbookError::InternalError("Failed to deserialize bid prices".to_string())
-        })?;
-        let bid_quantities = &binding
-            .first_mut()
-            .ok_or(DeepLookOrderbookError::InternalError(
-                "No return values for bid quantities".to_string(),
-            ))?
-            .return_values
-            .get(1)
-            .ok_or(DeepLookOrderbookError::InternalError(
-                "No bid quantity data found".to_string(),
-            ))?
-            .0;
-        let bid_parsed_quantities: Vec<u64> = bcs::from_bytes(bid_quantities).map_err(|_| {
-            DeepLookOrderbookError::InternalError(
-                "Failed to deserialize bid quantities".to_string(),
-            )
-        })?;
+    pub fn with_config(self, config: &ManagerConfig) -> Self {
+        self.with_validity_log_interval(config.validity_log_interval)
+            .with_strict_levels(config.strict_levels)
+            .with_round_to_ticks(config.round_to_ticks)
+            .with_strict(config.strict)
+            .with_full_publish_interval(config.full_publish_interval)
+            .with_metrics(config.metrics.clone())
+    }
 
-        let ask_prices = &binding
-            .first_mut()
-            .ok_or(DeepLookOrderbookError::InternalError(
-                "No return values for ask prices".to_string(),
-            ))?
-            .return_values
-            .get(2)
-            .ok_or(DeepLookOrderbookError::InternalError(
-                "No ask price data found".to_string(),
-            ))?
-            .0;
-        let ask_parsed_prices: Vec<u64> = bcs::from_bytes(ask_prices).map_err(|_| {
-            DeepLookOrderbookError::InternalError("Failed to deserialize ask prices".to_string())
-        })?;
-        let ask_quantities = &binding
-            .first_mut()
-            .ok_or(DeepLookOrderbookError::InternalError(
-                "No return values for ask quantities".to_string(),
-            ))?
-            .return_values
-            .get(3)
-            .ok_or(DeepLookOrderbookError::InternalError(
-                "No ask quantity data found".to_string(),
-            ))?
-            .0;
-        let ask_parsed_quantities: Vec<u64> = bcs::from_bytes(ask_quantities).map_err(|_| {
-            DeepLookOrderbookError::InternalError(
-                "Failed to deserialize ask quantities".to_string(),
-            )
-        })?;
+    /// Validity transitions of a flapping book are logged at most once per `interval`
+    pub fn with_validity_log_interval(mut self, interval: Duration) -> Self {
+        self.validity_log_sampler = LogSampler::new(interval);
+        self
+    }
 
-        let bids: Vec<Order> = bid_parsed_prices
-            .into_iter()
-            .zip(bid_parsed_quantities.into_iter())
-            .take(ticks_from_mid as usize)
-            .map(|(price, quantity)| Order {
-                price: price as i64,
-                size: quantity as i64,
-            })
-            .collect();
+    /// In strict mode subtractions from missing price levels are dropped with a warning,
+    /// lenient mode keeps them as negative levels so out of order replays can still net out
+    pub fn with_strict_levels(mut self, strict_levels: bool) -> Self {
+        self.strict_levels = strict_levels;
+        self
+    }
 
-        let asks: Vec<Order> = ask_parsed_prices
-            .into_iter()
-            .zip(ask_parsed_quantities.into_iter())
-            .take(ticks_from_mid as usize)
-            .map(|(price, quantity)| Order {
-                price: price as i64,
-                size: quantity as i64,
-            })
-            .collect();
+    pub fn with_round_to_ticks(mut self, round_to_ticks: bool) -> Self {
+        self.round_to_ticks = round_to_ticks;
+        self
+    }
+
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.health = BookHealth::new(strict);
+        self
+    }
+
+    /// Publishes diffs and the full book only every `interval`, see [`ManagerConfig`]
+    pub fn with_full_publish_interval(mut self, interval: Option<Duration>) -> Self {
+        self.diffs = interval.map(DiffPublisher::new);
+        self
+    }
 
-        Ok((Orderbook { asks, bids }, now))
+    pub fn with_metrics(mut self, metrics: Arc<OrderbookMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    pub async fn get_onchain_orderbook(&self) -> Result<(Orderbook, u64), DeepLookOrderbookError> {
+        fetch_onchain_orderbook(&self.sui_client, &self.pool).await
+    }
+
+    /// Replaces the book with the current onchain state, labeled with the checkpoint read right
+    /// after it, see [`fetch_onchain_orderbook_at_tip`]. Returns that checkpoint, events up to
+    /// and including it are skipped afterwards.
+    pub async fn sync_with_chain_at_tip(&mut self) -> Result<i64, DeepLookOrderbookError> {
+        let (orderbook, checkpoint) =
+            fetch_onchain_orderbook_at_tip(&self.sui_client, &self.pool).await?;
+        self.replace_orderbook(orderbook, checkpoint);
+        Ok(checkpoint)
+    }
+
+    fn replace_orderbook(&mut self, orderbook: Orderbook, checkpoint: i64) {
+        self.orderbook = orderbook;
+        self.initial_checkpoint = checkpoint;
+        // a replaced book may differ everywhere, clients get it whole
+        if let Some(diffs) = self.diffs.as_mut() {
+            diffs.last_full_publish = None;
+        }
+        self.update_orderbook();
+    }
+
+    /// Whether the book stopped being valid and has to be resynced before it's published again
+    pub fn needs_resync(&self) -> bool {
+        self.health.needs_resync(&self.orderbook)
+    }
+
+    /// Replaces the invalid book, which became invalid at `checkpoint`, with the onchain book
+    /// read after it, labeled with the checkpoint it was read at so later events aren't applied
+    /// to it twice. When it couldn't be read the pool is marked unhealthy and its live book is
+    /// removed from Redis.
+    pub fn finish_resync(
+        &mut self,
+        onchain: Result<(Orderbook, i64), DeepLookOrderbookError>,
+        checkpoint: i64,
+    ) {
+        let unhealthy = self
+            .metrics
+            .unhealthy_pools
+            .with_label_values(&[self.pool.pool_name.as_str()]);
+        match onchain {
+            Ok((orderbook, onchain_checkpoint)) => {
+                let checkpoint = checkpoint.max(onchain_checkpoint);
+                info!(
+                    pool_name = %self.pool.pool_name,
+                    checkpoint,
+                    "Resynced invalid orderbook with chain"
+                );
+                self.health.resynced();
+                unhealthy.set(0);
+                self.replace_orderbook(orderbook, checkpoint);
+            }
+            Err(e) => {
+                error!(
+                    pool_name = %self.pool.pool_name,
+                    checkpoint,
+                    "Failed resyncing invalid orderbook, pool is not published: {:?}",
+                    e
+                );
+                self.health.resync_failed();
+                unhealthy.set(1);
+                self.remove_orderbook();
+            }
+        }
     }
 
     fn should_skip_order(&self, checkpoint: i64) -> bool {
@@ -338,55 +471,168 @@ impl OrderbookManager {
     }
 
     fn get_readable_orderbook(&self) -> OrderbookReadable {
-        let convert = |order: &Order| OrderReadable {
-            price: (order.price as f64) / (self.price_factor as f64),
-            size: (order.size as f64) / (self.size_factor as f64),
+        let (tick_size, lot_size) = if self.round_to_ticks {
+            (
+                Some(self.pool.tick_size as i64),
+                Some(self.pool.lot_size as i64),
+            )
+        } else {
+            (None, None)
+        };
+        let convert = |order: Order| OrderReadable {
+            price: to_readable(order.price, self.price_factor, tick_size),
+            size: to_readable(order.size, self.size_factor, lot_size),
         };
 
         OrderbookReadable {
-            asks: self.orderbook.asks.iter().map(convert).collect(),
-            bids: self.orderbook.bids.iter().map(convert).collect(),
+            asks: self.orderbook.asks_iter().map(convert).collect(),
+            bids: self.orderbook.bids_iter().map(convert).collect(),
+            epoch: None,
+            seq: None,
         }
     }
 
-    fn is_valid_orderbook(&self) -> bool {
-        // All sizes must be non-negative
-        let all_sizes_valid = self
-            .orderbook
-            .asks
-            .iter()
-            .chain(self.orderbook.bids.iter())
-            .all(|o| o.size >= 0);
+    fn readable_changes(&self, changes: Vec<Order>) -> Vec<LevelChange> {
+        let (tick_size, lot_size) = if self.round_to_ticks {
+            (
+                Some(self.pool.tick_size as i64),
+                Some(self.pool.lot_size as i64),
+            )
+        } else {
+            (None, None)
+        };
+        changes
+            .into_iter()
+            .map(|order| LevelChange {
+                price: to_readable(order.price, self.price_factor, tick_size),
+                new_size: to_readable(order.size, self.size_factor, lot_size),
+            })
+            .collect()
+    }
 
-        // Get lowest ask price
-        let min_ask = self.orderbook.asks.iter().map(|o| o.price).min();
-        // Get highest bid price
-        let max_bid = self.orderbook.bids.iter().map(|o| o.price).max();
+    /// Stores the book as the snapshot at `checkpoint`, which must be the last checkpoint
+    /// applied to it. Invalid books and checkpoints before the initial one are skipped,
+    /// returns whether the snapshot was written.
+    pub fn persist_snapshot(
+        &self,
+        conn: &mut PgConnection,
+        checkpoint: i64,
+    ) -> Result<bool, DeepLookOrderbookError> {
+        if checkpoint < self.initial_checkpoint || !self.is_valid_orderbook() {
+            return Ok(false);
+        }
 
-        let prices_ok = match (min_ask, max_bid) {
-            (Some(ask), Some(bid)) => ask > bid,
-            _ => true, // Valid if either side is empty
+        let snapshot = OrderbookSnapshot {
+            checkpoint,
+            pool_id: self.pool.pool_id.clone(),
+            asks: serde_json::to_value(&self.orderbook.asks)?,
+            bids: serde_json::to_value(&self.orderbook.bids)?,
+            timestamp: chrono::Utc::now().naive_utc(),
         };
+        diesel::insert_into(orderbook_snapshots::table)
+            .values(&snapshot)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
 
-        all_sizes_valid && prices_ok
+        Ok(true)
+    }
+
+    fn is_valid_orderbook(&self) -> bool {
+        self.orderbook.is_valid()
     }
 
     fn remove_zero_orders(&mut self) {
-        self.orderbook.asks.retain(|o| o.size != 0);
-        self.orderbook.bids.retain(|o| o.size != 0);
+        self.orderbook.asks.retain(|_, size| *size != 0);
+        self.orderbook.bids.retain(|_, size| *size != 0);
     }
 
-    fn update_orderbook(&self) {
-        let key = format!("orderbook::{}", self.pool.pool_name);
+    fn update_orderbook(&mut self) {
+        if self.diffs.is_some() {
+            self.publish_diff();
+            return;
+        }
         let ob = self.get_readable_orderbook();
+        self.set_orderbook(&ob);
+    }
+
+    fn set_orderbook(&self, ob: &OrderbookReadable) {
+        self.set_book(&format!("orderbook::{}", self.pool.pool_name), ob);
+    }
+
+    fn set_book(&self, key: &str, ob: &OrderbookReadable) {
         if let Ok(mut locked_cache) = self.cache.lock() {
-            match locked_cache.set(&key, &ob) {
+            match locked_cache.set(key, ob) {
                 Ok(()) => info!("redis value set {}", key),
                 Err(e) => error!("redis failed setting value {} {:?}", key, e),
             }
         }
     }
 
+    /// Publishes the levels changed since the last publish. The full book with the diff's
+    /// `seq` is stored first, so it never lags behind the diffs: under
+    /// [`orderbook_diff_book_key`] every time, and as `orderbook::{pool}` when that's due.
+    fn publish_diff(&mut self) {
+        let Some(diffs) = self.diffs.as_ref() else {
+            return;
+        };
+        let asks = level_changes(&diffs.published.asks, &self.orderbook.asks);
+        let bids = level_changes(&diffs.published.bids, &self.orderbook.bids);
+        let full_publish_due = diffs.full_publish_due();
+        if asks.is_empty() && bids.is_empty() && !full_publish_due {
+            return;
+        }
+        let (epoch, seq) = (diffs.epoch, diffs.seq + 1);
+
+        let mut ob = self.get_readable_orderbook();
+        ob.epoch = Some(epoch);
+        ob.seq = Some(seq);
+        self.set_book(&orderbook_diff_book_key(&self.pool.pool_name), &ob);
+        if full_publish_due {
+            self.set_orderbook(&ob);
+        }
+        if !asks.is_empty() || !bids.is_empty() {
+            let diff = OrderbookDiff {
+                epoch,
+                seq,
+                asks: self.readable_changes(asks),
+                bids: self.readable_changes(bids),
+            };
+            let channel = orderbook_diff_channel(&self.pool.pool_name);
+            if let Ok(mut locked_cache) = self.cache.lock() {
+                if let Err(e) = locked_cache.publish(&channel, &diff) {
+                    error!("redis failed publishing {} {:?}", channel, e);
+                }
+            }
+        }
+
+        let orderbook = self.orderbook.clone();
+        if let Some(diffs) = self.diffs.as_mut() {
+            diffs.seq = seq;
+            diffs.published = orderbook;
+            if full_publish_due {
+                diffs.last_full_publish = Some(Instant::now());
+            }
+        }
+    }
+
+    fn remove_orderbook(&mut self) {
+        // the next publish stores the book again
+        if let Some(diffs) = self.diffs.as_mut() {
+            diffs.last_full_publish = None;
+        }
+        let keys = [
+            format!("orderbook::{}", self.pool.pool_name),
+            orderbook_diff_book_key(&self.pool.pool_name),
+        ];
+        if let Ok(mut locked_cache) = self.cache.lock() {
+            for key in keys {
+                if let Err(e) = locked_cache.del(&key) {
+                    error!("redis failed deleting value {} {:?}", key, e);
+                }
+            }
+        }
+    }
+
     fn add_order(&mut self, price: i64, size: i64, is_bid: bool) {
         // Decide which side of the book we are working with
         let side = if is_bid {
@@ -395,15 +641,10 @@ impl OrderbookManager {
             &mut self.orderbook.asks
         };
 
-        // Try to find an existing order at the same price level
-        if let Some(order) = side.iter_mut().find(|o| o.price == price) {
-            order.size += size;
-        } else {
-            side.push(Order { price, size });
-        }
+        *side.entry(price).or_insert(0) += size;
     }
 
-    fn subtract_order(&mut self, price: i64, size: i64, is_bid: bool) {
+    fn subtract_order(&mut self, price: i64, size: i64, is_bid: bool, checkpoint: i64) {
         // Decide which side of the book we are working with
         let side = if is_bid {
             &mut self.orderbook.bids
@@ -411,12 +652,26 @@ impl OrderbookManager {
             &mut self.orderbook.asks
         };
 
-        // Try to find an existing order at the same price level
-        if let Some(order) = side.iter_mut().find(|o| o.price == price) {
-            order.size -= size;
-        } else {
-            side.push(Order { price, size: -size });
+        if !side.contains_key(&price) {
+            self.metrics
+                .phantom_levels
+                .with_label_values(&[self.pool.pool_name.as_str()])
+                .inc();
+            if self.strict_levels {
+                warn!(
+                    "Subtracting {} from missing {} level, pool {}, price {}, checkpoint {}",
+                    size,
+                    if is_bid { "bid" } else { "ask" },
+                    self.pool.pool_name,
+                    price,
+                    checkpoint
+                );
+                return;
+            }
         }
+
+        // Missing price level goes negative, caught by `is_valid_orderbook`
+        *side.entry(price).or_insert(0) -= size;
     }
 
     pub fn handle_fill(&mut self, order: OrderFill) {
@@ -424,31 +679,28 @@ impl OrderbookManager {
             return;
         }
 
-        self.subtract_order(order.price, order.base_quantity, !order.taker_is_bid);
+        self.apply_step(&OrderStep::from_fill(&order));
     }
 
     pub fn handle_update(&mut self, order: OrderUpdate) {
         if self.should_skip_order(order.checkpoint) {
             return;
         }
-        match order.status {
-            OrderUpdateStatus::Placed => {
-                self.add_order(order.price, order.quantity, order.is_bid);
-            }
-            OrderUpdateStatus::Canceled => {
-                self.subtract_order(order.price, order.quantity, order.is_bid);
-            }
-            OrderUpdateStatus::Expired => {
-                self.subtract_order(order.price, order.quantity, order.is_bid);
-            }
-            OrderUpdateStatus::Modified => {
-                let to_sub = order.original_quantity - order.quantity;
-                self.subtract_order(order.price, to_sub, order.is_bid)
+
+        self.apply_step(&OrderStep::from_update(&order));
+    }
+
+    /// Applies events the way historic snapshots replay them, so both books agree
+    fn apply_step(&mut self, step: &OrderStep) {
+        match step.op {
+            Op::Add => self.add_order(step.price, step.quantity, step.is_bid),
+            Op::Subtract => {
+                self.subtract_order(step.price, step.quantity, step.is_bid, step.checkpoint)
             }
         }
     }
 
-    fn store_latest_trade(&mut self, trade: Trade) {
+    fn store_latest_trade(&mut self, trade: LatestTrade) {
         let key = format!("latest_trades::{}", self.pool.pool_name);
         if let Ok(mut locked_cache) = self.cache.lock() {
             match locked_cache.push(&key, &trade) {
@@ -458,53 +710,296 @@ impl OrderbookManager {
         }
     }
 
-    pub fn handle_batch(&mut self, updates: Vec<OrderUpdate>, fills: Vec<OrderFill>) {
-        let updates_count = updates.len();
-        let fills_count = fills.len();
-        let checkpoint_maybe = match (updates.first(), fills.first()) {
-            (Some(update), _) => Some(update.checkpoint),
-            (_, Some(fill)) => Some(fill.checkpoint),
-            (None, None) => None,
-        };
+    /// Applies the events of a checkpoint, they are expected in emission order, see [`interleave`]
+    pub fn handle_batch(&mut self, events: Vec<BookEvent>) {
+        let fills_count = events
+            .iter()
+            .filter(|event| matches!(event, BookEvent::Fill(_)))
+            .count();
+        let updates_count = events.len() - fills_count;
+        let checkpoint_maybe = events.first().map(BookEvent::checkpoint);
         let mut is_valid_before = true;
 
         if !self.is_valid_orderbook() {
             is_valid_before = false;
         }
 
-        for update in updates {
-            self.handle_update(update);
-        }
-
-        for fill in fills {
-            self.store_latest_trade(Trade::from(&fill));
-            self.handle_fill(fill);
+        for event in events {
+            match event {
+                BookEvent::Update(update) => self.handle_update(update),
+                BookEvent::Fill(fill) => {
+                    self.store_latest_trade(LatestTrade::from(&fill));
+                    self.handle_fill(fill);
+                }
+            }
         }
 
         let is_valid_after = self.is_valid_orderbook();
 
-        // orderbook stopped being valid after this update
-        if is_valid_before && !is_valid_after {
-            info!(
-                "Orderbook STOPPED BEING VALID: pool {}, checkpoint {:?}, {} updates, {} fills",
-                self.pool.pool_name, checkpoint_maybe, updates_count, fills_count
-            );
-        }
-        // orderbook became valid after this update
-        if !is_valid_before && is_valid_after {
-            info!(
-                "Orderbook BECAME VALID: pool {}, checkpoint {:?}, {} updates, {} fills",
-                self.pool.pool_name, checkpoint_maybe, updates_count, fills_count
-            );
+        if is_valid_before != is_valid_after {
+            let transition = if is_valid_after {
+                "became_valid"
+            } else {
+                "stopped_valid"
+            };
+            self.metrics
+                .validity_transitions
+                .with_label_values(&[self.pool.pool_name.as_str(), transition])
+                .inc();
+
+            if let Some(suppressed) = self.validity_log_sampler.sample(Instant::now()) {
+                warn!(
+                    pool_name = %self.pool.pool_name,
+                    transition,
+                    checkpoint = ?checkpoint_maybe,
+                    updates = updates_count,
+                    fills = fills_count,
+                    suppressed,
+                    "Orderbook validity changed"
+                );
+            }
         }
 
         self.remove_zero_orders();
 
-        // upload new state to Redis
-        self.update_orderbook();
+        // upload new state to Redis, strict managers hold invalid books back until resynced
+        if self.health.should_publish(&self.orderbook) {
+            self.update_orderbook();
+        }
+    }
+}
+
+/// Order update or fill of a pool
+#[derive(Debug)]
+pub enum BookEvent {
+    Update(OrderUpdate),
+    Fill(OrderFill),
+}
+
+impl BookEvent {
+    pub fn checkpoint(&self) -> i64 {
+        match self {
+            BookEvent::Update(update) => update.checkpoint,
+            BookEvent::Fill(fill) => fill.checkpoint,
+        }
+    }
+
+    fn digests(&self) -> (&str, &str) {
+        match self {
+            BookEvent::Update(update) => (&update.event_digest, &update.digest),
+            BookEvent::Fill(fill) => (&fill.event_digest, &fill.digest),
+        }
     }
 }
 
+/// Index of the event within its transaction, the event digest is the transaction digest
+/// followed by that index
+pub fn event_index(event_digest: &str, digest: &str) -> Option<usize> {
+    event_digest.strip_prefix(digest)?.parse().ok()
+}
+
+/// Merges updates and fills of a checkpoint into the order their events were emitted in.
+/// `tx_order` maps transaction digests to their position in the checkpoint, events of unknown
+/// transactions or without an index keep their relative order after the known ones.
+pub fn interleave(
+    updates: Vec<OrderUpdate>,
+    fills: Vec<OrderFill>,
+    tx_order: &HashMap<String, usize>,
+) -> Vec<BookEvent> {
+    let mut events: Vec<BookEvent> = updates
+        .into_iter()
+        .map(BookEvent::Update)
+        .chain(fills.into_iter().map(BookEvent::Fill))
+        .collect();
+    events.sort_by_key(|event| {
+        let (event_digest, digest) = event.digests();
+        let tx_position = tx_order.get(digest).copied().unwrap_or(usize::MAX);
+        let index = event_index(event_digest, digest).unwrap_or(usize::MAX);
+        (tx_position, index)
+    });
+    events
+}
+
+/// Full onchain orderbook of the pool and the latest checkpoint read right after it. The book
+/// holds every event up to that checkpoint, except ones executed between both reads.
+pub async fn fetch_onchain_orderbook_at_tip(
+    sui_client: &SuiClient,
+    pool: &Pool,
+) -> Result<(Orderbook, i64), DeepLookOrderbookError> {
+    let (orderbook, _) = fetch_onchain_orderbook(sui_client, pool).await?;
+    let checkpoint = sui_client
+        .read_api()
+        .get_latest_checkpoint_sequence_number()
+        .await?;
+    Ok((orderbook, checkpoint as i64))
+}
+
+/// Full onchain orderbook of the pool and the time it was read at
+pub async fn fetch_onchain_orderbook(
+    sui_client: &SuiClient,
+    pool: &Pool,
+) -> Result<(Orderbook, u64), DeepLookOrderbookError> {
+    let pool_id = &pool.pool_id;
+    let pool_name = &pool.pool_name;
+    let base_asset_id = &pool.base_asset_id;
+    let quote_asset_id = &pool.quote_asset_id;
+    let ticks_from_mid = u64::MAX;
+    let pool_address = ObjectID::from_hex_literal(pool_id)?;
+
+    let mut ptb = ProgrammableTransactionBuilder::new();
+
+    let pool_object: SuiObjectResponse = sui_client
+        .read_api()
+        .get_object_with_options(pool_address, SuiObjectDataOptions::full_content())
+        .await?;
+    let pool_data: &SuiObjectData =
+        pool_object
+            .data
+            .as_ref()
+            .ok_or(DeepLookOrderbookError::InternalError(format!(
+                "Missing data in pool object response for '{}'",
+                pool_name
+            )))?;
+    let pool_object_ref: ObjectRef = (pool_data.object_id, pool_data.version, pool_data.digest);
+
+    let pool_input = CallArg::Object(ObjectArg::ImmOrOwnedObject(pool_object_ref));
+    ptb.input(pool_input)?;
+
+    let input_argument = CallArg::Pure(bcs::to_bytes(&ticks_from_mid).map_err(|_| {
+        DeepLookOrderbookError::InternalError("Failed to serialize ticks_from_mid".to_string())
+    })?);
+    ptb.input(input_argument)?;
+
+    let sui_clock_object_id = ObjectID::from_hex_literal(
+        "0x0000000000000000000000000000000000000000000000000000000000000006",
+    )?;
+    let sui_clock_object: SuiObjectResponse = sui_client
+        .read_api()
+        .get_object_with_options(sui_clock_object_id, SuiObjectDataOptions::full_content())
+        .await?;
+    let clock_data: &SuiObjectData =
+        sui_clock_object
+            .data
+            .as_ref()
+            .ok_or(DeepLookOrderbookError::InternalError(
+                "Missing data in clock object response".to_string(),
+            ))?;
+
+    let sui_clock_object_ref: ObjectRef =
+        (clock_data.object_id, clock_data.version, clock_data.digest);
+
+    let clock_input = CallArg::Object(ObjectArg::ImmOrOwnedObject(sui_clock_object_ref));
+    ptb.input(clock_input)?;
+
+    let base_coin_type = parse_type_input(&base_asset_id)?;
+    let quote_coin_type = parse_type_input(&quote_asset_id)?;
+
+    let package = ObjectID::from_hex_literal(DEEPBOOK_PACKAGE_ID)
+        .map_err(|e| DeepLookOrderbookError::InternalError(format!("Invalid pool ID: {}", e)))?;
+    let module = LEVEL2_MODULE.to_string();
+    let function = LEVEL2_FUNCTION.to_string();
+
+    ptb.command(Command::MoveCall(Box::new(ProgrammableMoveCall {
+        package,
+        module,
+        function,
+        type_arguments: vec![base_coin_type, quote_coin_type],
+        arguments: vec![Argument::Input(0), Argument::Input(1), Argument::Input(2)],
+    })));
+
+    let builder = ptb.finish();
+    let tx = TransactionKind::ProgrammableTransaction(builder);
+
+    let result = sui_client
+        .read_api()
+        .dev_inspect_transaction_block(SuiAddress::default(), tx, None, None, None)
+        .await?;
+
+    let _sui_clock_ts = extract_timestamp(&clock_data.content).expect("Failed to parse timestamp");
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_millis() as u64;
+
+    let mut binding: Vec<sui_sdk::rpc_types::SuiExecutionResult> =
+        result.results.ok_or(DeepLookOrderbookError::InternalError(
+            "No results from dev_inspect_transaction_block".to_string(),
+        ))?;
+    let bid_prices = &binding
+        .first_mut()
+        .ok_or(DeepLookOrderbookError::InternalError(
+            "No return values for bid prices".to_string(),
+        ))?
+        .return_values
+        .first_mut()
+        .ok_or(DeepLookOrderbookError::InternalError(
+            "No bid price data found".to_string(),
+        ))?
+        .0;
+    let bid_parsed_prices: Vec<u64> = bcs::from_bytes(bid_prices).map_err(|_| {
+        DeepLookOrderbookError::InternalError("Failed to deserialize bid prices".to_string())
+    })?;
+    let bid_quantities = &binding
+        .first_mut()
+        .ok_or(DeepLookOrderbookError::InternalError(
+            "No return values for bid quantities".to_string(),
+        ))?
+        .return_values
+        .get(1)
+        .ok_or(DeepLookOrderbookError::InternalError(
+            "No bid quantity data found".to_string(),
+        ))?
+        .0;
+    let bid_parsed_quantities: Vec<u64> = bcs::from_bytes(bid_quantities).map_err(|_| {
+        DeepLookOrderbookError::InternalError("Failed to deserialize bid quantities".to_string())
+    })?;
+
+    let ask_prices = &binding
+        .first_mut()
+        .ok_or(DeepLookOrderbookError::InternalError(
+            "No return values for ask prices".to_string(),
+        ))?
+        .return_values
+        .get(2)
+        .ok_or(DeepLookOrderbookError::InternalError(
+            "No ask price data found".to_string(),
+        ))?
+        .0;
+    let ask_parsed_prices: Vec<u64> = bcs::from_bytes(ask_prices).map_err(|_| {
+        DeepLookOrderbookError::InternalError("Failed to deserialize ask prices".to_string())
+    })?;
+    let ask_quantities = &binding
+        .first_mut()
+        .ok_or(DeepLookOrderbookError::InternalError(
+            "No return values for ask quantities".to_string(),
+        ))?
+        .return_values
+        .get(3)
+        .ok_or(DeepLookOrderbookError::InternalError(
+            "No ask quantity data found".to_string(),
+        ))?
+        .0;
+    let ask_parsed_quantities: Vec<u64> = bcs::from_bytes(ask_quantities).map_err(|_| {
+        DeepLookOrderbookError::InternalError("Failed to deserialize ask quantities".to_string())
+    })?;
+
+    let bids: BTreeMap<i64, i64> = bid_parsed_prices
+        .into_iter()
+        .zip(bid_parsed_quantities.into_iter())
+        .take(ticks_from_mid as usize)
+        .map(|(price, quantity)| (price as i64, quantity as i64))
+        .collect();
+
+    let asks: BTreeMap<i64, i64> = ask_parsed_prices
+        .into_iter()
+        .zip(ask_parsed_quantities.into_iter())
+        .take(ticks_from_mid as usize)
+        .map(|(price, quantity)| (price as i64, quantity as i64))
+        .collect();
+
+    Ok((Orderbook { asks, bids }, now))
+}
+
 pub fn parse_type_input(type_str: &str) -> Result<TypeInput, DeepLookOrderbookError> {
     let type_tag = TypeTag::from_str(type_str)?;
     Ok(TypeInput::from(type_tag))
@@ -0,0 +1,94 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use deeplook_schema::{models::Pool, schema::pools};
+use deeplook_utils::cache::Cache;
+use diesel::{Connection, PgConnection, RunQueryDsl};
+use sui_sdk::SuiClient;
+use tracing::{error, info};
+use url::Url;
+
+use crate::{
+    SharedOrderbookManagerMap,
+    error::DeepLookOrderbookError,
+    orderbook::{InitMode, ManagerConfig, OrderbookManager},
+    register_orderbook_manager,
+};
+
+/// Periodically polls the pools table and starts tracking pools
+/// created after the service started.
+pub async fn watch_new_pools(
+    database_url: Url,
+    sui_client: Arc<SuiClient>,
+    cache: Cache,
+    orderbook_managers: SharedOrderbookManagerMap,
+    poll_interval: Duration,
+    manager_config: ManagerConfig,
+) {
+    let mut interval = tokio::time::interval(poll_interval);
+    loop {
+        interval.tick().await;
+        if let Err(e) = register_new_pools(
+            &database_url,
+            sui_client.clone(),
+            &cache,
+            &orderbook_managers,
+            &manager_config,
+        )
+        .await
+        {
+            error!("failed registering new pools: {:?}", e);
+        }
+    }
+}
+
+/// Registers orderbook managers for pools missing in the map. New orderbooks
+/// start from the onchain state at the latest checkpoint, since keep up
+/// is already past their earlier events.
+pub async fn register_new_pools(
+    database_url: &Url,
+    sui_client: Arc<SuiClient>,
+    cache: &Cache,
+    orderbook_managers: &SharedOrderbookManagerMap,
+    manager_config: &ManagerConfig,
+) -> Result<usize, DeepLookOrderbookError> {
+    let mut db_connection = PgConnection::establish(database_url.as_str())?;
+    let pools = pools::table.load::<Pool>(&mut db_connection)?;
+
+    let new_pools: Vec<Pool> = {
+        let managers = orderbook_managers.read().map_err(|_| {
+            DeepLookOrderbookError::InternalError("orderbook managers lock poisoned".to_string())
+        })?;
+        pools
+            .into_iter()
+            .filter(|pool| !managers.contains_key(&pool.pool_id))
+            .collect()
+    };
+
+    let registered = new_pools.len();
+    for pool in new_pools {
+        let mut ob_manager = OrderbookManager::new(
+            pool,
+            sui_client.clone(),
+            Mutex::new(cache.clone()),
+            database_url.clone(),
+            InitMode::Onchain,
+        )?
+        .with_config(manager_config);
+        // the book is read before the tip, so keep up doesn't apply events between both twice
+        let checkpoint = ob_manager.sync_with_chain_at_tip().await?;
+
+        info!(
+            "Registered new pool {} from checkpoint {}",
+            ob_manager.pool.pool_name, checkpoint
+        );
+        let mut managers = orderbook_managers.write().map_err(|_| {
+            DeepLookOrderbookError::InternalError("orderbook managers lock poisoned".to_string())
+        })?;
+        register_orderbook_manager(&mut managers, ob_manager);
+    }
+
+    Ok(registered)
+}
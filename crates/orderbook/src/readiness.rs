@@ -0,0 +1,62 @@
+use std::{
+    net::SocketAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use axum::{Router, extract::State, http::StatusCode, routing::get};
+use tokio::{net::TcpListener, task::JoinHandle};
+use tracing::{error, info};
+
+pub const READYZ_PATH: &str = "/readyz";
+
+/// Whether every orderbook is synced and caught up to the chain, shared with `/readyz`
+#[derive(Clone, Default)]
+pub struct Readiness(Arc<AtomicBool>);
+
+impl Readiness {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_ready(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// Router answering `/readyz` with 200 once ready and 503 before
+pub fn readiness_router(readiness: Readiness) -> Router {
+    Router::new()
+        .route(READYZ_PATH, get(readyz))
+        .with_state(readiness)
+}
+
+async fn readyz(State(readiness): State<Readiness>) -> StatusCode {
+    if readiness.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+/// Serves `/readyz` on `address` in the background. It can't share the metrics port, since
+/// the metrics service only serves `/metrics` and isn't started until books caught up.
+pub async fn serve_readiness(
+    address: SocketAddr,
+    readiness: Readiness,
+) -> Result<JoinHandle<()>, anyhow::Error> {
+    let listener = TcpListener::bind(address).await?;
+    info!("Serving {} on {}", READYZ_PATH, address);
+
+    Ok(tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, readiness_router(readiness)).await {
+            error!("readiness server failed: {:?}", e);
+        }
+    }))
+}
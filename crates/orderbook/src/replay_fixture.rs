@@ -0,0 +1,310 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::Path,
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use deeplook_schema::{
+    models::{OrderFill, OrderUpdate, OrderbookSnapshot, Pool},
+    schema,
+};
+use diesel::{
+    Connection, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper, dsl::min,
+};
+use serde::{Deserialize, Serialize};
+use sui_sdk::{SuiClient, rpc_types::CheckpointId};
+use tracing::info;
+use url::Url;
+
+use crate::{
+    historic_orderbook::{get_historic_orderbook, get_latest_snapshot},
+    orderbook::{BookEvent, Orderbook, fetch_onchain_orderbook, interleave},
+};
+
+/// Pipelines the replayed events are read from
+const REPLAY_PIPELINES: [&str; 2] = ["order_update", "order_fill"];
+
+const WATERMARK_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Rows are inserted in chunks, to stay below the bind parameter limit
+const INSERT_CHUNK: usize = 1000;
+
+/// Recorded replay scenario: the stored snapshot of a pool, the `order_updates` and
+/// `order_fills` rows indexed after it up to `end_checkpoint` and the onchain book at
+/// `end_checkpoint`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplayFixture {
+    pub description: String,
+    pub pool: Pool,
+    pub snapshot: OrderbookSnapshot,
+    pub end_checkpoint: i64,
+    pub order_updates: Vec<OrderUpdate>,
+    pub order_fills: Vec<OrderFill>,
+    /// Position of each transaction of the recorded events within its checkpoint
+    pub tx_order: HashMap<String, usize>,
+    pub onchain_asks: BTreeMap<i64, i64>,
+    pub onchain_bids: BTreeMap<i64, i64>,
+}
+
+/// Price level where the replayed and the onchain book differ, missing level has size 0
+#[derive(Debug, PartialEq)]
+pub struct LevelDivergence {
+    pub is_bid: bool,
+    pub price: i64,
+    pub replayed: i64,
+    pub onchain: i64,
+}
+
+impl ReplayFixture {
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), anyhow::Error> {
+        Ok(fs::write(path, serde_json::to_string_pretty(self)?)?)
+    }
+
+    /// Stores the pool, its snapshot and its events, rows of the pool left by earlier runs
+    /// are replaced
+    pub fn seed(&self, conn: &mut PgConnection) -> Result<(), diesel::result::Error> {
+        let pool_id = &self.pool.pool_id;
+        conn.transaction(|conn| {
+            diesel::delete(schema::pools::table.filter(schema::pools::pool_id.eq(pool_id)))
+                .execute(conn)?;
+            diesel::delete(
+                schema::orderbook_snapshots::table
+                    .filter(schema::orderbook_snapshots::pool_id.eq(pool_id)),
+            )
+            .execute(conn)?;
+            diesel::delete(
+                schema::order_updates::table.filter(schema::order_updates::pool_id.eq(pool_id)),
+            )
+            .execute(conn)?;
+            diesel::delete(
+                schema::order_fills::table.filter(schema::order_fills::pool_id.eq(pool_id)),
+            )
+            .execute(conn)?;
+
+            diesel::insert_into(schema::pools::table)
+                .values(&self.pool)
+                .execute(conn)?;
+            diesel::insert_into(schema::orderbook_snapshots::table)
+                .values(&self.snapshot)
+                .execute(conn)?;
+            for updates in self.order_updates.chunks(INSERT_CHUNK) {
+                diesel::insert_into(schema::order_updates::table)
+                    .values(updates)
+                    .execute(conn)?;
+            }
+            for fills in self.order_fills.chunks(INSERT_CHUNK) {
+                diesel::insert_into(schema::order_fills::table)
+                    .values(fills)
+                    .execute(conn)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Rebuilds the book at `end_checkpoint` from the seeded rows, the same way historic
+    /// snapshots are computed
+    pub fn replay(&self, database_url: Url) -> Result<Orderbook, anyhow::Error> {
+        let snapshot =
+            get_historic_orderbook(database_url, &self.pool.pool_id, self.end_checkpoint)
+                .map_err(|e| anyhow!("Failed replaying: {:?}", e))?;
+
+        snapshot_book(&snapshot)
+    }
+
+    /// Book of the recorded snapshot, the one replays start from
+    pub fn snapshot_book(&self) -> Result<Orderbook, anyhow::Error> {
+        snapshot_book(&self.snapshot)
+    }
+
+    /// Recorded events of each checkpoint in emission order, oldest checkpoint first, as the
+    /// live orderbook receives them
+    pub fn into_live_batches(self) -> Vec<Vec<BookEvent>> {
+        let mut checkpoints: BTreeMap<i64, (Vec<OrderUpdate>, Vec<OrderFill>)> = BTreeMap::new();
+        for update in self.order_updates {
+            checkpoints
+                .entry(update.checkpoint)
+                .or_default()
+                .0
+                .push(update);
+        }
+        for fill in self.order_fills {
+            checkpoints.entry(fill.checkpoint).or_default().1.push(fill);
+        }
+
+        checkpoints
+            .into_values()
+            .map(|(updates, fills)| interleave(updates, fills, &self.tx_order))
+            .collect()
+    }
+
+    /// Every price level of `replayed` that doesn't match the onchain book
+    pub fn divergence(&self, replayed: &Orderbook) -> Vec<LevelDivergence> {
+        let mut diverged = side_divergence(false, &replayed.asks, &self.onchain_asks);
+        diverged.extend(side_divergence(true, &replayed.bids, &self.onchain_bids));
+        diverged
+    }
+}
+
+fn snapshot_book(snapshot: &OrderbookSnapshot) -> Result<Orderbook, anyhow::Error> {
+    Ok(Orderbook {
+        asks: serde_json::from_value(snapshot.asks.clone())?,
+        bids: serde_json::from_value(snapshot.bids.clone())?,
+    })
+}
+
+fn side_divergence(
+    is_bid: bool,
+    replayed: &BTreeMap<i64, i64>,
+    onchain: &BTreeMap<i64, i64>,
+) -> Vec<LevelDivergence> {
+    let prices: BTreeSet<i64> = replayed.keys().chain(onchain.keys()).copied().collect();
+
+    prices
+        .into_iter()
+        .filter_map(|price| {
+            let replayed = replayed.get(&price).copied().unwrap_or(0);
+            let onchain = onchain.get(&price).copied().unwrap_or(0);
+            (replayed != onchain).then_some(LevelDivergence {
+                is_bid,
+                price,
+                replayed,
+                onchain,
+            })
+        })
+        .collect()
+}
+
+/// Lowest checkpoint both replay pipelines have indexed
+fn replay_watermark(conn: &mut PgConnection) -> Result<i64, anyhow::Error> {
+    schema::watermarks::table
+        .filter(schema::watermarks::pipeline.eq_any(REPLAY_PIPELINES))
+        .select(min(schema::watermarks::checkpoint_hi_inclusive))
+        .first::<Option<i64>>(conn)?
+        .ok_or_else(|| anyhow!("No watermarks for {:?}", REPLAY_PIPELINES))
+}
+
+/// Updates and fills of the pool after `start_checkpoint` up to `end_checkpoint`, inclusive
+fn load_events(
+    conn: &mut PgConnection,
+    pool_id: &str,
+    start_checkpoint: i64,
+    end_checkpoint: i64,
+) -> Result<(Vec<OrderUpdate>, Vec<OrderFill>), diesel::result::Error> {
+    let updates = schema::order_updates::table
+        .filter(schema::order_updates::pool_id.eq(pool_id))
+        .filter(schema::order_updates::checkpoint.gt(start_checkpoint))
+        .filter(schema::order_updates::checkpoint.le(end_checkpoint))
+        .select(OrderUpdate::as_select())
+        .load(conn)?;
+    let fills = schema::order_fills::table
+        .filter(schema::order_fills::pool_id.eq(pool_id))
+        .filter(schema::order_fills::checkpoint.gt(start_checkpoint))
+        .filter(schema::order_fills::checkpoint.le(end_checkpoint))
+        .select(OrderFill::as_select())
+        .load(conn)?;
+    Ok((updates, fills))
+}
+
+/// Positions of the transactions within the checkpoints of the recorded events
+async fn load_tx_order(
+    sui_client: &SuiClient,
+    updates: &[OrderUpdate],
+    fills: &[OrderFill],
+) -> Result<HashMap<String, usize>, anyhow::Error> {
+    let checkpoints: BTreeSet<i64> = updates
+        .iter()
+        .map(|update| update.checkpoint)
+        .chain(fills.iter().map(|fill| fill.checkpoint))
+        .collect();
+
+    let mut tx_order = HashMap::new();
+    for checkpoint in checkpoints {
+        let contents = sui_client
+            .read_api()
+            .get_checkpoint(CheckpointId::SequenceNumber(checkpoint as u64))
+            .await?;
+        tx_order.extend(
+            contents
+                .transactions
+                .iter()
+                .enumerate()
+                .map(|(position, digest)| (digest.to_string(), position)),
+        );
+    }
+    Ok(tx_order)
+}
+
+/// Records the latest snapshot of the pool, the events indexed after it and the current
+/// onchain book. Needs a running indexer and fails when the pool had new events before
+/// the onchain book was read, since the onchain book then doesn't match the end checkpoint.
+pub async fn capture_replay_fixture(
+    database_url: Url,
+    sui_client: &SuiClient,
+    pool: &Pool,
+) -> Result<ReplayFixture, anyhow::Error> {
+    let mut conn = PgConnection::establish(database_url.as_str())?;
+    let snapshot = get_latest_snapshot(&mut conn, &pool.pool_id)?
+        .ok_or_else(|| anyhow!("No snapshot for pool {}", pool.pool_name))?;
+
+    let end_checkpoint = replay_watermark(&mut conn)?;
+    let (order_updates, order_fills) = load_events(
+        &mut conn,
+        &pool.pool_id,
+        snapshot.checkpoint,
+        end_checkpoint,
+    )?;
+
+    let (onchain, timestamp) = fetch_onchain_orderbook(sui_client, pool)
+        .await
+        .map_err(|e| anyhow!("Failed fetching onchain orderbook: {:?}", e))?;
+
+    // The onchain book is at least at this checkpoint, wait until it's indexed and make sure
+    // the pool had no events since the end checkpoint
+    let onchain_checkpoint = sui_client
+        .read_api()
+        .get_latest_checkpoint_sequence_number()
+        .await? as i64;
+    while replay_watermark(&mut conn)? < onchain_checkpoint {
+        tokio::time::sleep(WATERMARK_POLL_INTERVAL).await;
+    }
+    let (later_updates, later_fills) =
+        load_events(&mut conn, &pool.pool_id, end_checkpoint, onchain_checkpoint)?;
+    if !later_updates.is_empty() || !later_fills.is_empty() {
+        return Err(anyhow!(
+            "Pool {} changed during capture, retry",
+            pool.pool_name
+        ));
+    }
+
+    let tx_order = load_tx_order(sui_client, &order_updates, &order_fills).await?;
+
+    info!(
+        "Captured {} updates and {} fills of pool {}, checkpoints {} - {}",
+        order_updates.len(),
+        order_fills.len(),
+        pool.pool_name,
+        snapshot.checkpoint,
+        end_checkpoint
+    );
+
+    Ok(ReplayFixture {
+        description: format!(
+            "{} captured at {} ms, checkpoints {} - {}",
+            pool.pool_name, timestamp, snapshot.checkpoint, end_checkpoint
+        ),
+        pool: pool.clone(),
+        snapshot,
+        end_checkpoint,
+        order_updates,
+        order_fills,
+        tx_order,
+        onchain_asks: onchain.asks,
+        onchain_bids: onchain.bids,
+    })
+}
@@ -0,0 +1,223 @@
+//! Builds `pools` rows from onchain pool metadata.
+
+use std::str::FromStr;
+
+use anyhow::{Context, anyhow};
+use async_trait::async_trait;
+use deeplook_schema::{models::Pool, schema::pools};
+use deeplook_server::server::price_exponent;
+use diesel::{ExpressionMethods, PgConnection, RunQueryDsl, upsert::excluded};
+use sui_sdk::{
+    SuiClient,
+    rpc_types::{SuiObjectData, SuiObjectDataOptions, SuiObjectResponse},
+};
+use sui_types::{
+    TypeTag,
+    base_types::{ObjectID, ObjectRef, SuiAddress},
+    programmable_transaction_builder::ProgrammableTransactionBuilder,
+    transaction::{Argument, CallArg, Command, ObjectArg, ProgrammableMoveCall, TransactionKind},
+};
+
+use crate::orderbook::{DEEPBOOK_PACKAGE_ID, parse_type_input};
+
+pub const BOOK_PARAMS_MODULE: &str = "pool";
+pub const BOOK_PARAMS_FUNCTION: &str = "pool_book_params";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoinMetadata {
+    pub decimals: u8,
+    pub symbol: String,
+    pub name: String,
+}
+
+/// Tick size, lot size and min size of a pool
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookParams {
+    pub tick_size: u64,
+    pub lot_size: u64,
+    pub min_size: u64,
+}
+
+/// Onchain data a `pools` row is built from, implemented by `SuiClient` and by mocks in tests
+#[async_trait]
+pub trait PoolMetadataSource: Send + Sync {
+    /// Full type of the pool object, `<package>::pool::Pool<Base, Quote>`
+    async fn pool_type(&self, pool_id: &str) -> Result<String, anyhow::Error>;
+
+    async fn book_params(
+        &self,
+        pool_id: &str,
+        base_asset_id: &str,
+        quote_asset_id: &str,
+    ) -> Result<BookParams, anyhow::Error>;
+
+    async fn coin_metadata(&self, coin_type: &str) -> Result<CoinMetadata, anyhow::Error>;
+}
+
+#[async_trait]
+impl PoolMetadataSource for SuiClient {
+    async fn pool_type(&self, pool_id: &str) -> Result<String, anyhow::Error> {
+        let pool_object = self
+            .read_api()
+            .get_object_with_options(
+                ObjectID::from_hex_literal(pool_id)?,
+                SuiObjectDataOptions::new().with_type(),
+            )
+            .await?;
+        pool_object
+            .data
+            .and_then(|data| data.type_)
+            .map(|object_type| object_type.to_string())
+            .ok_or_else(|| anyhow!("Missing type of pool object '{}'", pool_id))
+    }
+
+    async fn book_params(
+        &self,
+        pool_id: &str,
+        base_asset_id: &str,
+        quote_asset_id: &str,
+    ) -> Result<BookParams, anyhow::Error> {
+        let pool_object: SuiObjectResponse = self
+            .read_api()
+            .get_object_with_options(
+                ObjectID::from_hex_literal(pool_id)?,
+                SuiObjectDataOptions::full_content(),
+            )
+            .await?;
+        let pool_data: &SuiObjectData = pool_object
+            .data
+            .as_ref()
+            .ok_or_else(|| anyhow!("Missing data in pool object response for '{}'", pool_id))?;
+        let pool_object_ref: ObjectRef = (pool_data.object_id, pool_data.version, pool_data.digest);
+
+        let mut ptb = ProgrammableTransactionBuilder::new();
+        ptb.input(CallArg::Object(ObjectArg::ImmOrOwnedObject(
+            pool_object_ref,
+        )))?;
+        ptb.command(Command::MoveCall(Box::new(ProgrammableMoveCall {
+            package: ObjectID::from_hex_literal(DEEPBOOK_PACKAGE_ID)?,
+            module: BOOK_PARAMS_MODULE.to_string(),
+            function: BOOK_PARAMS_FUNCTION.to_string(),
+            type_arguments: vec![
+                parse_type_input(base_asset_id).map_err(|e| anyhow!("{:?}", e))?,
+                parse_type_input(quote_asset_id).map_err(|e| anyhow!("{:?}", e))?,
+            ],
+            arguments: vec![Argument::Input(0)],
+        })));
+        let tx = TransactionKind::ProgrammableTransaction(ptb.finish());
+
+        let result = self
+            .read_api()
+            .dev_inspect_transaction_block(SuiAddress::default(), tx, None, None, None)
+            .await?;
+        let return_values = result
+            .results
+            .and_then(|results| results.into_iter().next())
+            .map(|result| result.return_values)
+            .ok_or_else(|| anyhow!("No results from dev_inspect_transaction_block"))?;
+        let value = |i: usize| -> Result<u64, anyhow::Error> {
+            let (bytes, _) = return_values
+                .get(i)
+                .ok_or_else(|| anyhow!("Missing book param {}", i))?;
+            Ok(bcs::from_bytes(bytes)?)
+        };
+
+        Ok(BookParams {
+            tick_size: value(0)?,
+            lot_size: value(1)?,
+            min_size: value(2)?,
+        })
+    }
+
+    async fn coin_metadata(&self, coin_type: &str) -> Result<CoinMetadata, anyhow::Error> {
+        let metadata = self
+            .coin_read_api()
+            .get_coin_metadata(coin_type.to_string())
+            .await?
+            .ok_or_else(|| anyhow!("Missing coin metadata of '{}'", coin_type))?;
+        Ok(CoinMetadata {
+            decimals: metadata.decimals,
+            symbol: metadata.symbol,
+            name: metadata.name,
+        })
+    }
+}
+
+/// Base and quote coin types of a `Pool<Base, Quote>` object type
+pub fn parse_pool_type(pool_type: &str) -> Result<(String, String), anyhow::Error> {
+    let type_tag = TypeTag::from_str(pool_type)
+        .with_context(|| format!("Invalid pool type '{}'", pool_type))?;
+    match type_tag {
+        TypeTag::Struct(pool_struct) if pool_struct.type_params.len() == 2 => Ok((
+            pool_struct.type_params[0].to_canonical_string(true),
+            pool_struct.type_params[1].to_canonical_string(true),
+        )),
+        _ => Err(anyhow!("'{}' is not a pool type", pool_type)),
+    }
+}
+
+/// Builds the `pools` row of a pool, named `<BASE>_<QUOTE>` by the coin symbols
+pub async fn fetch_pool<S: PoolMetadataSource + ?Sized>(
+    source: &S,
+    pool_id: &str,
+) -> Result<Pool, anyhow::Error> {
+    let (base_asset_id, quote_asset_id) = parse_pool_type(&source.pool_type(pool_id).await?)?;
+    let base = source.coin_metadata(&base_asset_id).await?;
+    let quote = source.coin_metadata(&quote_asset_id).await?;
+    // prices are scaled by `10^(9 - base_decimals + quote_decimals)` and sizes by
+    // `10^base_decimals`
+    price_exponent(base.decimals.into(), quote.decimals.into()).ok_or_else(|| {
+        anyhow!(
+            "Unsupported asset decimals, base {} and quote {}",
+            base.decimals,
+            quote.decimals
+        )
+    })?;
+
+    let params = source
+        .book_params(pool_id, &base_asset_id, &quote_asset_id)
+        .await?;
+    let size = |name: &str, value: u64| {
+        i32::try_from(value)
+            .with_context(|| format!("{} {} doesn't fit the pools table", name, value))
+    };
+
+    Ok(Pool {
+        pool_id: pool_id.to_string(),
+        pool_name: format!("{}_{}", base.symbol, quote.symbol),
+        base_asset_id,
+        base_asset_decimals: base.decimals as i16,
+        base_asset_symbol: base.symbol,
+        base_asset_name: base.name,
+        quote_asset_id,
+        quote_asset_decimals: quote.decimals as i16,
+        quote_asset_symbol: quote.symbol,
+        quote_asset_name: quote.name,
+        min_size: size("min_size", params.min_size)?,
+        lot_size: size("lot_size", params.lot_size)?,
+        tick_size: size("tick_size", params.tick_size)?,
+    })
+}
+
+/// Inserts the pool or overwrites the existing row with the same pool id
+pub fn upsert_pool(conn: &mut PgConnection, pool: &Pool) -> Result<usize, diesel::result::Error> {
+    diesel::insert_into(pools::table)
+        .values(pool)
+        .on_conflict(pools::pool_id)
+        .do_update()
+        .set((
+            pools::pool_name.eq(excluded(pools::pool_name)),
+            pools::base_asset_id.eq(excluded(pools::base_asset_id)),
+            pools::base_asset_decimals.eq(excluded(pools::base_asset_decimals)),
+            pools::base_asset_symbol.eq(excluded(pools::base_asset_symbol)),
+            pools::base_asset_name.eq(excluded(pools::base_asset_name)),
+            pools::quote_asset_id.eq(excluded(pools::quote_asset_id)),
+            pools::quote_asset_decimals.eq(excluded(pools::quote_asset_decimals)),
+            pools::quote_asset_symbol.eq(excluded(pools::quote_asset_symbol)),
+            pools::quote_asset_name.eq(excluded(pools::quote_asset_name)),
+            pools::min_size.eq(excluded(pools::min_size)),
+            pools::lot_size.eq(excluded(pools::lot_size)),
+            pools::tick_size.eq(excluded(pools::tick_size)),
+        ))
+        .execute(conn)
+}
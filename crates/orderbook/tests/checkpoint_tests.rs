@@ -0,0 +1,12 @@
+use deeplook_utils::checkpoint::{CheckpointDigest, StubChain};
+
+#[tokio::test]
+async fn keep_up_start_comes_from_the_shared_digest() {
+    let chain = StubChain { latest: 42 };
+    let latest = CheckpointDigest::get_latest(&chain).await.unwrap();
+    assert_eq!(latest.sequence_number, 42);
+    assert_eq!(
+        CheckpointDigest::get_sequence_number(&chain).await.unwrap(),
+        latest.sequence_number
+    );
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use deeplook_orderbook::historic_orderbook::{OrderStep, apply_steps};
+use deeplook_schema::models::{OrderFill, OrderUpdate, OrderUpdateStatus};
+
+const POOL_ID: &str = "0xe05dafb5133bcffb8d59f4e12465dc0e9faeaa05e3e342a08fe135800e3e4407";
+const CHECKPOINT: i64 = 201;
+
+/// Book before the checkpoint
+fn initial_book() -> (HashMap<i64, i64>, HashMap<i64, i64>) {
+    (
+        HashMap::from([(3_510_000, 10_000_000_000), (3_520_000, 20_000_000_000)]),
+        HashMap::from([(3_490_000, 40_000_000_000)]),
+    )
+}
+
+fn timestamp() -> NaiveDateTime {
+    NaiveDateTime::parse_from_str("2025-08-20 11:00:01", "%Y-%m-%d %H:%M:%S").unwrap()
+}
+
+fn placed(pool_id: &str, order_id: &str, price: i64, is_bid: bool, quantity: i64) -> OrderUpdate {
+    OrderUpdate {
+        event_digest: format!("residual_{order_id}_placed"),
+        digest: "residual".to_string(),
+        sender: "0x0".to_string(),
+        checkpoint: CHECKPOINT,
+        checkpoint_timestamp_ms: 0,
+        timestamp: timestamp(),
+        package: "0x0".to_string(),
+        status: OrderUpdateStatus::Placed,
+        pool_id: pool_id.to_string(),
+        order_id: order_id.to_string(),
+        client_order_id: 0,
+        price,
+        is_bid,
+        original_quantity: quantity,
+        quantity,
+        filled_quantity: 0,
+        onchain_timestamp: 0,
+        trader: "0x0".to_string(),
+        balance_manager_id: "0xtaker".to_string(),
+    }
+}
+
+fn filled(
+    pool_id: &str,
+    maker_order_id: &str,
+    price: i64,
+    taker_is_bid: bool,
+    base: i64,
+) -> OrderFill {
+    OrderFill {
+        event_digest: format!("residual_{maker_order_id}_filled"),
+        digest: "residual".to_string(),
+        sender: "0x0".to_string(),
+        checkpoint: CHECKPOINT,
+        checkpoint_timestamp_ms: 0,
+        timestamp: timestamp(),
+        package: "0x0".to_string(),
+        pool_id: pool_id.to_string(),
+        maker_order_id: maker_order_id.to_string(),
+        taker_order_id: "taker".to_string(),
+        maker_client_order_id: 0,
+        taker_client_order_id: 0,
+        price,
+        taker_fee: 0,
+        taker_fee_is_deep: false,
+        maker_fee: 0,
+        maker_fee_is_deep: false,
+        taker_is_bid,
+        base_quantity: base,
+        quote_quantity: 0,
+        maker_balance_manager_id: "0xmaker".to_string(),
+        taker_balance_manager_id: "0xtaker".to_string(),
+        onchain_timestamp: 0,
+    }
+}
+
+/// Steps of the checkpoint's events in emission order: the taker bid's fill, then the
+/// placement of its rest, then the taker ask's fill
+fn checkpoint_steps(pool_id: &str) -> Vec<OrderStep> {
+    vec![
+        OrderStep::from_fill(&filled(
+            pool_id,
+            "ask_maker",
+            3_510_000,
+            true,
+            10_000_000_000,
+        )),
+        OrderStep::from_update(&placed(
+            pool_id,
+            "taker_bid",
+            3_515_000,
+            true,
+            15_000_000_000,
+        )),
+        OrderStep::from_fill(&filled(
+            pool_id,
+            "taker_bid",
+            3_515_000,
+            false,
+            5_000_000_000,
+        )),
+    ]
+}
+
+#[test]
+fn taker_rest_is_placed_net_of_its_fills() {
+    let (mut asks, mut bids) = initial_book();
+    apply_steps(&mut asks, &mut bids, &checkpoint_steps(POOL_ID), POOL_ID).unwrap();
+    asks.retain(|_, size| *size != 0);
+    bids.retain(|_, size| *size != 0);
+
+    // the 25 SUI taker bid took the 10 SUI ask and rests with 15 SUI, 5 of them filled after
+    assert_eq!(asks, HashMap::from([(3_520_000, 20_000_000_000)]));
+    assert_eq!(
+        bids,
+        HashMap::from([(3_490_000, 40_000_000_000), (3_515_000, 10_000_000_000)])
+    );
+}
+
+#[test]
+fn fill_only_reduces_the_maker_side() {
+    let step = OrderStep::from_fill(&filled("0xpool", "ask_maker", 3_510_000, true, 7));
+
+    assert!(!step.is_bid);
+    assert_eq!(step.price, 3_510_000);
+    assert_eq!(step.quantity, 7);
+}
@@ -0,0 +1,156 @@
+use std::env;
+
+use chrono::{DateTime, NaiveDateTime};
+use deeplook_orderbook::historic_orderbook::get_historic_orderbook;
+use deeplook_schema::{
+    MIGRATIONS,
+    models::{OrderFill, OrderUpdate, OrderUpdateStatus},
+    schema::{order_fills, order_updates},
+};
+use diesel::{Connection, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+use diesel_migrations::MigrationHarness;
+use serde_json::json;
+use sui_pg_db::temp::TempDb;
+use url::Url;
+
+const POOL_A: &str = "0xreplay_pool_a";
+const POOL_B: &str = "0xreplay_pool_b";
+const START_MS: i64 = 1_735_689_600_000;
+
+fn timestamp(checkpoint: i64) -> NaiveDateTime {
+    DateTime::from_timestamp_millis(START_MS + checkpoint * 1000)
+        .unwrap()
+        .naive_utc()
+}
+
+fn update(
+    pool_id: &str,
+    checkpoint: i64,
+    order_id: &str,
+    status: OrderUpdateStatus,
+    (price, is_bid): (i64, bool),
+    (original_quantity, quantity): (i64, i64),
+) -> OrderUpdate {
+    OrderUpdate {
+        event_digest: format!("{pool_id}_{order_id}_{}", status.as_ref()),
+        digest: format!("digest_{checkpoint}"),
+        sender: "0x0".to_string(),
+        checkpoint,
+        checkpoint_timestamp_ms: START_MS + checkpoint * 1000,
+        timestamp: timestamp(checkpoint),
+        package: "0x0".to_string(),
+        status,
+        pool_id: pool_id.to_string(),
+        order_id: order_id.to_string(),
+        client_order_id: 0,
+        price,
+        is_bid,
+        original_quantity,
+        quantity,
+        filled_quantity: 0,
+        onchain_timestamp: START_MS + checkpoint * 1000,
+        trader: "0x0".to_string(),
+        balance_manager_id: "0xmanager".to_string(),
+    }
+}
+
+fn fill(
+    pool_id: &str,
+    checkpoint: i64,
+    price: i64,
+    base_quantity: i64,
+    taker_is_bid: bool,
+) -> OrderFill {
+    OrderFill {
+        event_digest: format!("{pool_id}_fill_{checkpoint}"),
+        digest: format!("digest_{checkpoint}"),
+        sender: "0x0".to_string(),
+        checkpoint,
+        checkpoint_timestamp_ms: START_MS + checkpoint * 1000,
+        timestamp: timestamp(checkpoint),
+        package: "0x0".to_string(),
+        pool_id: pool_id.to_string(),
+        maker_order_id: "maker".to_string(),
+        taker_order_id: "taker".to_string(),
+        maker_client_order_id: 0,
+        taker_client_order_id: 0,
+        price,
+        taker_fee: 0,
+        taker_fee_is_deep: false,
+        maker_fee: 0,
+        maker_fee_is_deep: false,
+        taker_is_bid,
+        base_quantity,
+        quote_quantity: base_quantity * price,
+        maker_balance_manager_id: "0xmaker".to_string(),
+        taker_balance_manager_id: "0xtaker".to_string(),
+        onchain_timestamp: START_MS + checkpoint * 1000,
+    }
+}
+
+fn seed(conn: &mut PgConnection) {
+    use OrderUpdateStatus::*;
+    let updates = vec![
+        update(POOL_A, 1, "1", Placed, (100, true), (10, 10)),
+        update(POOL_A, 1, "2", Placed, (200, false), (5, 5)),
+        update(POOL_A, 2, "3", Placed, (100, true), (4, 4)),
+        // total quantity of order 1 goes from 10 to 6, its level loses 4
+        update(POOL_A, 3, "1", Modified, (100, true), (10, 6)),
+        update(POOL_A, 3, "2", Canceled, (200, false), (5, 5)),
+        update(POOL_A, 4, "4", Placed, (210, false), (8, 8)),
+        // past the replayed range
+        update(POOL_A, 5, "5", Placed, (220, false), (2, 2)),
+        update(POOL_B, 2, "1", Placed, (100, true), (50, 50)),
+    ];
+    // a taker sells 3 into the bids of pool A
+    let fills = vec![fill(POOL_A, 2, 100, 3, false)];
+
+    // rows of an earlier run against a real database
+    diesel::delete(order_updates::table.filter(order_updates::pool_id.eq_any([POOL_A, POOL_B])))
+        .execute(conn)
+        .unwrap();
+    diesel::delete(order_fills::table.filter(order_fills::pool_id.eq_any([POOL_A, POOL_B])))
+        .execute(conn)
+        .unwrap();
+    diesel::insert_into(order_updates::table)
+        .values(&updates)
+        .execute(conn)
+        .unwrap();
+    diesel::insert_into(order_fills::table)
+        .values(&fills)
+        .execute(conn)
+        .unwrap();
+}
+
+#[test]
+#[ignore = "requires Postgres with TimescaleDB, set USE_REAL_DB=true and DATABASE_URL to skip the temporary one"]
+fn replay_rebuilds_books_per_pool() {
+    // IMPORTANT: Keep temp_db in scope for the entire test, otherwise it gets cleaned up
+    let (_temp_db, database_url) =
+        if env::var("USE_REAL_DB").unwrap_or_else(|_| "false".to_string()) == "true" {
+            let database_url = env::var("DATABASE_URL")
+                .expect("DATABASE_URL environment variable must be set when USE_REAL_DB=true");
+            (None, database_url)
+        } else {
+            let temp_db = TempDb::new().unwrap();
+            let url = temp_db.database().url().to_string();
+            (Some(temp_db), url)
+        };
+    let mut conn = PgConnection::establish(&database_url).unwrap();
+    conn.run_pending_migrations(MIGRATIONS).unwrap();
+    seed(&mut conn);
+
+    let database_url = Url::parse(&database_url).unwrap();
+    let pool_a = get_historic_orderbook(database_url.clone(), POOL_A, 4).unwrap();
+    let pool_b = get_historic_orderbook(database_url, POOL_B, 4).unwrap();
+
+    // bids 10 + 4 - 3 filled - 4 modified away, the canceled ask is gone
+    assert_eq!(pool_a.bids, json!({ "100": 7 }));
+    assert_eq!(pool_a.asks, json!({ "210": 8 }));
+    assert_eq!(pool_a.checkpoint, 4);
+    assert_eq!(pool_a.timestamp, timestamp(4));
+
+    assert_eq!(pool_b.bids, json!({ "100": 50 }));
+    assert_eq!(pool_b.asks, json!({}));
+    assert_eq!(pool_b.timestamp, timestamp(2));
+}
@@ -0,0 +1,106 @@
+use clap::ValueEnum;
+use deeplook_orderbook::orderbook::{InitMode, load_initial_orderbook};
+use deeplook_schema::{MIGRATIONS, models::OrderbookSnapshot, schema::orderbook_snapshots};
+use diesel::{Connection, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+use diesel_migrations::MigrationHarness;
+use serde_json::json;
+use url::Url;
+
+const POOL_ID: &str = "0x00000000000000000000000000000000000000000000000000000000000000aa";
+
+#[test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+fn pool_without_snapshot_starts_empty() {
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let mut conn = PgConnection::establish(&database_url).unwrap();
+    conn.run_pending_migrations(MIGRATIONS).unwrap();
+
+    conn.test_transaction::<_, diesel::result::Error, _>(|conn| {
+        assert!(load_initial_orderbook(conn, POOL_ID).unwrap().is_none());
+
+        for checkpoint in [100, 200] {
+            diesel::insert_into(orderbook_snapshots::table)
+                .values(OrderbookSnapshot {
+                    checkpoint,
+                    pool_id: POOL_ID.to_string(),
+                    asks: json!({ "2000": checkpoint }),
+                    bids: json!({ "1000": 5 }),
+                    timestamp: chrono::Utc::now().naive_utc(),
+                })
+                .execute(conn)?;
+        }
+
+        let (orderbook, checkpoint) = load_initial_orderbook(conn, POOL_ID).unwrap().unwrap();
+        assert_eq!(checkpoint, 200);
+        assert_eq!(orderbook.asks.get(&2000), Some(&200));
+        assert_eq!(orderbook.bids.get(&1000), Some(&5));
+        Ok(())
+    });
+}
+
+#[test]
+fn init_mode_is_parsed_from_flag() {
+    assert_eq!(InitMode::default(), InitMode::Snapshot);
+    assert_eq!(
+        InitMode::from_str("snapshot", false).unwrap(),
+        InitMode::Snapshot
+    );
+    assert_eq!(
+        InitMode::from_str("onchain", false).unwrap(),
+        InitMode::Onchain
+    );
+    assert!(InitMode::from_str("genesis", false).is_err());
+}
+
+#[test]
+fn onchain_mode_ignores_the_database() {
+    // Nothing listens here, onchain mode must not connect
+    let database_url = Url::parse("postgres://postgres@127.0.0.1:1/deeplook").unwrap();
+    assert!(
+        InitMode::Onchain
+            .initial_orderbook(&database_url, POOL_ID)
+            .unwrap()
+            .is_none()
+    );
+    assert!(
+        InitMode::Snapshot
+            .initial_orderbook(&database_url, POOL_ID)
+            .is_err()
+    );
+}
+
+#[test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+fn snapshot_mode_starts_from_latest_snapshot() {
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let mut conn = PgConnection::establish(&database_url).unwrap();
+    conn.run_pending_migrations(MIGRATIONS).unwrap();
+
+    // Committed so the connection opened by `initial_orderbook` sees it, removed afterwards
+    let snapshot = OrderbookSnapshot {
+        checkpoint: 300,
+        pool_id: POOL_ID.to_string(),
+        asks: json!({ "2000": 7 }),
+        bids: json!({ "1000": 5 }),
+        timestamp: chrono::Utc::now().naive_utc(),
+    };
+    diesel::insert_into(orderbook_snapshots::table)
+        .values(&snapshot)
+        .execute(&mut conn)
+        .unwrap();
+
+    let database_url = Url::parse(&database_url).unwrap();
+    let initial = InitMode::Snapshot.initial_orderbook(&database_url, POOL_ID);
+    let onchain = InitMode::Onchain.initial_orderbook(&database_url, POOL_ID);
+
+    diesel::delete(orderbook_snapshots::table)
+        .filter(orderbook_snapshots::pool_id.eq(POOL_ID))
+        .execute(&mut conn)
+        .unwrap();
+
+    let (orderbook, checkpoint) = initial.unwrap().unwrap();
+    assert_eq!(checkpoint, 300);
+    assert_eq!(orderbook.best_ask().unwrap().price, 2000);
+    assert_eq!(orderbook.best_bid().unwrap().size, 5);
+    assert!(onchain.unwrap().is_none());
+}
@@ -0,0 +1,64 @@
+use deeplook_orderbook::historic_orderbook::insert_snapshots;
+use deeplook_schema::{MIGRATIONS, models::OrderbookSnapshot, schema::orderbook_snapshots};
+use diesel::{Connection, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl, dsl::count_star};
+use diesel_migrations::MigrationHarness;
+use serde_json::json;
+
+const CHECKPOINT: i64 = 500;
+
+fn snapshot(pool_id: &str) -> OrderbookSnapshot {
+    OrderbookSnapshot {
+        checkpoint: CHECKPOINT,
+        pool_id: pool_id.to_string(),
+        asks: json!({ "2000": 1 }),
+        bids: json!({ "1000": 1 }),
+        timestamp: chrono::Utc::now().naive_utc(),
+    }
+}
+
+fn stored(conn: &mut PgConnection, pool_ids: &[&str]) -> i64 {
+    orderbook_snapshots::table
+        .filter(orderbook_snapshots::pool_id.eq_any(pool_ids))
+        .filter(orderbook_snapshots::checkpoint.eq(CHECKPOINT))
+        .select(count_star())
+        .first(conn)
+        .unwrap()
+}
+
+#[test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+fn snapshots_are_stored_together() {
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let mut conn = PgConnection::establish(&database_url).unwrap();
+    conn.run_pending_migrations(MIGRATIONS).unwrap();
+
+    conn.test_transaction::<_, diesel::result::Error, _>(|conn| {
+        let pools = ["0xbatch_a", "0xbatch_b", "0xbatch_c"];
+        let snapshots: Vec<_> = pools.iter().map(|pool_id| snapshot(pool_id)).collect();
+
+        assert_eq!(insert_snapshots(conn, &snapshots)?, 3);
+        assert_eq!(stored(conn, &pools), 3);
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+fn failed_insert_rolls_back_the_run() {
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let mut conn = PgConnection::establish(&database_url).unwrap();
+    conn.run_pending_migrations(MIGRATIONS).unwrap();
+
+    conn.test_transaction::<_, diesel::result::Error, _>(|conn| {
+        // The second pool already has a snapshot at the checkpoint, its insert fails
+        insert_snapshots(conn, &[snapshot("0xbatch_b")])?;
+
+        let pools = ["0xbatch_a", "0xbatch_b", "0xbatch_c"];
+        let snapshots: Vec<_> = pools.iter().map(|pool_id| snapshot(pool_id)).collect();
+
+        assert!(insert_snapshots(conn, &snapshots).is_err());
+        assert_eq!(stored(conn, &["0xbatch_a", "0xbatch_c"]), 0);
+        assert_eq!(stored(conn, &["0xbatch_b"]), 1);
+        Ok(())
+    });
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use deeplook_orderbook::orderbook::{BookEvent, event_index, interleave};
+use deeplook_schema::models::{OrderFill, OrderUpdate, OrderUpdateStatus};
+
+const POOL_ID: &str = "0xorder_test_pool";
+const CHECKPOINT: i64 = 7;
+
+fn update(digest: &str, index: usize, order_id: &str, status: OrderUpdateStatus) -> OrderUpdate {
+    OrderUpdate {
+        event_digest: format!("{digest}{index}"),
+        digest: digest.to_string(),
+        sender: "0x0".to_string(),
+        checkpoint: CHECKPOINT,
+        checkpoint_timestamp_ms: 0,
+        timestamp: NaiveDateTime::default(),
+        package: "0x0".to_string(),
+        status,
+        pool_id: POOL_ID.to_string(),
+        order_id: order_id.to_string(),
+        client_order_id: 0,
+        price: 100,
+        is_bid: true,
+        original_quantity: 10,
+        quantity: 10,
+        filled_quantity: 0,
+        onchain_timestamp: 0,
+        trader: "0x0".to_string(),
+        balance_manager_id: "0xmanager".to_string(),
+    }
+}
+
+fn fill(digest: &str, index: usize, maker_order_id: &str) -> OrderFill {
+    OrderFill {
+        event_digest: format!("{digest}{index}"),
+        digest: digest.to_string(),
+        sender: "0x0".to_string(),
+        checkpoint: CHECKPOINT,
+        checkpoint_timestamp_ms: 0,
+        timestamp: NaiveDateTime::default(),
+        package: "0x0".to_string(),
+        pool_id: POOL_ID.to_string(),
+        maker_order_id: maker_order_id.to_string(),
+        taker_order_id: "taker".to_string(),
+        maker_client_order_id: 0,
+        taker_client_order_id: 0,
+        price: 100,
+        taker_fee: 0,
+        taker_fee_is_deep: false,
+        maker_fee: 0,
+        maker_fee_is_deep: false,
+        taker_is_bid: false,
+        base_quantity: 4,
+        quote_quantity: 400,
+        maker_balance_manager_id: "0xmaker".to_string(),
+        taker_balance_manager_id: "0xtaker".to_string(),
+        onchain_timestamp: 0,
+    }
+}
+
+fn event_digests(events: &[BookEvent]) -> Vec<String> {
+    events
+        .iter()
+        .map(|event| match event {
+            BookEvent::Update(update) => update.event_digest.clone(),
+            BookEvent::Fill(fill) => fill.event_digest.clone(),
+        })
+        .collect()
+}
+
+#[test]
+fn event_index_is_the_digest_suffix() {
+    assert_eq!(event_index("Abc123", "Abc"), Some(123));
+    assert_eq!(event_index("Abc0", "Abc"), Some(0));
+    // base58 digests may end in digits, the index is what follows the whole digest
+    assert_eq!(event_index("Ab912", "Ab9"), Some(12));
+    assert_eq!(event_index("Xyz1", "Abc"), None);
+    assert_eq!(event_index("Abc", "Abc"), None);
+}
+
+#[test]
+fn fill_before_cancel_in_the_same_transaction() {
+    let tx_order = HashMap::from([("TxA".to_string(), 0)]);
+    let updates = vec![update("TxA", 1, "1", OrderUpdateStatus::Canceled)];
+    let fills = vec![fill("TxA", 0, "1")];
+
+    let events = interleave(updates, fills, &tx_order);
+
+    assert_eq!(event_digests(&events), vec!["TxA0", "TxA1"]);
+    assert!(matches!(events[0], BookEvent::Fill(_)));
+}
+
+#[test]
+fn events_follow_transaction_order() {
+    let tx_order = HashMap::from([("TxB".to_string(), 0), ("TxA".to_string(), 1)]);
+    let updates = vec![
+        update("TxA", 0, "1", OrderUpdateStatus::Placed),
+        update("TxB", 2, "2", OrderUpdateStatus::Expired),
+    ];
+    let fills = vec![fill("TxA", 1, "1"), fill("TxB", 10, "3")];
+
+    let events = interleave(updates, fills, &tx_order);
+
+    assert_eq!(
+        event_digests(&events),
+        vec!["TxB2", "TxB10", "TxA0", "TxA1"]
+    );
+}
+
+#[test]
+fn unknown_transactions_go_last_in_arrival_order() {
+    let tx_order = HashMap::from([("TxA".to_string(), 0)]);
+    let updates = vec![
+        update("TxC", 0, "3", OrderUpdateStatus::Placed),
+        update("TxA", 0, "1", OrderUpdateStatus::Placed),
+    ];
+    let fills = vec![fill("TxB", 0, "2")];
+
+    let events = interleave(updates, fills, &tx_order);
+
+    assert_eq!(event_digests(&events), vec!["TxA0", "TxC0", "TxB0"]);
+}
@@ -0,0 +1,36 @@
+use std::collections::BTreeMap;
+
+use deeplook_orderbook::orderbook::level_changes;
+
+fn changes(old: &[(i64, i64)], new: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let old: BTreeMap<i64, i64> = old.iter().copied().collect();
+    let new: BTreeMap<i64, i64> = new.iter().copied().collect();
+    level_changes(&old, &new)
+        .into_iter()
+        .map(|order| (order.price, order.size))
+        .collect()
+}
+
+#[test]
+fn unchanged_book_has_no_changes() {
+    assert!(changes(&[(100, 5), (101, 3)], &[(100, 5), (101, 3)]).is_empty());
+}
+
+#[test]
+fn changed_added_and_removed_levels_by_price() {
+    assert_eq!(
+        changes(
+            &[(100, 5), (101, 3), (103, 1)],
+            &[(99, 2), (101, 4), (103, 1)]
+        ),
+        vec![(99, 2), (100, 0), (101, 4)]
+    );
+}
+
+#[test]
+fn emptied_book_removes_every_level() {
+    assert_eq!(
+        changes(&[(100, 5), (101, 3)], &[]),
+        vec![(100, 0), (101, 0)]
+    );
+}
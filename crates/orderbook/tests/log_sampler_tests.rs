@@ -0,0 +1,26 @@
+use std::time::{Duration, Instant};
+
+use deeplook_orderbook::log_sampler::LogSampler;
+
+#[test]
+fn rapid_flapping_is_logged_once_per_interval() {
+    let mut sampler = LogSampler::new(Duration::from_secs(10));
+    let start = Instant::now();
+
+    // book flapping every 100ms for 25 seconds
+    let logged: Vec<(u64, u64)> = (0..250)
+        .filter_map(|i| {
+            let now = start + Duration::from_millis(i * 100);
+            sampler.sample(now).map(|suppressed| (i, suppressed))
+        })
+        .collect();
+
+    assert_eq!(logged, vec![(0, 0), (100, 99), (200, 99)]);
+}
+
+#[test]
+fn zero_interval_logs_everything() {
+    let mut sampler = LogSampler::new(Duration::ZERO);
+    let now = Instant::now();
+    assert!((0..10).all(|_| sampler.sample(now) == Some(0)));
+}
@@ -0,0 +1,50 @@
+use std::{collections::HashMap, sync::Arc};
+
+use deeplook_orderbook::insert_by_id_and_name;
+
+const FIRST_ID: &str = "0x01";
+const SECOND_ID: &str = "0x02";
+
+#[test]
+fn duplicate_name_keeps_both_pools_reachable_by_id() {
+    let mut map: HashMap<String, Arc<&str>> = HashMap::new();
+
+    assert!(insert_by_id_and_name(
+        &mut map,
+        FIRST_ID,
+        "SUI_USDC",
+        Arc::new(FIRST_ID)
+    ));
+    assert!(!insert_by_id_and_name(
+        &mut map,
+        SECOND_ID,
+        "SUI_USDC",
+        Arc::new(SECOND_ID)
+    ));
+
+    assert_eq!(map.get(FIRST_ID).map(|v| **v), Some(FIRST_ID));
+    assert_eq!(map.get(SECOND_ID).map(|v| **v), Some(SECOND_ID));
+    // the name keeps pointing at the pool registered first
+    assert_eq!(map.get("SUI_USDC").map(|v| **v), Some(FIRST_ID));
+}
+
+#[test]
+fn reregistered_pool_replaces_its_name_entry() {
+    let mut map: HashMap<String, Arc<&str>> = HashMap::new();
+
+    assert!(insert_by_id_and_name(
+        &mut map,
+        FIRST_ID,
+        "SUI_USDC",
+        Arc::new("old")
+    ));
+    assert!(insert_by_id_and_name(
+        &mut map,
+        FIRST_ID,
+        "SUI_USDC",
+        Arc::new("new")
+    ));
+
+    assert_eq!(map.get(FIRST_ID).map(|v| **v), Some("new"));
+    assert_eq!(map.get("SUI_USDC").map(|v| **v), Some("new"));
+}
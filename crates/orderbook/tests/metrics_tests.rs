@@ -0,0 +1,28 @@
+use deeplook_orderbook::metrics::OrderbookMetrics;
+use prometheus::Registry;
+
+#[test]
+fn phantom_levels_are_shared_by_registries() {
+    let metrics = OrderbookMetrics::new();
+    let catch_up = Registry::new();
+    let keep_up = Registry::new();
+    metrics.register(&catch_up).unwrap();
+    metrics.register(&keep_up).unwrap();
+
+    metrics
+        .phantom_levels
+        .with_label_values(&["SUI_USDC"])
+        .inc();
+
+    for registry in [catch_up, keep_up] {
+        let families = registry.gather();
+        let phantom_levels = families
+            .iter()
+            .find(|family| family.get_name() == "orderbook_phantom_levels")
+            .unwrap();
+        assert_eq!(
+            phantom_levels.get_metric()[0].get_counter().get_value(),
+            1.0
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use deeplook_orderbook::historic_orderbook::{Op, OrderStep, apply_steps, modified_size_change};
+
+const POOL_ID: &str = "0xmodify_test_pool";
+const PRICE: i64 = 3_500_000;
+
+fn step(quantity: i64, op: Op, checkpoint: i64) -> OrderStep {
+    OrderStep {
+        price: PRICE,
+        quantity,
+        op,
+        checkpoint,
+        is_bid: true,
+        timestamp: NaiveDateTime::default(),
+    }
+}
+
+#[test]
+fn modify_after_fill_subtracts_only_the_reduction() {
+    let mut asks = HashMap::new();
+    let mut bids = HashMap::new();
+
+    // bid of 10 placed, 3 filled, then the total is reduced to 6 within the same checkpoint:
+    // OrderModified { previous_quantity: 10, filled_quantity: 3, new_quantity: 6 }
+    let steps = vec![
+        step(10, Op::Add, 1),
+        step(3, Op::Subtract, 2),
+        step(modified_size_change(10, 6), Op::Subtract, 2),
+    ];
+    apply_steps(&mut asks, &mut bids, &steps, POOL_ID).unwrap();
+
+    // remaining size is the new total minus what was filled
+    assert_eq!(bids.get(&PRICE), Some(&(6 - 3)));
+    assert!(asks.is_empty());
+}
+
+#[test]
+fn modify_without_fills_reduces_level() {
+    let mut asks = HashMap::new();
+    let mut bids = HashMap::new();
+
+    let steps = vec![
+        step(10, Op::Add, 1),
+        step(modified_size_change(10, 7), Op::Subtract, 2),
+    ];
+    apply_steps(&mut asks, &mut bids, &steps, POOL_ID).unwrap();
+
+    assert_eq!(bids.get(&PRICE), Some(&7));
+}
@@ -0,0 +1,77 @@
+use deeplook_orderbook::historic_orderbook::{
+    insert_snapshots, prune_snapshots, prune_snapshots_except,
+};
+use deeplook_schema::{MIGRATIONS, models::OrderbookSnapshot, schema::orderbook_snapshots};
+use diesel::{Connection, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+use diesel_migrations::MigrationHarness;
+use serde_json::json;
+
+const POOL_ID: &str = "0xprune";
+
+fn snapshots(pool_id: &str, checkpoints: &[i64]) -> Vec<OrderbookSnapshot> {
+    checkpoints
+        .iter()
+        .map(|&checkpoint| OrderbookSnapshot {
+            checkpoint,
+            pool_id: pool_id.to_string(),
+            asks: json!({ "2000": 1 }),
+            bids: json!({ "1000": 1 }),
+            timestamp: chrono::Utc::now().naive_utc(),
+        })
+        .collect()
+}
+
+fn stored(conn: &mut PgConnection, pool_id: &str) -> Vec<i64> {
+    orderbook_snapshots::table
+        .filter(orderbook_snapshots::pool_id.eq(pool_id))
+        .select(orderbook_snapshots::checkpoint)
+        .order(orderbook_snapshots::checkpoint.asc())
+        .load(conn)
+        .unwrap()
+}
+
+fn connect() -> PgConnection {
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let mut conn = PgConnection::establish(&database_url).unwrap();
+    conn.run_pending_migrations(MIGRATIONS).unwrap();
+    conn
+}
+
+#[test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+fn only_latest_snapshots_are_kept() {
+    connect().test_transaction::<_, diesel::result::Error, _>(|conn| {
+        insert_snapshots(conn, &snapshots(POOL_ID, &[100, 200, 300, 400]))?;
+        insert_snapshots(conn, &snapshots("0xother", &[100]))?;
+
+        assert_eq!(prune_snapshots(conn, POOL_ID, 2)?, 2);
+        assert_eq!(stored(conn, POOL_ID), vec![300, 400]);
+        assert_eq!(stored(conn, "0xother"), vec![100]);
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+fn latest_snapshot_is_always_kept() {
+    connect().test_transaction::<_, diesel::result::Error, _>(|conn| {
+        insert_snapshots(conn, &snapshots(POOL_ID, &[100, 200]))?;
+
+        assert_eq!(prune_snapshots(conn, POOL_ID, 0)?, 1);
+        assert_eq!(stored(conn, POOL_ID), vec![200]);
+        assert_eq!(prune_snapshots(conn, "0xmissing", 1)?, 0);
+        Ok(())
+    });
+}
+
+#[test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+fn initial_snapshot_of_a_live_book_is_kept() {
+    connect().test_transaction::<_, diesel::result::Error, _>(|conn| {
+        insert_snapshots(conn, &snapshots(POOL_ID, &[100, 200, 300]))?;
+
+        assert_eq!(prune_snapshots_except(conn, POOL_ID, 1, Some(100))?, 1);
+        assert_eq!(stored(conn, POOL_ID), vec![100, 300]);
+        Ok(())
+    });
+}
@@ -0,0 +1,27 @@
+use axum::{body::Body, http::Request, http::StatusCode};
+use deeplook_orderbook::readiness::{READYZ_PATH, Readiness, readiness_router};
+use tower::ServiceExt;
+
+async fn readyz_status(readiness: &Readiness) -> StatusCode {
+    readiness_router(readiness.clone())
+        .oneshot(Request::get(READYZ_PATH).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn not_ready_until_caught_up() {
+    let readiness = Readiness::new();
+
+    assert!(!readiness.is_ready());
+    assert_eq!(
+        readyz_status(&readiness).await,
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+
+    readiness.set_ready();
+
+    assert!(readiness.is_ready());
+    assert_eq!(readyz_status(&readiness).await, StatusCode::OK);
+}
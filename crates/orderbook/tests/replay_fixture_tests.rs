@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use deeplook_orderbook::orderbook::{BookEvent, InitMode, OrderbookManager};
+use deeplook_orderbook::replay_fixture::ReplayFixture;
+use deeplook_schema::MIGRATIONS;
+use deeplook_server::server::SUI_MAINNET_URL;
+use deeplook_utils::cache::Cache;
+use diesel::{Connection, PgConnection};
+use diesel_migrations::MigrationHarness;
+use sui_pg_db::temp::TempDb;
+use sui_sdk::SuiClientBuilder;
+use url::Url;
+
+/// Fixtures recorded with `capture-replay-fixture`
+fn fixtures() -> Vec<(PathBuf, ReplayFixture)> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/replay");
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    assert!(
+        !paths.is_empty(),
+        "no fixtures in {}, record one with capture-replay-fixture",
+        dir.display()
+    );
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let fixture = ReplayFixture::load(&path).unwrap();
+            (path, fixture)
+        })
+        .collect()
+}
+
+/// Temporary database, or `DATABASE_URL` when `USE_REAL_DB=true`. Keep the `TempDb` in scope
+/// for the entire test, otherwise it gets cleaned up.
+fn database() -> (Option<TempDb>, Url) {
+    let (temp_db, database_url) =
+        if env::var("USE_REAL_DB").unwrap_or_else(|_| "false".to_string()) == "true" {
+            let database_url = env::var("DATABASE_URL")
+                .expect("DATABASE_URL environment variable must be set when USE_REAL_DB=true");
+            (None, database_url)
+        } else {
+            let temp_db = TempDb::new().unwrap();
+            let url = temp_db.database().url().to_string();
+            (Some(temp_db), url)
+        };
+    PgConnection::establish(&database_url)
+        .unwrap()
+        .run_pending_migrations(MIGRATIONS)
+        .unwrap();
+    (temp_db, Url::parse(&database_url).unwrap())
+}
+
+#[test]
+#[ignore = "requires Postgres with TimescaleDB and fixtures recorded with capture-replay-fixture, set USE_REAL_DB=true and DATABASE_URL to skip the temporary one"]
+fn replayed_books_match_onchain() {
+    let fixtures = fixtures();
+    let (_temp_db, database_url) = database();
+    let mut conn = PgConnection::establish(database_url.as_str()).unwrap();
+
+    for (path, fixture) in fixtures {
+        fixture.seed(&mut conn).unwrap();
+        let replayed = fixture
+            .replay(database_url.clone())
+            .unwrap_or_else(|e| panic!("{} failed replaying: {:?}", path.display(), e));
+        let divergence = fixture.divergence(&replayed);
+        assert!(
+            divergence.is_empty(),
+            "{} diverged from onchain book: {:?}",
+            path.display(),
+            divergence
+        );
+    }
+}
+
+#[test]
+#[ignore = "requires Postgres with TimescaleDB and fixtures recorded with capture-replay-fixture, set USE_REAL_DB=true and DATABASE_URL to skip the temporary one"]
+fn missing_event_diverges() {
+    let fixtures = fixtures();
+    let (_temp_db, database_url) = database();
+    let mut conn = PgConnection::establish(database_url.as_str()).unwrap();
+
+    for (path, mut fixture) in fixtures {
+        if fixture.order_fills.is_empty() {
+            fixture.order_updates.remove(0);
+        } else {
+            fixture.order_fills.remove(0);
+        }
+        fixture.seed(&mut conn).unwrap();
+        let diverged = match fixture.replay(database_url.clone()) {
+            Ok(replayed) => !fixture.divergence(&replayed).is_empty(),
+            Err(_) => true,
+        };
+        assert!(diverged, "{} didn't notice a missing event", path.display());
+    }
+}
+
+fn non_empty(levels: &BTreeMap<i64, i64>) -> BTreeMap<i64, i64> {
+    levels
+        .iter()
+        .filter(|(_, size)| **size != 0)
+        .map(|(price, size)| (*price, *size))
+        .collect()
+}
+
+#[tokio::test]
+#[ignore = "requires Postgres with TimescaleDB, Redis at localhost, a Sui mainnet full node and fixtures recorded with capture-replay-fixture, set USE_REAL_DB=true and DATABASE_URL to skip the temporary one"]
+async fn live_manager_matches_historic_reconstruction() {
+    let fixtures = fixtures();
+    let (_temp_db, database_url) = database();
+    let mut conn = PgConnection::establish(database_url.as_str()).unwrap();
+    let sui_client = Arc::new(
+        SuiClientBuilder::default()
+            .build(SUI_MAINNET_URL)
+            .await
+            .unwrap(),
+    );
+    let redis_url = Url::parse("redis://localhost:6379").unwrap();
+
+    for (path, fixture) in fixtures {
+        fixture.seed(&mut conn).unwrap();
+        let historic = fixture.replay(database_url.clone()).unwrap();
+
+        // handle_fill and handle_update only change the book, nothing is published
+        let mut manager = OrderbookManager::new(
+            fixture.pool.clone(),
+            sui_client.clone(),
+            Mutex::new(Cache::new(redis_url.clone())),
+            database_url.clone(),
+            InitMode::Onchain,
+        )
+        .unwrap();
+        manager.orderbook = fixture.snapshot_book().unwrap();
+        manager.initial_checkpoint = fixture.snapshot.checkpoint;
+
+        for event in fixture.into_live_batches().into_iter().flatten() {
+            match event {
+                BookEvent::Update(update) => manager.handle_update(update),
+                BookEvent::Fill(fill) => manager.handle_fill(fill),
+            }
+        }
+
+        assert_eq!(
+            non_empty(&manager.orderbook.asks),
+            non_empty(&historic.asks),
+            "{} live asks differ from the historic reconstruction",
+            path.display()
+        );
+        assert_eq!(
+            non_empty(&manager.orderbook.bids),
+            non_empty(&historic.bids),
+            "{} live bids differ from the historic reconstruction",
+            path.display()
+        );
+    }
+}
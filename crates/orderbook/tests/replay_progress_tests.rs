@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use deeplook_orderbook::historic_orderbook::{
+    Op, OrderStep, ReplayProgress, apply_steps_with_progress,
+};
+use prometheus::IntGauge;
+
+const POOL_ID: &str = "0xprogress_test_pool";
+
+fn step(price: i64, quantity: i64, op: Op, checkpoint: i64) -> OrderStep {
+    OrderStep {
+        price,
+        quantity,
+        op,
+        checkpoint,
+        is_bid: true,
+        timestamp: NaiveDateTime::default(),
+    }
+}
+
+fn gauge() -> IntGauge {
+    IntGauge::new("replay_checkpoint_test", "test gauge").unwrap()
+}
+
+#[test]
+fn gauge_follows_logged_checkpoints() {
+    let gauge = gauge();
+    let mut progress = ReplayProgress::new(POOL_ID, 10, 100).with_checkpoint_gauge(gauge.clone());
+
+    progress.record(1);
+    assert_eq!(gauge.get(), 1);
+    // within the interval of the last log
+    progress.record(5);
+    assert_eq!(gauge.get(), 1);
+    progress.record(11);
+    assert_eq!(gauge.get(), 11);
+    assert_eq!(progress.steps(), 3);
+
+    progress.finish();
+    assert_eq!(gauge.get(), 100);
+}
+
+#[test]
+fn zero_interval_disables_logs() {
+    let gauge = gauge();
+    let mut progress = ReplayProgress::new(POOL_ID, 0, 100).with_checkpoint_gauge(gauge.clone());
+
+    progress.record(50);
+    assert_eq!(gauge.get(), 0);
+    assert_eq!(progress.steps(), 1);
+}
+
+#[test]
+fn steps_are_counted_while_applied() {
+    let mut asks = HashMap::new();
+    let mut bids = HashMap::new();
+    let mut progress = ReplayProgress::new(POOL_ID, 1, 3);
+
+    let steps = vec![
+        step(100, 10, Op::Add, 1),
+        step(101, 5, Op::Add, 2),
+        step(100, 4, Op::Subtract, 3),
+    ];
+    apply_steps_with_progress(&mut asks, &mut bids, &steps, POOL_ID, Some(&mut progress)).unwrap();
+
+    assert_eq!(progress.steps(), 3);
+    assert_eq!(bids.get(&100), Some(&6));
+    assert_eq!(bids.get(&101), Some(&5));
+}
@@ -0,0 +1,130 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use deeplook_orderbook::seed_pools::{
+    BookParams, CoinMetadata, PoolMetadataSource, fetch_pool, parse_pool_type, upsert_pool,
+};
+use deeplook_schema::{MIGRATIONS, models::Pool, schema::pools};
+use diesel::{Connection, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper};
+use diesel_migrations::MigrationHarness;
+use serde::Deserialize;
+
+const SUI: &str = "0x0000000000000000000000000000000000000000000000000000000000000002::sui::SUI";
+const USDC: &str = "0xdba34672e30cb065b1f93e3ab55318768fd6fef66c15942c9f7cb846e2f900e7::usdc::USDC";
+
+#[derive(Deserialize)]
+struct FixtureCoin {
+    decimals: u8,
+    symbol: String,
+    name: String,
+}
+
+/// Pool object and coin metadata as returned by the chain
+#[derive(Deserialize)]
+struct PoolFixture {
+    pool_id: String,
+    pool_type: String,
+    book_params: (u64, u64, u64),
+    coins: HashMap<String, FixtureCoin>,
+}
+
+impl PoolFixture {
+    fn load(name: &str) -> Self {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/pools")
+            .join(name);
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+}
+
+#[async_trait]
+impl PoolMetadataSource for PoolFixture {
+    async fn pool_type(&self, pool_id: &str) -> Result<String, anyhow::Error> {
+        (pool_id == self.pool_id)
+            .then(|| self.pool_type.clone())
+            .ok_or_else(|| anyhow!("unknown pool {}", pool_id))
+    }
+
+    async fn book_params(
+        &self,
+        _pool_id: &str,
+        _base_asset_id: &str,
+        _quote_asset_id: &str,
+    ) -> Result<BookParams, anyhow::Error> {
+        let (tick_size, lot_size, min_size) = self.book_params;
+        Ok(BookParams {
+            tick_size,
+            lot_size,
+            min_size,
+        })
+    }
+
+    async fn coin_metadata(&self, coin_type: &str) -> Result<CoinMetadata, anyhow::Error> {
+        let coin = self
+            .coins
+            .get(coin_type)
+            .ok_or_else(|| anyhow!("unknown coin {}", coin_type))?;
+        Ok(CoinMetadata {
+            decimals: coin.decimals,
+            symbol: coin.symbol.clone(),
+            name: coin.name.clone(),
+        })
+    }
+}
+
+#[test]
+fn pool_type_params_are_canonical() {
+    let fixture = PoolFixture::load("sui_usdc.json");
+    assert_eq!(
+        parse_pool_type(&fixture.pool_type).unwrap(),
+        (SUI.to_string(), USDC.to_string())
+    );
+    assert!(parse_pool_type("0x2::sui::SUI").is_err());
+}
+
+#[tokio::test]
+async fn pool_row_is_built_from_fixture() {
+    let fixture = PoolFixture::load("sui_usdc.json");
+    let pool = fetch_pool(&fixture, &fixture.pool_id).await.unwrap();
+
+    assert_eq!(pool.pool_name, "SUI_USDC");
+    assert_eq!(pool.base_asset_id, SUI);
+    assert_eq!(pool.base_asset_decimals, 9);
+    assert_eq!(pool.quote_asset_id, USDC);
+    assert_eq!(pool.quote_asset_decimals, 6);
+    assert_eq!(
+        (pool.tick_size, pool.lot_size, pool.min_size),
+        (1000, 100_000_000, 1_000_000_000)
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn upsert_is_idempotent() {
+    let fixture = PoolFixture::load("sui_usdc.json");
+    let pool = fetch_pool(&fixture, &fixture.pool_id).await.unwrap();
+    let changed = Pool {
+        tick_size: 10,
+        ..fetch_pool(&fixture, &fixture.pool_id).await.unwrap()
+    };
+
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let mut conn = PgConnection::establish(&database_url).unwrap();
+    conn.run_pending_migrations(MIGRATIONS).unwrap();
+
+    conn.test_transaction::<_, diesel::result::Error, _>(|conn| {
+        upsert_pool(conn, &pool)?;
+        upsert_pool(conn, &pool)?;
+        upsert_pool(conn, &changed)?;
+
+        let rows: Vec<Pool> = pools::table
+            .find(&fixture.pool_id)
+            .select(Pool::as_select())
+            .load(conn)?;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].tick_size, 10);
+        assert_eq!(rows[0].pool_name, "SUI_USDC");
+        Ok(())
+    });
+}
@@ -0,0 +1,160 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::DateTime;
+use deeplook_orderbook::{
+    error::DeepLookOrderbookError,
+    orderbook::{BookEvent, BookHealth, InitMode, Orderbook, OrderbookManager},
+};
+use deeplook_schema::models::{OrderFill, Pool};
+use deeplook_server::server::SUI_MAINNET_URL;
+use deeplook_utils::cache::Cache;
+use sui_sdk::SuiClientBuilder;
+use url::Url;
+
+const POOL_ID: &str = "0xe05dafb5133bcffb8d59f4e12465dc0e9faeaa05e3e342a08fe135800e3e4407";
+/// Own name, so the test's Redis key doesn't clash with a running orderbook
+const POOL_NAME: &str = "STRICT_TEST_SUI_USDC";
+const REDIS_KEY: &str = "orderbook::STRICT_TEST_SUI_USDC";
+
+fn book(asks: &[(i64, i64)], bids: &[(i64, i64)]) -> Orderbook {
+    Orderbook {
+        asks: asks.iter().copied().collect::<BTreeMap<_, _>>(),
+        bids: bids.iter().copied().collect::<BTreeMap<_, _>>(),
+    }
+}
+
+#[test]
+fn strict_mode_stops_publishing_corrupted_pool() {
+    // crossed book with a negative level left by a missed placement
+    let corrupted = book(&[(100, 5), (110, -3)], &[(105, 2)]);
+    let valid = book(&[(110, 5)], &[(100, 2)]);
+    let mut health = BookHealth::new(true);
+
+    assert!(health.needs_resync(&corrupted));
+    assert!(!health.should_publish(&corrupted));
+
+    health.resync_failed();
+    assert!(!health.is_healthy());
+    // unhealthy pools stay unpublished even if their book looks valid again
+    assert!(!health.should_publish(&valid));
+
+    health.resynced();
+    assert!(health.is_healthy());
+    assert!(!health.needs_resync(&valid));
+    assert!(health.should_publish(&valid));
+}
+
+#[test]
+fn lenient_mode_publishes_corrupted_pool() {
+    let corrupted = book(&[(100, 5)], &[(105, 2)]);
+    let health = BookHealth::new(false);
+
+    assert!(!health.needs_resync(&corrupted));
+    assert!(health.should_publish(&corrupted));
+}
+
+#[test]
+fn book_with_empty_side_is_valid() {
+    assert!(book(&[(100, 5)], &[]).is_valid());
+    assert!(!book(&[], &[(100, -1)]).is_valid());
+}
+
+fn sui_usdc() -> Pool {
+    Pool {
+        pool_id: POOL_ID.to_string(),
+        pool_name: POOL_NAME.to_string(),
+        base_asset_id: "0x2::sui::SUI".to_string(),
+        base_asset_decimals: 9,
+        base_asset_symbol: "SUI".to_string(),
+        base_asset_name: "Sui".to_string(),
+        quote_asset_id:
+            "0xdba34672e30cb065b1f93e3ab55318768fd6fef66c15942c9f7cb846e2f900e7::usdc::USDC"
+                .to_string(),
+        quote_asset_decimals: 6,
+        quote_asset_symbol: "USDC".to_string(),
+        quote_asset_name: "USDC".to_string(),
+        min_size: 1_000_000_000,
+        lot_size: 100_000_000,
+        tick_size: 1_000,
+    }
+}
+
+/// Fill of an ask at a price the book has no level at, leaving a negative crossed level
+fn missed_fill(checkpoint: i64) -> OrderFill {
+    OrderFill {
+        event_digest: "missed_0".to_string(),
+        digest: "missed".to_string(),
+        sender: "0x0".to_string(),
+        checkpoint,
+        checkpoint_timestamp_ms: 0,
+        timestamp: DateTime::from_timestamp_millis(0).unwrap().naive_utc(),
+        package: "0x0".to_string(),
+        pool_id: POOL_ID.to_string(),
+        maker_order_id: "missed".to_string(),
+        taker_order_id: "taker".to_string(),
+        maker_client_order_id: 0,
+        taker_client_order_id: 0,
+        price: 1_000,
+        taker_fee: 0,
+        taker_fee_is_deep: false,
+        maker_fee: 0,
+        maker_fee_is_deep: false,
+        taker_is_bid: true,
+        base_quantity: 1_000_000_000,
+        quote_quantity: 0,
+        maker_balance_manager_id: "0xmaker".to_string(),
+        taker_balance_manager_id: "0xtaker".to_string(),
+        onchain_timestamp: 0,
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires Redis at localhost and a Sui mainnet full node"]
+async fn corrupted_pool_is_no_longer_published() {
+    let redis_url = Url::parse("redis://localhost:6379").unwrap();
+    let sui_client = Arc::new(
+        SuiClientBuilder::default()
+            .build(SUI_MAINNET_URL)
+            .await
+            .unwrap(),
+    );
+    let mut cache = Cache::new(redis_url.clone());
+    let mut manager = OrderbookManager::new(
+        sui_usdc(),
+        sui_client,
+        Mutex::new(Cache::new(redis_url)),
+        // not read in onchain mode
+        Url::parse("postgres://localhost/unused").unwrap(),
+        InitMode::Onchain,
+    )
+    .unwrap()
+    .with_strict(true);
+
+    let tip = manager.sync_with_chain_at_tip().await.unwrap();
+    let (onchain, _) = manager.get_onchain_orderbook().await.unwrap();
+    assert!(cache.exists(REDIS_KEY).unwrap());
+
+    manager.handle_batch(vec![BookEvent::Fill(missed_fill(tip + 1))]);
+    assert!(manager.needs_resync());
+
+    manager.finish_resync(
+        Err(DeepLookOrderbookError::InternalError(
+            "full node unreachable".to_string(),
+        )),
+        tip + 1,
+    );
+    assert!(!cache.exists(REDIS_KEY).unwrap());
+
+    // a later valid batch doesn't publish the unhealthy pool
+    manager.handle_batch(vec![]);
+    assert!(!cache.exists(REDIS_KEY).unwrap());
+
+    // a resync with a book read at a later checkpoint publishes it again, labeled with that one
+    manager.finish_resync(Ok((onchain, tip + 50)), tip + 2);
+    assert_eq!(manager.initial_checkpoint, tip + 50);
+    assert!(cache.exists(REDIS_KEY).unwrap());
+    cache.del(REDIS_KEY).unwrap();
+}
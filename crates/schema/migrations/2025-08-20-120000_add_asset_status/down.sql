@@ -0,0 +1,3 @@
+ALTER TABLE assets
+    DROP COLUMN IF EXISTS can_deposit,
+    DROP COLUMN IF EXISTS can_withdraw;
@@ -0,0 +1,3 @@
+ALTER TABLE assets
+    ADD COLUMN IF NOT EXISTS can_deposit  BOOLEAN NOT NULL DEFAULT TRUE,
+    ADD COLUMN IF NOT EXISTS can_withdraw BOOLEAN NOT NULL DEFAULT TRUE;
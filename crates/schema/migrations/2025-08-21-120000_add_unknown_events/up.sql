@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS unknown_events
+(
+    event_type       TEXT   PRIMARY KEY,
+    first_checkpoint BIGINT NOT NULL,
+    first_digest     TEXT   NOT NULL,
+    last_checkpoint  BIGINT NOT NULL,
+    occurrences      BIGINT NOT NULL
+);
@@ -0,0 +1,2 @@
+ALTER TABLE trade_params_update
+    DROP COLUMN IF EXISTS epoch;
@@ -0,0 +1,2 @@
+ALTER TABLE trade_params_update
+    ADD COLUMN IF NOT EXISTS epoch BIGINT;
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS decode_failures
+(
+    event_digest            TEXT   PRIMARY KEY,
+    digest                  TEXT   NOT NULL,
+    checkpoint              BIGINT NOT NULL,
+    checkpoint_timestamp_ms BIGINT NOT NULL,
+    event_type              TEXT   NOT NULL,
+    error                   TEXT   NOT NULL
+);
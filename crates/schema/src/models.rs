@@ -1,7 +1,7 @@
 use crate::schema::{
-    balances, balances_summary, flashloans, order_fills, order_updates, orderbook_snapshots,
-    pool_prices, pools, proposals, rebates, stakes, sui_error_transactions, trade_params_update,
-    votes,
+    balances, balances_summary, decode_failures, flashloans, order_fills, order_updates,
+    orderbook_snapshots, pool_prices, pools, proposals, rebates, stakes, sui_error_transactions,
+    trade_params_update, unknown_events, votes,
 };
 use crate::view::{ohlcv_15min, ohlcv_1h, ohlcv_1min, ohlcv_4h};
 use bigdecimal::BigDecimal;
@@ -16,7 +16,9 @@ use std::str::FromStr;
 use strum_macros::{AsRefStr, EnumString};
 use sui_field_count::FieldCount;
 
-#[derive(Queryable, Selectable, Insertable, Identifiable, Debug, FieldCount)]
+#[derive(
+    Queryable, Selectable, Insertable, Identifiable, Debug, FieldCount, Serialize, Deserialize,
+)]
 #[diesel(table_name = order_updates, primary_key(event_digest))]
 pub struct OrderUpdate {
     pub event_digest: String,
@@ -40,7 +42,7 @@ pub struct OrderUpdate {
     pub balance_manager_id: String,
 }
 
-#[derive(Debug, AsExpression, EnumString, AsRefStr)]
+#[derive(Debug, AsExpression, EnumString, AsRefStr, Serialize, Deserialize)]
 #[diesel(sql_type = Text)]
 pub enum OrderUpdateStatus {
     Placed,
@@ -62,7 +64,9 @@ impl ToSql<Text, Pg> for OrderUpdateStatus {
     }
 }
 
-#[derive(Queryable, Selectable, Insertable, Identifiable, Debug, FieldCount)]
+#[derive(
+    Queryable, Selectable, Insertable, Identifiable, Debug, FieldCount, Serialize, Deserialize,
+)]
 #[diesel(table_name = order_fills, primary_key(event_digest))]
 pub struct OrderFill {
     pub event_digest: String,
@@ -90,6 +94,33 @@ pub struct OrderFill {
     pub onchain_timestamp: i64,
 }
 
+/// Trade of the latest trades list the orderbook service keeps in Redis, also the shape
+/// `/recent_trades` serves so clients can switch to the websocket without reconciling schemas
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatestTrade {
+    pub digest: String,
+    pub checkpoint: i64,
+    pub timestamp: NaiveDateTime,
+    pub price: i64,
+    pub base_quantity: i64,
+    pub quote_quantity: i64,
+    pub taker_is_bid: bool,
+}
+
+impl From<&OrderFill> for LatestTrade {
+    fn from(fill: &OrderFill) -> Self {
+        LatestTrade {
+            digest: fill.digest.clone(),
+            checkpoint: fill.checkpoint,
+            timestamp: fill.timestamp,
+            price: fill.price,
+            base_quantity: fill.base_quantity,
+            quote_quantity: fill.quote_quantity,
+            taker_is_bid: fill.taker_is_bid,
+        }
+    }
+}
+
 #[derive(Queryable, FieldCount)]
 pub struct OrderFillSummary {
     pub pool_id: String,
@@ -218,6 +249,8 @@ pub struct TradeParamsUpdate {
     pub taker_fee: i64,
     pub maker_fee: i64,
     pub stake_required: i64,
+    /// Unknown for updates indexed before the epoch was recorded
+    pub epoch: Option<i64>,
 }
 
 #[derive(Queryable, Selectable, Insertable, Identifiable, Debug, FieldCount)]
@@ -239,7 +272,15 @@ pub struct Votes {
 }
 
 #[derive(
-    Queryable, Selectable, Insertable, Identifiable, Debug, FieldCount, Serialize, Deserialize,
+    Queryable,
+    Selectable,
+    Insertable,
+    Identifiable,
+    Debug,
+    Clone,
+    FieldCount,
+    Serialize,
+    Deserialize,
 )]
 #[diesel(table_name = pools, primary_key(pool_id))]
 pub struct Pool {
@@ -269,6 +310,30 @@ pub struct SuiErrorTransactions {
     pub cmd_idx: Option<i64>,
 }
 
+/// DeepBook event type none of the handlers parse, `event_type` has no type parameters
+#[derive(Queryable, Selectable, Insertable, Identifiable, Debug, Clone, PartialEq, FieldCount)]
+#[diesel(table_name = unknown_events, primary_key(event_type))]
+pub struct UnknownEvent {
+    pub event_type: String,
+    pub first_checkpoint: i64,
+    pub first_digest: String,
+    pub last_checkpoint: i64,
+    pub occurrences: i64,
+}
+
+/// DeepBook event of a parsed type whose contents didn't decode, `event_type` keeps its type
+/// parameters
+#[derive(Queryable, Selectable, Insertable, Identifiable, Debug, Clone, PartialEq, FieldCount)]
+#[diesel(table_name = decode_failures, primary_key(event_digest))]
+pub struct DecodeFailure {
+    pub event_digest: String,
+    pub digest: String,
+    pub checkpoint: i64,
+    pub checkpoint_timestamp_ms: i64,
+    pub event_type: String,
+    pub error: String,
+}
+
 #[derive(Queryable, Selectable, Debug, FieldCount)]
 #[diesel(table_name = ohlcv_1min)]
 pub struct OHLCV1min {
@@ -321,8 +386,9 @@ pub struct OHLCV4h {
     pub volume_quote: BigDecimal,
 }
 
-// table agnostic OHLCV
-#[derive(Debug, Queryable)]
+// table agnostic OHLCV, all ohlcv views and raw queries share the columns of ohlcv_1min
+#[derive(Debug, Clone, PartialEq, Queryable, QueryableByName)]
+#[diesel(table_name = ohlcv_1min)]
 pub struct OHLCV {
     pub bucket: NaiveDateTime,
     pub pool_id: String,
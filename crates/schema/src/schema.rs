@@ -10,6 +10,8 @@ diesel::table! {
         ucid -> Nullable<Int4>,
         package_id -> Nullable<Text>,
         package_address_url -> Nullable<Text>,
+        can_deposit -> Bool,
+        can_withdraw -> Bool,
     }
 }
 
@@ -30,6 +32,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    decode_failures (event_digest) {
+        event_digest -> Text,
+        digest -> Text,
+        checkpoint -> Int8,
+        checkpoint_timestamp_ms -> Int8,
+        event_type -> Text,
+        error -> Text,
+    }
+}
+
 diesel::table! {
     flashloans (event_digest, timestamp) {
         event_digest -> Text,
@@ -225,6 +238,17 @@ diesel::table! {
         taker_fee -> Int8,
         maker_fee -> Int8,
         stake_required -> Int8,
+        epoch -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    unknown_events (event_type) {
+        event_type -> Text,
+        first_checkpoint -> Int8,
+        first_digest -> Text,
+        last_checkpoint -> Int8,
+        occurrences -> Int8,
     }
 }
 
@@ -263,6 +287,7 @@ diesel::table! {
 diesel::allow_tables_to_appear_in_same_query!(
     assets,
     balances,
+    decode_failures,
     flashloans,
     order_fills,
     order_updates,
@@ -274,6 +299,7 @@ diesel::allow_tables_to_appear_in_same_query!(
     stakes,
     sui_error_transactions,
     trade_params_update,
+    unknown_events,
     votes,
     watermarks,
 );
@@ -1,13 +1,11 @@
 use bigdecimal::{BigDecimal, ToPrimitive};
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 use serde::Serialize;
 use serde_json::{json, Value};
 use std::{collections::HashMap, i64, sync::Arc};
-use url::Url;
 
 use std::time::{SystemTime, UNIX_EPOCH};
 use sui_sdk::rpc_types::{SuiObjectData, SuiObjectDataOptions, SuiObjectResponse};
-use sui_sdk::SuiClientBuilder;
 use sui_types::{
     base_types::{ObjectID, ObjectRef, SuiAddress},
     programmable_transaction_builder::ProgrammableTransactionBuilder,
@@ -15,8 +13,8 @@ use sui_types::{
 };
 
 use crate::server::{
-    naive_datetime_from_millis, parse_type_input, DEEPBOOK_PACKAGE_ID, LEVEL2_FUNCTION,
-    LEVEL2_MODULE,
+    naive_datetime_from_millis, parse_depth, parse_type_input, parse_window_ms,
+    price_scaling_factor, DEEPBOOK_PACKAGE_ID, LEVEL2_FUNCTION, LEVEL2_MODULE,
 };
 
 use diesel::prelude::*;
@@ -30,34 +28,276 @@ use diesel::{
 
 use axum::{
     extract::{Path, Query, State},
+    http::HeaderMap,
+    response::Response,
     Json,
 };
 
+use crate::csv::{rows_response, wants_csv};
 use crate::error::DeepBookError;
+use crate::params::CommonParams;
+use crate::rpc_retry::retry_rpc;
 use crate::server::{AppState, ParameterUtil};
 use deeplook_schema::{
-    models::{OrderFill24hSummary, OHLCV},
+    models::{OrderFill24hSummary, Pool, OHLCV},
     schema, view,
 };
 
-const AVAILABLE_OHLCV_TIMEFRAMES: [&str; 4] = ["1m", "15m", "1h", "4h"];
+/// Supported OHLCV timeframes with their bucket length in milliseconds, finest first
+pub const OHLCV_TIMEFRAMES: [(&str, i64); 4] = [
+    ("1m", 60 * 1000),
+    ("15m", 15 * 60 * 1000),
+    ("1h", 60 * 60 * 1000),
+    ("4h", 4 * 60 * 60 * 1000),
+];
+
+/// Fields of an OHLCV candle, all are returned unless `fields` selects some
+pub const OHLCV_FIELDS: [&str; 8] = [
+    "timestamp",
+    "open",
+    "high",
+    "low",
+    "close",
+    "volume_base",
+    "volume_quote",
+    "interval",
+];
+
+/// Max number of bars a moving average of `/indicators` may span
+pub const INDICATOR_MAX_PERIOD: usize = 500;
+
+/// Window of `/volatility` when none is requested
+pub const VOLATILITY_DEFAULT_WINDOW: &str = "24h";
+
+/// Pools trade around the clock, annualized volatility scales by buckets per 365 days
+pub const MS_PER_YEAR: i64 = 365 * 24 * 60 * 60 * 1000;
+
+/// TWAP is computed from 1 minute closes
+pub const TWAP_BUCKET_MS: i64 = 60 * 1000;
+
+/// Default max number of candles when timeframe is not requested
+pub const OHLCV_MAX_POINTS: i64 = 1500;
+
+/// Default max number of rows an aggregation query may return
+pub const MAX_AGGREGATION_ROWS: i64 = 10_000;
+
+/// Wraps a raw SQL aggregation to fetch at most `max_rows + 1` rows,
+/// so `enforce_row_cap` can tell an oversized result from a full one
+pub fn capped_query(query: &str, max_rows: i64) -> String {
+    format!(
+        "SELECT * FROM ({}) capped LIMIT {}",
+        query.trim(),
+        max_rows.saturating_add(1)
+    )
+}
+
+/// Rejects the request instead of serving a truncated result when more than `max_rows` rows
+/// came back
+pub fn enforce_row_cap<T>(rows: Vec<T>, max_rows: i64) -> Result<Vec<T>, DeepBookError> {
+    if rows.len() as i64 > max_rows {
+        return Err(DeepBookError::BadRequest(format!(
+            "More than {} rows in the requested range, narrow it",
+            max_rows
+        )));
+    }
+    Ok(rows)
+}
+
+/// OHLCV from raw `order_fills` with the same bucketing as TimescaleDB `time_bucket`.
+/// Binds: bucket seconds, pool id, start and end of the bucket range.
+pub const RAW_OHLCV_QUERY: &str = r#"
+SELECT * FROM (
+    SELECT
+        date_bin($1 * INTERVAL '1 second', timestamp, TIMESTAMP '2000-01-03') AS bucket,
+        pool_id,
+        (array_agg(price ORDER BY timestamp))[1]      AS open,
+        MAX(price)                                    AS high,
+        MIN(price)                                    AS low,
+        (array_agg(price ORDER BY timestamp DESC))[1] AS close,
+        SUM(base_quantity)                            AS volume_base,
+        SUM(quote_quantity)                           AS volume_quote
+    FROM order_fills
+    WHERE pool_id = $2
+      AND timestamp >= $3
+      AND timestamp < $4 + $1 * INTERVAL '1 second'
+    GROUP BY 1, pool_id
+) ohlcv
+WHERE bucket BETWEEN $3 AND $4
+ORDER BY bucket
+"#;
+
+fn invalid_ohlcv_timeframe(timeframe: &str) -> DeepBookError {
+    DeepBookError::BadRequest(format!(
+        "Invalid timeframe `{}`, must be one of: [{}]",
+        timeframe,
+        OHLCV_TIMEFRAMES
+            .iter()
+            .map(|(label, _)| *label)
+            .collect::<Vec<_>>()
+            .join(",")
+    ))
+}
+
+/// Candle fields of the comma separated `fields` query parameter, `None` keeps all of them
+pub fn parse_ohlcv_fields(fields: Option<&str>) -> Result<Option<Vec<&str>>, DeepBookError> {
+    let Some(fields) = fields else {
+        return Ok(None);
+    };
+    let fields: Vec<&str> = fields.split(',').map(str::trim).collect();
+    if let Some(unknown) = fields.iter().find(|field| !OHLCV_FIELDS.contains(field)) {
+        return Err(DeepBookError::BadRequest(format!(
+            "Invalid field `{}`, must be one of: [{}]",
+            unknown,
+            OHLCV_FIELDS.join(",")
+        )));
+    }
+    Ok(Some(fields))
+}
+
+/// Picks the finest timeframe which fits the range into `max_points` candles,
+/// falls back to the coarsest one
+pub fn select_ohlcv_timeframe(start_time: i64, end_time: i64, max_points: i64) -> &'static str {
+    let range = (end_time - start_time).max(0);
+    OHLCV_TIMEFRAMES
+        .iter()
+        .find(|(_, bucket_ms)| range / bucket_ms <= max_points)
+        .unwrap_or(&OHLCV_TIMEFRAMES[OHLCV_TIMEFRAMES.len() - 1])
+        .0
+}
+
+/// Candles of a pool from the cagg of `timeframe`, or raw fills when caggs aren't available.
+/// Cagg rows come unordered. Fails when the range has more than `max_aggregation_rows` candles.
+pub(crate) async fn load_ohlcv(
+    state: &AppState,
+    pool_id: &str,
+    timeframe: &str,
+    start_dt: NaiveDateTime,
+    end_dt: NaiveDateTime,
+) -> Result<Vec<OHLCV>, DeepBookError> {
+    let max_rows = state.config.max_aggregation_rows;
+    let rows = if state.config.ohlcv_from_raw {
+        let (_, bucket_ms) = OHLCV_TIMEFRAMES
+            .iter()
+            .find(|(label, _)| *label == timeframe)
+            .ok_or_else(|| invalid_ohlcv_timeframe(timeframe))?;
+        state
+            .reader
+            .get_ohlcv_from_raw(pool_id, bucket_ms / 1000, start_dt, end_dt, max_rows)
+            .await?
+    } else {
+        match timeframe {
+            "1m" => {
+                state
+                    .reader
+                    .results(
+                        view::ohlcv_1min::table
+                            .select((
+                                view::ohlcv_1min::bucket,
+                                view::ohlcv_1min::pool_id,
+                                view::ohlcv_1min::open,
+                                view::ohlcv_1min::high,
+                                view::ohlcv_1min::low,
+                                view::ohlcv_1min::close,
+                                view::ohlcv_1min::volume_base,
+                                view::ohlcv_1min::volume_quote,
+                            ))
+                            .filter(view::ohlcv_1min::pool_id.eq(pool_id.to_string()))
+                            .filter(view::ohlcv_1min::bucket.between(start_dt, end_dt))
+                            .limit(max_rows.saturating_add(1)),
+                    )
+                    .await?
+            }
+            "15m" => {
+                state
+                    .reader
+                    .results(
+                        view::ohlcv_15min::table
+                            .select((
+                                view::ohlcv_15min::bucket,
+                                view::ohlcv_15min::pool_id,
+                                view::ohlcv_15min::open,
+                                view::ohlcv_15min::high,
+                                view::ohlcv_15min::low,
+                                view::ohlcv_15min::close,
+                                view::ohlcv_15min::volume_base,
+                                view::ohlcv_15min::volume_quote,
+                            ))
+                            .filter(view::ohlcv_15min::pool_id.eq(pool_id.to_string()))
+                            .filter(view::ohlcv_15min::bucket.between(start_dt, end_dt))
+                            .limit(max_rows.saturating_add(1)),
+                    )
+                    .await?
+            }
+            "1h" => {
+                state
+                    .reader
+                    .results(
+                        view::ohlcv_1h::table
+                            .select((
+                                view::ohlcv_1h::bucket,
+                                view::ohlcv_1h::pool_id,
+                                view::ohlcv_1h::open,
+                                view::ohlcv_1h::high,
+                                view::ohlcv_1h::low,
+                                view::ohlcv_1h::close,
+                                view::ohlcv_1h::volume_base,
+                                view::ohlcv_1h::volume_quote,
+                            ))
+                            .filter(view::ohlcv_1h::pool_id.eq(pool_id.to_string()))
+                            .filter(view::ohlcv_1h::bucket.between(start_dt, end_dt))
+                            .limit(max_rows.saturating_add(1)),
+                    )
+                    .await?
+            }
+            "4h" => {
+                state
+                    .reader
+                    .results(
+                        view::ohlcv_4h::table
+                            .select((
+                                view::ohlcv_4h::bucket,
+                                view::ohlcv_4h::pool_id,
+                                view::ohlcv_4h::open,
+                                view::ohlcv_4h::high,
+                                view::ohlcv_4h::low,
+                                view::ohlcv_4h::close,
+                                view::ohlcv_4h::volume_base,
+                                view::ohlcv_4h::volume_quote,
+                            ))
+                            .filter(view::ohlcv_4h::pool_id.eq(pool_id.to_string()))
+                            .filter(view::ohlcv_4h::bucket.between(start_dt, end_dt))
+                            .limit(max_rows.saturating_add(1)),
+                    )
+                    .await?
+            }
+            _ => return Err(invalid_ohlcv_timeframe(timeframe)),
+        }
+    };
+    enforce_row_cap(rows, max_rows)
+}
 
 pub async fn get_ohlcv(
     Path(pool_name): Path<String>,
     Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
+    headers: HeaderMap,
     State(state): State<Arc<AppState>>,
-) -> Result<Json<Vec<HashMap<String, Value>>>, DeepBookError> {
+) -> Result<Response, DeepBookError> {
+    let fields = parse_ohlcv_fields(params.get("fields").map(String::as_str))?;
+    let csv = wants_csv(&params, &headers)?;
     let (pool_id, base_decimals, quote_decimals) =
         state.reader.get_pool_decimals(&pool_name).await?;
 
-    let timeframe = params.ohlcv_timeframe();
-
     // Parse start_time and end_time from query parameters (in seconds) and convert to milliseconds
-    let end_time = params.end_time();
-    let start_time = params
-        .start_time()
+    let end_time = common.end_time_ms();
+    let start_time = common
+        .start_time_ms()
         .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
 
+    let timeframe = params.ohlcv_timeframe().unwrap_or_else(|| {
+        select_ohlcv_timeframe(start_time, end_time, state.config.ohlcv_max_points)
+    });
+
     let start_dt = DateTime::from_timestamp_millis(start_time)
         .unwrap()
         .naive_utc();
@@ -65,135 +305,335 @@ pub async fn get_ohlcv(
         .unwrap()
         .naive_utc();
 
-    // Query the right cagg; reuse the same OHLCV (Queryable-only) model
-    let rows: Vec<OHLCV> = match timeframe {
-        "1m" => {
-            state
-                .reader
-                .results(
-                    view::ohlcv_1min::table
-                        .select((
-                            view::ohlcv_1min::bucket,
-                            view::ohlcv_1min::pool_id,
-                            view::ohlcv_1min::open,
-                            view::ohlcv_1min::high,
-                            view::ohlcv_1min::low,
-                            view::ohlcv_1min::close,
-                            view::ohlcv_1min::volume_base,
-                            view::ohlcv_1min::volume_quote,
-                        ))
-                        .filter(view::ohlcv_1min::pool_id.eq(pool_id.to_string()))
-                        .filter(view::ohlcv_1min::bucket.between(start_dt, end_dt)),
-                )
-                .await?
-        }
-        "15m" => {
-            state
-                .reader
-                .results(
-                    view::ohlcv_15min::table
-                        .select((
-                            view::ohlcv_15min::bucket,
-                            view::ohlcv_15min::pool_id,
-                            view::ohlcv_15min::open,
-                            view::ohlcv_15min::high,
-                            view::ohlcv_15min::low,
-                            view::ohlcv_15min::close,
-                            view::ohlcv_15min::volume_base,
-                            view::ohlcv_15min::volume_quote,
-                        ))
-                        .filter(view::ohlcv_15min::pool_id.eq(pool_id.to_string()))
-                        .filter(view::ohlcv_15min::bucket.between(start_dt, end_dt)),
-                )
-                .await?
-        }
-        "1h" => {
-            state
-                .reader
-                .results(
-                    view::ohlcv_1h::table
-                        .select((
-                            view::ohlcv_1h::bucket,
-                            view::ohlcv_1h::pool_id,
-                            view::ohlcv_1h::open,
-                            view::ohlcv_1h::high,
-                            view::ohlcv_1h::low,
-                            view::ohlcv_1h::close,
-                            view::ohlcv_1h::volume_base,
-                            view::ohlcv_1h::volume_quote,
-                        ))
-                        .filter(view::ohlcv_1h::pool_id.eq(pool_id.to_string()))
-                        .filter(view::ohlcv_1h::bucket.between(start_dt, end_dt)),
-                )
-                .await?
-        }
-        "4h" => {
-            state
-                .reader
-                .results(
-                    view::ohlcv_4h::table
-                        .select((
-                            view::ohlcv_4h::bucket,
-                            view::ohlcv_4h::pool_id,
-                            view::ohlcv_4h::open,
-                            view::ohlcv_4h::high,
-                            view::ohlcv_4h::low,
-                            view::ohlcv_4h::close,
-                            view::ohlcv_4h::volume_base,
-                            view::ohlcv_4h::volume_quote,
-                        ))
-                        .filter(view::ohlcv_4h::pool_id.eq(pool_id.to_string()))
-                        .filter(view::ohlcv_4h::bucket.between(start_dt, end_dt)),
-                )
-                .await?
-        }
-        _ => {
-            return Err(DeepBookError::InternalError(format!(
-                "Invalid timeframe `{}`, must be one of: [{}]",
-                timeframe,
-                AVAILABLE_OHLCV_TIMEFRAMES.join(",")
-            )))
-        }
-    };
-
-    // Same scaling math as before
-    let bd = base_decimals as u8;
-    let qd = quote_decimals as u8;
-    let base_factor = (10f64).powf(bd.into());
-    let quote_factor = (10f64).powf(qd.into());
-    let price_factor = (10f64).powf((9i32 - bd as i32 + qd as i32) as f64);
+    let rows = load_ohlcv(&state, &pool_id, timeframe, start_dt, end_dt).await?;
+    let scale = OhlcvScale::new(base_decimals as u8, quote_decimals as u8)?;
 
     let out = rows
         .into_iter()
         .map(|ohlc| {
-            let vol_b = (ohlc.volume_base / base_factor).to_plain_string();
-            let vol_q = (ohlc.volume_quote / quote_factor).to_plain_string();
-            let open = ohlc.open as f64 / price_factor;
-            let high = ohlc.high as f64 / price_factor;
-            let low = ohlc.low as f64 / price_factor;
-            let close = ohlc.close as f64 / price_factor;
+            let mut candle = scale.candle(ohlc, timeframe);
+            if let Some(fields) = &fields {
+                candle.retain(|field, _| fields.contains(&field.as_str()));
+            }
+            candle
+        })
+        .collect();
 
-            HashMap::from([
-                (
-                    "timestamp".to_string(),
-                    Value::from(ohlc.bucket.and_utc().timestamp()),
-                ),
-                ("open".to_string(), Value::from(open)),
-                ("high".to_string(), Value::from(high)),
-                ("low".to_string(), Value::from(low)),
-                ("close".to_string(), Value::from(close)),
-                ("volume_base".to_string(), Value::from(vol_b)),
-                ("volume_quote".to_string(), Value::from(vol_q)),
-            ])
+    Ok(rows_response(out, csv))
+}
+
+/// Factors scaling raw candles of a pool by its asset decimals
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OhlcvScale {
+    pub base_factor: f64,
+    pub quote_factor: f64,
+    pub price_factor: f64,
+}
+
+impl OhlcvScale {
+    pub fn new(base_decimals: u8, quote_decimals: u8) -> Result<Self, DeepBookError> {
+        Ok(Self {
+            base_factor: (10f64).powf(base_decimals.into()),
+            quote_factor: (10f64).powf(quote_decimals.into()),
+            price_factor: price_scaling_factor(base_decimals.into(), quote_decimals.into())?,
+        })
+    }
+
+    /// Candle as served by `/ohlcv` and `/ws_ohlcv`, `timestamp` is the bucket start in seconds
+    pub fn candle(&self, ohlc: OHLCV, timeframe: &str) -> HashMap<String, Value> {
+        let vol_b = (ohlc.volume_base / self.base_factor).to_plain_string();
+        let vol_q = (ohlc.volume_quote / self.quote_factor).to_plain_string();
+        HashMap::from([
+            (
+                "timestamp".to_string(),
+                Value::from(ohlc.bucket.and_utc().timestamp()),
+            ),
+            (
+                "open".to_string(),
+                Value::from(ohlc.open as f64 / self.price_factor),
+            ),
+            (
+                "high".to_string(),
+                Value::from(ohlc.high as f64 / self.price_factor),
+            ),
+            (
+                "low".to_string(),
+                Value::from(ohlc.low as f64 / self.price_factor),
+            ),
+            (
+                "close".to_string(),
+                Value::from(ohlc.close as f64 / self.price_factor),
+            ),
+            ("volume_base".to_string(), Value::from(vol_b)),
+            ("volume_quote".to_string(), Value::from(vol_q)),
+            ("interval".to_string(), Value::from(timeframe)),
+        ])
+    }
+}
+
+/// Timeframe and bucket length of the `interval` query parameter of `/ws_ohlcv`, 1 minute
+/// when missing
+pub fn parse_ohlcv_interval(interval: Option<&str>) -> Result<(&'static str, i64), DeepBookError> {
+    let interval = interval.unwrap_or(OHLCV_TIMEFRAMES[0].0);
+    OHLCV_TIMEFRAMES
+        .iter()
+        .find(|(label, _)| *label == interval)
+        .copied()
+        .ok_or_else(|| invalid_ohlcv_timeframe(interval))
+}
+
+/// Time by which buckets count as closed: `now_ms`, or the `timestamp_ms_hi_inclusive` of the
+/// `order_fills` watermark while the indexer lags behind. `None` before anything is indexed.
+pub fn candles_closed_by(now_ms: i64, indexed_ms: Option<i64>) -> Option<i64> {
+    indexed_ms.map(|indexed_ms| indexed_ms.min(now_ms))
+}
+
+/// Candles of buckets that closed by `closed_by_ms` and started after `after_ms`, oldest first
+pub fn completed_candles(
+    mut rows: Vec<OHLCV>,
+    after_ms: Option<i64>,
+    closed_by_ms: i64,
+    bucket_ms: i64,
+) -> Vec<OHLCV> {
+    rows.retain(|row| {
+        let start = row.bucket.and_utc().timestamp_millis();
+        start + bucket_ms <= closed_by_ms && after_ms.is_none_or(|after| start > after)
+    });
+    rows.sort_by_key(|row| row.bucket);
+    rows
+}
+
+/// Moving average kind of `/indicators`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MovingAverage {
+    Sma,
+    Ema,
+}
+
+impl MovingAverage {
+    /// `sma` or `ema` of the `type` query parameter, SMA when missing
+    pub fn parse(kind: Option<&str>) -> Result<Self, DeepBookError> {
+        match kind {
+            None | Some("sma") => Ok(MovingAverage::Sma),
+            Some("ema") => Ok(MovingAverage::Ema),
+            Some(other) => Err(DeepBookError::BadRequest(format!(
+                "Invalid type `{}`, must be one of: [sma,ema]",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct IndicatorPoint {
+    pub timestamp: i64,
+    pub value: f64,
+}
+
+/// Moving average of `(timestamp, close)` pairs ordered by time. The first value is at the
+/// `period`-th close, so fewer than `period` closes give no values. EMA starts from the SMA of
+/// the first `period` closes.
+pub fn moving_average(
+    closes: &[(i64, f64)],
+    period: usize,
+    kind: MovingAverage,
+) -> Vec<IndicatorPoint> {
+    if period == 0 || closes.len() < period {
+        return vec![];
+    }
+    let mut sum: f64 = closes[..period].iter().map(|(_, close)| close).sum();
+    let mut value = sum / period as f64;
+    let mut points = vec![IndicatorPoint {
+        timestamp: closes[period - 1].0,
+        value,
+    }];
+    let alpha = 2.0 / (period as f64 + 1.0);
+    for (i, (timestamp, close)) in closes.iter().enumerate().skip(period) {
+        value = match kind {
+            MovingAverage::Sma => {
+                sum += close - closes[i - period].1;
+                sum / period as f64
+            }
+            MovingAverage::Ema => alpha * close + (1.0 - alpha) * value,
+        };
+        points.push(IndicatorPoint {
+            timestamp: *timestamp,
+            value,
+        });
+    }
+    points
+}
+
+/// SMA or EMA of a pool's candle closes over `period` candles of `timeframe`. Only candles
+/// within the time range count, so the series starts `period - 1` candles after the first one.
+pub async fn get_indicators(
+    Path(pool_name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<IndicatorPoint>>, DeepBookError> {
+    let kind = MovingAverage::parse(params.get("type").map(String::as_str))?;
+    let period = params
+        .get("period")
+        .ok_or_else(|| DeepBookError::BadRequest("Missing period".to_string()))?
+        .parse::<usize>()
+        .ok()
+        .filter(|period| (1..=INDICATOR_MAX_PERIOD).contains(period))
+        .ok_or_else(|| {
+            DeepBookError::BadRequest(format!(
+                "period must be between 1 and {}",
+                INDICATOR_MAX_PERIOD
+            ))
+        })?;
+
+    let (pool_id, base_decimals, quote_decimals) =
+        state.reader.get_pool_decimals(&pool_name).await?;
+
+    let end_time = common.end_time_ms();
+    let start_time = common
+        .start_time_ms()
+        .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
+    let timeframe = params.ohlcv_timeframe().unwrap_or_else(|| {
+        select_ohlcv_timeframe(start_time, end_time, state.config.ohlcv_max_points)
+    });
+
+    let mut rows = load_ohlcv(
+        &state,
+        &pool_id,
+        timeframe,
+        naive_datetime_from_millis(start_time)?,
+        naive_datetime_from_millis(end_time)?,
+    )
+    .await?;
+    rows.sort_by_key(|row| row.bucket);
+
+    let price_factor = price_scaling_factor(base_decimals, quote_decimals)?;
+    let closes: Vec<(i64, f64)> = rows
+        .iter()
+        .map(|row| {
+            (
+                row.bucket.and_utc().timestamp(),
+                row.close as f64 / price_factor,
+            )
         })
         .collect();
 
-    Ok(Json(out))
+    Ok(Json(moving_average(&closes, period, kind)))
 }
 
-pub async fn avg_trade_size(
+/// Sample standard deviation of the log returns between consecutive closes, `None` with fewer
+/// than two returns
+pub fn realized_volatility(closes: &[f64]) -> Option<f64> {
+    let returns: Vec<f64> = closes
+        .windows(2)
+        .map(|pair| (pair[1] / pair[0]).ln())
+        .collect();
+    sample_std_dev(&returns)
+}
+
+/// Yearly volatility of `(timestamp_ms, close)` pairs. Each log return is scaled by the time
+/// between its two closes, so a return spanning buckets without trades isn't counted as one
+/// bucket's move. `None` with fewer than two returns.
+pub fn annualized_volatility(closes: &[(i64, f64)]) -> Option<f64> {
+    let returns: Vec<f64> = closes
+        .windows(2)
+        .filter(|pair| pair[1].0 > pair[0].0)
+        .map(|pair| (pair[1].1 / pair[0].1).ln() * annualization_factor(pair[1].0 - pair[0].0))
+        .collect();
+    sample_std_dev(&returns)
+}
+
+fn sample_std_dev(values: &[f64]) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance =
+        values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+    Some(variance.sqrt())
+}
+
+/// Multiplier turning the volatility of `bucket_ms` returns into yearly volatility
+pub fn annualization_factor(bucket_ms: i64) -> f64 {
+    (MS_PER_YEAR as f64 / bucket_ms as f64).sqrt()
+}
+
+/// Realized volatility of a pool's candle closes of `timeframe` over `window` ending at
+/// `end_time`. Buckets without trades have no candle, so returns of illiquid pools may span
+/// several buckets, which annualizing accounts for.
+pub async fn get_volatility(
     Path(pool_name): Path<String>,
     Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<HashMap<String, Value>>, DeepBookError> {
+    let window = params
+        .get("window")
+        .map(String::as_str)
+        .unwrap_or(VOLATILITY_DEFAULT_WINDOW);
+    let window_ms = parse_window_ms(window)
+        .ok_or_else(|| DeepBookError::BadRequest(format!("Invalid window: {}", window)))?;
+    let annualize = match params.get("annualize").map(String::as_str) {
+        None | Some("false") => false,
+        Some("true") => true,
+        Some(other) => {
+            return Err(DeepBookError::BadRequest(format!(
+                "Invalid annualize `{}`, must be true or false",
+                other
+            )))
+        }
+    };
+
+    let pool_id = state.reader.get_pool_id_by_name(&pool_name).await?;
+
+    let end_time = common.end_time_ms();
+    let start_time = end_time - window_ms;
+    let timeframe = params.ohlcv_timeframe().unwrap_or_else(|| {
+        select_ohlcv_timeframe(start_time, end_time, state.config.ohlcv_max_points)
+    });
+    let (timeframe, _) = *OHLCV_TIMEFRAMES
+        .iter()
+        .find(|(label, _)| *label == timeframe)
+        .ok_or_else(|| invalid_ohlcv_timeframe(timeframe))?;
+
+    let mut rows = load_ohlcv(
+        &state,
+        &pool_id,
+        timeframe,
+        naive_datetime_from_millis(start_time)?,
+        naive_datetime_from_millis(end_time)?,
+    )
+    .await?;
+    rows.sort_by_key(|row| row.bucket);
+
+    // log returns don't depend on the price scale, raw closes do
+    let closes: Vec<(i64, f64)> = rows
+        .iter()
+        .filter(|row| row.close > 0)
+        .map(|row| (row.bucket.and_utc().timestamp_millis(), row.close as f64))
+        .collect();
+    let volatility = if annualize {
+        annualized_volatility(&closes)
+    } else {
+        let closes: Vec<f64> = closes.iter().map(|(_, close)| *close).collect();
+        realized_volatility(&closes)
+    };
+
+    Ok(Json(HashMap::from([
+        ("volatility".to_string(), Value::from(volatility)),
+        (
+            "samples".to_string(),
+            Value::from(closes.len().saturating_sub(1)),
+        ),
+        ("window".to_string(), Value::from(window)),
+        ("timeframe".to_string(), Value::from(timeframe)),
+        ("annualized".to_string(), Value::from(annualize)),
+    ])))
+}
+
+pub async fn avg_trade_size(
+    Path(pool_name): Path<String>,
+    common: CommonParams,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<HashMap<String, Value>>, DeepBookError> {
     // Fetch all pools to map names to IDs and decimals
@@ -201,9 +641,9 @@ pub async fn avg_trade_size(
         state.reader.get_pool_decimals(&pool_name).await?;
 
     // Parse start_time and end_time
-    let end_time = params.end_time();
-    let start_time = params
-        .start_time()
+    let end_time = common.end_time_ms();
+    let start_time = common
+        .start_time_ms()
         .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
 
     let base_decimals = base_decimals as u8;
@@ -260,17 +700,18 @@ pub async fn avg_trade_size(
 
 pub async fn avg_duration_between_trades(
     Path(pool_name): Path<String>,
-    Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Value>, DeepBookError> {
     // Fetch all pools to map names to IDs and decimals
     let (pool_id, _, _) = state.reader.get_pool_decimals(&pool_name).await?;
     // Parse start_time and end_time
-    let end_time = params.end_time();
-    let start_time = params
-        .start_time()
+    let end_time = common.end_time_ms();
+    let start_time = common
+        .start_time_ms()
         .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
 
+    let max_rows = state.config.max_aggregation_rows;
     let trades = state
         .reader
         .get_orders(
@@ -278,15 +719,18 @@ pub async fn avg_duration_between_trades(
             pool_id,
             start_time,
             end_time,
-            i64::MAX,
+            max_rows.saturating_add(1),
             None,
             None,
+            false,
+            None,
         )
         .await?;
+    let trades = enforce_row_cap(trades, max_rows)?;
 
     let timestamps: Vec<i64> = trades
         .into_iter()
-        .map(|(_, _, _, _, _, timestamp, _, _, _)| timestamp)
+        .map(|(_, _, _, _, _, timestamp, _, _, _, _)| timestamp)
         .rev()
         .collect();
 
@@ -301,18 +745,19 @@ pub async fn avg_duration_between_trades(
 
 pub async fn get_vwap(
     Path(pool_name): Path<String>,
-    Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Option<f64>>, DeepBookError> {
     // Fetch all pools to map names to IDs and decimals
     let (pool_id, base_decimals, quote_decimals) =
         state.reader.get_pool_decimals(&pool_name).await?;
     // Parse start_time and end_time
-    let end_time = params.end_time();
-    let start_time = params
-        .start_time()
+    let end_time = common.end_time_ms();
+    let start_time = common
+        .start_time_ms()
         .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
 
+    let max_rows = state.config.max_aggregation_rows;
     let trades = state
         .reader
         .get_orders(
@@ -320,21 +765,24 @@ pub async fn get_vwap(
             pool_id,
             start_time,
             end_time,
-            i64::MAX,
+            max_rows.saturating_add(1),
             None,
             None,
+            false,
+            None,
         )
         .await?;
+    let trades = enforce_row_cap(trades, max_rows)?;
 
     // Conversion factors for decimals
     let base_factor = (10u64).pow(base_decimals as u32);
-    let price_factor = (10u64).pow((9 - base_decimals + quote_decimals) as u32);
+    let price_factor = price_scaling_factor(base_decimals, quote_decimals)?;
 
     let mut total_price_qty: f64 = 0.0;
     let mut total_qty: f64 = 0.0;
 
-    for (_, _, price, base_quantity, _, _, _, _, _) in trades {
-        let scaled_price = (price as f64) / (price_factor as f64);
+    for (_, _, price, base_quantity, _, _, _, _, _, _) in trades {
+        let scaled_price = (price as f64) / price_factor;
         let scaled_base_quantity = (base_quantity as f64) / (base_factor as f64);
 
         total_price_qty += scaled_price * scaled_base_quantity;
@@ -350,85 +798,129 @@ pub async fn get_vwap(
     Ok(Json(vwap))
 }
 
-pub async fn orderbook_imbalance(
+pub async fn get_twap(
     Path(pool_name): Path<String>,
-    Query(params): Query<HashMap<String, String>>,
-    State((state, rpc_url)): State<(Arc<AppState>, Url)>,
-) -> Result<Json<HashMap<String, Value>>, DeepBookError> {
-    let depth = params
-        .get("depth")
-        .map(|v| v.parse::<u64>())
-        .transpose()
-        .map_err(|_| {
-            DeepBookError::InternalError("Depth must be a non-negative integer".to_string())
-        })?
-        .map(|depth| if depth == 0 { 200 } else { depth });
-
-    if let Some(depth) = depth {
-        if depth == 1 {
-            return Err(DeepBookError::InternalError(
-                "Depth cannot be 1. Use a value greater than 1 or 0 for the entire orderbook"
-                    .to_string(),
-            ));
-        }
-    }
+    common: CommonParams,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Option<f64>>, DeepBookError> {
+    let (pool_id, base_decimals, quote_decimals) =
+        state.reader.get_pool_decimals(&pool_name).await?;
+    let end_time = common.end_time_ms();
+    let start_time = common
+        .start_time_ms()
+        .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
 
-    let level = params
-        .get("level")
-        .map(|v| v.parse::<u64>())
-        .transpose()
-        .map_err(|_| {
-            DeepBookError::InternalError("Level must be an integer between 1 and 2".to_string())
-        })?;
+    let start_dt = DateTime::from_timestamp_millis(start_time)
+        .unwrap()
+        .naive_utc();
+    let end_dt = DateTime::from_timestamp_millis(end_time)
+        .unwrap()
+        .naive_utc();
 
-    if let Some(level) = level {
-        if !(1..=2).contains(&level) {
-            return Err(DeepBookError::InternalError(
-                "Level must be 1 or 2".to_string(),
-            ));
-        }
+    // Per-minute closes ordered by bucket
+    let max_rows = state.config.max_aggregation_rows;
+    let closes: Vec<(NaiveDateTime, i64)> = if state.config.ohlcv_from_raw {
+        state
+            .reader
+            .get_ohlcv_from_raw(&pool_id, TWAP_BUCKET_MS / 1000, start_dt, end_dt, max_rows)
+            .await?
+            .into_iter()
+            .map(|row| (row.bucket, row.close))
+            .collect()
+    } else {
+        state
+            .reader
+            .results(
+                view::ohlcv_1min::table
+                    .select((view::ohlcv_1min::bucket, view::ohlcv_1min::close))
+                    .filter(view::ohlcv_1min::pool_id.eq(pool_id))
+                    .filter(view::ohlcv_1min::bucket.between(start_dt, end_dt))
+                    .order_by(view::ohlcv_1min::bucket.asc())
+                    .limit(max_rows.saturating_add(1)),
+            )
+            .await?
+    };
+    let closes = enforce_row_cap(closes, max_rows)?;
+
+    let price_factor = price_scaling_factor(base_decimals, quote_decimals)?;
+    let closes: Vec<(i64, f64)> = closes
+        .into_iter()
+        .map(|(bucket, close)| {
+            (
+                bucket.and_utc().timestamp_millis(),
+                (close as f64) / price_factor,
+            )
+        })
+        .collect();
+
+    Ok(Json(time_weighted_average(
+        &closes,
+        TWAP_BUCKET_MS,
+        end_time,
+    )))
+}
+
+/// Average of bucket close prices weighted by how long each price held. A close holds until
+/// the next bucket with trades, the last one until `end_time` but at least for one bucket.
+/// Returns None when there are no closes.
+pub fn time_weighted_average(closes: &[(i64, f64)], bucket_ms: i64, end_time: i64) -> Option<f64> {
+    let mut total_price_duration = 0.0;
+    let mut total_duration = 0.0;
+
+    for (i, (bucket_start, price)) in closes.iter().enumerate() {
+        let held_until = closes
+            .get(i + 1)
+            .map(|(next_start, _)| *next_start)
+            .unwrap_or_else(|| end_time.max(bucket_start + bucket_ms));
+        let duration = (held_until - bucket_start) as f64;
+
+        total_price_duration += price * duration;
+        total_duration += duration;
     }
 
-    let ticks_from_mid = match (depth, level) {
-        (Some(_), Some(1)) => 1u64, // Depth + Level 1 → Best bid and ask
-        (Some(depth), Some(2)) | (Some(depth), None) => depth / 2, // Depth + Level 2 → Use depth
-        (None, Some(1)) => 1u64,    // Only Level 1 → Best bid and ask
-        (None, Some(2)) | (None, None) => 100u64, // Level 2 or default → 100 ticks
-        _ => 100u64,                // Fallback to default
-    };
+    if total_duration > 0.0 {
+        Some(total_price_duration / total_duration)
+    } else {
+        None
+    }
+}
 
-    // Fetch the pool data from the `pools` table
-    let query = schema::pools::table
-        .filter(schema::pools::pool_name.eq(pool_name.clone()))
-        .select((
-            schema::pools::pool_id,
-            schema::pools::base_asset_id,
-            schema::pools::base_asset_decimals,
-            schema::pools::quote_asset_id,
-            schema::pools::quote_asset_decimals,
-        ));
-    let pool_data: (String, String, i16, String, i16) = state.reader.first(query).await?;
-    let (pool_id, base_asset_id, base_decimals, quote_asset_id, quote_decimals) = pool_data;
+pub async fn orderbook_imbalance(
+    Path(pool_name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<HashMap<String, Value>>, DeepBookError> {
+    let ticks_from_mid = parse_depth(
+        params.get("depth").map(String::as_str),
+        params.get("level").map(String::as_str),
+        state.config.max_orderbook_depth,
+    )?;
+
+    let Pool {
+        pool_id,
+        base_asset_id,
+        base_asset_decimals: base_decimals,
+        quote_asset_id,
+        quote_asset_decimals: quote_decimals,
+        ..
+    } = state.reader.pool_by_name(&pool_name).await?;
     let base_decimals = base_decimals as u8;
     let quote_decimals = quote_decimals as u8;
 
     let pool_address = ObjectID::from_hex_literal(&pool_id)?;
 
-    let sui_client = SuiClientBuilder::default().build(rpc_url.as_str()).await?;
+    let sui_client = state.sui_client().await?;
     let mut ptb = ProgrammableTransactionBuilder::new();
 
-    let pool_object: SuiObjectResponse = sui_client
-        .read_api()
-        .get_object_with_options(pool_address, SuiObjectDataOptions::full_content())
-        .await?;
-    let pool_data: &SuiObjectData =
-        pool_object
-            .data
-            .as_ref()
-            .ok_or(DeepBookError::InternalError(format!(
-                "Missing data in pool object response for '{}'",
-                pool_name
-            )))?;
+    let pool_object: SuiObjectResponse = retry_rpc(|| {
+        sui_client
+            .read_api()
+            .get_object_with_options(pool_address, SuiObjectDataOptions::full_content())
+    })
+    .await?;
+    let pool_data: &SuiObjectData = pool_object.data.as_ref().ok_or(DeepBookError::Rpc(
+        format!("Missing data in pool object response for '{}'", pool_name),
+    ))?;
     let pool_object_ref: ObjectRef = (pool_data.object_id, pool_data.version, pool_data.digest);
 
     let pool_input = CallArg::Object(ObjectArg::ImmOrOwnedObject(pool_object_ref));
@@ -442,17 +934,15 @@ pub async fn orderbook_imbalance(
     let sui_clock_object_id = ObjectID::from_hex_literal(
         "0x0000000000000000000000000000000000000000000000000000000000000006",
     )?;
-    let sui_clock_object: SuiObjectResponse = sui_client
-        .read_api()
-        .get_object_with_options(sui_clock_object_id, SuiObjectDataOptions::full_content())
-        .await?;
-    let clock_data: &SuiObjectData =
-        sui_clock_object
-            .data
-            .as_ref()
-            .ok_or(DeepBookError::InternalError(
-                "Missing data in clock object response".to_string(),
-            ))?;
+    let sui_clock_object: SuiObjectResponse = retry_rpc(|| {
+        sui_client
+            .read_api()
+            .get_object_with_options(sui_clock_object_id, SuiObjectDataOptions::full_content())
+    })
+    .await?;
+    let clock_data: &SuiObjectData = sui_clock_object.data.as_ref().ok_or(DeepBookError::Rpc(
+        "Missing data in clock object response".to_string(),
+    ))?;
 
     let sui_clock_object_ref: ObjectRef =
         (clock_data.object_id, clock_data.version, clock_data.digest);
@@ -479,70 +969,66 @@ pub async fn orderbook_imbalance(
     let builder = ptb.finish();
     let tx = TransactionKind::ProgrammableTransaction(builder);
 
-    let result = sui_client
-        .read_api()
-        .dev_inspect_transaction_block(SuiAddress::default(), tx, None, None, None)
-        .await?;
+    let result = retry_rpc(|| {
+        sui_client.read_api().dev_inspect_transaction_block(
+            SuiAddress::default(),
+            tx.clone(),
+            None,
+            None,
+            None,
+        )
+    })
+    .await?;
 
-    let mut binding = result.results.ok_or(DeepBookError::InternalError(
+    let mut binding = result.results.ok_or(DeepBookError::Rpc(
         "No results from dev_inspect_transaction_block".to_string(),
     ))?;
     let bid_prices = &binding
         .first_mut()
-        .ok_or(DeepBookError::InternalError(
+        .ok_or(DeepBookError::Rpc(
             "No return values for bid prices".to_string(),
         ))?
         .return_values
         .first_mut()
-        .ok_or(DeepBookError::InternalError(
-            "No bid price data found".to_string(),
-        ))?
+        .ok_or(DeepBookError::Rpc("No bid price data found".to_string()))?
         .0;
-    let bid_parsed_prices: Vec<u64> = bcs::from_bytes(bid_prices).map_err(|_| {
-        DeepBookError::InternalError("Failed to deserialize bid prices".to_string())
-    })?;
+    let bid_parsed_prices: Vec<u64> = bcs::from_bytes(bid_prices)
+        .map_err(|_| DeepBookError::Deserialize("Failed to deserialize bid prices".to_string()))?;
     let bid_quantities = &binding
         .first_mut()
-        .ok_or(DeepBookError::InternalError(
+        .ok_or(DeepBookError::Rpc(
             "No return values for bid quantities".to_string(),
         ))?
         .return_values
         .get(1)
-        .ok_or(DeepBookError::InternalError(
-            "No bid quantity data found".to_string(),
-        ))?
+        .ok_or(DeepBookError::Rpc("No bid quantity data found".to_string()))?
         .0;
     let bid_parsed_quantities: Vec<u64> = bcs::from_bytes(bid_quantities).map_err(|_| {
-        DeepBookError::InternalError("Failed to deserialize bid quantities".to_string())
+        DeepBookError::Deserialize("Failed to deserialize bid quantities".to_string())
     })?;
 
     let ask_prices = &binding
         .first_mut()
-        .ok_or(DeepBookError::InternalError(
+        .ok_or(DeepBookError::Rpc(
             "No return values for ask prices".to_string(),
         ))?
         .return_values
         .get(2)
-        .ok_or(DeepBookError::InternalError(
-            "No ask price data found".to_string(),
-        ))?
+        .ok_or(DeepBookError::Rpc("No ask price data found".to_string()))?
         .0;
-    let ask_parsed_prices: Vec<u64> = bcs::from_bytes(ask_prices).map_err(|_| {
-        DeepBookError::InternalError("Failed to deserialize ask prices".to_string())
-    })?;
+    let ask_parsed_prices: Vec<u64> = bcs::from_bytes(ask_prices)
+        .map_err(|_| DeepBookError::Deserialize("Failed to deserialize ask prices".to_string()))?;
     let ask_quantities = &binding
         .first_mut()
-        .ok_or(DeepBookError::InternalError(
+        .ok_or(DeepBookError::Rpc(
             "No return values for ask quantities".to_string(),
         ))?
         .return_values
         .get(3)
-        .ok_or(DeepBookError::InternalError(
-            "No ask quantity data found".to_string(),
-        ))?
+        .ok_or(DeepBookError::Rpc("No ask quantity data found".to_string()))?
         .0;
     let ask_parsed_quantities: Vec<u64> = bcs::from_bytes(ask_quantities).map_err(|_| {
-        DeepBookError::InternalError("Failed to deserialize ask quantities".to_string())
+        DeepBookError::Deserialize("Failed to deserialize ask quantities".to_string())
     })?;
 
     let mut result = HashMap::new();
@@ -553,15 +1039,15 @@ pub async fn orderbook_imbalance(
         .as_millis() as i64;
     result.insert("timestamp".to_string(), Value::from(timestamp.to_string()));
 
+    let price_factor = price_scaling_factor(base_decimals.into(), quote_decimals.into())?;
     let bids: Vec<Value> = bid_parsed_prices
         .into_iter()
         .zip(bid_parsed_quantities.into_iter())
         .take(ticks_from_mid as usize)
         .map(|(price, quantity)| {
-            let price_factor = (10u64).pow((9 - base_decimals + quote_decimals).into());
             let quantity_factor = (10u64).pow(base_decimals.into());
             Value::Array(vec![
-                Value::from(((price as f64) / (price_factor as f64)).to_string()),
+                Value::from(((price as f64) / price_factor).to_string()),
                 Value::from(((quantity as f64) / (quantity_factor as f64)).to_string()),
             ])
         })
@@ -572,10 +1058,9 @@ pub async fn orderbook_imbalance(
         .zip(ask_parsed_quantities.into_iter())
         .take(ticks_from_mid as usize)
         .map(|(price, quantity)| {
-            let price_factor = (10u64).pow((9 - base_decimals + quote_decimals).into());
             let quantity_factor = (10u64).pow(base_decimals.into());
             Value::Array(vec![
-                Value::from(((price as f64) / (price_factor as f64)).to_string()),
+                Value::from(((price as f64) / price_factor).to_string()),
                 Value::from(((quantity as f64) / (quantity_factor as f64)).to_string()),
             ])
         })
@@ -615,12 +1100,18 @@ fn sum_quantities(orderbook_side: &[Value]) -> f64 {
 pub async fn get_order_fill_24h_summary(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<HashMap<String, Value>>>, DeepBookError> {
+    let max_rows = state.config.max_aggregation_rows;
+
     // Load results from the view
     let result: Vec<OrderFill24hSummary> = state
         .reader
-        .results(view::order_fill_24h_summary_view::dsl::order_fill_24h_summary_view)
+        .results(
+            view::order_fill_24h_summary_view::dsl::order_fill_24h_summary_view
+                .limit(max_rows.saturating_add(1)),
+        )
         .await
-        .map_err(|e| DeepBookError::InternalError(e.to_string()))?;
+        .map_err(DeepBookError::from)?;
+    let result = enforce_row_cap(result, max_rows)?;
 
     // Format into JSON-compatible HashMaps
     let rows: Vec<HashMap<String, Value>> = result
@@ -653,23 +1144,42 @@ pub async fn get_volume_last_n_days(
         state.reader.get_pool_decimals(&pool_name).await?;
 
     // Parse days from query parameters
-    let days = params.days();
+    let days = params.days()?;
     let now = Utc::now().naive_utc();
-    let start_time = now - Duration::days(days);
+    let start_time = now
+        .checked_sub_signed(Duration::days(days))
+        .ok_or_else(|| DeepBookError::BadRequest("days is out of range".to_string()))?;
 
-    let result: Option<(BigDecimal, BigDecimal)> = state
-        .reader
-        .results(
-            view::ohlcv_1min::table
-                .filter(view::ohlcv_1min::pool_id.eq(pool_id))
-                .filter(view::ohlcv_1min::bucket.ge(start_time))
-                .select((
-                    sum(view::ohlcv_1min::volume_base),
-                    sum(view::ohlcv_1min::volume_quote),
-                )),
-        )
-        .await
-        .map(|rows: Vec<(Option<BigDecimal>, Option<BigDecimal>)>| {
+    let rows: Result<Vec<(Option<BigDecimal>, Option<BigDecimal>)>, _> =
+        if state.config.ohlcv_from_raw {
+            state
+                .reader
+                .results(
+                    schema::order_fills::table
+                        .filter(schema::order_fills::pool_id.eq(pool_id))
+                        .filter(schema::order_fills::timestamp.ge(start_time))
+                        .select((
+                            sum(schema::order_fills::base_quantity),
+                            sum(schema::order_fills::quote_quantity),
+                        )),
+                )
+                .await
+        } else {
+            state
+                .reader
+                .results(
+                    view::ohlcv_1min::table
+                        .filter(view::ohlcv_1min::pool_id.eq(pool_id))
+                        .filter(view::ohlcv_1min::bucket.ge(start_time))
+                        .select((
+                            sum(view::ohlcv_1min::volume_base),
+                            sum(view::ohlcv_1min::volume_quote),
+                        )),
+                )
+                .await
+        };
+    let result: Option<(BigDecimal, BigDecimal)> = rows
+        .map(|rows| {
             rows.into_iter()
                 .map(|(base, quote)| {
                     (
@@ -679,7 +1189,7 @@ pub async fn get_volume_last_n_days(
                 })
                 .next()
         })
-        .map_err(|e| DeepBookError::InternalError(e.to_string()))?;
+        .map_err(DeepBookError::from)?;
 
     let (base_volume, quote_volume) =
         result.unwrap_or((BigDecimal::new(0.into(), 0), BigDecimal::new(0.into(), 0)));
@@ -712,6 +1222,26 @@ pub struct VolumeWindowed {
     pub volume_30d: BigDecimal,
 }
 
+/// Base volumes of the last 1, 7 and 30 days from `ohlcv_1min`, one row. Binds: pool_id.
+pub const VOLUME_MULTI_WINDOW_QUERY: &str = r#"
+    SELECT
+        COALESCE(SUM(volume_base) FILTER (WHERE bucket >= now() - INTERVAL '1 day'), 0)  AS volume_1d,
+        COALESCE(SUM(volume_base) FILTER (WHERE bucket >= now() - INTERVAL '7 day'), 0)  AS volume_7d,
+        COALESCE(SUM(volume_base) FILTER (WHERE bucket >= now() - INTERVAL '30 day'), 0) AS volume_30d
+    FROM ohlcv_1min
+    WHERE pool_id = $1
+"#;
+
+/// Same as `VOLUME_MULTI_WINDOW_QUERY` computed from raw `order_fills`
+pub const VOLUME_MULTI_WINDOW_RAW_QUERY: &str = r#"
+    SELECT
+        COALESCE(SUM(base_quantity) FILTER (WHERE timestamp >= now() - INTERVAL '1 day'), 0)  AS volume_1d,
+        COALESCE(SUM(base_quantity) FILTER (WHERE timestamp >= now() - INTERVAL '7 day'), 0)  AS volume_7d,
+        COALESCE(SUM(base_quantity) FILTER (WHERE timestamp >= now() - INTERVAL '30 day'), 0) AS volume_30d
+    FROM order_fills
+    WHERE pool_id = $1
+"#;
+
 pub async fn get_volume_multi_window(
     Path(pool_name): Path<String>,
     State(state): State<Arc<AppState>>,
@@ -720,24 +1250,17 @@ pub async fn get_volume_multi_window(
     let (pool_id, base_decimals, _) = state.reader.get_pool_decimals(&pool_name).await?;
 
     // SQL query using FILTER clause for each time window
-    let result: Option<VolumeWindowed> = state
+    let query = if state.config.ohlcv_from_raw {
+        VOLUME_MULTI_WINDOW_RAW_QUERY
+    } else {
+        VOLUME_MULTI_WINDOW_QUERY
+    };
+    let max_rows = state.config.max_aggregation_rows;
+    let rows: Vec<VolumeWindowed> = state
         .reader
-        .results(
-            sql_query(
-                r#"
-            SELECT
-                SUM(volume_base) FILTER (WHERE bucket >= now() - INTERVAL '1 day')  AS volume_1d,
-                SUM(volume_base) FILTER (WHERE bucket >= now() - INTERVAL '7 day')  AS volume_7d,
-                SUM(volume_base) FILTER (WHERE bucket >= now() - INTERVAL '30 day') AS volume_30d
-            FROM ohlcv_1min
-            WHERE pool_id = $1
-            "#,
-            )
-            .bind::<Text, _>(pool_id.clone()),
-        )
-        .await
-        .map(|mut rows: Vec<VolumeWindowed>| rows.pop())
-        .map_err(|e| DeepBookError::InternalError(e.to_string()))?;
+        .results(sql_query(capped_query(query, max_rows)).bind::<Text, _>(pool_id))
+        .await?;
+    let result = enforce_row_cap(rows, max_rows)?.pop();
 
     // Prepare default values if missing
     let summary = result.unwrap_or(VolumeWindowed {
@@ -774,6 +1297,183 @@ pub async fn get_volume_multi_window(
     Ok(Json(map))
 }
 
+/// Lifetime volumes barely move between requests, so they are served from Redis for a while
+pub const LIFETIME_VOLUME_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(300);
+
+#[derive(Debug, Serialize, diesel::QueryableByName)]
+pub struct LifetimeVolume {
+    #[diesel(sql_type = Numeric)]
+    pub base_volume: BigDecimal,
+    #[diesel(sql_type = Numeric)]
+    pub quote_volume: BigDecimal,
+}
+
+/// Base and quote volumes since the first trade of a pool from `ohlcv_4h`, one row.
+/// Binds: pool_id.
+pub const LIFETIME_VOLUME_QUERY: &str = r#"
+    SELECT
+        COALESCE(SUM(volume_base), 0)  AS base_volume,
+        COALESCE(SUM(volume_quote), 0) AS quote_volume
+    FROM ohlcv_4h
+    WHERE pool_id = $1
+"#;
+
+/// Same as `LIFETIME_VOLUME_QUERY` computed from raw `order_fills`
+pub const LIFETIME_VOLUME_RAW_QUERY: &str = r#"
+    SELECT
+        COALESCE(SUM(base_quantity), 0)  AS base_volume,
+        COALESCE(SUM(quote_quantity), 0) AS quote_volume
+    FROM order_fills
+    WHERE pool_id = $1
+"#;
+
+pub fn lifetime_volume_cache_key(pool_name: &str) -> String {
+    format!("lifetime_volume::{}", pool_name)
+}
+
+pub async fn get_lifetime_volume(
+    Path(pool_name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<HashMap<String, f64>>, DeepBookError> {
+    let key = lifetime_volume_cache_key(&pool_name);
+    match state.reader.cache.get::<HashMap<String, f64>>(&key).await {
+        Ok(Some(volumes)) => return Ok(Json(volumes)),
+        Ok(None) => {}
+        Err(e) => println!("Failed reading cached lifetime volume {}: {:?}", key, e),
+    }
+
+    let (pool_id, base_decimals, quote_decimals) =
+        state.reader.get_pool_decimals(&pool_name).await?;
+    let query = if state.config.ohlcv_from_raw {
+        LIFETIME_VOLUME_RAW_QUERY
+    } else {
+        LIFETIME_VOLUME_QUERY
+    };
+    let volume: Option<LifetimeVolume> = state
+        .reader
+        .first_sql(sql_query(query).bind::<Text, _>(pool_id))
+        .await?;
+
+    let volumes = match volume {
+        Some(volume) => HashMap::from([
+            (
+                "base_volume".to_string(),
+                volume
+                    .base_volume
+                    .to_decimal_f64(base_decimals as u32)
+                    .unwrap_or(0.0),
+            ),
+            (
+                "quote_volume".to_string(),
+                volume
+                    .quote_volume
+                    .to_decimal_f64(quote_decimals as u32)
+                    .unwrap_or(0.0),
+            ),
+        ]),
+        None => HashMap::from([
+            ("base_volume".to_string(), 0.0),
+            ("quote_volume".to_string(), 0.0),
+        ]),
+    };
+
+    if let Err(e) = state
+        .reader
+        .cache
+        .set_with_ttl(&key, &volumes, LIFETIME_VOLUME_CACHE_TTL)
+        .await
+    {
+        println!("Failed caching lifetime volume {}: {:?}", key, e);
+    }
+
+    Ok(Json(volumes))
+}
+
+/// Decimals of the DEEP token
+pub const DEEP_DECIMALS: u32 = 6;
+
+/// `(taker_is_bid, taker_fee_is_deep, maker_fee_is_deep, taker_fee, maker_fee)` summed over a
+/// group of fills
+pub type FeeSums = (bool, bool, bool, Option<BigDecimal>, Option<BigDecimal>);
+
+/// Raw fees of one side of the fills by the asset they were paid in
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FeeSplit {
+    pub deep: BigDecimal,
+    pub base: BigDecimal,
+    pub quote: BigDecimal,
+}
+
+impl FeeSplit {
+    fn add(&mut self, fee: Option<BigDecimal>, is_deep: bool, pays_base: bool) {
+        let Some(fee) = fee else { return };
+        if is_deep {
+            self.deep += fee;
+        } else if pays_base {
+            self.base += fee;
+        } else {
+            self.quote += fee;
+        }
+    }
+
+    /// `{"deep", "base", "quote"}` scaled by the decimals of DEEP and of the pool's assets
+    pub fn to_json(&self, base_decimals: u32, quote_decimals: u32) -> Value {
+        json!({
+            "deep": self.deep.clone().to_decimal_f64(DEEP_DECIMALS).unwrap_or(0.0),
+            "base": self.base.clone().to_decimal_f64(base_decimals).unwrap_or(0.0),
+            "quote": self.quote.clone().to_decimal_f64(quote_decimals).unwrap_or(0.0),
+        })
+    }
+}
+
+/// Taker and maker fees of the fill groups. Fees not paid in DEEP are paid in the asset the side
+/// gives up: a buying taker pays quote and the asks it fills pay base, the other way around for
+/// a selling taker.
+pub fn split_fees(groups: Vec<FeeSums>) -> (FeeSplit, FeeSplit) {
+    let mut taker = FeeSplit::default();
+    let mut maker = FeeSplit::default();
+    for (taker_is_bid, taker_fee_is_deep, maker_fee_is_deep, taker_fee, maker_fee) in groups {
+        taker.add(taker_fee, taker_fee_is_deep, !taker_is_bid);
+        maker.add(maker_fee, maker_fee_is_deep, taker_is_bid);
+    }
+    (taker, maker)
+}
+
+/// Taker and maker fees paid by the pool's fills between `start_time` and `end_time`, the last
+/// 24 hours by default
+pub async fn get_fees(
+    Path(pool_name): Path<String>,
+    common: CommonParams,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<HashMap<String, Value>>, DeepBookError> {
+    let (pool_id, base_decimals, quote_decimals) =
+        state.reader.get_pool_decimals(&pool_name).await?;
+    let end_time = common.end_time_ms();
+    let start_time = common
+        .start_time_ms()
+        .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
+
+    let groups = state
+        .reader
+        .get_fee_sums(&pool_id, start_time, end_time)
+        .await?;
+    let (taker, maker) = split_fees(groups);
+    let (base_decimals, quote_decimals) = (base_decimals as u32, quote_decimals as u32);
+
+    Ok(Json(HashMap::from([
+        (
+            "taker_fees".to_string(),
+            taker.to_json(base_decimals, quote_decimals),
+        ),
+        (
+            "maker_fees".to_string(),
+            maker.to_json(base_decimals, quote_decimals),
+        ),
+        ("start_time".to_string(), Value::from(start_time / 1000)),
+        ("end_time".to_string(), Value::from(end_time / 1000)),
+    ])))
+}
+
 pub async fn get_avg_trade_size_multi_window(
     Path(pool_name): Path<String>,
     State(state): State<Arc<AppState>>,
@@ -810,7 +1510,7 @@ pub async fn get_avg_trade_size_multi_window(
             .reader
             .results(query)
             .await
-            .map_err(|e| DeepBookError::InternalError(e.to_string()))?;
+            .map_err(DeepBookError::from)?;
 
         let avg = rows
             .into_iter()
@@ -0,0 +1,73 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header::AUTHORIZATION, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::error::DeepBookError;
+
+/// Query parameter carrying the websocket token, browsers can't set upgrade headers
+pub const WS_TOKEN_PARAM: &str = "token";
+
+/// Set of tokens accepted on websocket upgrades, empty set disables auth
+#[derive(Clone, Debug, Default)]
+pub struct WsAuth {
+    tokens: Arc<HashSet<String>>,
+}
+
+impl WsAuth {
+    pub fn new(tokens: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            tokens: Arc::new(
+                tokens
+                    .into_iter()
+                    .filter(|token| !token.is_empty())
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.tokens.is_empty()
+    }
+
+    /// Checks `token` query parameter or `Authorization: Bearer <token>` header
+    pub fn authorize<B>(&self, req: &Request<B>) -> Result<(), DeepBookError> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        let query_token = Query::<HashMap<String, String>>::try_from_uri(req.uri())
+            .ok()
+            .and_then(|Query(params)| params.get(WS_TOKEN_PARAM).cloned());
+        let header_token = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::to_string);
+
+        match query_token.or(header_token) {
+            Some(token) if self.tokens.contains(&token) => Ok(()),
+            Some(_) => Err(DeepBookError::Unauthorized(
+                "Invalid websocket token".to_string(),
+            )),
+            None => Err(DeepBookError::Unauthorized(
+                "Missing websocket token".to_string(),
+            )),
+        }
+    }
+}
+
+// Axum middleware rejecting unauthorized websocket upgrades before `on_upgrade`
+pub async fn ws_auth(State(auth): State<WsAuth>, req: Request<Body>, next: Next) -> Response {
+    if let Err(err) = auth.authorize(&req) {
+        return err.into_response();
+    }
+    next.run(req).await
+}
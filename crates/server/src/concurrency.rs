@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::Request,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::error::DeepBookError;
+
+/// Number of requests routes sharing the limit may handle at once, requests over it are
+/// rejected instead of queueing for database connections. Zero disables the limit.
+#[derive(Clone, Debug)]
+pub struct ConcurrencyLimit {
+    permits: Option<Arc<Semaphore>>,
+}
+
+impl ConcurrencyLimit {
+    pub fn new(max_requests: usize) -> Self {
+        Self {
+            permits: (max_requests > 0).then(|| Arc::new(Semaphore::new(max_requests))),
+        }
+    }
+
+    /// Permit held while the request is handled, `None` when the limit is disabled
+    pub fn try_acquire(&self) -> Result<Option<OwnedSemaphorePermit>, DeepBookError> {
+        let Some(permits) = &self.permits else {
+            return Ok(None);
+        };
+        permits.clone().try_acquire_owned().map(Some).map_err(|_| {
+            DeepBookError::Unavailable("Too many concurrent requests, retry later".to_string())
+        })
+    }
+}
+
+// Axum middleware answering 503 when all permits of the limit are taken
+pub async fn limit_concurrency(
+    State(limit): State<ConcurrencyLimit>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let _permit = match limit.try_acquire() {
+        Ok(permit) => permit,
+        Err(err) => return err.into_response(),
+    };
+    next.run(req).await
+}
@@ -0,0 +1,131 @@
+use crate::aggregations::{MAX_AGGREGATION_ROWS, OHLCV_MAX_POINTS};
+use axum::http::HeaderValue;
+use deeplook_utils::cache::{Codec, LATEST_TRADE_SIZE};
+
+/// Short enough that cached books are never noticeably stale
+pub const ORDERBOOK_RPC_CACHE_TTL_MS: u64 = 1000;
+
+/// Well below the default database pool size, so light routes always find a connection
+pub const HEAVY_ROUTE_CONCURRENCY: usize = 32;
+
+/// Price levels of both sides returned for the whole orderbook, 100 ticks from mid
+pub const MAX_ORDERBOOK_DEPTH: u64 = 200;
+
+/// Often enough to notice a Redis outage before websocket clients report it
+pub const REDIS_PING_INTERVAL_SECS: u64 = 10;
+
+/// Leaves aggregations over long ranges room, still below common 60s proxy timeouts
+pub const DB_QUERY_TIMEOUT_MS: u64 = 30_000;
+
+/// Pools are listed rarely, unknown pool names reload them right away anyway
+pub const POOL_CACHE_TTL_SECS: u64 = 60;
+
+/// Below the common 60s idle timeout of proxies and load balancers
+pub const WS_HEARTBEAT_INTERVAL_SECS: u64 = 30;
+
+/// Tunable options of the API server
+#[derive(clap::Args, Clone, Debug)]
+pub struct ServerConfig {
+    /// Number of latest trades served per pool, keep in sync with the orderbook service
+    #[clap(env, long, default_value_t = LATEST_TRADE_SIZE)]
+    pub latest_trades_size: usize,
+    /// Tokens accepted on websocket upgrades, websocket auth is disabled when empty
+    #[clap(env, long, value_delimiter = ',')]
+    pub ws_auth_tokens: Vec<String>,
+    /// Max number of OHLCV candles used when picking timeframe automatically
+    #[clap(env, long, default_value_t = OHLCV_MAX_POINTS)]
+    pub ohlcv_max_points: i64,
+    /// Compute OHLCV and volumes from raw `order_fills` instead of TimescaleDB caggs,
+    /// enabled automatically when the caggs are missing
+    #[clap(env, long)]
+    pub ohlcv_from_raw: bool,
+    /// Max rows an aggregation query may return, larger results fail instead of being loaded
+    #[clap(env, long, default_value_t = MAX_AGGREGATION_ROWS)]
+    pub max_aggregation_rows: i64,
+    /// Round readable prices of trades and orderbooks to the pool's tick size and sizes
+    /// to its lot size
+    #[clap(env, long)]
+    pub round_to_ticks: bool,
+    /// Milliseconds onchain orderbooks are served from Redis before being fetched again,
+    /// 0 disables the cache
+    #[clap(env, long, default_value_t = ORDERBOOK_RPC_CACHE_TTL_MS)]
+    pub orderbook_rpc_cache_ttl_ms: u64,
+    /// Seconds between websocket pings, clients not answering a ping until the next one
+    /// are disconnected, 0 disables pings
+    #[clap(env, long, default_value_t = WS_HEARTBEAT_INTERVAL_SECS)]
+    pub ws_heartbeat_interval_secs: u64,
+    /// Explorer URL of asset packages without a stored `package_address_url`,
+    /// `{package_id}` is replaced with the package id, e.g.
+    /// `https://suiscan.xyz/mainnet/object/{package_id}`
+    #[clap(env, long)]
+    pub asset_url_template: Option<String>,
+    /// Seconds between Redis pings reported as `redis_up` and `redis_ping_latency`,
+    /// 0 disables the checks
+    #[clap(env, long, default_value_t = REDIS_PING_INTERVAL_SECS)]
+    pub redis_ping_interval_secs: u64,
+    /// Max requests handled at once by trade, volume and aggregation routes, requests over
+    /// it get 503 so cheap routes keep their database connections, 0 disables the limit
+    #[clap(env, long, default_value_t = HEAVY_ROUTE_CONCURRENCY)]
+    pub heavy_route_concurrency: usize,
+    /// Max price levels of `/orderbook` and `/orderbook_imbalance`, also returned for
+    /// `depth=0` or `depth=full`
+    #[clap(env, long, default_value_t = MAX_ORDERBOOK_DEPTH)]
+    pub max_orderbook_depth: u64,
+    /// Encoding of orderbooks and trades in Redis, `json` or `msgpack`, must match the
+    /// orderbook service
+    #[clap(env, long, default_value_t = Codec::Json)]
+    pub cache_codec: Codec,
+    /// Milliseconds a database query may take before the request fails with 504,
+    /// 0 disables the timeout
+    #[clap(env, long, default_value_t = DB_QUERY_TIMEOUT_MS)]
+    pub db_query_timeout_ms: u64,
+    /// Leave out the routes reading from the full node at `rpc_url`: `/orderbook`,
+    /// `/orderbooks`, `/deep_supply`, `/chain_tip`, `/summary` and `/orderbook_imbalance`
+    #[clap(env, long)]
+    pub disable_rpc_routes: bool,
+    /// Origins browsers may call the API from, e.g. `https://app.example.com`, any origin
+    /// is allowed when empty
+    #[clap(env, long, value_delimiter = ',', value_parser = parse_origin)]
+    pub cors_allowed_origins: Vec<HeaderValue>,
+    /// Seconds pool metadata is served from memory before it's reloaded from the database,
+    /// 0 reads it from the database on every request
+    #[clap(env, long, default_value_t = POOL_CACHE_TTL_SECS)]
+    pub pool_cache_ttl_secs: u64,
+}
+
+impl Default for ServerConfig {
+    /// Same values as the command line defaults
+    fn default() -> Self {
+        Self {
+            latest_trades_size: LATEST_TRADE_SIZE,
+            ws_auth_tokens: vec![],
+            ohlcv_max_points: OHLCV_MAX_POINTS,
+            ohlcv_from_raw: false,
+            max_aggregation_rows: MAX_AGGREGATION_ROWS,
+            round_to_ticks: false,
+            orderbook_rpc_cache_ttl_ms: ORDERBOOK_RPC_CACHE_TTL_MS,
+            ws_heartbeat_interval_secs: WS_HEARTBEAT_INTERVAL_SECS,
+            asset_url_template: None,
+            redis_ping_interval_secs: REDIS_PING_INTERVAL_SECS,
+            heavy_route_concurrency: HEAVY_ROUTE_CONCURRENCY,
+            max_orderbook_depth: MAX_ORDERBOOK_DEPTH,
+            cache_codec: Codec::Json,
+            db_query_timeout_ms: DB_QUERY_TIMEOUT_MS,
+            disable_rpc_routes: false,
+            cors_allowed_origins: vec![],
+            pool_cache_ttl_secs: POOL_CACHE_TTL_SECS,
+        }
+    }
+}
+
+/// Origin of `cors_allowed_origins`, browsers send origins without a trailing slash
+fn parse_origin(origin: &str) -> Result<HeaderValue, String> {
+    let origin = origin.trim().trim_end_matches('/');
+    if !origin.starts_with("http://") && !origin.starts_with("https://") {
+        return Err(format!(
+            "Invalid origin `{}`, must start with http(s)://",
+            origin
+        ));
+    }
+    HeaderValue::from_str(origin).map_err(|e| format!("Invalid origin `{}`: {}", origin, e))
+}
@@ -0,0 +1,90 @@
+//! CSV output of the row endpoints, selected by `?format=csv` or an `Accept: text/csv` header.
+
+use std::collections::{BTreeSet, HashMap};
+
+use axum::http::{header, HeaderMap};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::Value;
+
+use crate::error::DeepBookError;
+
+pub const CSV_CONTENT_TYPE: &str = "text/csv; charset=utf-8";
+
+/// Whether the rows are requested as CSV. The `format` query parameter (`json` or `csv`) takes
+/// precedence over the `Accept` header, JSON is the default.
+pub fn wants_csv(
+    params: &HashMap<String, String>,
+    headers: &HeaderMap,
+) -> Result<bool, DeepBookError> {
+    match params.get("format").map(String::as_str) {
+        Some("csv") => return Ok(true),
+        Some("json") => return Ok(false),
+        Some(other) => {
+            return Err(DeepBookError::BadRequest(format!(
+                "Invalid format `{}`, must be one of: [json,csv]",
+                other
+            )))
+        }
+        None => {}
+    }
+    Ok(headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media_type| {
+            media_type
+                .split(';')
+                .next()
+                .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case("text/csv"))
+        }))
+}
+
+/// Rows as CSV with a header line of all keys in alphabetical order. Keys missing from a row and
+/// nulls are empty cells, nested values are written as JSON.
+pub fn to_csv(rows: &[HashMap<String, Value>]) -> String {
+    let columns: BTreeSet<&str> = rows
+        .iter()
+        .flat_map(|row| row.keys().map(String::as_str))
+        .collect();
+
+    let mut out = String::new();
+    push_record(&mut out, columns.iter().map(|column| column.to_string()));
+    for row in rows {
+        push_record(
+            &mut out,
+            columns.iter().map(|column| match row.get(*column) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(s)) => s.clone(),
+                Some(value) => value.to_string(),
+            }),
+        );
+    }
+    out
+}
+
+fn push_record(out: &mut String, cells: impl Iterator<Item = String>) {
+    for (i, cell) in cells.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        if cell.contains([',', '"', '\n', '\r']) {
+            out.push('"');
+            out.push_str(&cell.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(&cell);
+        }
+    }
+    out.push_str("\r\n");
+}
+
+/// Rows as CSV or JSON
+pub fn rows_response(rows: Vec<HashMap<String, Value>>, csv: bool) -> Response {
+    if csv {
+        ([(header::CONTENT_TYPE, CSV_CONTENT_TYPE)], to_csv(&rows)).into_response()
+    } else {
+        Json(rows).into_response()
+    }
+}
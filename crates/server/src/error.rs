@@ -1,7 +1,78 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::fmt;
+use std::time::Duration;
+
 #[derive(Debug, Clone)]
 pub enum DeepBookError {
     InternalError(String),
+    /// Database query failed, returned as 500
+    Db(String),
+    /// Full node call failed or answered with an unexpected response, returned as 502
+    Rpc(String),
+    /// Redis read or write failed, returned as 500
+    Cache(String),
+    /// Stored or returned data couldn't be decoded, returned as 500
+    Deserialize(String),
+    /// Invalid request parameters, returned as 400
+    BadRequest(String),
+    /// Requested resource (e.g. pool) does not exist, returned as 404
+    NotFound(String),
+    /// Missing or invalid auth token, returned as 401
+    Unauthorized(String),
+    /// Server is at capacity, returned as 503
+    Unavailable(String),
+    /// Database query didn't finish within `DB_QUERY_TIMEOUT_MS`, returned as 504
+    Timeout(String),
+}
+
+/// Error of a database call cut off by the query timeout, converts to [`DeepBookError::Timeout`]
+#[derive(Debug, Clone, Copy)]
+pub struct QueryTimeout(pub Duration);
+
+impl fmt::Display for QueryTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Database query timed out after {}ms", self.0.as_millis())
+    }
+}
+
+impl std::error::Error for QueryTimeout {}
+
+/// Code of the [`DeepBookError`] a response was built from, kept in the response extensions so
+/// the metrics middleware can label failed requests by it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCode(pub &'static str);
+
+impl DeepBookError {
+    /// Machine readable error code used in JSON error responses
+    pub fn code(&self) -> &'static str {
+        match self {
+            DeepBookError::InternalError(_) => "internal_error",
+            DeepBookError::Db(_) => "db_error",
+            DeepBookError::Rpc(_) => "rpc_error",
+            DeepBookError::Cache(_) => "cache_error",
+            DeepBookError::Deserialize(_) => "deserialize_error",
+            DeepBookError::BadRequest(_) => "bad_request",
+            DeepBookError::NotFound(_) => "not_found",
+            DeepBookError::Unauthorized(_) => "unauthorized",
+            DeepBookError::Unavailable(_) => "unavailable",
+            DeepBookError::Timeout(_) => "timeout",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            DeepBookError::InternalError(message)
+            | DeepBookError::Db(message)
+            | DeepBookError::Rpc(message)
+            | DeepBookError::Cache(message)
+            | DeepBookError::Deserialize(message)
+            | DeepBookError::BadRequest(message)
+            | DeepBookError::NotFound(message)
+            | DeepBookError::Unauthorized(message)
+            | DeepBookError::Unavailable(message)
+            | DeepBookError::Timeout(message) => message,
+        }
+    }
 }
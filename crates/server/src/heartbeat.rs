@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use axum::extract::ws::Message;
+use futures::{Sink, SinkExt};
+use tokio::time::{interval_at, Instant, Interval, MissedTickBehavior};
+
+/// Keeps idle websockets open behind proxies with idle timeouts by pinging the client, and
+/// detects clients that stopped answering
+pub struct Heartbeat {
+    interval: Option<Interval>,
+    awaiting_pong: bool,
+}
+
+impl Heartbeat {
+    /// Pings every `period`, zero disables the heartbeat
+    pub fn new(period: Duration) -> Self {
+        let interval = (!period.is_zero()).then(|| {
+            // The connection was just opened, first ping is due after a whole period
+            let mut interval = interval_at(Instant::now() + period, period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        });
+        Self {
+            interval,
+            awaiting_pong: false,
+        }
+    }
+
+    /// Resolves when the next ping is due, never when the heartbeat is disabled
+    pub async fn tick(&mut self) {
+        match &mut self.interval {
+            Some(interval) => {
+                interval.tick().await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Marks the client alive, any message counts since pongs may arrive late
+    pub fn received(&mut self) {
+        self.awaiting_pong = false;
+    }
+
+    /// Pings the client, returns false when the previous ping wasn't answered or the
+    /// ping couldn't be sent, the connection should be closed then
+    pub async fn ping<S>(&mut self, socket: &mut S) -> bool
+    where
+        S: Sink<Message> + Unpin,
+    {
+        if self.awaiting_pong {
+            return false;
+        }
+        self.awaiting_pong = true;
+        socket.send(Message::Ping(vec![])).await.is_ok()
+    }
+}
@@ -2,7 +2,22 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod aggregations;
+pub mod auth;
+pub mod concurrency;
+pub mod config;
+pub mod csv;
 pub mod error;
+pub mod heartbeat;
 mod metrics;
+pub mod ohlcv_feed;
+pub mod orderbook_diff;
+pub mod paginated;
+pub mod params;
+pub mod pool_cache;
 mod reader;
+pub mod redis_health;
+pub mod rpc_retry;
 pub mod server;
+pub mod time_bucket;
+pub mod ws_metrics;
+pub mod ws_protocol;
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use clap::Parser;
+use deeplook_server::config::ServerConfig;
 use deeplook_server::server::run_server;
 use std::net::SocketAddr;
 use sui_pg_db::DbArgs;
@@ -27,6 +28,8 @@ struct Args {
     rpc_url: Url,
     #[clap(env, long, default_value = "redis://localhost:6379")]
     redis_url: Url,
+    #[command(flatten)]
+    server_config: ServerConfig,
 }
 
 #[tokio::main]
@@ -42,6 +45,7 @@ async fn main() -> Result<(), anyhow::Error> {
         database_url,
         rpc_url,
         redis_url,
+        server_config,
     } = Args::parse();
     let cancel = CancellationToken::new();
 
@@ -53,6 +57,7 @@ async fn main() -> Result<(), anyhow::Error> {
         cancel.child_token(),
         metrics_address,
         redis_url,
+        server_config,
     )
     .await?;
 
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use crate::error::ErrorCode;
 use crate::server::AppState;
 use axum::{
     body::Body,
@@ -46,9 +47,14 @@ pub(crate) async fn track_metrics(
             .with_label_values(&route_labels)
             .inc();
     } else {
+        // errors of the handlers carry their code, other failures have none
+        let error_code = response
+            .extensions()
+            .get::<ErrorCode>()
+            .map_or("", |code| code.0);
         app.metrics()
             .requests_failed
-            .with_label_values(&[axum_route.as_str(), status.as_str()])
+            .with_label_values(&[axum_route.as_str(), status.as_str(), error_code])
             .inc();
     }
 
@@ -72,8 +72,8 @@ impl RpcMetrics {
 
             requests_failed: register_int_counter_vec_with_registry!(
                 "deeplook_api_requests_failed",
-                "Number of requests that completed with an error for each Deeplook API method, by error code",
-                &["method", "code"],
+                "Number of requests that completed with an error for each Deeplook API method, by HTTP status and error code",
+                &["method", "code", "error_code"],
                 registry
             ).unwrap(),
         })
@@ -0,0 +1,55 @@
+//! Completed candles of `/ws_ohlcv`, polled once per pool and interval and broadcast to every
+//! socket streaming them.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use deeplook_schema::models::OHLCV;
+use tokio::sync::broadcast;
+
+/// Batches of candles a slow socket may fall behind by before it misses some
+pub const OHLCV_FEED_CAPACITY: usize = 16;
+
+/// Pool id and timeframe of a feed
+pub type FeedKey = (String, &'static str);
+
+/// Candles of buckets that completed since the previous batch, oldest first
+pub type CandleBatch = Arc<Vec<OHLCV>>;
+
+/// Feeds with at least one socket, shared by all `/ws_ohlcv` connections
+#[derive(Default)]
+pub struct OhlcvFeeds {
+    feeds: Mutex<HashMap<FeedKey, broadcast::Sender<CandleBatch>>>,
+}
+
+impl OhlcvFeeds {
+    /// Receiver of the feed. The sender is returned too when the feed is new, the caller has to
+    /// start polling it.
+    pub fn subscribe(
+        &self,
+        key: FeedKey,
+    ) -> (
+        broadcast::Receiver<CandleBatch>,
+        Option<broadcast::Sender<CandleBatch>>,
+    ) {
+        let mut feeds = self.feeds.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(sender) = feeds.get(&key) {
+            return (sender.subscribe(), None);
+        }
+        let (sender, receiver) = broadcast::channel(OHLCV_FEED_CAPACITY);
+        feeds.insert(key, sender.clone());
+        (receiver, Some(sender))
+    }
+
+    /// Removes the feed once no socket streams it, its poller stops when this returns true
+    pub fn close_if_unused(&self, key: &FeedKey) -> bool {
+        let mut feeds = self.feeds.lock().unwrap_or_else(|e| e.into_inner());
+        match feeds.get(key) {
+            Some(sender) if sender.receiver_count() > 0 => false,
+            _ => {
+                feeds.remove(key);
+                true
+            }
+        }
+    }
+}
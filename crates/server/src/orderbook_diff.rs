@@ -0,0 +1,89 @@
+//! Orderbook diffs the orderbook service publishes in diff mode and `/ws_orderbook_diff` forwards.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Redis channel of the diffs of a pool
+pub fn orderbook_diff_channel(pool_name: &str) -> String {
+    format!("orderbook_diff::{}", pool_name)
+}
+
+/// Redis key of the full book of a pool as of its latest diff. It's stored with every diff, so
+/// websockets start from the current `seq` instead of the interval's `orderbook::{pool}` book.
+pub fn orderbook_diff_book_key(pool_name: &str) -> String {
+    format!("orderbook_diff_book::{}", pool_name)
+}
+
+/// Price level whose size changed, `new_size` is 0 when the level was removed
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct LevelChange {
+    pub price: f64,
+    pub new_size: f64,
+}
+
+/// Levels changed by one publish. `seq` increases by one with every publish of the pool,
+/// `epoch` changes when the orderbook service restarts and starts counting again.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OrderbookDiff {
+    pub epoch: i64,
+    pub seq: u64,
+    pub asks: Vec<LevelChange>,
+    pub bids: Vec<LevelChange>,
+}
+
+/// Position of a client in the diff sequence of a pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffCursor {
+    pub epoch: i64,
+    pub seq: u64,
+}
+
+/// What a websocket does with a received diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffStep {
+    /// Next diff of the sequence
+    Send,
+    /// Already contained in the snapshot the client got
+    Skip,
+    /// Diffs were missed or the service restarted, the client needs a new snapshot
+    Resnapshot,
+}
+
+impl DiffCursor {
+    /// Cursor of a stored full book, `None` for books published without diffs
+    pub fn of_snapshot(snapshot: &Value) -> Option<Self> {
+        Some(Self {
+            epoch: snapshot.get("epoch")?.as_i64()?,
+            seq: snapshot.get("seq")?.as_u64()?,
+        })
+    }
+
+    pub fn step(&self, diff: &OrderbookDiff) -> DiffStep {
+        if diff.epoch != self.epoch {
+            DiffStep::Resnapshot
+        } else if diff.seq <= self.seq {
+            DiffStep::Skip
+        } else if diff.seq == self.seq + 1 {
+            DiffStep::Send
+        } else {
+            DiffStep::Resnapshot
+        }
+    }
+}
+
+/// Websocket payload of a full book, `{"kind": "snapshot", "epoch", "seq", "asks", "bids"}`
+pub fn snapshot_message(mut snapshot: Value) -> Value {
+    if let Some(fields) = snapshot.as_object_mut() {
+        fields.insert("kind".to_string(), Value::from("snapshot"));
+    }
+    snapshot
+}
+
+/// Websocket payload of a diff, `{"kind": "diff", "epoch", "seq", "asks", "bids"}`
+pub fn diff_message(diff: &OrderbookDiff) -> Value {
+    let mut message = serde_json::to_value(diff).unwrap_or_default();
+    if let Some(fields) = message.as_object_mut() {
+        fields.insert("kind".to_string(), Value::from("diff"));
+    }
+    message
+}
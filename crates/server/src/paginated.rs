@@ -0,0 +1,110 @@
+//! `{"data", "next_cursor", "count"}` envelope of the list endpoints, returned instead of the bare
+//! array with `?envelope=true`.
+
+use std::collections::HashMap;
+
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::DeepBookError;
+
+/// One page of rows. `next_cursor` is set when the page is full and more rows may exist.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Paginated<T> {
+    pub data: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub count: usize,
+}
+
+impl<T> Paginated<T> {
+    /// Page of a query limited to `limit` rows, `cursor` of the last row continues it
+    pub fn new(data: Vec<T>, limit: i64, cursor: impl FnOnce(&T) -> Option<String>) -> Self {
+        let next_cursor = if limit > 0 && data.len() as i64 >= limit {
+            data.last().and_then(cursor)
+        } else {
+            None
+        };
+        Self {
+            count: data.len(),
+            data,
+            next_cursor,
+        }
+    }
+}
+
+/// Whether the `envelope` query parameter (`true` or `false`) asks for a `Paginated` response,
+/// the bare array is the default.
+pub fn wants_envelope(params: &HashMap<String, String>) -> Result<bool, DeepBookError> {
+    match params.get("envelope").map(String::as_str) {
+        None | Some("false") => Ok(false),
+        Some("true") => Ok(true),
+        Some(other) => Err(DeepBookError::BadRequest(format!(
+            "Invalid envelope `{}`, must be one of: [true,false]",
+            other
+        ))),
+    }
+}
+
+/// Position of a row in `(checkpoint_timestamp_ms, event_digest)` order. Clients get it as an
+/// opaque `next_cursor` and send it back as `cursor`, the next page starts strictly after the
+/// row, so rows sharing a timestamp are neither repeated nor skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageCursor {
+    pub timestamp_ms: i64,
+    pub event_digest: String,
+}
+
+impl PageCursor {
+    pub fn encode(&self) -> String {
+        format!("{}:{}", self.timestamp_ms, self.event_digest)
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, DeepBookError> {
+        let invalid = || DeepBookError::BadRequest(format!("Invalid cursor `{}`", cursor));
+        let (timestamp_ms, event_digest) = cursor.split_once(':').ok_or_else(invalid)?;
+        if event_digest.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            timestamp_ms: timestamp_ms.parse().map_err(|_| invalid())?,
+            event_digest: event_digest.to_string(),
+        })
+    }
+
+    /// `cursor` query parameter, a `next_cursor` of an earlier page
+    pub fn from_params(params: &HashMap<String, String>) -> Result<Option<Self>, DeepBookError> {
+        params
+            .get("cursor")
+            .map(|cursor| Self::decode(cursor))
+            .transpose()
+    }
+}
+
+/// Cursor of a row with a millisecond timestamp under `key` and its `event_digest`
+pub fn page_cursor(row: &HashMap<String, Value>, key: &str) -> Option<String> {
+    let cursor = PageCursor {
+        timestamp_ms: row.get(key)?.as_i64()?,
+        event_digest: row.get("event_digest")?.as_str()?.to_string(),
+    };
+    Some(cursor.encode())
+}
+
+/// Rows as a bare JSON array or, with `envelope`, as a `Paginated` page continued after the
+/// millisecond timestamp under `timestamp_key` and the `event_digest` of its last row
+pub fn page_response(
+    rows: Vec<HashMap<String, Value>>,
+    envelope: bool,
+    limit: i64,
+    timestamp_key: &str,
+) -> Response {
+    if envelope {
+        Json(Paginated::new(rows, limit, |row| {
+            page_cursor(row, timestamp_key)
+        }))
+        .into_response()
+    } else {
+        Json(rows).into_response()
+    }
+}
@@ -0,0 +1,69 @@
+//! Query parameters shared by the time range endpoints, parsed strictly so malformed values
+//! return 400 instead of silently falling back to defaults.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::async_trait;
+use axum::extract::{FromRequestParts, Query};
+use axum::http::request::Parts;
+use serde::Deserialize;
+
+use crate::error::DeepBookError;
+
+/// `start_time` and `end_time` are unix seconds. Handlers pick their own defaults, the
+/// parameters are extracted next to a `Query<HashMap<String, String>>` of the
+/// endpoint specific ones.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct CommonParams {
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub limit: Option<i64>,
+    pub volume_in_base: Option<bool>,
+    /// Leave out fills whose maker and taker are the same balance manager
+    pub exclude_self_trades: Option<bool>,
+}
+
+impl CommonParams {
+    /// `start_time` in milliseconds
+    pub fn start_time_ms(&self) -> Option<i64> {
+        self.start_time.map(|t| t.saturating_mul(1000))
+    }
+
+    /// `end_time` in milliseconds, now when missing
+    pub fn end_time_ms(&self) -> i64 {
+        self.end_time
+            .map(|t| t.saturating_mul(1000))
+            .unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_millis() as i64
+            })
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for CommonParams
+where
+    S: Send + Sync,
+{
+    type Rejection = DeepBookError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Query(params) = Query::<CommonParams>::try_from_uri(&parts.uri)
+            .map_err(|e| DeepBookError::BadRequest(e.body_text()))?;
+        // times are converted to milliseconds
+        for (name, time) in [
+            ("start_time", params.start_time),
+            ("end_time", params.end_time),
+        ] {
+            if time.is_some_and(|t| t.checked_mul(1000).is_none()) {
+                return Err(DeepBookError::BadRequest(format!(
+                    "{} is out of range",
+                    name
+                )));
+            }
+        }
+        Ok(params)
+    }
+}
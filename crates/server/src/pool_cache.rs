@@ -0,0 +1,124 @@
+//! Pool metadata kept in memory so handlers don't query the `pools` table on every request.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use deeplook_schema::models::Pool;
+use tokio::sync::Mutex;
+
+/// Lookups of unknown pools reload the pools at most this often, so a newly listed pool shows
+/// up right away while requests for names that don't exist can't scan the table every time
+pub const POOL_CACHE_MISS_RELOAD: Duration = Duration::from_secs(5);
+
+/// Pools of one load of the `pools` table, by name and by id
+#[derive(Debug, Clone, Default)]
+pub struct PoolSet {
+    pools: Vec<Pool>,
+    by_name: HashMap<String, usize>,
+    by_id: HashMap<String, usize>,
+}
+
+impl PoolSet {
+    pub fn new(pools: Vec<Pool>) -> Self {
+        let by_name = pools
+            .iter()
+            .enumerate()
+            .map(|(i, pool)| (pool.pool_name.clone(), i))
+            .collect();
+        let by_id = pools
+            .iter()
+            .enumerate()
+            .map(|(i, pool)| (pool.pool_id.clone(), i))
+            .collect();
+        Self {
+            pools,
+            by_name,
+            by_id,
+        }
+    }
+
+    pub fn pools(&self) -> &[Pool] {
+        &self.pools
+    }
+
+    pub fn by_name(&self, pool_name: &str) -> Option<&Pool> {
+        self.by_name.get(pool_name).map(|&i| &self.pools[i])
+    }
+
+    pub fn by_id(&self, pool_id: &str) -> Option<&Pool> {
+        self.by_id.get(pool_id).map(|&i| &self.pools[i])
+    }
+}
+
+/// Whether pools loaded at `loaded_at` have to be reloaded: once older than `ttl`, or after a
+/// lookup of an unknown pool (`missed`) once older than [`POOL_CACHE_MISS_RELOAD`]. A zero
+/// `ttl` disables the cache.
+pub fn needs_reload(loaded_at: Option<Instant>, now: Instant, ttl: Duration, missed: bool) -> bool {
+    let Some(loaded_at) = loaded_at else {
+        return true;
+    };
+    let age = now.saturating_duration_since(loaded_at);
+    ttl.is_zero() || age >= ttl || (missed && age >= POOL_CACHE_MISS_RELOAD)
+}
+
+/// Pools with the time they were loaded at
+type LoadedPools = Option<(Instant, Arc<PoolSet>)>;
+
+/// Last loaded [`PoolSet`], shared by the clones of the reader
+#[derive(Clone)]
+pub struct PoolCache {
+    ttl: Duration,
+    loaded: Arc<RwLock<LoadedPools>>,
+    /// Held while pools are loaded, so concurrent misses wait for one load
+    loading: Arc<Mutex<()>>,
+}
+
+impl PoolCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            loaded: Arc::new(RwLock::new(None)),
+            loading: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Cached pools unless [`needs_reload`] says they're too old
+    pub fn get(&self, now: Instant, missed: bool) -> Option<Arc<PoolSet>> {
+        let loaded = self.loaded.read().unwrap_or_else(|e| e.into_inner());
+        let (loaded_at, pools) = loaded.as_ref()?;
+        (!needs_reload(Some(*loaded_at), now, self.ttl, missed)).then(|| pools.clone())
+    }
+
+    /// Replaces the cached pools with ones loaded at `loaded_at`
+    pub fn store(&self, pools: PoolSet, loaded_at: Instant) -> Arc<PoolSet> {
+        let pools = Arc::new(pools);
+        *self.loaded.write().unwrap_or_else(|e| e.into_inner()) = Some((loaded_at, pools.clone()));
+        pools
+    }
+    /// Cached pools, or pools from `load` when [`needs_reload`] says they're too old. Only one
+    /// load runs at a time, callers waiting for it get its pools instead of loading again.
+    pub async fn get_or_load<F, Fut, E>(
+        &self,
+        now: Instant,
+        missed: bool,
+        load: F,
+    ) -> Result<Arc<PoolSet>, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<PoolSet, E>>,
+    {
+        if let Some(pools) = self.get(now, missed) {
+            return Ok(pools);
+        }
+        let _loading = self.loading.lock().await;
+        // a load finished while waiting is recent enough
+        if let Some(pools) = self.get(now, missed) {
+            return Ok(pools);
+        }
+        let loaded_at = Instant::now();
+        let pools = load().await?;
+        Ok(self.store(pools, loaded_at))
+    }
+}
@@ -1,28 +1,115 @@
-use crate::error::DeepBookError;
+use crate::aggregations::RAW_OHLCV_QUERY;
+use crate::aggregations::{capped_query, enforce_row_cap, FeeSums};
+use crate::error::{DeepBookError, QueryTimeout};
 use crate::metrics::RpcMetrics;
-use deeplook_schema::models::{OrderFillSummary, Pool};
-use deeplook_schema::schema;
-use deeplook_utils::cache::AsyncCache;
+use crate::paginated::PageCursor;
+use crate::pool_cache::{PoolCache, PoolSet};
+use crate::server::{
+    AssetFlowSum, LatestOrderUpdate, PoolVolumeSums, SortOrder, BALANCE_MANAGERS_QUERY,
+    BALANCE_MANAGER_FLOWS_QUERY, SPREAD_HISTORY_QUERY, TOP_TRADERS_QUERY,
+};
+use bigdecimal::BigDecimal;
+use chrono::NaiveDateTime;
+use deeplook_schema::models::{
+    Flashloan, OrderFill, OrderFillSummary, OrderbookSnapshot, Pool, Proposals, Stakes,
+    TradeParamsUpdate, Votes, OHLCV,
+};
+use deeplook_schema::{schema, view};
+use deeplook_utils::cache::{AsyncCache, Codec};
 use diesel::deserialize::FromSqlRow;
-use diesel::dsl::sql;
+use diesel::dsl::{sql, sum};
 use diesel::expression::QueryMetadata;
 use diesel::pg::Pg;
 use diesel::query_builder::{Query, QueryFragment, QueryId};
 use diesel::query_dsl::CompatibleType;
-use diesel::{BoolExpressionMethods, ExpressionMethods, QueryDsl, SelectableHelper};
+use diesel::sql_types::{BigInt, Bool, Integer, Nullable, Numeric, Text, Timestamp};
+use diesel::{
+    sql_query, BoolExpressionMethods, ExpressionMethods, QueryDsl, QueryableByName,
+    SelectableHelper,
+};
 use diesel_async::methods::LoadQuery;
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use futures::channel::mpsc;
+use futures::{SinkExt, Stream, StreamExt};
 use prometheus::Registry;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use sui_indexer_alt_metrics::db::DbConnectionStatsCollector;
 use sui_pg_db::{Db, DbArgs};
 use url::Url;
 
+/// Rows buffered by [`Reader::stream`] while the consumer is busy
+pub const STREAM_BUFFER_ROWS: usize = 1000;
+
+/// Index of a fill's event in its transaction, `event_digest` is the transaction digest followed
+/// by it. Fills of a taker sweeping several levels share the checkpoint and differ only in this.
+const FILL_EVENT_INDEX: &str = "substring(event_digest from length(digest) + 1)::bigint";
+
+/// `(maker_order_id, taker_order_id, price, base_quantity, quote_quantity,
+/// checkpoint_timestamp_ms, taker_is_bid, maker_balance_manager_id, taker_balance_manager_id,
+/// event_digest)` of a fill
+pub type OrderRow = (
+    String,
+    String,
+    i64,
+    i64,
+    i64,
+    i64,
+    bool,
+    String,
+    String,
+    String,
+);
+
+/// `(order_id, price, original_quantity, quantity, filled_quantity, checkpoint_timestamp_ms,
+/// is_bid, balance_manager_id, status, event_digest)` of an order update
+pub type OrderUpdateRow = (
+    String,
+    i64,
+    i64,
+    i64,
+    i64,
+    i64,
+    bool,
+    String,
+    String,
+    String,
+);
+
+#[derive(QueryableByName)]
+struct BalanceManagerId {
+    #[diesel(sql_type = Text)]
+    balance_manager_id: String,
+}
+
+#[derive(QueryableByName)]
+struct TraderVolume {
+    #[diesel(sql_type = Text)]
+    balance_manager_id: String,
+    #[diesel(sql_type = Numeric)]
+    volume: BigDecimal,
+}
+
+#[derive(QueryableByName)]
+struct SnapshotBests {
+    #[diesel(sql_type = BigInt)]
+    checkpoint: i64,
+    #[diesel(sql_type = Timestamp)]
+    timestamp: NaiveDateTime,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    best_bid: Option<i64>,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    best_ask: Option<i64>,
+}
+
 #[derive(Clone)]
 pub struct Reader {
     db: Db,
     metrics: Arc<RpcMetrics>,
     pub cache: AsyncCache,
+    query_timeout: Option<Duration>,
+    pool_cache: PoolCache,
 }
 
 impl Reader {
@@ -32,6 +119,10 @@ impl Reader {
         metrics: Arc<RpcMetrics>,
         registry: &Registry,
         redis_url: Url,
+        latest_trades_size: usize,
+        cache_codec: Codec,
+        query_timeout_ms: u64,
+        pool_cache_ttl_secs: u64,
     ) -> Result<Self, anyhow::Error> {
         let db = Db::for_read(database_url, db_args).await?;
         registry.register(Box::new(DbConnectionStatsCollector::new(
@@ -43,9 +134,42 @@ impl Reader {
         // connect to the DB on startup.
         let _ = db.connect().await?;
 
-        let cache = AsyncCache::new(redis_url);
+        let cache = AsyncCache::with_options(redis_url, latest_trades_size, cache_codec);
 
-        Ok(Self { db, metrics, cache })
+        Ok(Self {
+            db,
+            metrics,
+            cache,
+            query_timeout: (query_timeout_ms > 0).then(|| Duration::from_millis(query_timeout_ms)),
+            pool_cache: PoolCache::new(Duration::from_secs(pool_cache_ttl_secs)),
+        })
+    }
+
+    /// Awaits a database call, failing with [`QueryTimeout`] once the query timeout passes so
+    /// a slow database doesn't hold the request forever
+    async fn with_timeout<T, E>(
+        &self,
+        call: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, anyhow::Error>
+    where
+        E: Into<anyhow::Error>,
+    {
+        let Some(timeout) = self.query_timeout else {
+            return call.await.map_err(Into::into);
+        };
+        match tokio::time::timeout(timeout, call).await {
+            Ok(res) => res.map_err(Into::into),
+            Err(_) => Err(QueryTimeout(timeout).into()),
+        }
+    }
+
+    /// Database connection from the pool, a failure or timeout counts as a failed request
+    async fn connect(&self) -> Result<sui_pg_db::Connection<'_>, anyhow::Error> {
+        let conn = self.with_timeout(self.db.connect()).await;
+        if conn.is_err() {
+            self.metrics.db_requests_failed.inc();
+        }
+        conn
     }
 
     pub(crate) async fn results<Q, U>(&self, query: Q) -> Result<Vec<U>, anyhow::Error>
@@ -54,9 +178,9 @@ impl Reader {
         Q: RunQueryDsl<AsyncPgConnection> + 'static,
         Q: LoadQuery<'static, AsyncPgConnection, U> + QueryFragment<Pg> + Send,
     {
-        let mut conn = self.db.connect().await?;
+        let mut conn = self.connect().await?;
         let _guard = self.metrics.db_latency.start_timer();
-        let res = query.get_results(&mut conn).await;
+        let res = self.with_timeout(query.get_results(&mut conn)).await;
 
         if res.is_ok() {
             self.metrics.db_requests_succeeded.inc();
@@ -64,7 +188,60 @@ impl Reader {
             self.metrics.db_requests_failed.inc();
         }
 
-        Ok(res?)
+        res
+    }
+
+    /// Streams the rows of `query` as the database returns them instead of loading them all.
+    /// The query runs on its own connection in a separate task, which stops once the stream is
+    /// dropped. At most `STREAM_BUFFER_ROWS` rows wait for a slow consumer.
+    pub fn stream<Q, U>(
+        &self,
+        query: Q,
+    ) -> impl Stream<Item = Result<U, DeepBookError>> + Send + 'static
+    where
+        U: Send + 'static,
+        Q: RunQueryDsl<AsyncPgConnection> + 'static,
+        Q: LoadQuery<'static, AsyncPgConnection, U> + QueryFragment<Pg> + Send,
+    {
+        let (mut sender, receiver) = mpsc::channel(STREAM_BUFFER_ROWS);
+        let reader = self.clone();
+
+        tokio::spawn(async move {
+            let metrics = reader.metrics.clone();
+            let mut conn = match reader.connect().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    let _ = sender.send(Err(DeepBookError::from(e))).await;
+                    return;
+                }
+            };
+            let res = async {
+                let _guard = metrics.db_latency.start_timer();
+                // only the first row is awaited within the timeout, exports may take longer
+                let rows = reader
+                    .with_timeout(query.load_stream::<U>(&mut conn))
+                    .await?;
+                let mut rows = std::pin::pin!(rows);
+                while let Some(row) = rows.next().await {
+                    // the consumer is gone, stop reading
+                    if sender.send(row.map_err(DeepBookError::from)).await.is_err() {
+                        break;
+                    }
+                }
+                Ok::<_, anyhow::Error>(())
+            }
+            .await;
+
+            match res {
+                Ok(()) => metrics.db_requests_succeeded.inc(),
+                Err(e) => {
+                    metrics.db_requests_failed.inc();
+                    let _ = sender.send(Err(DeepBookError::from(e))).await;
+                }
+            }
+        });
+
+        receiver
     }
 
     pub async fn first<'q, Q, ST, U>(&self, query: Q) -> Result<U, anyhow::Error>
@@ -76,31 +253,173 @@ impl Reader {
         Pg: QueryMetadata<<Q::Output as Query>::SqlType>,
         ST: 'static,
     {
-        let mut conn = self.db.connect().await?;
+        let mut conn = self.connect().await?;
         let _guard = self.metrics.db_latency.start_timer();
 
-        let res = query.first(&mut conn).await;
+        let res = self.with_timeout(query.first(&mut conn)).await;
         if res.is_ok() {
             self.metrics.db_requests_succeeded.inc();
         } else {
             self.metrics.db_requests_failed.inc();
         }
 
-        Ok(res?)
+        res
+    }
+
+    /// First row of a raw SQL query loaded into `T`, `None` when it returns no rows. Meant for
+    /// aggregates returning a single row, e.g. `sql_query(QUERY).bind::<Text, _>(pool_id)`.
+    pub async fn first_sql<Q, T>(&self, query: Q) -> Result<Option<T>, DeepBookError>
+    where
+        T: QueryableByName<Pg> + Send,
+        Q: RunQueryDsl<AsyncPgConnection> + 'static,
+        Q: LoadQuery<'static, AsyncPgConnection, T> + QueryFragment<Pg> + Send,
+    {
+        let rows = self.results(query).await.map_err(DeepBookError::from)?;
+        Ok(rows.into_iter().next())
+    }
+
+    /// Runs a trivial query to check the database is reachable
+    pub async fn ping(&self) -> Result<(), anyhow::Error> {
+        let _: i32 = self.first(diesel::select(sql::<Integer>("1"))).await?;
+        Ok(())
     }
 
+    /// Checks if TimescaleDB OHLCV continuous aggregates are available
+    pub async fn has_ohlcv_caggs(&self) -> Result<bool, DeepBookError> {
+        let query = diesel::select(sql::<Bool>("to_regclass('ohlcv_1min') IS NOT NULL"));
+        Ok(self.first(query).await?)
+    }
+
+    /// OHLCV derived from raw order fills ordered by bucket, used when caggs are not available.
+    /// Fails when the range has more than `max_rows` candles.
+    pub async fn get_ohlcv_from_raw(
+        &self,
+        pool_id: &str,
+        bucket_seconds: i64,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        max_rows: i64,
+    ) -> Result<Vec<OHLCV>, DeepBookError> {
+        let query = sql_query(capped_query(RAW_OHLCV_QUERY, max_rows))
+            .bind::<BigInt, _>(bucket_seconds)
+            .bind::<Text, _>(pool_id.to_string())
+            .bind::<Timestamp, _>(start)
+            .bind::<Timestamp, _>(end);
+        let mut rows: Vec<OHLCV> = enforce_row_cap(self.results(query).await?, max_rows)?;
+        // the cap wraps the query, so its order isn't guaranteed
+        rows.sort_by_key(|row| row.bucket);
+        Ok(rows)
+    }
+
+    /// `(pool_id, bucket, close)` of each pool's latest 1 minute bucket between `start` and `end`
+    pub async fn get_cagg_last_prices(
+        &self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<(String, NaiveDateTime, i64)>, DeepBookError> {
+        let query = view::ohlcv_1min::table
+            .filter(view::ohlcv_1min::bucket.between(start, end))
+            .select((
+                view::ohlcv_1min::pool_id,
+                view::ohlcv_1min::bucket,
+                view::ohlcv_1min::close,
+            ))
+            .order_by((
+                view::ohlcv_1min::pool_id.asc(),
+                view::ohlcv_1min::bucket.desc(),
+            ))
+            .distinct_on(view::ohlcv_1min::pool_id);
+        Ok(self.results(query).await?)
+    }
+
+    /// `(pool_id, price)` of the latest fill of each of the pools between `start_time` and
+    /// `end_time` in milliseconds
+    pub async fn get_raw_last_prices(
+        &self,
+        pool_ids: Vec<String>,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<Vec<(String, i64)>, DeepBookError> {
+        let query = schema::order_fills::table
+            .filter(schema::order_fills::pool_id.eq_any(pool_ids))
+            .filter(schema::order_fills::checkpoint_timestamp_ms.between(start_time, end_time))
+            .select((schema::order_fills::pool_id, schema::order_fills::price))
+            .order_by((
+                schema::order_fills::pool_id.asc(),
+                schema::order_fills::checkpoint_timestamp_ms.desc(),
+                schema::order_fills::checkpoint.desc(),
+                schema::order_fills::onchain_timestamp.desc(),
+                schema::order_fills::digest.desc(),
+                sql::<BigInt>(FILL_EVENT_INDEX).desc(),
+            ))
+            .distinct_on(schema::order_fills::pool_id);
+        Ok(self.results(query).await?)
+    }
+
+    /// Price of the pool's latest fill, `None` before its first trade
+    pub async fn get_last_price(&self, pool_id: &str) -> Result<Option<i64>, DeepBookError> {
+        let query = schema::order_fills::table
+            .filter(schema::order_fills::pool_id.eq(pool_id.to_string()))
+            .select(schema::order_fills::price)
+            .order_by((
+                schema::order_fills::checkpoint_timestamp_ms.desc(),
+                schema::order_fills::checkpoint.desc(),
+                schema::order_fills::onchain_timestamp.desc(),
+                schema::order_fills::digest.desc(),
+                sql::<BigInt>(FILL_EVENT_INDEX).desc(),
+            ))
+            .limit(1);
+        Ok(self.results(query).await?.into_iter().next())
+    }
+
+    /// All pools read from the database, handlers use the cached [`Reader::pools`]
     pub async fn get_pools(&self) -> Result<Vec<Pool>, DeepBookError> {
         Ok(self
             .results(schema::pools::table.select(Pool::as_select()))
             .await?)
     }
 
-    pub async fn get_pool_id_by_name(&self, target_name: &str) -> Result<String, DeepBookError> {
-        let query = schema::pools::table
-            .filter(schema::pools::pool_name.eq(target_name))
-            .select(schema::pools::pool_id);
+    /// Cached pools, reloaded once older than `pool_cache_ttl_secs` or, after a lookup of an
+    /// unknown pool, once older than [`crate::pool_cache::POOL_CACHE_MISS_RELOAD`]. Concurrent
+    /// lookups share one reload.
+    async fn pool_set(&self, missed: bool) -> Result<Arc<PoolSet>, DeepBookError> {
+        self.pool_cache
+            .get_or_load(Instant::now(), missed, || async {
+                Ok(PoolSet::new(self.get_pools().await?))
+            })
+            .await
+    }
 
-        Ok(self.first(query).await?)
+    /// Looks a pool up in the cache, reloading it once when the pool isn't there so newly
+    /// listed pools are found
+    async fn find_pool(
+        &self,
+        find: impl Fn(&PoolSet) -> Option<&Pool>,
+        key: &str,
+    ) -> Result<Pool, DeepBookError> {
+        if let Some(pool) = find(&*self.pool_set(false).await?) {
+            return Ok(pool.clone());
+        }
+        find(&*self.pool_set(true).await?)
+            .cloned()
+            .ok_or_else(|| DeepBookError::NotFound(format!("Pool '{}' not found", key)))
+    }
+
+    pub async fn pools(&self) -> Result<Vec<Pool>, DeepBookError> {
+        Ok(self.pool_set(false).await?.pools().to_vec())
+    }
+
+    pub async fn pool_by_name(&self, pool_name: &str) -> Result<Pool, DeepBookError> {
+        self.find_pool(|pools| pools.by_name(pool_name), pool_name)
+            .await
+    }
+
+    pub async fn pool_by_id(&self, pool_id: &str) -> Result<Pool, DeepBookError> {
+        self.find_pool(|pools| pools.by_id(pool_id), pool_id).await
+    }
+
+    pub async fn get_pool_id_by_name(&self, target_name: &str) -> Result<String, DeepBookError> {
+        Ok(self.pool_by_name(target_name).await?.pool_id)
     }
 
     pub async fn get_historical_volume(
@@ -109,6 +428,7 @@ impl Reader {
         end_time: i64,
         pool_ids: &Vec<String>,
         volume_in_base: bool,
+        exclude_self_trades: bool,
     ) -> Result<Vec<(String, i64)>, DeepBookError> {
         let column_to_query = if volume_in_base {
             sql::<diesel::sql_types::BigInt>("base_quantity")
@@ -116,14 +436,48 @@ impl Reader {
             sql::<diesel::sql_types::BigInt>("quote_quantity")
         };
 
-        let query = schema::order_fills::table
+        let mut query = schema::order_fills::table
             .filter(schema::order_fills::checkpoint_timestamp_ms.between(start_time, end_time))
             .filter(schema::order_fills::pool_id.eq_any(pool_ids.clone()))
-            .select((schema::order_fills::pool_id, column_to_query));
+            .select((schema::order_fills::pool_id, column_to_query))
+            .into_boxed();
+        if exclude_self_trades {
+            query = query.filter(
+                schema::order_fills::maker_balance_manager_id
+                    .ne(schema::order_fills::taker_balance_manager_id),
+            );
+        }
 
         Ok(self.results(query).await?)
     }
 
+    /// Base and quote quantities filled in each of `pool_ids`, summed by the database
+    pub async fn get_historical_volume_both(
+        &self,
+        start_time: i64,
+        end_time: i64,
+        pool_ids: &[String],
+        exclude_self_trades: bool,
+    ) -> Result<Vec<PoolVolumeSums>, DeepBookError> {
+        let mut query = schema::order_fills::table
+            .filter(schema::order_fills::checkpoint_timestamp_ms.between(start_time, end_time))
+            .filter(schema::order_fills::pool_id.eq_any(pool_ids.to_vec()))
+            .group_by(schema::order_fills::pool_id)
+            .select((
+                schema::order_fills::pool_id,
+                sum(schema::order_fills::base_quantity),
+                sum(schema::order_fills::quote_quantity),
+            ))
+            .into_boxed();
+        if exclude_self_trades {
+            query = query.filter(
+                schema::order_fills::maker_balance_manager_id
+                    .ne(schema::order_fills::taker_balance_manager_id),
+            );
+        }
+        Ok(self.results(query).await?)
+    }
+
     pub async fn get_order_fill_summary(
         &self,
         start_time: i64,
@@ -131,6 +485,7 @@ impl Reader {
         pool_ids: &Vec<String>,
         balance_manager_id: &str,
         volume_in_base: bool,
+        exclude_self_trades: bool,
     ) -> Result<Vec<OrderFillSummary>, DeepBookError> {
         let column_to_query = if volume_in_base {
             sql::<diesel::sql_types::BigInt>("base_quantity")
@@ -138,7 +493,7 @@ impl Reader {
             sql::<diesel::sql_types::BigInt>("quote_quantity")
         };
         let balance_manager_id = balance_manager_id.to_string();
-        let query = schema::order_fills::table
+        let mut query = schema::order_fills::table
             .select((
                 schema::order_fills::pool_id,
                 schema::order_fills::maker_balance_manager_id,
@@ -151,7 +506,14 @@ impl Reader {
                 schema::order_fills::maker_balance_manager_id
                     .eq(balance_manager_id.clone())
                     .or(schema::order_fills::taker_balance_manager_id.eq(balance_manager_id)),
+            )
+            .into_boxed();
+        if exclude_self_trades {
+            query = query.filter(
+                schema::order_fills::maker_balance_manager_id
+                    .ne(schema::order_fills::taker_balance_manager_id),
             );
+        }
         Ok(self.results(query).await?)
     }
 
@@ -164,7 +526,13 @@ impl Reader {
         let query = schema::order_fills::table
             .filter(schema::order_fills::checkpoint_timestamp_ms.between(start_time, end_time))
             .filter(schema::order_fills::pool_id.eq(pool_id))
-            .order_by(schema::order_fills::checkpoint_timestamp_ms.desc())
+            .order_by((
+                schema::order_fills::checkpoint_timestamp_ms.desc(),
+                schema::order_fills::checkpoint.desc(),
+                schema::order_fills::onchain_timestamp.desc(),
+                schema::order_fills::digest.desc(),
+                sql::<BigInt>(FILL_EVENT_INDEX).desc(),
+            ))
             .select(schema::order_fills::price);
         Ok(self.first(query).await?)
     }
@@ -173,16 +541,33 @@ impl Reader {
         &self,
         pool_name: &str,
     ) -> Result<(String, i16, i16), DeepBookError> {
-        let query = schema::pools::table
-            .filter(schema::pools::pool_name.eq(pool_name))
-            .select((
-                schema::pools::pool_id,
-                schema::pools::base_asset_decimals,
-                schema::pools::quote_asset_decimals,
-            ));
-        self.first(query)
-            .await
-            .map_err(|_| DeepBookError::InternalError(format!("Pool '{}' not found", pool_name)))
+        let pool = self.pool_by_name(pool_name).await?;
+        Ok((
+            pool.pool_id,
+            pool.base_asset_decimals,
+            pool.quote_asset_decimals,
+        ))
+    }
+
+    /// `(type, decimals)` of every asset
+    pub async fn get_asset_decimals(&self) -> Result<Vec<(String, i16)>, DeepBookError> {
+        let query = schema::assets::table.select((schema::assets::type_, schema::assets::decimals));
+        Ok(self.results(query).await?)
+    }
+
+    /// Summed deposits and withdrawals of a balance manager per asset between `start_time` and
+    /// `end_time` in milliseconds, unscaled
+    pub async fn get_balance_manager_flows(
+        &self,
+        balance_manager_id: &str,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<Vec<AssetFlowSum>, DeepBookError> {
+        let query = sql_query(BALANCE_MANAGER_FLOWS_QUERY)
+            .bind::<Text, _>(balance_manager_id.to_string())
+            .bind::<BigInt, _>(start_time)
+            .bind::<BigInt, _>(end_time);
+        Ok(self.results(query).await?)
     }
 
     pub async fn get_orders(
@@ -194,9 +579,10 @@ impl Reader {
         limit: i64,
         maker_balance_manager: Option<String>,
         taker_balance_manager: Option<String>,
-    ) -> Result<Vec<(String, String, i64, i64, i64, i64, bool, String, String)>, DeepBookError>
-    {
-        let mut connection = self.db.connect().await?;
+        exclude_self_trades: bool,
+        cursor: Option<PageCursor>,
+    ) -> Result<Vec<OrderRow>, DeepBookError> {
+        let mut connection = self.connect().await?;
         // Build the query dynamically
         let mut query = schema::order_fills::table
             .filter(schema::order_fills::pool_id.eq(pool_id))
@@ -210,12 +596,30 @@ impl Reader {
         if let Some(taker_id) = taker_balance_manager {
             query = query.filter(schema::order_fills::taker_balance_manager_id.eq(taker_id));
         }
+        if exclude_self_trades {
+            query = query.filter(
+                schema::order_fills::maker_balance_manager_id
+                    .ne(schema::order_fills::taker_balance_manager_id),
+            );
+        }
+        if let Some(cursor) = cursor {
+            query = query.filter(
+                schema::order_fills::checkpoint_timestamp_ms
+                    .lt(cursor.timestamp_ms)
+                    .or(schema::order_fills::checkpoint_timestamp_ms
+                        .eq(cursor.timestamp_ms)
+                        .and(schema::order_fills::event_digest.lt(cursor.event_digest))),
+            );
+        }
 
         let _guard = self.metrics.db_latency.start_timer();
 
         // Fetch latest trades (sorted by timestamp in descending order) within the time range, applying the limit
         let res = query
-            .order_by(schema::order_fills::checkpoint_timestamp_ms.desc()) // Ensures latest trades come first
+            .order_by((
+                schema::order_fills::checkpoint_timestamp_ms.desc(), // Ensures latest trades come first
+                schema::order_fills::event_digest.desc(),
+            ))
             .limit(limit) // Apply limit to get the most recent trades
             .select((
                 schema::order_fills::maker_order_id,
@@ -227,15 +631,18 @@ impl Reader {
                 schema::order_fills::taker_is_bid,
                 schema::order_fills::maker_balance_manager_id,
                 schema::order_fills::taker_balance_manager_id,
+                schema::order_fills::event_digest,
             ))
-            .load::<(String, String, i64, i64, i64, i64, bool, String, String)>(&mut connection)
-            .await
-            .map_err(|_| {
-                DeepBookError::InternalError(format!(
-                    "No trades found for pool '{}' in the specified time range",
-                    pool_name
-                ))
-            });
+            .load::<OrderRow>(&mut connection);
+        let res = self.with_timeout(res).await.map_err(|e| {
+            if e.is::<QueryTimeout>() {
+                return DeepBookError::from(e);
+            }
+            DeepBookError::Db(format!(
+                "No trades found for pool '{}' in the specified time range",
+                pool_name
+            ))
+        });
 
         if res.is_ok() {
             self.metrics.db_requests_succeeded.inc();
@@ -245,6 +652,81 @@ impl Reader {
         res
     }
 
+    /// Fills of the pool between `start_time` and `end_time`, events of one checkpoint
+    /// are ordered by digest so pages continued after `cursor` don't overlap
+    pub async fn get_order_fills(
+        &self,
+        pool_id: &str,
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+        limit: i64,
+        order: SortOrder,
+        cursor: Option<PageCursor>,
+    ) -> Result<Vec<OrderFill>, DeepBookError> {
+        let mut query = schema::order_fills::table
+            .select(OrderFill::as_select())
+            .filter(schema::order_fills::timestamp.between(start_time, end_time))
+            .filter(schema::order_fills::pool_id.eq(pool_id.to_string()))
+            .limit(limit)
+            .into_boxed();
+        if let Some(cursor) = cursor {
+            query = match order {
+                SortOrder::Asc => query.filter(
+                    schema::order_fills::checkpoint_timestamp_ms
+                        .gt(cursor.timestamp_ms)
+                        .or(schema::order_fills::checkpoint_timestamp_ms
+                            .eq(cursor.timestamp_ms)
+                            .and(schema::order_fills::event_digest.gt(cursor.event_digest))),
+                ),
+                SortOrder::Desc => query.filter(
+                    schema::order_fills::checkpoint_timestamp_ms
+                        .lt(cursor.timestamp_ms)
+                        .or(schema::order_fills::checkpoint_timestamp_ms
+                            .eq(cursor.timestamp_ms)
+                            .and(schema::order_fills::event_digest.lt(cursor.event_digest))),
+                ),
+            };
+        }
+        let query = match order {
+            SortOrder::Asc => query.order_by((
+                schema::order_fills::checkpoint_timestamp_ms.asc(),
+                schema::order_fills::event_digest.asc(),
+            )),
+            SortOrder::Desc => query.order_by((
+                schema::order_fills::checkpoint_timestamp_ms.desc(),
+                schema::order_fills::event_digest.desc(),
+            )),
+        };
+
+        Ok(self.results(query).await?)
+    }
+
+    /// Raw taker and maker fees of the pool's fills between `start_time` and `end_time` in
+    /// milliseconds, summed per `(taker_is_bid, taker_fee_is_deep, maker_fee_is_deep)`
+    pub async fn get_fee_sums(
+        &self,
+        pool_id: &str,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<Vec<FeeSums>, DeepBookError> {
+        let query = schema::order_fills::table
+            .filter(schema::order_fills::pool_id.eq(pool_id.to_string()))
+            .filter(schema::order_fills::checkpoint_timestamp_ms.between(start_time, end_time))
+            .group_by((
+                schema::order_fills::taker_is_bid,
+                schema::order_fills::taker_fee_is_deep,
+                schema::order_fills::maker_fee_is_deep,
+            ))
+            .select((
+                schema::order_fills::taker_is_bid,
+                schema::order_fills::taker_fee_is_deep,
+                schema::order_fills::maker_fee_is_deep,
+                sum(schema::order_fills::taker_fee),
+                sum(schema::order_fills::maker_fee),
+            ));
+        Ok(self.results(query).await?)
+    }
+
     pub async fn get_order_updates(
         &self,
         pool_id: String,
@@ -253,12 +735,17 @@ impl Reader {
         limit: i64,
         balance_manager_filter: Option<String>,
         status_filter: Option<String>,
-    ) -> Result<Vec<(String, i64, i64, i64, i64, i64, bool, String, String)>, DeepBookError> {
-        let mut connection = self.db.connect().await?;
+        trader_filter: Option<String>,
+        cursor: Option<PageCursor>,
+    ) -> Result<Vec<OrderUpdateRow>, DeepBookError> {
+        let mut connection = self.connect().await?;
         let mut query = schema::order_updates::table
             .filter(schema::order_updates::checkpoint_timestamp_ms.between(start_time, end_time))
             .filter(schema::order_updates::pool_id.eq(pool_id))
-            .order_by(schema::order_updates::checkpoint_timestamp_ms.desc())
+            .order_by((
+                schema::order_updates::checkpoint_timestamp_ms.desc(),
+                schema::order_updates::event_digest.desc(),
+            ))
             .select((
                 schema::order_updates::order_id,
                 schema::order_updates::price,
@@ -269,10 +756,21 @@ impl Reader {
                 schema::order_updates::is_bid,
                 schema::order_updates::balance_manager_id,
                 schema::order_updates::status,
+                schema::order_updates::event_digest,
             ))
             .limit(limit)
             .into_boxed();
 
+        if let Some(cursor) = cursor {
+            query = query.filter(
+                schema::order_updates::checkpoint_timestamp_ms
+                    .lt(cursor.timestamp_ms)
+                    .or(schema::order_updates::checkpoint_timestamp_ms
+                        .eq(cursor.timestamp_ms)
+                        .and(schema::order_updates::event_digest.lt(cursor.event_digest))),
+            );
+        }
+
         if let Some(manager_id) = balance_manager_filter {
             query = query.filter(schema::order_updates::balance_manager_id.eq(manager_id));
         }
@@ -281,12 +779,19 @@ impl Reader {
             query = query.filter(schema::order_updates::status.eq(status));
         }
 
+        if let Some(trader) = trader_filter {
+            query = query.filter(schema::order_updates::trader.eq(trader));
+        }
+
         let _guard = self.metrics.db_latency.start_timer();
 
-        let res = query
-            .load::<(String, i64, i64, i64, i64, i64, bool, String, String)>(&mut connection)
-            .await
-            .map_err(|_| DeepBookError::InternalError("Error fetching trade details".to_string()));
+        let res = query.load::<OrderUpdateRow>(&mut connection);
+        let res = self.with_timeout(res).await.map_err(|e| {
+            if e.is::<QueryTimeout>() {
+                return DeepBookError::from(e);
+            }
+            DeepBookError::Db("Error fetching trade details".to_string())
+        });
 
         if res.is_ok() {
             self.metrics.db_requests_succeeded.inc();
@@ -295,4 +800,328 @@ impl Reader {
         }
         res
     }
+
+    /// Latest update between `start_time` and `end_time` of every order of the balance manager
+    /// in the pool. Updates of one transaction share a timestamp, so placements sort before
+    /// later statuses. At most `max_rows + 1` are loaded, so callers can tell more orders than
+    /// the cap.
+    pub async fn get_latest_order_updates(
+        &self,
+        pool_id: &str,
+        balance_manager_id: &str,
+        start_time: i64,
+        end_time: i64,
+        max_rows: i64,
+    ) -> Result<Vec<LatestOrderUpdate>, DeepBookError> {
+        let query = schema::order_updates::table
+            .filter(schema::order_updates::pool_id.eq(pool_id.to_string()))
+            .filter(schema::order_updates::balance_manager_id.eq(balance_manager_id.to_string()))
+            .filter(schema::order_updates::checkpoint_timestamp_ms.between(start_time, end_time))
+            .distinct_on(schema::order_updates::order_id)
+            .order_by((
+                schema::order_updates::order_id,
+                schema::order_updates::checkpoint_timestamp_ms.desc(),
+                sql::<Integer>(
+                    "CASE status WHEN 'Placed' THEN 0 WHEN 'Modified' THEN 1 ELSE 2 END",
+                )
+                .desc(),
+            ))
+            .select((
+                schema::order_updates::order_id,
+                schema::order_updates::price,
+                schema::order_updates::original_quantity,
+                schema::order_updates::quantity,
+                schema::order_updates::filled_quantity,
+                schema::order_updates::checkpoint,
+                schema::order_updates::checkpoint_timestamp_ms,
+                schema::order_updates::is_bid,
+                schema::order_updates::balance_manager_id,
+                schema::order_updates::status,
+            ))
+            .limit(max_rows.saturating_add(1));
+
+        Ok(self.results(query).await?)
+    }
+
+    /// `(maker_order_id, checkpoint, base_quantity)` of every fill of the orders as maker
+    /// between `start_time` and `end_time`. At most `max_rows + 1` are loaded, so callers can
+    /// tell more fills than the cap.
+    pub async fn get_maker_fills(
+        &self,
+        pool_id: &str,
+        order_ids: Vec<String>,
+        start_time: i64,
+        end_time: i64,
+        max_rows: i64,
+    ) -> Result<Vec<(String, i64, i64)>, DeepBookError> {
+        if order_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let query = schema::order_fills::table
+            .filter(schema::order_fills::pool_id.eq(pool_id.to_string()))
+            .filter(schema::order_fills::checkpoint_timestamp_ms.between(start_time, end_time))
+            .filter(schema::order_fills::maker_order_id.eq_any(order_ids))
+            .select((
+                schema::order_fills::maker_order_id,
+                schema::order_fills::checkpoint,
+                schema::order_fills::base_quantity,
+            ))
+            .limit(max_rows.saturating_add(1));
+
+        Ok(self.results(query).await?)
+    }
+
+    pub async fn get_flash_loans(
+        &self,
+        pool_id: &str,
+        start_time: i64,
+        end_time: i64,
+        limit: i64,
+    ) -> Result<Vec<Flashloan>, DeepBookError> {
+        let query = schema::flashloans::table
+            .filter(schema::flashloans::pool_id.eq(pool_id.to_string()))
+            .filter(schema::flashloans::checkpoint_timestamp_ms.between(start_time, end_time))
+            .order_by(schema::flashloans::checkpoint_timestamp_ms.desc())
+            .limit(limit)
+            .select(Flashloan::as_select());
+
+        Ok(self.results(query).await?)
+    }
+
+    pub async fn get_proposals(
+        &self,
+        pool_id: &str,
+        epoch: Option<i64>,
+        start_time: i64,
+        end_time: i64,
+        limit: i64,
+        cursor: Option<PageCursor>,
+    ) -> Result<Vec<Proposals>, DeepBookError> {
+        let mut query = schema::proposals::table
+            .filter(schema::proposals::pool_id.eq(pool_id.to_string()))
+            .filter(schema::proposals::checkpoint_timestamp_ms.between(start_time, end_time))
+            .order_by((
+                schema::proposals::checkpoint_timestamp_ms.desc(),
+                schema::proposals::event_digest.desc(),
+            ))
+            .limit(limit)
+            .select(Proposals::as_select())
+            .into_boxed();
+
+        if let Some(epoch) = epoch {
+            query = query.filter(schema::proposals::epoch.eq(epoch));
+        }
+        if let Some(cursor) = cursor {
+            query = query.filter(
+                schema::proposals::checkpoint_timestamp_ms
+                    .lt(cursor.timestamp_ms)
+                    .or(schema::proposals::checkpoint_timestamp_ms
+                        .eq(cursor.timestamp_ms)
+                        .and(schema::proposals::event_digest.lt(cursor.event_digest))),
+            );
+        }
+
+        Ok(self.results(query).await?)
+    }
+
+    pub async fn get_trade_params(
+        &self,
+        pool_id: &str,
+        start_time: i64,
+        end_time: i64,
+        limit: i64,
+    ) -> Result<Vec<TradeParamsUpdate>, DeepBookError> {
+        let query = schema::trade_params_update::table
+            .filter(schema::trade_params_update::pool_id.eq(pool_id.to_string()))
+            .filter(
+                schema::trade_params_update::checkpoint_timestamp_ms.between(start_time, end_time),
+            )
+            .order_by(schema::trade_params_update::checkpoint_timestamp_ms.desc())
+            .limit(limit)
+            .select(TradeParamsUpdate::as_select());
+
+        Ok(self.results(query).await?)
+    }
+
+    pub async fn get_votes(
+        &self,
+        pool_id: &str,
+        epoch: Option<i64>,
+        start_time: i64,
+        end_time: i64,
+        limit: i64,
+    ) -> Result<Vec<Votes>, DeepBookError> {
+        let mut query = schema::votes::table
+            .filter(schema::votes::pool_id.eq(pool_id.to_string()))
+            .filter(schema::votes::checkpoint_timestamp_ms.between(start_time, end_time))
+            .order_by(schema::votes::checkpoint_timestamp_ms.desc())
+            .limit(limit)
+            .select(Votes::as_select())
+            .into_boxed();
+
+        if let Some(epoch) = epoch {
+            query = query.filter(schema::votes::epoch.eq(epoch));
+        }
+
+        Ok(self.results(query).await?)
+    }
+
+    pub async fn get_stakes(
+        &self,
+        pool_id: &str,
+        epoch: Option<i64>,
+        start_time: i64,
+        end_time: i64,
+        limit: i64,
+    ) -> Result<Vec<Stakes>, DeepBookError> {
+        let mut query = schema::stakes::table
+            .filter(schema::stakes::pool_id.eq(pool_id.to_string()))
+            .filter(schema::stakes::checkpoint_timestamp_ms.between(start_time, end_time))
+            .order_by(schema::stakes::checkpoint_timestamp_ms.desc())
+            .limit(limit)
+            .select(Stakes::as_select())
+            .into_boxed();
+
+        if let Some(epoch) = epoch {
+            query = query.filter(schema::stakes::epoch.eq(epoch));
+        }
+
+        Ok(self.results(query).await?)
+    }
+
+    pub async fn get_balance_managers(
+        &self,
+        pool_id: &str,
+        start_time: i64,
+        end_time: i64,
+        after: &str,
+        limit: i64,
+    ) -> Result<Vec<String>, DeepBookError> {
+        let query = sql_query(BALANCE_MANAGERS_QUERY)
+            .bind::<Text, _>(pool_id.to_string())
+            .bind::<BigInt, _>(start_time)
+            .bind::<BigInt, _>(end_time)
+            .bind::<Text, _>(after.to_string())
+            .bind::<BigInt, _>(limit);
+        let rows: Vec<BalanceManagerId> = self.results(query).await?;
+        Ok(rows.into_iter().map(|row| row.balance_manager_id).collect())
+    }
+
+    /// Checkpoint, time, raw best bid and best ask of the snapshots in the range
+    pub async fn get_spread_history(
+        &self,
+        pool_id: &str,
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+        max_rows: i64,
+    ) -> Result<Vec<(i64, NaiveDateTime, Option<i64>, Option<i64>)>, DeepBookError> {
+        let query = sql_query(capped_query(SPREAD_HISTORY_QUERY, max_rows))
+            .bind::<Text, _>(pool_id.to_string())
+            .bind::<Timestamp, _>(start_time)
+            .bind::<Timestamp, _>(end_time);
+        let mut rows: Vec<SnapshotBests> = enforce_row_cap(self.results(query).await?, max_rows)?;
+        // the cap wraps the query, so its order isn't guaranteed
+        rows.sort_by_key(|row| row.checkpoint);
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.checkpoint, row.timestamp, row.best_bid, row.best_ask))
+            .collect())
+    }
+
+    /// Latest stored snapshot of the pool at or before `checkpoint`
+    pub async fn get_snapshot_at(
+        &self,
+        pool_id: &str,
+        checkpoint: i64,
+    ) -> Result<Option<OrderbookSnapshot>, DeepBookError> {
+        let query = schema::orderbook_snapshots::table
+            .filter(schema::orderbook_snapshots::pool_id.eq(pool_id.to_string()))
+            .filter(schema::orderbook_snapshots::checkpoint.le(checkpoint))
+            .order_by(schema::orderbook_snapshots::checkpoint.desc())
+            .limit(1);
+
+        Ok(self.results(query).await?.pop())
+    }
+
+    /// Stored snapshots of the pool between both checkpoints, inclusive, oldest first. At most
+    /// `max_rows + 1` are loaded, so callers can tell a range over the cap.
+    pub async fn get_snapshots_range(
+        &self,
+        pool_id: &str,
+        from_checkpoint: i64,
+        to_checkpoint: i64,
+        max_rows: i64,
+    ) -> Result<Vec<OrderbookSnapshot>, DeepBookError> {
+        let query = schema::orderbook_snapshots::table
+            .filter(schema::orderbook_snapshots::pool_id.eq(pool_id.to_string()))
+            .filter(schema::orderbook_snapshots::checkpoint.between(from_checkpoint, to_checkpoint))
+            .order_by(schema::orderbook_snapshots::checkpoint.asc())
+            .limit(max_rows.saturating_add(1));
+
+        Ok(self.results(query).await?)
+    }
+
+    /// `(reference_pool, conversion_rate, checkpoint_timestamp_ms)` of the latest DEEP price
+    /// point added to the pool
+    pub async fn get_latest_pool_price(
+        &self,
+        target_pool: &str,
+    ) -> Result<Option<(String, i64, i64)>, DeepBookError> {
+        let query = schema::pool_prices::table
+            .filter(schema::pool_prices::target_pool.eq(target_pool.to_string()))
+            .order_by(schema::pool_prices::checkpoint_timestamp_ms.desc())
+            .select((
+                schema::pool_prices::reference_pool,
+                schema::pool_prices::conversion_rate,
+                schema::pool_prices::checkpoint_timestamp_ms,
+            ))
+            .limit(1);
+
+        Ok(self.results(query).await?.pop())
+    }
+
+    /// `timestamp_ms_hi_inclusive` of the pipeline's watermark, its rows up to then are indexed
+    pub async fn get_watermark_timestamp_ms(
+        &self,
+        pipeline: &str,
+    ) -> Result<Option<i64>, DeepBookError> {
+        let query = schema::watermarks::table
+            .filter(schema::watermarks::pipeline.eq(pipeline.to_string()))
+            .select(schema::watermarks::timestamp_ms_hi_inclusive);
+
+        Ok(self.results(query).await?.pop())
+    }
+
+    /// `(pipeline, checkpoint_hi_inclusive, timestamp_ms_hi_inclusive)` of every pipeline
+    pub async fn get_watermarks(&self) -> Result<Vec<(String, i64, i64)>, DeepBookError> {
+        let query = schema::watermarks::table
+            .order_by(schema::watermarks::pipeline)
+            .select((
+                schema::watermarks::pipeline,
+                schema::watermarks::checkpoint_hi_inclusive,
+                schema::watermarks::timestamp_ms_hi_inclusive,
+            ));
+
+        Ok(self.results(query).await?)
+    }
+
+    /// Balance managers with their raw maker plus taker base volume, largest first
+    pub async fn get_top_traders(
+        &self,
+        pool_id: &str,
+        start_time: i64,
+        end_time: i64,
+        limit: i64,
+    ) -> Result<Vec<(String, BigDecimal)>, DeepBookError> {
+        let query = sql_query(TOP_TRADERS_QUERY)
+            .bind::<Text, _>(pool_id.to_string())
+            .bind::<BigInt, _>(start_time)
+            .bind::<BigInt, _>(end_time)
+            .bind::<BigInt, _>(limit);
+        let rows: Vec<TraderVolume> = self.results(query).await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.balance_manager_id, row.volume))
+            .collect())
+    }
 }
@@ -0,0 +1,84 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use deeplook_utils::cache::AsyncCache;
+use prometheus::{
+    register_histogram_with_registry, register_int_gauge_with_registry, Histogram, IntGauge,
+    Registry,
+};
+
+/// Redis answers pings in well under a millisecond when healthy
+const REDIS_PING_SEC_BUCKETS: &[f64] = &[
+    0.0005, 0.001, 0.002, 0.005, 0.01, 0.02, 0.05, 0.1, 0.2, 0.5, 1.0, 2.0, 5.0,
+];
+
+#[derive(Clone)]
+pub struct RedisHealth {
+    pub up: IntGauge,
+    pub ping_latency: Histogram,
+    /// Result of the last ping, `None` before the first one
+    last_up: Arc<Mutex<Option<bool>>>,
+}
+
+impl RedisHealth {
+    pub fn new(registry: &Registry) -> Self {
+        Self {
+            up: register_int_gauge_with_registry!(
+                "redis_up",
+                "1 when the last Redis ping succeeded, 0 when it failed",
+                registry
+            )
+            .unwrap(),
+            ping_latency: register_histogram_with_registry!(
+                "redis_ping_latency",
+                "Time taken by Redis to answer successful pings",
+                REDIS_PING_SEC_BUCKETS.to_vec(),
+                registry
+            )
+            .unwrap(),
+            last_up: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Records the result of a ping, returns whether it differs from the previous one
+    pub fn record(&self, up: bool) -> bool {
+        self.up.set(up as i64);
+        match self.last_up.lock() {
+            Ok(mut last_up) => last_up.replace(up) != Some(up),
+            Err(_) => false,
+        }
+    }
+
+    /// Pings Redis once and records the result, returns whether Redis answered. Only changes
+    /// are logged, so an outage doesn't log every ping.
+    pub async fn check(&self, cache: &AsyncCache) -> bool {
+        let start = Instant::now();
+        match cache.ping().await {
+            Ok(()) => {
+                self.ping_latency.observe(start.elapsed().as_secs_f64());
+                if self.record(true) {
+                    println!("Redis is reachable");
+                }
+                true
+            }
+            Err(e) => {
+                if self.record(false) {
+                    println!("Redis ping failed: {:?}", e);
+                }
+                false
+            }
+        }
+    }
+}
+
+/// Pings Redis every `interval` forever, zero interval disables the checks
+pub async fn monitor_redis(health: RedisHealth, cache: AsyncCache, interval: Duration) {
+    if interval.is_zero() {
+        return;
+    }
+    let mut ticks = tokio::time::interval(interval);
+    loop {
+        ticks.tick().await;
+        health.check(&cache).await;
+    }
+}
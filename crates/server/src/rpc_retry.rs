@@ -0,0 +1,90 @@
+//! Retries of idempotent Sui RPC reads, so a transient full node error doesn't fail the request.
+
+use std::fmt::Display;
+use std::future::Future;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use jsonrpsee::core::ClientError;
+
+/// Number of attempts and delay before the first retry, doubled for every further retry
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub base_delay: Duration,
+}
+
+/// Policy of the object reads and dev-inspect calls of the handlers
+pub const RPC_RETRY: RetryPolicy = RetryPolicy {
+    attempts: 3,
+    base_delay: Duration::from_millis(100),
+};
+
+impl RetryPolicy {
+    /// Delay before retry `retry`, counted from 1. `jitter` in `[0, 1)` shortens it by up to
+    /// half, so requests failing together don't retry together.
+    pub fn backoff_delay(&self, retry: u32, jitter: f64) -> Duration {
+        let delay = self.base_delay * 2u32.saturating_pow(retry.saturating_sub(1));
+        delay.mul_f64(1.0 - jitter.clamp(0.0, 1.0) / 2.0)
+    }
+}
+
+/// Transport failures, timeouts and dropped connections. Errors the full node answered with
+/// and errors decoding its answer would fail again.
+pub fn is_retryable_rpc_error(err: &sui_sdk::error::Error) -> bool {
+    matches!(err, sui_sdk::error::Error::RpcError(e) if is_retryable_client_error(e))
+}
+
+/// The JSON-RPC client errors of [`is_retryable_rpc_error`]
+pub fn is_retryable_client_error(err: &ClientError) -> bool {
+    matches!(
+        err,
+        ClientError::Transport(_) | ClientError::RequestTimeout | ClientError::RestartNeeded(_)
+    )
+}
+
+/// Calls `call` until it succeeds, fails with an error `is_retryable` rejects or runs out of
+/// attempts, returning the last error. Only for calls that are safe to repeat.
+pub async fn with_retry<T, E, F, Fut>(
+    policy: RetryPolicy,
+    is_retryable: impl Fn(&E) -> bool,
+    mut call: F,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: Display,
+{
+    let mut attempt = 1;
+    loop {
+        match call().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < policy.attempts && is_retryable(&e) => {
+                let delay = policy.backoff_delay(attempt, jitter());
+                println!(
+                    "RPC call failed on attempt {}/{}, retrying in {:?}: {}",
+                    attempt, policy.attempts, delay, e
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// [`with_retry`] with the handlers' policy and retryable errors
+pub async fn retry_rpc<T, F, Fut>(call: F) -> Result<T, sui_sdk::error::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, sui_sdk::error::Error>>,
+{
+    with_retry(RPC_RETRY, is_retryable_rpc_error, call).await
+}
+
+fn jitter() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    nanos as f64 / 1_000_000_000.0
+}
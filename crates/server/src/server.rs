@@ -1,43 +1,69 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::error::DeepBookError;
-use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
-use axum::http::Method;
-use axum::response::IntoResponse;
+use crate::auth::{ws_auth, WsAuth};
+use crate::concurrency::{limit_concurrency, ConcurrencyLimit};
+use crate::config::ServerConfig;
+use crate::csv::{rows_response, wants_csv};
+use crate::error::{DeepBookError, ErrorCode, QueryTimeout};
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::http::{HeaderMap, HeaderValue, Method};
+use axum::response::{IntoResponse, Response};
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
     routing::get,
     Json, Router,
 };
+use bigdecimal::{BigDecimal, ToPrimitive};
 use chrono::NaiveDateTime;
-use deeplook_schema::models::{BalancesSummary, OrderFill, Pool};
+use deeplook_schema::models::{
+    BalancesSummary, LatestTrade, OrderFill, OrderbookSnapshot, Pool, TradeParamsUpdate, OHLCV,
+};
 use deeplook_schema::*;
+use deeplook_utils::{cache::AsyncCache, checkpoint::CheckpointDigest, rounding::to_readable};
 use diesel::dsl::count_star;
 use diesel::dsl::{max, min};
-use diesel::{ExpressionMethods, QueryDsl, SelectableHelper};
-use futures::{FutureExt, StreamExt};
-use serde_json::Value;
+use diesel::{BoolExpressionMethods, ExpressionMethods, QueryDsl, Queryable, SelectableHelper};
+use futures::{FutureExt, Sink, SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::net::{IpAddr, Ipv4Addr};
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
-use std::{collections::HashMap, net::SocketAddr};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+};
 use sui_pg_db::DbArgs;
 use tokio::net::TcpListener;
-use tower_http::cors::{AllowMethods, Any, CorsLayer};
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowMethods, AllowOrigin, Any, CorsLayer};
 use url::Url;
 
+use crate::heartbeat::Heartbeat;
 use crate::metrics::middleware::track_metrics;
 use crate::metrics::RpcMetrics;
+use crate::ohlcv_feed::{CandleBatch, FeedKey, OhlcvFeeds};
+use crate::orderbook_diff::{
+    diff_message, orderbook_diff_book_key, orderbook_diff_channel, snapshot_message, DiffCursor,
+    DiffStep, OrderbookDiff,
+};
+use crate::paginated::{page_response, wants_envelope, PageCursor};
+use crate::params::CommonParams;
 use crate::reader::Reader;
+use crate::redis_health::{monitor_redis, RedisHealth};
+use crate::rpc_retry::retry_rpc;
+use crate::time_bucket::bucket_start_ms;
+use crate::ws_metrics::{send_counted, WsMetrics, WsStream};
+use crate::ws_protocol::{WsVersion, WS_PROTOCOLS};
 use axum::middleware::from_fn_with_state;
 use futures::future::join_all;
-use prometheus::Registry;
+use prometheus::{IntCounter, Registry};
 use std::str::FromStr;
 use sui_indexer_alt_metrics::{MetricsArgs, MetricsService};
 use sui_sdk::rpc_types::{SuiObjectData, SuiObjectDataOptions, SuiObjectResponse};
-use sui_sdk::SuiClientBuilder;
+use sui_sdk::{SuiClient, SuiClientBuilder};
 use sui_types::{
     base_types::{ObjectID, ObjectRef, SuiAddress},
     programmable_transaction_builder::ProgrammableTransactionBuilder,
@@ -46,30 +72,45 @@ use sui_types::{
     TypeTag,
 };
 use tokio::join;
+use tokio::sync::OnceCell;
 use tokio_util::sync::CancellationToken;
 
 use crate::aggregations::{
-    avg_duration_between_trades, avg_trade_size, get_avg_trade_size_multi_window, get_ohlcv,
-    get_order_fill_24h_summary, get_volume_last_n_days, get_volume_multi_window, get_vwap,
-    orderbook_imbalance,
+    avg_duration_between_trades, avg_trade_size, get_avg_trade_size_multi_window, get_fees,
+    get_indicators, get_lifetime_volume, get_ohlcv, get_order_fill_24h_summary, get_twap,
+    get_volatility, get_volume_last_n_days, get_volume_multi_window, get_vwap, orderbook_imbalance,
+    ToDecimalFloat64,
+};
+use crate::aggregations::{
+    candles_closed_by, completed_candles, enforce_row_cap, load_ohlcv, parse_ohlcv_interval,
+    OhlcvScale,
 };
 
 pub const SUI_MAINNET_URL: &str = "https://fullnode.mainnet.sui.io:443";
+pub const READYZ_PATH: &str = "/readyz";
 pub const GET_POOLS_PATH: &str = "/get_pools";
 pub const GET_HISTORICAL_VOLUME_BY_BALANCE_MANAGER_ID_WITH_INTERVAL: &str =
     "/historical_volume_by_balance_manager_id_with_interval/:pool_names/:balance_manager_id";
 pub const GET_HISTORICAL_VOLUME_BY_BALANCE_MANAGER_ID: &str =
     "/historical_volume_by_balance_manager_id/:pool_names/:balance_manager_id";
 pub const HISTORICAL_VOLUME_PATH: &str = "/historical_volume/:pool_names";
+pub const HISTORICAL_VOLUME_BOTH_PATH: &str = "/historical_volume_both/:pool_names";
 pub const ALL_HISTORICAL_VOLUME_PATH: &str = "/all_historical_volume";
 pub const GET_NET_DEPOSITS: &str = "/get_net_deposits/:asset_ids/:timestamp";
 pub const TICKER_PATH: &str = "/ticker";
 pub const TRADES_PATH: &str = "/trades/:pool_name";
+pub const RECENT_TRADES_PATH: &str = "/recent_trades/:pool_name";
 pub const ORDER_UPDATES_PATH: &str = "/order_updates/:pool_name";
+pub const OPEN_ORDERS_PATH: &str = "/open_orders/:pool_name";
+/// Orders without updates in this window are left out of `/open_orders` by default
+pub const OPEN_ORDERS_DEFAULT_WINDOW_MS: i64 = 30 * 24 * 60 * 60 * 1000;
 pub const TRADE_COUNT_PATH: &str = "/trade_count";
 pub const ASSETS_PATH: &str = "/assets";
 pub const SUMMARY_PATH: &str = "/summary";
 pub const LEVEL2_PATH: &str = "/orderbook/:pool_name";
+pub const ORDERBOOKS_PATH: &str = "/orderbooks";
+/// Max pools of one `/orderbooks` request, each pool is a separate dev-inspect call
+pub const ORDERBOOKS_MAX_POOLS: usize = 20;
 pub const LEVEL2_MODULE: &str = "pool";
 pub const LEVEL2_FUNCTION: &str = "get_level2_ticks_from_mid";
 pub const DEEPBOOK_PACKAGE_ID: &str =
@@ -81,27 +122,149 @@ pub const DEEP_TREASURY_ID: &str =
 pub const DEEP_SUPPLY_MODULE: &str = "deep";
 pub const DEEP_SUPPLY_FUNCTION: &str = "total_supply";
 pub const DEEP_SUPPLY_PATH: &str = "/deep_supply";
+pub const CHAIN_TIP_PATH: &str = "/chain_tip";
 pub const ORDER_FILLS_PATH: &str = "/order_fills/:pool_name";
+pub const FLASH_LOANS_PATH: &str = "/flash_loans/:pool_name";
+pub const PROPOSALS_PATH: &str = "/proposals/:pool_name";
+pub const VOTES_PATH: &str = "/votes/:pool_name";
+pub const STAKES_PATH: &str = "/stakes/:pool_name";
+pub const TRADE_PARAMS_PATH: &str = "/trade_params/:pool_name";
+pub const TRADE_PARAMS_LATEST_PATH: &str = "/trade_params/:pool_name/latest";
+pub const CONVERSION_RATE_PATH: &str = "/conversion_rate/:pool_name";
+
+/// Default and max number of trade params updates in the history
+pub const TRADE_PARAMS_DEFAULT_LIMIT: i64 = 100;
+pub const TRADE_PARAMS_MAX_LIMIT: i64 = 1000;
+pub const BALANCE_MANAGERS_PATH: &str = "/pool/:pool_name/balance_managers";
+
+/// Default and max number of fills of one `/order_fills` response
+pub const ORDER_FILLS_DEFAULT_LIMIT: i64 = 1000;
+pub const ORDER_FILLS_MAX_LIMIT: i64 = 10000;
+
+/// Default and max number of balance managers in one page
+pub const BALANCE_MANAGERS_PAGE_SIZE: i64 = 1000;
+
+/// Distinct makers and takers of a pool in a time range, sorted by id.
+/// Binds: pool id, start and end in milliseconds, id to continue after and page size.
+pub const BALANCE_MANAGERS_QUERY: &str = r#"
+SELECT balance_manager_id FROM (
+    SELECT maker_balance_manager_id AS balance_manager_id
+    FROM order_fills
+    WHERE pool_id = $1 AND checkpoint_timestamp_ms BETWEEN $2 AND $3
+    UNION
+    SELECT taker_balance_manager_id AS balance_manager_id
+    FROM order_fills
+    WHERE pool_id = $1 AND checkpoint_timestamp_ms BETWEEN $2 AND $3
+) managers
+WHERE balance_manager_id > $4
+ORDER BY balance_manager_id
+LIMIT $5
+"#;
+pub const BALANCE_MANAGER_FLOWS_PATH: &str = "/balance_manager/:balance_manager_id/flows";
+
+/// Deposited and withdrawn totals of a balance manager per asset.
+/// Binds: balance manager id, start and end in milliseconds.
+pub const BALANCE_MANAGER_FLOWS_QUERY: &str = r#"
+SELECT asset, SUM(amount)::NUMERIC AS amount, deposit
+FROM balances
+WHERE balance_manager_id = $1 AND checkpoint_timestamp_ms BETWEEN $2 AND $3
+GROUP BY asset, deposit
+"#;
+pub const TOP_TRADERS_PATH: &str = "/pool/:pool_name/top_traders";
+
+/// Default and max number of ranked balance managers
+pub const TOP_TRADERS_DEFAULT_LIMIT: i64 = 10;
+pub const TOP_TRADERS_MAX_LIMIT: i64 = 100;
+
+/// Balance managers of a pool ranked by base volume traded as maker or taker.
+/// Binds: pool id, start and end in milliseconds and number of managers.
+pub const TOP_TRADERS_QUERY: &str = r#"
+SELECT balance_manager_id, SUM(base_quantity) AS volume FROM (
+    SELECT maker_balance_manager_id AS balance_manager_id, base_quantity
+    FROM order_fills
+    WHERE pool_id = $1 AND checkpoint_timestamp_ms BETWEEN $2 AND $3
+    UNION ALL
+    SELECT taker_balance_manager_id AS balance_manager_id, base_quantity
+    FROM order_fills
+    WHERE pool_id = $1 AND checkpoint_timestamp_ms BETWEEN $2 AND $3
+) sides
+GROUP BY balance_manager_id
+ORDER BY volume DESC, balance_manager_id
+LIMIT $4
+"#;
+pub const SPREAD_HISTORY_PATH: &str = "/spread_history/:pool_name";
+pub const BEST_PATH: &str = "/best/:pool_name";
+pub const LAST_PRICE_PATH: &str = "/last_price/:pool_name";
+pub const ORDERBOOK_SNAPSHOT_PATH: &str = "/orderbook_snapshot/:pool_name";
+pub const ORDERBOOK_SNAPSHOTS_PATH: &str = "/orderbook_snapshots/:pool_name";
+/// Max snapshots of one `/orderbook_snapshots` response, each holds both sides of the book
+pub const ORDERBOOK_SNAPSHOTS_MAX: i64 = 1000;
+pub const STATUS_PATH: &str = "/status";
+
+/// Best ask and bid of each stored snapshot of a pool, ignoring empty levels.
+/// Binds: pool id, start and end timestamp.
+pub const SPREAD_HISTORY_QUERY: &str = r#"
+SELECT
+    checkpoint,
+    timestamp,
+    (
+        SELECT MIN(price::BIGINT)
+        FROM jsonb_each_text(asks) AS levels(price, size)
+        WHERE size::BIGINT > 0
+    ) AS best_ask,
+    (
+        SELECT MAX(price::BIGINT)
+        FROM jsonb_each_text(bids) AS levels(price, size)
+        WHERE size::BIGINT > 0
+    ) AS best_bid
+FROM orderbook_snapshots
+WHERE pool_id = $1 AND timestamp BETWEEN $2 AND $3
+ORDER BY checkpoint
+"#;
 pub const WEBSOCKET_ORDERBOOK: &str = "/ws_orderbook/:pool_name";
+pub const WEBSOCKET_ORDERBOOK_DIFF: &str = "/ws_orderbook_diff/:pool_name";
 pub const WEBSOCKET_ORDERBOOK_BESTS: &str = "/ws_orderbook_bests/:pool_name";
 pub const WEBSOCKET_ORDERBOOK_SPREAD: &str = "/ws_orderbook_spread/:pool_name";
 pub const WEBSOCKET_LATEST_TRADES: &str = "/latest_trades/:pool_name";
+pub const WEBSOCKET_OHLCV: &str = "/ws_ohlcv/:pool_name";
+
+/// Completed candles `/ws_ohlcv` sends when a client connects
+pub const OHLCV_WS_INITIAL_CANDLES: i64 = 100;
+/// Wait after a bucket closes before its candle is read, and between reads while its fills
+/// aren't indexed yet
+pub const OHLCV_WS_SETTLE: Duration = Duration::from_secs(2);
+/// Indexer pipeline of `order_fills`, candles are complete up to its watermark
+pub const OHLCV_WS_PIPELINE: &str = "order_fill";
 
 // Data Aggregation
 pub const OHLCV_PATH: &str = "/ohlcv/:pool_name";
+pub const INDICATORS_PATH: &str = "/indicators/:pool_name";
+pub const VOLATILITY_PATH: &str = "/volatility/:pool_name";
 pub const AVG_TRADE_PATH: &str = "/get_avg_trade_size/:pool_name";
 pub const AVG_DURATION_BETWEEN_TRADES_PATH: &str = "/get_avg_duration_between_trades/:pool_name";
 pub const VWAP: &str = "/get_vwap/:pool_name";
+pub const TWAP: &str = "/get_twap/:pool_name";
 pub const OBI: &str = "/orderbook_imbalance/:pool_name";
 pub const FILLS_24H_SUMMARY: &str = "/fills_24h_summary";
 pub const VOLUME: &str = "/volume/:pool_name";
 pub const VOLUME_MULTI_WINDOW: &str = "/volume_multi_window/:pool_name";
+pub const LIFETIME_VOLUME_PATH: &str = "/pool/:pool_name/lifetime_volume";
+pub const FEES_PATH: &str = "/fees/:pool_name";
 pub const AVERAGE_TRADE_SIZE_MULTI_WINDOW: &str = "/average_trade_multi_window/:pool_name";
 
 #[derive(Clone)]
 pub struct AppState {
     pub reader: Reader,
+    pub config: ServerConfig,
+    pub ws_auth: WsAuth,
+    pub ws_metrics: WsMetrics,
+    /// Cancelled on shutdown, websockets close when it is
+    pub ws_shutdown: CancellationToken,
+    /// Candle feeds of the `/ws_ohlcv` sockets
+    pub ohlcv_feeds: OhlcvFeeds,
     metrics: Arc<RpcMetrics>,
+    rpc_url: Url,
+    sui_client: OnceCell<Arc<SuiClient>>,
 }
 
 impl AppState {
@@ -110,14 +273,59 @@ impl AppState {
         args: DbArgs,
         registry: &Registry,
         redis_url: Url,
+        rpc_url: Url,
+        mut config: ServerConfig,
     ) -> Result<Self, anyhow::Error> {
         let metrics = RpcMetrics::new(registry);
-        let reader = Reader::new(database_url, args, metrics.clone(), registry, redis_url).await?;
-        Ok(Self { reader, metrics })
+        let reader = Reader::new(
+            database_url,
+            args,
+            metrics.clone(),
+            registry,
+            redis_url,
+            config.latest_trades_size,
+            config.cache_codec,
+            config.db_query_timeout_ms,
+            config.pool_cache_ttl_secs,
+        )
+        .await?;
+        if !config.ohlcv_from_raw && !reader.has_ohlcv_caggs().await? {
+            println!("OHLCV continuous aggregates not found, computing OHLCV from order_fills");
+            config.ohlcv_from_raw = true;
+        }
+        let ws_auth = WsAuth::new(config.ws_auth_tokens.clone());
+        let ws_metrics = WsMetrics::new(registry);
+        Ok(Self {
+            reader,
+            config,
+            ws_auth,
+            ws_metrics,
+            ws_shutdown: CancellationToken::new(),
+            ohlcv_feeds: OhlcvFeeds::default(),
+            metrics,
+            rpc_url,
+            sui_client: OnceCell::new(),
+        })
     }
     pub(crate) fn metrics(&self) -> &RpcMetrics {
         &self.metrics
     }
+
+    /// RPC client of the full node at `rpc_url` shared by all requests, connected on first use
+    /// so the API starts while the full node is unreachable. A failed connection is retried by
+    /// the next request.
+    pub async fn sui_client(&self) -> Result<Arc<SuiClient>, DeepBookError> {
+        let client = self
+            .sui_client
+            .get_or_try_init(|| async {
+                SuiClientBuilder::default()
+                    .build(self.rpc_url.as_str())
+                    .await
+                    .map(Arc::new)
+            })
+            .await?;
+        Ok(client.clone())
+    }
 }
 
 pub async fn run_server(
@@ -128,23 +336,41 @@ pub async fn run_server(
     cancellation_token: CancellationToken,
     metrics_address: SocketAddr,
     redis_url: Url,
+    config: ServerConfig,
 ) -> Result<(), anyhow::Error> {
     let registry = Registry::new_custom(Some("deeplook_api".into()), None)
         .expect("Failed to create Prometheus registry.");
 
     let metrics = MetricsService::new(MetricsArgs { metrics_address }, registry);
 
-    let state = AppState::new(database_url, db_arg, metrics.registry(), redis_url).await?;
+    let mut state = AppState::new(
+        database_url,
+        db_arg,
+        metrics.registry(),
+        redis_url,
+        rpc_url,
+        config,
+    )
+    .await?;
+    state.ws_shutdown = cancellation_token.child_token();
+    tokio::spawn(monitor_redis(
+        RedisHealth::new(metrics.registry()),
+        state.reader.cache.clone(),
+        Duration::from_secs(state.config.redis_ping_interval_secs),
+    ));
     let socket_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), server_port);
 
     println!("🚀 Server started successfully on port {}", server_port);
+    if state.config.disable_rpc_routes {
+        println!("RPC routes are disabled, {} is not used", state.rpc_url);
+    }
 
     let _handle = tokio::spawn(async move {
         let _ = metrics.run().await;
     });
 
     let listener = TcpListener::bind(socket_address).await?;
-    axum::serve(listener, make_router(Arc::new(state), rpc_url))
+    axum::serve(listener, make_router(Arc::new(state)))
         .with_graceful_shutdown(async move {
             cancellation_token.cancelled().await;
         })
@@ -152,16 +378,52 @@ pub async fn run_server(
 
     Ok(())
 }
-pub(crate) fn make_router(state: Arc<AppState>, rpc_url: Url) -> Router {
-    let cors = CorsLayer::new()
+
+/// Gzip, deflate or brotli encodes responses for clients sending `Accept-Encoding`
+pub fn compression_layer() -> CompressionLayer {
+    CompressionLayer::new()
+}
+
+/// CORS of GET requests from the allowed origins, or from any origin when none are set
+pub fn cors_layer(allowed_origins: &[HeaderValue]) -> CorsLayer {
+    let allow_origin = if allowed_origins.is_empty() {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(allowed_origins.iter().cloned())
+    };
+    CorsLayer::new()
         .allow_methods(AllowMethods::list(vec![Method::GET, Method::OPTIONS]))
         .allow_headers(Any)
-        .allow_origin(Any);
+        .allow_origin(allow_origin)
+}
+
+pub fn make_router(state: Arc<AppState>) -> Router {
+    let cors = cors_layer(&state.config.cors_allowed_origins);
 
     let db_routes = Router::new()
         .route("/", get(health_check))
+        .route(READYZ_PATH, get(readyz))
         .route(GET_POOLS_PATH, get(get_pools))
+        .route(ASSETS_PATH, get(assets))
+        .route(FLASH_LOANS_PATH, get(flash_loans))
+        .route(PROPOSALS_PATH, get(proposals))
+        .route(VOTES_PATH, get(votes))
+        .route(STAKES_PATH, get(stakes))
+        .route(TRADE_PARAMS_PATH, get(trade_params))
+        .route(TRADE_PARAMS_LATEST_PATH, get(latest_trade_params))
+        .route(CONVERSION_RATE_PATH, get(conversion_rate))
+        .route(BEST_PATH, get(best))
+        .route(LAST_PRICE_PATH, get(last_price))
+        .route(ORDERBOOK_SNAPSHOT_PATH, get(orderbook_snapshot))
+        .route(STATUS_PATH, get(status))
+        .with_state(state.clone());
+
+    // scans of fills and updates share one limit with the aggregations
+    let heavy_limit = ConcurrencyLimit::new(state.config.heavy_route_concurrency);
+
+    let heavy_db_routes = Router::new()
         .route(HISTORICAL_VOLUME_PATH, get(historical_volume))
+        .route(HISTORICAL_VOLUME_BOTH_PATH, get(historical_volume_both))
         .route(ALL_HISTORICAL_VOLUME_PATH, get(all_historical_volume))
         .route(
             GET_HISTORICAL_VOLUME_BY_BALANCE_MANAGER_ID_WITH_INTERVAL,
@@ -174,55 +436,100 @@ pub(crate) fn make_router(state: Arc<AppState>, rpc_url: Url) -> Router {
         .route(GET_NET_DEPOSITS, get(get_net_deposits))
         .route(TICKER_PATH, get(ticker))
         .route(TRADES_PATH, get(trades))
+        .route(RECENT_TRADES_PATH, get(recent_trades))
         .route(TRADE_COUNT_PATH, get(trade_count))
         .route(ORDER_UPDATES_PATH, get(order_updates))
-        .route(ASSETS_PATH, get(assets))
+        .route(OPEN_ORDERS_PATH, get(open_orders))
         .route(ORDER_FILLS_PATH, get(get_order_fills))
+        .route(BALANCE_MANAGERS_PATH, get(balance_managers))
+        .route(BALANCE_MANAGER_FLOWS_PATH, get(balance_manager_flows))
+        .route(TOP_TRADERS_PATH, get(top_traders))
+        .route(SPREAD_HISTORY_PATH, get(spread_history))
+        .route(ORDERBOOK_SNAPSHOTS_PATH, get(orderbook_snapshots))
+        .route_layer(from_fn_with_state(heavy_limit.clone(), limit_concurrency))
         .with_state(state.clone());
 
-    let rpc_routes = Router::new()
-        .route(LEVEL2_PATH, get(orderbook))
-        .route(DEEP_SUPPLY_PATH, get(deep_supply))
-        .route(SUMMARY_PATH, get(summary))
-        .route(OBI, get(orderbook_imbalance))
+    // deployments without a full node leave these out, they return 404
+    let rpc_routes = if state.config.disable_rpc_routes {
+        Router::new()
+    } else {
+        Router::new()
+            .route(LEVEL2_PATH, get(orderbook))
+            .route(ORDERBOOKS_PATH, get(orderbooks))
+            .route(DEEP_SUPPLY_PATH, get(deep_supply))
+            .route(CHAIN_TIP_PATH, get(chain_tip))
+            .route(SUMMARY_PATH, get(summary))
+            .route(OBI, get(orderbook_imbalance))
+            .with_state(state.clone())
+    };
+
+    let ws_routes = Router::new()
         .route(WEBSOCKET_ORDERBOOK, get(orderbook_ws))
+        .route(WEBSOCKET_ORDERBOOK_DIFF, get(orderbook_diff_ws))
         .route(WEBSOCKET_ORDERBOOK_BESTS, get(orderbook_bests_ws))
         .route(WEBSOCKET_ORDERBOOK_SPREAD, get(orderbook_spread_ws))
         .route(WEBSOCKET_LATEST_TRADES, get(latest_trades_ws))
-        .with_state((state.clone(), rpc_url));
+        .route(WEBSOCKET_OHLCV, get(ohlcv_ws))
+        .route_layer(from_fn_with_state(state.ws_auth.clone(), ws_auth))
+        .with_state(state.clone());
 
     let aggregation_routes = Router::new()
         .route(OHLCV_PATH, get(get_ohlcv))
+        .route(INDICATORS_PATH, get(get_indicators))
+        .route(VOLATILITY_PATH, get(get_volatility))
         .route(AVG_TRADE_PATH, get(avg_trade_size))
         .route(
             AVG_DURATION_BETWEEN_TRADES_PATH,
             get(avg_duration_between_trades),
         )
         .route(VWAP, get(get_vwap))
+        .route(TWAP, get(get_twap))
         .route(FILLS_24H_SUMMARY, get(get_order_fill_24h_summary))
         .route(VOLUME, get(get_volume_last_n_days))
         .route(VOLUME_MULTI_WINDOW, get(get_volume_multi_window))
+        .route(LIFETIME_VOLUME_PATH, get(get_lifetime_volume))
+        .route(FEES_PATH, get(get_fees))
         .route(
             AVERAGE_TRADE_SIZE_MULTI_WINDOW,
             get(get_avg_trade_size_multi_window),
         )
+        .route_layer(from_fn_with_state(heavy_limit, limit_concurrency))
         .with_state(state.clone());
 
+    // websocket upgrades are left uncompressed
     db_routes
+        .merge(heavy_db_routes)
         .merge(rpc_routes)
         .merge(aggregation_routes)
+        .layer(compression_layer())
+        .merge(ws_routes)
         .layer(cors)
         .layer(from_fn_with_state(state, track_metrics))
 }
 
 impl axum::response::IntoResponse for DeepBookError {
-    // TODO: distinguish client error.
     fn into_response(self) -> axum::response::Response {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Something went wrong: {:?}", self),
-        )
-            .into_response()
+        let status = match self {
+            DeepBookError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            DeepBookError::NotFound(_) => StatusCode::NOT_FOUND,
+            DeepBookError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            DeepBookError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            DeepBookError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            DeepBookError::Rpc(_) => StatusCode::BAD_GATEWAY,
+            DeepBookError::InternalError(_)
+            | DeepBookError::Db(_)
+            | DeepBookError::Cache(_)
+            | DeepBookError::Deserialize(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = json!({
+            "error": {
+                "code": self.code(),
+                "message": self.message(),
+            }
+        });
+        let mut response = (status, Json(body)).into_response();
+        response.extensions_mut().insert(ErrorCode(self.code()));
+        response
     }
 }
 
@@ -231,56 +538,129 @@ where
     E: Into<anyhow::Error>,
 {
     fn from(err: E) -> Self {
-        Self::InternalError(err.into().to_string())
+        let err = err.into();
+        let message = err.to_string();
+        if err.is::<QueryTimeout>() {
+            Self::Timeout(message)
+        } else if err.downcast_ref::<diesel::result::Error>().is_some()
+            || err.downcast_ref::<diesel::ConnectionError>().is_some()
+        {
+            Self::Db(message)
+        } else if err.downcast_ref::<sui_sdk::error::Error>().is_some() {
+            Self::Rpc(message)
+        } else if err.downcast_ref::<redis::RedisError>().is_some() {
+            Self::Cache(message)
+        } else if err.downcast_ref::<bcs::Error>().is_some()
+            || err.downcast_ref::<serde_json::Error>().is_some()
+        {
+            Self::Deserialize(message)
+        } else {
+            Self::InternalError(message)
+        }
     }
 }
 
+/// Liveness probe, doesn't touch any dependency
 async fn health_check() -> StatusCode {
     StatusCode::OK
 }
 
+/// Readiness probe, 503 with the failed dependencies when Postgres or Redis is unreachable
+async fn readyz(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Value>) {
+    let (database, redis) = join!(state.reader.ping(), state.reader.cache.ping());
+    let database = database.map_err(|e| e.to_string());
+    let redis = redis.map_err(|e| format!("{:?}", e));
+
+    let check = |result: &Result<(), String>| match result {
+        Ok(()) => Value::from("ok"),
+        Err(e) => Value::from(e.as_str()),
+    };
+    let status = if database.is_ok() && redis.is_ok() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(json!({
+            "database": check(&database),
+            "redis": check(&redis),
+        })),
+    )
+}
+
 /// Get all pools stored in database
 async fn get_pools(State(state): State<Arc<AppState>>) -> Result<Json<Vec<Pool>>, DeepBookError> {
-    Ok(Json(state.reader.get_pools().await?))
+    Ok(Json(state.reader.pools().await?))
+}
+
+/// Ids of comma separated pool names. Unknown names are dropped, or reported as
+/// `BadRequest` when `strict` is set.
+pub fn resolve_pool_names(
+    pool_names: &str,
+    pool_name_to_id: &HashMap<String, String>,
+    strict: bool,
+) -> Result<Vec<String>, DeepBookError> {
+    let (known, unknown): (Vec<&str>, Vec<&str>) = pool_names
+        .split(',')
+        .partition(|name| pool_name_to_id.contains_key(*name));
+
+    if strict && !unknown.is_empty() {
+        return Err(DeepBookError::BadRequest(format!(
+            "Unknown pool names: {}",
+            unknown.join(", ")
+        )));
+    }
+
+    Ok(known
+        .into_iter()
+        .map(|name| pool_name_to_id[name].clone())
+        .collect())
 }
 
 async fn historical_volume(
     Path(pool_names): Path<String>,
     Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<HashMap<String, u64>>, DeepBookError> {
     // Fetch all pools to map names to IDs
-    let pools = state.reader.get_pools().await?;
+    let pools = state.reader.pools().await?;
     let pool_name_to_id = pools
         .into_iter()
         .map(|pool| (pool.pool_name, pool.pool_id))
         .collect::<HashMap<_, _>>();
 
     // Map provided pool names to pool IDs
-    let pool_ids: Vec<String> = pool_names
-        .split(',')
-        .filter_map(|name| pool_name_to_id.get(name).cloned())
-        .collect();
+    let strict = params
+        .get("strict")
+        .map(|v| v == "true")
+        .unwrap_or_default();
+    let pool_ids = resolve_pool_names(&pool_names, &pool_name_to_id, strict)?;
 
     if pool_ids.is_empty() {
-        return Err(DeepBookError::InternalError(
+        return Err(DeepBookError::NotFound(
             "No valid pool names provided".to_string(),
         ));
     }
 
-    // Parse start_time and end_time from query parameters (in seconds) and convert to milliseconds
-    let end_time = params.end_time();
-    let start_time = params
-        .start_time() // Convert to milliseconds
-        .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
+    let (start_time, end_time) = volume_window(&common);
 
     // Determine whether to query volume in base or quote
-    let volume_in_base = params.volume_in_base();
+    let volume_in_base = common.volume_in_base.unwrap_or(false);
+    let exclude_self_trades = common.exclude_self_trades.unwrap_or(false);
 
     // Query the database for the historical volume
     let results = state
         .reader
-        .get_historical_volume(start_time, end_time, &pool_ids, volume_in_base)
+        .get_historical_volume(
+            start_time,
+            end_time,
+            &pool_ids,
+            volume_in_base,
+            exclude_self_trades,
+        )
         .await?;
 
     // Aggregate volume by pool ID and map back to pool names
@@ -301,9 +681,10 @@ async fn historical_volume(
 /// Get all historical volume for all pools
 async fn all_historical_volume(
     Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<HashMap<String, u64>>, DeepBookError> {
-    let pools = state.reader.get_pools().await?;
+    let pools = state.reader.pools().await?;
 
     let pool_names: String = pools
         .into_iter()
@@ -311,15 +692,109 @@ async fn all_historical_volume(
         .collect::<Vec<String>>()
         .join(",");
 
-    historical_volume(Path(pool_names), Query(params), State(state)).await
+    historical_volume(Path(pool_names), Query(params), common, State(state)).await
+}
+
+/// Base and quote volume of each pool in one query, `{pool_name: {base_volume, quote_volume}}`
+async fn historical_volume_both(
+    Path(pool_names): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<HashMap<String, HashMap<String, u64>>>, DeepBookError> {
+    let pools = state.reader.pools().await?;
+    let pool_name_to_id = pools
+        .iter()
+        .map(|pool| (pool.pool_name.clone(), pool.pool_id.clone()))
+        .collect::<HashMap<_, _>>();
+
+    let strict = params
+        .get("strict")
+        .map(|v| v == "true")
+        .unwrap_or_default();
+    let pool_ids = resolve_pool_names(&pool_names, &pool_name_to_id, strict)?;
+
+    if pool_ids.is_empty() {
+        return Err(DeepBookError::NotFound(
+            "No valid pool names provided".to_string(),
+        ));
+    }
+
+    let volumes = historical_volumes_by_name(&state, &pools, &pool_ids, &common).await?;
+
+    Ok(Json(
+        volumes
+            .into_iter()
+            .map(|(pool_name, (base_volume, quote_volume))| {
+                (
+                    pool_name,
+                    HashMap::from([
+                        ("base_volume".to_string(), base_volume),
+                        ("quote_volume".to_string(), quote_volume),
+                    ]),
+                )
+            })
+            .collect(),
+    ))
+}
+
+/// Window of the historical volume endpoints in milliseconds, the last 24 hours by default
+fn volume_window(common: &CommonParams) -> (i64, i64) {
+    let end_time = common.end_time_ms();
+    let start_time = common
+        .start_time_ms()
+        .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
+    (start_time, end_time)
+}
+
+/// `(base, quote)` volume of `pool_ids` within the window of `common`, by pool name
+async fn historical_volumes_by_name(
+    state: &AppState,
+    pools: &[Pool],
+    pool_ids: &[String],
+    common: &CommonParams,
+) -> Result<HashMap<String, (u64, u64)>, DeepBookError> {
+    let (start_time, end_time) = volume_window(common);
+    let sums = state
+        .reader
+        .get_historical_volume_both(
+            start_time,
+            end_time,
+            pool_ids,
+            common.exclude_self_trades.unwrap_or(false),
+        )
+        .await?;
+    Ok(volumes_by_pool_name(pools, sums))
+}
+
+/// `(pool_id, base_quantity, quote_quantity)` summed over the fills of a pool
+pub type PoolVolumeSums = (String, Option<BigDecimal>, Option<BigDecimal>);
+
+/// Raw `(base, quote)` volumes by pool name, sums of unknown pools are dropped
+pub fn volumes_by_pool_name(
+    pools: &[Pool],
+    sums: Vec<PoolVolumeSums>,
+) -> HashMap<String, (u64, u64)> {
+    let pool_id_to_name = pools
+        .iter()
+        .map(|pool| (pool.pool_id.as_str(), pool.pool_name.as_str()))
+        .collect::<HashMap<_, _>>();
+    let volume = |sum: Option<BigDecimal>| sum.and_then(|sum| sum.to_u64()).unwrap_or(0);
+
+    sums.into_iter()
+        .filter_map(|(pool_id, base, quote)| {
+            let pool_name = pool_id_to_name.get(pool_id.as_str())?;
+            Some((pool_name.to_string(), (volume(base), volume(quote))))
+        })
+        .collect()
 }
 
 async fn get_historical_volume_by_balance_manager_id(
     Path((pool_names, balance_manager_id)): Path<(String, String)>,
-    Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<HashMap<String, Vec<i64>>>, DeepBookError> {
-    let pools = state.reader.get_pools().await?;
+    let pools = state.reader.pools().await?;
     let pool_name_to_id = pools
         .into_iter()
         .map(|pool| (pool.pool_name, pool.pool_id))
@@ -331,18 +806,18 @@ async fn get_historical_volume_by_balance_manager_id(
         .collect();
 
     if pool_ids.is_empty() {
-        return Err(DeepBookError::InternalError(
+        return Err(DeepBookError::NotFound(
             "No valid pool names provided".to_string(),
         ));
     }
 
     // Parse start_time and end_time
-    let end_time = params.end_time();
-    let start_time = params
-        .start_time() // Convert to milliseconds
+    let end_time = common.end_time_ms();
+    let start_time = common
+        .start_time_ms()
         .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
 
-    let volume_in_base = params.volume_in_base();
+    let volume_in_base = common.volume_in_base.unwrap_or(false);
 
     let results = state
         .reader
@@ -352,6 +827,7 @@ async fn get_historical_volume_by_balance_manager_id(
             &pool_ids,
             &balance_manager_id,
             volume_in_base,
+            common.exclude_self_trades.unwrap_or(false),
         )
         .await?;
 
@@ -380,9 +856,10 @@ async fn get_historical_volume_by_balance_manager_id(
 async fn get_historical_volume_by_balance_manager_id_with_interval(
     Path((pool_names, balance_manager_id)): Path<(String, String)>,
     Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<HashMap<String, HashMap<String, Vec<i64>>>>, DeepBookError> {
-    let pools = state.reader.get_pools().await?;
+    let pools = state.reader.pools().await?;
     let pool_name_to_id: HashMap<String, String> = pools
         .into_iter()
         .map(|pool| (pool.pool_name, pool.pool_id))
@@ -394,7 +871,7 @@ async fn get_historical_volume_by_balance_manager_id_with_interval(
         .collect::<Vec<_>>();
 
     if pool_ids.is_empty() {
-        return Err(DeepBookError::InternalError(
+        return Err(DeepBookError::NotFound(
             "No valid pool names provided".to_string(),
         ));
     }
@@ -406,35 +883,38 @@ async fn get_historical_volume_by_balance_manager_id_with_interval(
         .unwrap_or(3600); // Default interval: 1 hour
 
     if interval <= 0 {
-        return Err(DeepBookError::InternalError(
+        return Err(DeepBookError::BadRequest(
             "Interval must be greater than 0".to_string(),
         ));
     }
 
     let interval_ms = interval * 1000;
     // Parse start_time and end_time
-    let end_time = params.end_time();
+    let end_time = common.end_time_ms();
 
-    let start_time = params
-        .start_time() // Convert to milliseconds
+    let start_time = common
+        .start_time_ms()
         .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
 
     let mut metrics_by_interval: HashMap<String, HashMap<String, Vec<i64>>> = HashMap::new();
 
+    let volume_in_base = common.volume_in_base.unwrap_or(false);
+    let exclude_self_trades = common.exclude_self_trades.unwrap_or(false);
+
     let mut current_start = start_time;
     while current_start + interval_ms <= end_time {
         let current_end = current_start + interval_ms;
 
-        let volume_in_base = params.volume_in_base();
-
+        // Query bounds are inclusive, end one ms early so fills on the boundary count once
         let results = state
             .reader
             .get_order_fill_summary(
-                start_time,
-                end_time,
+                current_start,
+                current_end - 1,
                 &pool_ids,
                 &balance_manager_id,
                 volume_in_base,
+                exclude_self_trades,
             )
             .await?;
 
@@ -470,14 +950,19 @@ async fn get_historical_volume_by_balance_manager_id_with_interval(
 
 async fn ticker(
     Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<HashMap<String, HashMap<String, Value>>>, DeepBookError> {
+    // Fetch pools data for metadata
+    let pools = state.reader.pools().await?;
+
     // Fetch base and quote historical volumes
-    let base_volumes = fetch_historical_volume(&params, true, &state).await?;
-    let quote_volumes = fetch_historical_volume(&params, false, &state).await?;
+    let pool_ids = pools
+        .iter()
+        .map(|pool| pool.pool_id.clone())
+        .collect::<Vec<_>>();
+    let volumes = historical_volumes_by_name(&state, &pools, &pool_ids, &common).await?;
 
-    // Fetch pools data for metadata
-    let pools = state.reader.get_pools().await?;
     let pool_map: HashMap<String, &Pool> = pools
         .iter()
         .map(|pool| (pool.pool_id.clone(), pool))
@@ -491,79 +976,196 @@ async fn ticker(
     // Calculate the start time for 24 hours ago
     let start_time = end_time - 24 * 60 * 60 * 1000;
 
-    // Fetch last prices for all pools in a single query. Only trades in the last 24 hours will count.
-    let query = schema::order_fills::table
-        .filter(schema::order_fills::checkpoint_timestamp_ms.between(start_time, end_time))
-        .select((schema::order_fills::pool_id, schema::order_fills::price))
-        .order_by((
-            schema::order_fills::pool_id.asc(),
-            schema::order_fills::checkpoint_timestamp_ms.desc(),
-        ))
-        .distinct_on(schema::order_fills::pool_id);
+    // Only trades in the last 24 hours count
+    let last_price_map = last_prices(&state, pool_map.keys(), start_time, end_time).await?;
 
-    let last_prices: Vec<(String, i64)> = state.reader.results(query).await?;
-    let last_price_map: HashMap<String, i64> = last_prices.into_iter().collect();
+    // Assets that can't be deposited or withdrawn freeze every pool they are traded in
+    let frozen_assets_query = schema::assets::table
+        .filter(
+            schema::assets::can_deposit
+                .eq(false)
+                .or(schema::assets::can_withdraw.eq(false)),
+        )
+        .select(schema::assets::type_);
+    let frozen_assets: Vec<String> = state.reader.results(frozen_assets_query).await?;
+    let frozen_assets: HashSet<String> = frozen_assets
+        .iter()
+        .map(|asset| normalize_coin_type(asset))
+        .collect();
+
+    let exclude_frozen = params
+        .get("exclude_frozen")
+        .map(|v| v == "true")
+        .unwrap_or(false);
 
     let mut response = HashMap::new();
 
     for (pool_id, pool) in &pool_map {
+        let is_frozen = is_pool_frozen(pool, &frozen_assets);
+        if is_frozen && exclude_frozen {
+            continue;
+        }
+
         let pool_name = &pool.pool_name;
-        let base_volume = base_volumes.get(pool_name).copied().unwrap_or(0);
-        let quote_volume = quote_volumes.get(pool_name).copied().unwrap_or(0);
+        let (base_volume, quote_volume) = volumes.get(pool_name).copied().unwrap_or_default();
         let last_price = last_price_map.get(pool_id).copied();
 
-        // Conversion factors based on decimals
-        let base_factor = (10u64).pow(pool.base_asset_decimals as u32);
-        let quote_factor = (10u64).pow(pool.quote_asset_decimals as u32);
-        let price_factor =
-            (10u64).pow((9 - pool.base_asset_decimals + pool.quote_asset_decimals) as u32);
-
         response.insert(
             pool_name.clone(),
-            HashMap::from([
-                (
-                    "last_price".to_string(),
-                    Value::from(
-                        last_price
-                            .map(|price| (price as f64) / (price_factor as f64))
-                            .unwrap_or(0.0),
-                    ),
-                ),
-                (
-                    "base_volume".to_string(),
-                    Value::from((base_volume as f64) / (base_factor as f64)),
-                ),
-                (
-                    "quote_volume".to_string(),
-                    Value::from((quote_volume as f64) / (quote_factor as f64)),
-                ),
-                ("isFrozen".to_string(), Value::from(0)), // Fixed to 0 because all pools in pools table are active
-            ]),
+            ticker_entry(pool, last_price, base_volume, quote_volume, is_frozen)?,
         );
     }
 
     Ok(Json(response))
 }
 
-async fn fetch_historical_volume(
-    params: &HashMap<String, String>,
-    volume_in_base: bool,
-    state: &Arc<AppState>,
-) -> Result<HashMap<String, u64>, DeepBookError> {
-    let mut params_with_volume = params.clone();
-    params_with_volume.insert("volume_in_base".to_string(), volume_in_base.to_string());
+/// Last traded price of the pools between `start_time` and `end_time` in milliseconds. Closes
+/// of the 1 minute cagg are used up to its newest bucket, fills from that bucket on are read
+/// raw, so a stalled cagg refresh widens the raw scan instead of serving stale closes. Only
+/// pools without either scan older raw fills.
+async fn last_prices<'a>(
+    state: &AppState,
+    pool_ids: impl Iterator<Item = &'a String>,
+    start_time: i64,
+    end_time: i64,
+) -> Result<HashMap<String, i64>, DeepBookError> {
+    let pool_ids: Vec<String> = pool_ids.cloned().collect();
+    let cagg_prices = if state.config.ohlcv_from_raw {
+        vec![]
+    } else {
+        state
+            .reader
+            .get_cagg_last_prices(
+                naive_datetime_from_millis(start_time)?,
+                naive_datetime_from_millis(end_time)?,
+            )
+            .await?
+    };
+    let fresh_from = cagg_prices
+        .iter()
+        .map(|(_, bucket, _)| bucket.and_utc().timestamp_millis())
+        .max()
+        .unwrap_or(start_time);
+    let recent_prices = state
+        .reader
+        .get_raw_last_prices(pool_ids.clone(), fresh_from, end_time)
+        .await?;
+    let cagg_prices = cagg_prices
+        .into_iter()
+        .map(|(pool_id, _, close)| (pool_id, close))
+        .collect();
+    let (mut prices, missing) = split_last_prices(cagg_prices, recent_prices, pool_ids.iter());
+    if !missing.is_empty() && fresh_from > start_time {
+        let raw_prices = state
+            .reader
+            .get_raw_last_prices(missing, start_time, fresh_from)
+            .await?;
+        prices.extend(raw_prices);
+    }
+    Ok(prices)
+}
+
+/// Cagg closes by pool, replaced by `recent_prices` of raw fills since the newest bucket, and
+/// the pools neither has, which need older raw fills
+pub fn split_last_prices<'a>(
+    cagg_prices: Vec<(String, i64)>,
+    recent_prices: Vec<(String, i64)>,
+    pool_ids: impl Iterator<Item = &'a String>,
+) -> (HashMap<String, i64>, Vec<String>) {
+    let mut prices: HashMap<String, i64> = cagg_prices.into_iter().collect();
+    prices.extend(recent_prices);
+    let missing = pool_ids
+        .filter(|pool_id| !prices.contains_key(*pool_id))
+        .cloned()
+        .collect();
+    (prices, missing)
+}
 
-    all_historical_volume(Query(params_with_volume), State(state.clone()))
-        .await
-        .map(|Json(volumes)| volumes)
+/// A pool is frozen when its base or quote asset can't be deposited or withdrawn.
+/// `frozen_assets` holds coin types normalized by `normalize_coin_type`.
+pub fn is_pool_frozen(pool: &Pool, frozen_assets: &HashSet<String>) -> bool {
+    frozen_assets.contains(&normalize_coin_type(&pool.base_asset_id))
+        || frozen_assets.contains(&normalize_coin_type(&pool.quote_asset_id))
+}
+
+/// Exponent of the factor between onchain integer prices and prices in quote units per base
+/// unit, `9 - base_decimals + quote_decimals`. `None` when it or the base decimals are outside
+/// `0..=19`, then the factor isn't an integer or scaled prices and sizes don't fit `u64`.
+pub fn price_exponent(base_decimals: i16, quote_decimals: i16) -> Option<i32> {
+    let exponent = 9 - base_decimals as i32 + quote_decimals as i32;
+    let fits = |exponent: i32| (0..=19).contains(&exponent);
+    (fits(exponent) && fits(base_decimals as i32)).then_some(exponent)
+}
+
+/// Factor between onchain integer prices and prices in quote units per base unit,
+/// `10^(9 - base_decimals + quote_decimals)`. Fails for decimals rejected by [`price_exponent`].
+pub fn price_scaling_factor(base_decimals: i16, quote_decimals: i16) -> Result<f64, DeepBookError> {
+    let exponent = price_exponent(base_decimals, quote_decimals).ok_or_else(|| {
+        DeepBookError::InternalError(format!(
+            "Unsupported pool decimals, base {} and quote {}",
+            base_decimals, quote_decimals
+        ))
+    })?;
+    Ok(10f64.powi(exponent))
+}
+
+/// Which asset of the target pool a DEEP price point from the reference pool converts, `true`
+/// for the base asset, and the decimals of DEEP. The reference pool trades DEEP against that
+/// asset, on either side. `None` when it shares no asset with the target pool.
+pub fn deep_price_side(target: &Pool, reference: &Pool) -> Option<(bool, i16)> {
+    let quotes_target_asset = reference.quote_asset_id == target.base_asset_id
+        || reference.quote_asset_id == target.quote_asset_id;
+    let (asset_id, deep_decimals) = if quotes_target_asset {
+        (&reference.quote_asset_id, reference.base_asset_decimals)
+    } else {
+        (&reference.base_asset_id, reference.quote_asset_decimals)
+    };
+
+    if *asset_id == target.base_asset_id {
+        Some((true, deep_decimals))
+    } else if *asset_id == target.quote_asset_id {
+        Some((false, deep_decimals))
+    } else {
+        None
+    }
+}
+
+/// Ticker data of a single pool, `last_price` is null when there were no trades in 24h
+pub fn ticker_entry(
+    pool: &Pool,
+    last_price: Option<i64>,
+    base_volume: u64,
+    quote_volume: u64,
+    is_frozen: bool,
+) -> Result<HashMap<String, Value>, DeepBookError> {
+    // Conversion factors based on decimals
+    let base_factor = (10u64).pow(pool.base_asset_decimals as u32);
+    let quote_factor = (10u64).pow(pool.quote_asset_decimals as u32);
+    let price_factor = price_scaling_factor(pool.base_asset_decimals, pool.quote_asset_decimals)?;
+
+    Ok(HashMap::from([
+        (
+            "last_price".to_string(),
+            Value::from(last_price.map(|price| (price as f64) / price_factor)),
+        ),
+        ("has_trades".to_string(), Value::from(last_price.is_some())),
+        (
+            "base_volume".to_string(),
+            Value::from((base_volume as f64) / (base_factor as f64)),
+        ),
+        (
+            "quote_volume".to_string(),
+            Value::from((quote_volume as f64) / (quote_factor as f64)),
+        ),
+        ("isFrozen".to_string(), Value::from(is_frozen as u8)),
+    ]))
 }
 
-#[allow(clippy::get_first)]
 async fn summary(
-    State((state, rpc_url)): State<(Arc<AppState>, Url)>,
+    State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<HashMap<String, Value>>>, DeepBookError> {
     // Fetch pools metadata first since it's required for other functions
-    let pools = state.reader.get_pools().await?;
+    let pools = state.reader.pools().await?;
     let pool_metadata: HashMap<String, (String, (i16, i16))> = pools
         .iter()
         .map(|pool| {
@@ -577,6 +1179,20 @@ async fn summary(
         })
         .collect();
 
+    // Currencies come from asset symbols, pool names may contain extra underscores
+    let pool_symbols: HashMap<&str, (&str, &str)> = pools
+        .iter()
+        .map(|pool| {
+            (
+                pool.pool_name.as_str(),
+                (
+                    pool.base_asset_symbol.as_str(),
+                    pool.quote_asset_symbol.as_str(),
+                ),
+            )
+        })
+        .collect();
+
     // Prepare pool decimals for scaling
     let pool_decimals: HashMap<String, (i16, i16)> = pool_metadata
         .iter()
@@ -585,7 +1201,11 @@ async fn summary(
 
     // Parallelize fetching ticker, price changes, and high/low prices
     let (ticker_result, price_change_result, high_low_result) = join!(
-        ticker(Query(HashMap::new()), State(state.clone())),
+        ticker(
+            Query(HashMap::new()),
+            CommonParams::default(),
+            State(state.clone())
+        ),
         price_change_24h(&pool_metadata, State(state.clone())),
         high_low_prices_24h(&pool_decimals, State(state.clone()))
     );
@@ -594,31 +1214,64 @@ async fn summary(
     let price_change_map = price_change_result?;
     let high_low_map = high_low_result?;
 
-    // Prepare futures for orderbook queries
-    let orderbook_futures: Vec<_> = ticker_map
+    // Best levels of the live books in one round trip, pools without one are read onchain
+    let live_keys: Vec<String> = ticker_map
         .keys()
-        .map(|pool_name| {
-            let pool_name_clone = pool_name.clone();
-            orderbook(
-                Path(pool_name_clone),
-                Query(HashMap::from([("level".to_string(), "1".to_string())])),
-                State((state.clone(), rpc_url.clone())),
-            )
+        .map(|pool_name| format!("orderbook::{}", pool_name))
+        .collect();
+    let live_keys: Vec<&str> = live_keys.iter().map(String::as_str).collect();
+    let live_books = state
+        .reader
+        .cache
+        .mget::<Value>(&live_keys)
+        .await
+        .unwrap_or_else(|e| {
+            println!("Failed reading live orderbooks: {:?}", e);
+            vec![None; live_keys.len()]
+        });
+
+    let bests_futures: Vec<_> = ticker_map
+        .keys()
+        .zip(live_books)
+        .map(|(pool_name, live_book)| {
+            let pool_name = pool_name.clone();
+            let state = state.clone();
+            async move {
+                if let Some(quote) = live_book.and_then(best_quote) {
+                    return (quote.bid_price, quote.ask_price);
+                }
+                let orderbook_data = orderbook(
+                    Path(pool_name),
+                    Query(HashMap::from([("level".to_string(), "1".to_string())])),
+                    State(state),
+                )
+                .await
+                .ok()
+                .map(|Json(data)| data);
+                (
+                    best_level_price(orderbook_data.as_ref(), "bids"),
+                    best_level_price(orderbook_data.as_ref(), "asks"),
+                )
+            }
         })
         .collect();
 
-    // Run all orderbook queries concurrently
-    let orderbook_results = join_all(orderbook_futures).await;
+    // Run all onchain orderbook queries concurrently
+    let bests = join_all(bests_futures).await;
 
     let mut response = Vec::new();
 
-    for ((pool_name, ticker_info), orderbook_result) in ticker_map.iter().zip(orderbook_results) {
+    for ((pool_name, ticker_info), (highest_bid, lowest_ask)) in ticker_map.iter().zip(bests) {
         if let Some((pool_id, _)) = pool_metadata.get(pool_name) {
-            // Extract data from the ticker function response
+            // Extract data from the ticker function response, null price means no trades
             let last_price = ticker_info
                 .get("last_price")
-                .and_then(|price| price.as_f64())
-                .unwrap_or(0.0);
+                .cloned()
+                .unwrap_or(Value::Null);
+            let has_trades = ticker_info
+                .get("has_trades")
+                .and_then(|has_trades| has_trades.as_bool())
+                .unwrap_or(false);
 
             let base_volume = ticker_info
                 .get("base_volume")
@@ -637,41 +1290,20 @@ async fn summary(
             let (highest_price, lowest_price) =
                 high_low_map.get(pool_id).copied().unwrap_or((0.0, 0.0));
 
-            // Process the parallel orderbook result
-            let orderbook_data = orderbook_result.ok().map(|Json(data)| data);
-
-            let highest_bid = orderbook_data
-                .as_ref()
-                .and_then(|data| data.get("bids"))
-                .and_then(|bids| bids.as_array())
-                .and_then(|bids| bids.get(0))
-                .and_then(|bid| bid.as_array())
-                .and_then(|bid| bid.get(0))
-                .and_then(|price| price.as_str()?.parse::<f64>().ok())
-                .unwrap_or(0.0);
-
-            let lowest_ask = orderbook_data
-                .as_ref()
-                .and_then(|data| data.get("asks"))
-                .and_then(|asks| asks.as_array())
-                .and_then(|asks| asks.get(0))
-                .and_then(|ask| ask.as_array())
-                .and_then(|ask| ask.get(0))
-                .and_then(|price| price.as_str()?.parse::<f64>().ok())
-                .unwrap_or(0.0);
-
             let mut summary_data = HashMap::new();
             summary_data.insert(
                 "trading_pairs".to_string(),
                 Value::String(pool_name.clone()),
             );
-            let parts: Vec<&str> = pool_name.split('_').collect();
-            let base_currency = parts.get(0).unwrap_or(&"Unknown").to_string();
-            let quote_currency = parts.get(1).unwrap_or(&"Unknown").to_string();
+            let (base_currency, quote_currency) = pool_symbols
+                .get(pool_name.as_str())
+                .map(|(base, quote)| (base.to_string(), quote.to_string()))
+                .unwrap_or_else(|| ("Unknown".to_string(), "Unknown".to_string()));
 
             summary_data.insert("base_currency".to_string(), Value::String(base_currency));
             summary_data.insert("quote_currency".to_string(), Value::String(quote_currency));
-            summary_data.insert("last_price".to_string(), Value::from(last_price));
+            summary_data.insert("last_price".to_string(), last_price);
+            summary_data.insert("has_trades".to_string(), Value::from(has_trades));
             summary_data.insert("base_volume".to_string(), Value::from(base_volume));
             summary_data.insert("quote_volume".to_string(), Value::from(quote_volume));
             summary_data.insert(
@@ -690,6 +1322,18 @@ async fn summary(
     Ok(Json(response))
 }
 
+/// Price of the best level of one side of a `/orderbook` response, 0 when it's empty
+fn best_level_price(orderbook: Option<&HashMap<String, Value>>, side: &str) -> f64 {
+    orderbook
+        .and_then(|data| data.get(side))
+        .and_then(|levels| levels.as_array())
+        .and_then(|levels| levels.first())
+        .and_then(|level| level.as_array())
+        .and_then(|level| level.first())
+        .and_then(|price| price.as_str()?.parse::<f64>().ok())
+        .unwrap_or(0.0)
+}
+
 async fn high_low_prices_24h(
     pool_decimals: &HashMap<String, (i16, i16)>,
     State(state): State<Arc<AppState>>,
@@ -719,7 +1363,7 @@ async fn high_low_prices_24h(
 
     for (pool_id, max_price_opt, min_price_opt) in results {
         if let Some((base_decimals, quote_decimals)) = pool_decimals.get(&pool_id) {
-            let scaling_factor = (10f64).powi((9 - base_decimals + quote_decimals) as i32);
+            let scaling_factor = price_scaling_factor(*base_decimals, *quote_decimals)?;
 
             let max_price_f64 = (max_price_opt.unwrap_or(0) as f64) / scaling_factor;
             let min_price_f64 = (min_price_opt.unwrap_or(0) as f64) / scaling_factor;
@@ -760,11 +1404,11 @@ async fn price_change_24h(
 
         if let (Ok(earliest_price), Ok(most_recent_price)) = (earliest_trade_24h, most_recent_trade)
         {
-            let price_factor = (10u64).pow((9 - base_decimals + quote_decimals) as u32);
+            let price_factor = price_scaling_factor(*base_decimals, *quote_decimals)?;
 
             // Scale the prices
-            let earliest_price_scaled = (earliest_price as f64) / (price_factor as f64);
-            let most_recent_price_scaled = (most_recent_price as f64) / (price_factor as f64);
+            let earliest_price_scaled = (earliest_price as f64) / price_factor;
+            let most_recent_price_scaled = (most_recent_price as f64) / price_factor;
 
             // Calculate price change percentage
             let price_change_percent =
@@ -783,24 +1427,27 @@ async fn price_change_24h(
 async fn order_updates(
     Path(pool_name): Path<String>,
     Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
     State(state): State<Arc<AppState>>,
-) -> Result<Json<Vec<HashMap<String, Value>>>, DeepBookError> {
+) -> Result<Response, DeepBookError> {
+    let envelope = wants_envelope(&params)?;
     // Fetch pool data with proper error handling
     let (pool_id, base_decimals, quote_decimals) =
         state.reader.get_pool_decimals(&pool_name).await?;
     let base_decimals = base_decimals as u8;
     let quote_decimals = quote_decimals as u8;
 
-    let end_time = params.end_time();
+    let end_time = common.end_time_ms();
 
-    let start_time = params
-        .start_time() // Convert to milliseconds
+    let start_time = common
+        .start_time_ms()
         .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
 
-    let limit = params.limit();
+    let limit = common.limit.unwrap_or(1);
 
     let balance_manager_filter = params.get("balance_manager_id").cloned();
     let status_filter = params.get("status").cloned();
+    let trader_filter = params.get("trader").cloned();
 
     let trades = state
         .reader
@@ -811,11 +1458,13 @@ async fn order_updates(
             limit,
             balance_manager_filter,
             status_filter,
+            trader_filter,
+            PageCursor::from_params(&params)?,
         )
         .await?;
 
     let base_factor = (10u64).pow(base_decimals as u32);
-    let price_factor = (10u64).pow((9 - base_decimals + quote_decimals) as u32);
+    let price_factor = price_scaling_factor(base_decimals.into(), quote_decimals.into())?;
 
     let trade_data: Vec<HashMap<String, Value>> = trades
         .into_iter()
@@ -830,13 +1479,15 @@ async fn order_updates(
                 is_bid,
                 balance_manager_id,
                 status,
+                event_digest,
             )| {
                 let trade_type = if is_bid { "buy" } else { "sell" };
                 HashMap::from([
+                    ("event_digest".to_string(), Value::from(event_digest)),
                     ("order_id".to_string(), Value::from(order_id)),
                     (
                         "price".to_string(),
-                        Value::from((price as f64) / (price_factor as f64)),
+                        Value::from((price as f64) / price_factor),
                     ),
                     (
                         "original_quantity".to_string(),
@@ -862,88 +1513,1020 @@ async fn order_updates(
         )
         .collect();
 
-    Ok(Json(trade_data))
+    Ok(page_response(trade_data, envelope, limit, "timestamp"))
 }
 
-async fn trades(
+/// Latest `order_updates` row of an order
+#[derive(Queryable, Debug, Clone, PartialEq)]
+pub struct LatestOrderUpdate {
+    pub order_id: String,
+    pub price: i64,
+    pub original_quantity: i64,
+    pub quantity: i64,
+    pub filled_quantity: i64,
+    pub checkpoint: i64,
+    pub checkpoint_timestamp_ms: i64,
+    pub is_bid: bool,
+    pub balance_manager_id: String,
+    pub status: String,
+}
+
+/// Orders still resting on the book with their remaining base quantity, latest first.
+/// Canceled and expired orders are dropped, as are orders whose maker `fills`
+/// `(maker_order_id, checkpoint, base_quantity)` since their latest update consumed the rest.
+/// Fills in the checkpoint of a modification are taken to precede it.
+pub fn filter_open_orders(
+    latest: Vec<LatestOrderUpdate>,
+    fills: &[(String, i64, i64)],
+) -> Vec<(LatestOrderUpdate, i64)> {
+    let mut open: Vec<(LatestOrderUpdate, i64)> = latest
+        .into_iter()
+        .filter(|update| update.status == "Placed" || update.status == "Modified")
+        .filter_map(|update| {
+            let filled_since: i64 = fills
+                .iter()
+                .filter(|(order_id, checkpoint, _)| {
+                    *order_id == update.order_id
+                        && (update.status == "Placed" || *checkpoint > update.checkpoint)
+                })
+                .map(|(_, _, base_quantity)| base_quantity)
+                .sum();
+            let remaining = update.quantity - update.filled_quantity - filled_since;
+            (remaining > 0).then_some((update, remaining))
+        })
+        .collect();
+    open.sort_by(|a, b| {
+        b.0.checkpoint_timestamp_ms
+            .cmp(&a.0.checkpoint_timestamp_ms)
+            .then_with(|| a.0.order_id.cmp(&b.0.order_id))
+    });
+    open
+}
+
+/// Open orders of a balance manager in the pool as of `end_time`, see [`filter_open_orders`].
+/// Only orders updated since `start_time` are searched, by default
+/// [`OPEN_ORDERS_DEFAULT_WINDOW_MS`] before `end_time`.
+async fn open_orders(
     Path(pool_name): Path<String>,
     Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Vec<HashMap<String, Value>>>, DeepBookError> {
-    // Fetch all pools to map names to IDs and decimals
+    let balance_manager_id = params
+        .get("balance_manager_id")
+        .cloned()
+        .ok_or_else(|| DeepBookError::BadRequest("Missing balance_manager_id".to_string()))?;
     let (pool_id, base_decimals, quote_decimals) =
         state.reader.get_pool_decimals(&pool_name).await?;
-    // Parse start_time and end_time
-    let end_time = params.end_time();
-    let start_time = params
-        .start_time()
-        .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
-
-    // Parse limit (default to 1 if not provided)
-    let limit = params.limit();
-
-    // Parse optional filters for balance managers
-    let maker_balance_manager_filter = params.get("maker_balance_manager_id").cloned();
-    let taker_balance_manager_filter = params.get("taker_balance_manager_id").cloned();
-
-    let base_decimals = base_decimals as u8;
-    let quote_decimals = quote_decimals as u8;
+    let end_time = common.end_time_ms();
+    let start_time = common
+        .start_time_ms()
+        .unwrap_or_else(|| end_time - OPEN_ORDERS_DEFAULT_WINDOW_MS);
+    let max_rows = state.config.max_aggregation_rows;
 
-    let trades = state
+    let latest = state
         .reader
-        .get_orders(
-            pool_name,
-            pool_id,
+        .get_latest_order_updates(
+            &pool_id,
+            &balance_manager_id,
             start_time,
             end_time,
-            limit,
-            maker_balance_manager_filter,
-            taker_balance_manager_filter,
+            max_rows,
         )
         .await?;
+    let latest = enforce_row_cap(latest, max_rows)?;
+    let order_ids: Vec<String> = latest
+        .iter()
+        .filter(|update| update.status == "Placed" || update.status == "Modified")
+        .map(|update| update.order_id.clone())
+        .collect();
+    // fills of orders updated since `start_time` can't be older
+    let fills = state
+        .reader
+        .get_maker_fills(&pool_id, order_ids, start_time, end_time, max_rows)
+        .await?;
+    let fills = enforce_row_cap(fills, max_rows)?;
 
-    // Conversion factors for decimals
-    let base_factor = (10u64).pow(base_decimals as u32);
-    let quote_factor = (10u64).pow(quote_decimals as u32);
-    let price_factor = (10u64).pow((9 - base_decimals + quote_decimals) as u32);
-
-    // Map trades to JSON format
-    let trade_data = trades
-        .into_iter()
-        .map(
-            |(
-                maker_order_id,
-                taker_order_id,
-                price,
-                base_quantity,
-                quote_quantity,
-                timestamp,
-                taker_is_bid,
-                maker_balance_manager_id,
-                taker_balance_manager_id,
-            )| {
-                let trade_id = calculate_trade_id(&maker_order_id, &taker_order_id).unwrap_or(0);
-                let trade_type = if taker_is_bid { "buy" } else { "sell" };
+    let base_factor = 10f64.powi(base_decimals as i32);
+    let price_factor = price_scaling_factor(base_decimals, quote_decimals)?;
 
+    Ok(Json(
+        filter_open_orders(latest, &fills)
+            .into_iter()
+            .map(|(update, remaining)| {
+                let order_type = if update.is_bid { "buy" } else { "sell" };
                 HashMap::from([
-                    ("trade_id".to_string(), Value::from(trade_id.to_string())),
-                    ("maker_order_id".to_string(), Value::from(maker_order_id)),
-                    ("taker_order_id".to_string(), Value::from(taker_order_id)),
+                    ("order_id".to_string(), Value::from(update.order_id)),
                     (
-                        "maker_balance_manager_id".to_string(),
-                        Value::from(maker_balance_manager_id),
+                        "price".to_string(),
+                        Value::from((update.price as f64) / price_factor),
                     ),
                     (
-                        "taker_balance_manager_id".to_string(),
-                        Value::from(taker_balance_manager_id),
+                        "original_quantity".to_string(),
+                        Value::from((update.original_quantity as f64) / base_factor),
+                    ),
+                    (
+                        "remaining_quantity".to_string(),
+                        Value::from((remaining as f64) / base_factor),
+                    ),
+                    (
+                        "timestamp".to_string(),
+                        Value::from(update.checkpoint_timestamp_ms as u64),
+                    ),
+                    ("type".to_string(), Value::from(order_type)),
+                    (
+                        "balance_manager_id".to_string(),
+                        Value::from(update.balance_manager_id),
+                    ),
+                    ("status".to_string(), Value::from(update.status)),
+                ])
+            })
+            .collect(),
+    ))
+}
+
+async fn flash_loans(
+    Path(pool_name): Path<String>,
+    common: CommonParams,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<HashMap<String, Value>>>, DeepBookError> {
+    let pool = state.reader.pool_by_name(&pool_name).await?;
+
+    let end_time = common.end_time_ms();
+    let start_time = common
+        .start_time_ms()
+        .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
+    let limit = common.limit.unwrap_or(1);
+
+    let flash_loans = state
+        .reader
+        .get_flash_loans(&pool.pool_id, start_time, end_time, limit)
+        .await?;
+
+    let base_asset = normalize_coin_type(&pool.base_asset_id);
+    let base_factor = (10u64).pow(pool.base_asset_decimals as u32);
+    let quote_factor = (10u64).pow(pool.quote_asset_decimals as u32);
+
+    let data: Vec<HashMap<String, Value>> = flash_loans
+        .into_iter()
+        .map(|flash_loan| {
+            // Flash loans borrow either base or quote asset of the pool
+            let factor = if normalize_coin_type(&flash_loan.type_name) == base_asset {
+                base_factor
+            } else {
+                quote_factor
+            };
+            HashMap::from([
+                ("digest".to_string(), Value::from(flash_loan.digest)),
+                ("sender".to_string(), Value::from(flash_loan.sender)),
+                ("borrow".to_string(), Value::from(flash_loan.borrow)),
+                ("type_name".to_string(), Value::from(flash_loan.type_name)),
+                (
+                    "borrow_quantity".to_string(),
+                    Value::from((flash_loan.borrow_quantity as f64) / (factor as f64)),
+                ),
+                (
+                    "timestamp".to_string(),
+                    Value::from(flash_loan.checkpoint_timestamp_ms as u64),
+                ),
+                ("checkpoint".to_string(), Value::from(flash_loan.checkpoint)),
+            ])
+        })
+        .collect();
+
+    Ok(Json(data))
+}
+
+/// Balance managers active in the pool, paginated with `after` set to `next` of the previous page
+async fn balance_managers(
+    Path(pool_name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<HashMap<String, Value>>, DeepBookError> {
+    let pool_id = state.reader.get_pool_id_by_name(&pool_name).await?;
+
+    let end_time = common.end_time_ms();
+    let start_time = common
+        .start_time_ms()
+        .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
+    let limit = common
+        .limit
+        .unwrap_or(BALANCE_MANAGERS_PAGE_SIZE)
+        .clamp(1, BALANCE_MANAGERS_PAGE_SIZE);
+    let after = params.get("after").cloned().unwrap_or_default();
+
+    let managers = state
+        .reader
+        .get_balance_managers(&pool_id, start_time, end_time, &after, limit)
+        .await?;
+
+    // Full page means there may be more
+    let next = if managers.len() as i64 == limit {
+        managers.last().cloned()
+    } else {
+        None
+    };
+
+    Ok(Json(HashMap::from([
+        ("balance_managers".to_string(), Value::from(managers)),
+        ("next".to_string(), Value::from(next)),
+    ])))
+}
+
+/// Amounts a balance manager deposited and withdrew of one asset
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct AssetFlow {
+    pub deposited: f64,
+    pub withdrawn: f64,
+    pub net: f64,
+}
+
+/// Deposits or withdrawals of one asset, summed
+#[derive(Debug, diesel::QueryableByName)]
+pub struct AssetFlowSum {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub asset: String,
+    #[diesel(sql_type = diesel::sql_types::Numeric)]
+    pub amount: BigDecimal,
+    #[diesel(sql_type = diesel::sql_types::Bool)]
+    pub deposit: bool,
+}
+
+/// Flows per `0x` prefixed asset of the summed `balances` rows, scaled by the decimals of
+/// `decimals`, keyed by normalized coin type. Assets missing from it are left out, since their
+/// amounts can't be scaled.
+pub fn asset_flows(
+    rows: &[AssetFlowSum],
+    decimals: &HashMap<String, i16>,
+) -> HashMap<String, AssetFlow> {
+    let mut flows: HashMap<String, AssetFlow> = HashMap::new();
+    for row in rows {
+        let Some(decimals) = decimals.get(&normalize_coin_type(&row.asset)) else {
+            continue;
+        };
+        let amount = row.amount.to_f64().unwrap_or_default() / 10f64.powi(*decimals as i32);
+
+        let mut asset = row.asset.clone();
+        if !asset.starts_with("0x") {
+            asset.insert_str(0, "0x");
+        }
+        let flow = flows.entry(asset).or_default();
+        if row.deposit {
+            flow.deposited += amount;
+            flow.net += amount;
+        } else {
+            flow.withdrawn += amount;
+            flow.net -= amount;
+        }
+    }
+    flows
+}
+
+/// Deposits, withdrawals and net amount per asset of a balance manager, over its whole history
+/// unless `start_time` or `end_time` are given
+async fn balance_manager_flows(
+    Path(balance_manager_id): Path<String>,
+    common: CommonParams,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<HashMap<String, AssetFlow>>, DeepBookError> {
+    let end_time = common.end_time_ms();
+    let start_time = common.start_time_ms().unwrap_or(0);
+
+    let (rows, decimals) = join!(
+        state
+            .reader
+            .get_balance_manager_flows(&balance_manager_id, start_time, end_time),
+        state.reader.get_asset_decimals()
+    );
+    let decimals = decimals?
+        .into_iter()
+        .map(|(asset, decimals)| (normalize_coin_type(&asset), decimals))
+        .collect();
+
+    Ok(Json(asset_flows(&rows?, &decimals)))
+}
+
+async fn top_traders(
+    Path(pool_name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<HashMap<String, Value>>>, DeepBookError> {
+    let pool = state.reader.pool_by_name(&pool_name).await?;
+
+    let window = params.get("window").map(String::as_str).unwrap_or("24h");
+    let window_ms = parse_window_ms(window)
+        .ok_or_else(|| DeepBookError::BadRequest(format!("Invalid window: {}", window)))?;
+    let end_time = common.end_time_ms();
+    let limit = common
+        .limit
+        .unwrap_or(TOP_TRADERS_DEFAULT_LIMIT)
+        .clamp(1, TOP_TRADERS_MAX_LIMIT);
+
+    let traders = state
+        .reader
+        .get_top_traders(&pool.pool_id, end_time - window_ms, end_time, limit)
+        .await?;
+
+    let data = traders
+        .into_iter()
+        .map(|(balance_manager_id, volume)| {
+            HashMap::from([
+                (
+                    "balance_manager_id".to_string(),
+                    Value::from(balance_manager_id),
+                ),
+                (
+                    "volume".to_string(),
+                    Value::from(volume.to_decimal_f64(pool.base_asset_decimals as u32)),
+                ),
+            ])
+        })
+        .collect();
+
+    Ok(Json(data))
+}
+
+async fn spread_history(
+    Path(pool_name): Path<String>,
+    common: CommonParams,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<HashMap<String, Value>>>, DeepBookError> {
+    let pool = state.reader.pool_by_name(&pool_name).await?;
+
+    let end_time = common.end_time_ms();
+    let start_time = common
+        .start_time_ms()
+        .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
+
+    let snapshots = state
+        .reader
+        .get_spread_history(
+            &pool.pool_id,
+            naive_datetime_from_millis(start_time)?,
+            naive_datetime_from_millis(end_time)?,
+            state.config.max_aggregation_rows,
+        )
+        .await?;
+
+    let price_factor = price_scaling_factor(pool.base_asset_decimals, pool.quote_asset_decimals)?;
+    let scale = |price: Option<i64>| price.map(|price| price as f64 / price_factor);
+
+    let data = snapshots
+        .into_iter()
+        .map(|(checkpoint, timestamp, best_bid, best_ask)| {
+            let spread = best_bid.zip(best_ask).map(|(bid, ask)| ask - bid);
+            HashMap::from([
+                ("checkpoint".to_string(), Value::from(checkpoint)),
+                (
+                    "timestamp".to_string(),
+                    Value::from(timestamp.and_utc().timestamp_millis()),
+                ),
+                ("best_bid".to_string(), Value::from(scale(best_bid))),
+                ("best_ask".to_string(), Value::from(scale(best_ask))),
+                ("spread".to_string(), Value::from(scale(spread))),
+            ])
+        })
+        .collect();
+
+    Ok(Json(data))
+}
+
+/// Price levels of one side of a stored snapshot, `{price: size}` as written by the orderbook
+/// service. Empty levels are skipped, bids are sorted from the highest price, asks from the lowest.
+pub fn snapshot_levels(side: &Value, is_bid: bool) -> Result<Vec<(i64, i64)>, DeepBookError> {
+    let levels: HashMap<String, i64> = serde_json::from_value(side.clone())
+        .map_err(|e| DeepBookError::Deserialize(format!("Invalid snapshot levels: {}", e)))?;
+    let mut levels = levels
+        .into_iter()
+        .filter(|(_, size)| *size > 0)
+        .map(|(price, size)| {
+            price
+                .parse::<i64>()
+                .map(|price| (price, size))
+                .map_err(|_| {
+                    DeepBookError::Deserialize(format!("Invalid snapshot price {}", price))
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if is_bid {
+        levels.sort_by(|a, b| b.0.cmp(&a.0));
+    } else {
+        levels.sort_by_key(|level| level.0);
+    }
+    Ok(levels)
+}
+
+/// Stored orderbook snapshot at or before `checkpoint`, in the format of `/orderbook`.
+/// `checkpoint` in the response is the one the snapshot was taken at.
+async fn orderbook_snapshot(
+    Path(pool_name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<HashMap<String, Value>>, DeepBookError> {
+    let checkpoint = positive_checkpoint(&params, "checkpoint")?;
+    let pool = state.reader.pool_by_name(&pool_name).await?;
+
+    let snapshot = state
+        .reader
+        .get_snapshot_at(&pool.pool_id, checkpoint)
+        .await?
+        .ok_or_else(|| {
+            DeepBookError::NotFound(format!(
+                "No snapshot of pool {} at or before checkpoint {}",
+                pool_name, checkpoint
+            ))
+        })?;
+
+    Ok(Json(snapshot_entry(&state, &pool, snapshot)?))
+}
+
+/// Stored orderbook snapshots taken between `from_checkpoint` and `to_checkpoint`, inclusive,
+/// oldest first, each in the format of `/orderbook_snapshot`
+async fn orderbook_snapshots(
+    Path(pool_name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<HashMap<String, Value>>>, DeepBookError> {
+    let (from_checkpoint, to_checkpoint) = checkpoint_range(&params)?;
+    let pool = state.reader.pool_by_name(&pool_name).await?;
+
+    // snapshots hold whole books, so fewer of them are served than other rows
+    let max_rows = ORDERBOOK_SNAPSHOTS_MAX.min(state.config.max_aggregation_rows);
+    let snapshots = state
+        .reader
+        .get_snapshots_range(&pool.pool_id, from_checkpoint, to_checkpoint, max_rows)
+        .await?;
+    let snapshots = enforce_row_cap(snapshots, max_rows)?;
+
+    let entries = snapshots
+        .into_iter()
+        .map(|snapshot| snapshot_entry(&state, &pool, snapshot))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Json(entries))
+}
+
+fn positive_checkpoint(params: &HashMap<String, String>, name: &str) -> Result<i64, DeepBookError> {
+    params
+        .get(name)
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|checkpoint| *checkpoint > 0)
+        .ok_or_else(|| DeepBookError::BadRequest(format!("{} must be a positive integer", name)))
+}
+
+/// `from_checkpoint` and `to_checkpoint` of the query, both required with `from <= to`
+pub fn checkpoint_range(params: &HashMap<String, String>) -> Result<(i64, i64), DeepBookError> {
+    let from_checkpoint = positive_checkpoint(params, "from_checkpoint")?;
+    let to_checkpoint = positive_checkpoint(params, "to_checkpoint")?;
+    if from_checkpoint > to_checkpoint {
+        return Err(DeepBookError::BadRequest(
+            "from_checkpoint must not be after to_checkpoint".to_string(),
+        ));
+    }
+    Ok((from_checkpoint, to_checkpoint))
+}
+
+/// Snapshot in the format of `/orderbook` with the checkpoint it was taken at
+fn snapshot_entry(
+    state: &AppState,
+    pool: &Pool,
+    snapshot: OrderbookSnapshot,
+) -> Result<HashMap<String, Value>, DeepBookError> {
+    let price_factor = price_scaling_factor(pool.base_asset_decimals, pool.quote_asset_decimals)?;
+    let quantity_factor = 10f64.powi(pool.base_asset_decimals as i32);
+    let (tick_size, lot_size) = tick_and_lot_sizes(state, pool.tick_size, pool.lot_size);
+    let to_value = |(price, size): (i64, i64)| {
+        Value::Array(vec![
+            Value::from(to_readable(price, price_factor, tick_size).to_string()),
+            Value::from(to_readable(size, quantity_factor, lot_size).to_string()),
+        ])
+    };
+
+    let bids = snapshot_levels(&snapshot.bids, true)?;
+    let asks = snapshot_levels(&snapshot.asks, false)?;
+
+    Ok(HashMap::from([
+        ("checkpoint".to_string(), Value::from(snapshot.checkpoint)),
+        (
+            "timestamp".to_string(),
+            Value::from(snapshot.timestamp.and_utc().timestamp_millis().to_string()),
+        ),
+        (
+            "bids".to_string(),
+            Value::Array(bids.into_iter().map(to_value).collect()),
+        ),
+        (
+            "asks".to_string(),
+            Value::Array(asks.into_iter().map(to_value).collect()),
+        ),
+    ]))
+}
+
+/// Progress of one indexer pipeline
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PipelineStatus {
+    pub pipeline: String,
+    pub checkpoint: i64,
+    pub timestamp_ms: i64,
+    /// Seconds between the newest indexed checkpoint and now
+    pub lag_seconds: i64,
+}
+
+/// Statuses of `(pipeline, checkpoint_hi_inclusive, timestamp_ms_hi_inclusive)` watermarks
+/// at `now_ms`, a watermark ahead of the clock has no lag
+pub fn pipeline_statuses(watermarks: Vec<(String, i64, i64)>, now_ms: i64) -> Vec<PipelineStatus> {
+    watermarks
+        .into_iter()
+        .map(|(pipeline, checkpoint, timestamp_ms)| PipelineStatus {
+            pipeline,
+            checkpoint,
+            timestamp_ms,
+            lag_seconds: (now_ms - timestamp_ms).max(0) / 1000,
+        })
+        .collect()
+}
+
+/// Indexed checkpoint and lag behind now of every indexer pipeline
+async fn status(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<PipelineStatus>>, DeepBookError> {
+    let watermarks = state.reader.get_watermarks().await?;
+    Ok(Json(pipeline_statuses(watermarks, now_millis()?)))
+}
+
+/// Best levels of the live orderbook in Redis, same as the bests and spread websockets send
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BestQuote {
+    pub bid_price: f64,
+    pub bid_size: f64,
+    pub ask_price: f64,
+    pub ask_size: f64,
+    pub spread: f64,
+    pub mid: f64,
+}
+
+/// Best quote of an orderbook stored by the orderbook service, `None` when a side is empty
+pub fn best_quote(orderbook: Value) -> Option<BestQuote> {
+    let bests = get_bests_from_redis_orderbook(Some(orderbook))?;
+    let bid = bests.get("bids")?;
+    let ask = bests.get("asks")?;
+    let bid_price = *bid.get("price")?;
+    let ask_price = *ask.get("price")?;
+
+    Some(BestQuote {
+        bid_price,
+        bid_size: *bid.get("size")?,
+        ask_price,
+        ask_size: *ask.get("size")?,
+        spread: get_spread_from_bests(Some(bests.clone()))?,
+        mid: (bid_price + ask_price) / 2.0,
+    })
+}
+
+async fn best(
+    Path(pool_name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<BestQuote>, DeepBookError> {
+    let key = format!("orderbook::{}", pool_name);
+    let orderbook = state
+        .reader
+        .cache
+        .get::<Value>(&key)
+        .await
+        .map_err(|e| DeepBookError::Cache(format!("Failed reading {}: {:?}", key, e)))?
+        .ok_or_else(|| DeepBookError::NotFound(format!("No live orderbook for {}", pool_name)))?;
+
+    best_quote(orderbook).map(Json).ok_or_else(|| {
+        DeepBookError::NotFound(format!("Orderbook of {} has an empty side", pool_name))
+    })
+}
+
+/// Price of the pool's latest trade scaled like `/ticker`, `null` before its first trade
+async fn last_price(
+    Path(pool_name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Option<f64>>, DeepBookError> {
+    let pool = state.reader.pool_by_name(&pool_name).await?;
+    let price_factor = price_scaling_factor(pool.base_asset_decimals, pool.quote_asset_decimals)?;
+
+    let price = state.reader.get_last_price(&pool.pool_id).await?;
+
+    Ok(Json(price.map(|price| (price as f64) / price_factor)))
+}
+
+/// Parses windows like `15m`, `1h` or `7d` into milliseconds
+pub fn parse_window_ms(window: &str) -> Option<i64> {
+    let unit_ms = match window.chars().last()? {
+        'm' => 60 * 1000,
+        'h' => 60 * 60 * 1000,
+        'd' => 24 * 60 * 60 * 1000,
+        _ => return None,
+    };
+    let count = window[..window.len() - 1].parse::<i64>().ok()?;
+    (count > 0).then(|| count.checked_mul(unit_ms)).flatten()
+}
+
+/// Governance events are sparse, so without `start_time` the whole history is searched
+fn governance_time_range(common: &CommonParams) -> (i64, i64) {
+    (common.start_time_ms().unwrap_or(0), common.end_time_ms())
+}
+
+async fn proposals(
+    Path(pool_name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, DeepBookError> {
+    let envelope = wants_envelope(&params)?;
+    let pool_id = state.reader.get_pool_id_by_name(&pool_name).await?;
+    let (start_time, end_time) = governance_time_range(&common);
+    let limit = common.limit.unwrap_or(1);
+
+    let proposals = state
+        .reader
+        .get_proposals(
+            &pool_id,
+            params.epoch()?,
+            start_time,
+            end_time,
+            limit,
+            PageCursor::from_params(&params)?,
+        )
+        .await?;
+
+    let data = proposals
+        .into_iter()
+        .map(|proposal| {
+            HashMap::from([
+                (
+                    "event_digest".to_string(),
+                    Value::from(proposal.event_digest),
+                ),
+                ("digest".to_string(), Value::from(proposal.digest)),
+                (
+                    "balance_manager_id".to_string(),
+                    Value::from(proposal.balance_manager_id),
+                ),
+                ("epoch".to_string(), Value::from(proposal.epoch)),
+                ("taker_fee".to_string(), Value::from(proposal.taker_fee)),
+                ("maker_fee".to_string(), Value::from(proposal.maker_fee)),
+                (
+                    "stake_required".to_string(),
+                    Value::from(proposal.stake_required),
+                ),
+                (
+                    "timestamp".to_string(),
+                    Value::from(proposal.checkpoint_timestamp_ms as u64),
+                ),
+            ])
+        })
+        .collect();
+
+    Ok(page_response(data, envelope, limit, "timestamp"))
+}
+
+async fn votes(
+    Path(pool_name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<HashMap<String, Value>>>, DeepBookError> {
+    let pool_id = state.reader.get_pool_id_by_name(&pool_name).await?;
+    let (start_time, end_time) = governance_time_range(&common);
+
+    let votes = state
+        .reader
+        .get_votes(
+            &pool_id,
+            params.epoch()?,
+            start_time,
+            end_time,
+            common.limit.unwrap_or(1),
+        )
+        .await?;
+
+    let data = votes
+        .into_iter()
+        .map(|vote| {
+            HashMap::from([
+                ("digest".to_string(), Value::from(vote.digest)),
+                (
+                    "balance_manager_id".to_string(),
+                    Value::from(vote.balance_manager_id),
+                ),
+                ("epoch".to_string(), Value::from(vote.epoch)),
+                (
+                    "from_proposal_id".to_string(),
+                    Value::from(vote.from_proposal_id),
+                ),
+                (
+                    "to_proposal_id".to_string(),
+                    Value::from(vote.to_proposal_id),
+                ),
+                ("stake".to_string(), Value::from(vote.stake)),
+                (
+                    "timestamp".to_string(),
+                    Value::from(vote.checkpoint_timestamp_ms as u64),
+                ),
+            ])
+        })
+        .collect();
+
+    Ok(Json(data))
+}
+
+fn trade_params_entry(update: TradeParamsUpdate) -> HashMap<String, Value> {
+    HashMap::from([
+        ("digest".to_string(), Value::from(update.digest)),
+        ("epoch".to_string(), Value::from(update.epoch)),
+        ("taker_fee".to_string(), Value::from(update.taker_fee)),
+        ("maker_fee".to_string(), Value::from(update.maker_fee)),
+        (
+            "stake_required".to_string(),
+            Value::from(update.stake_required),
+        ),
+        (
+            "timestamp".to_string(),
+            Value::from(update.checkpoint_timestamp_ms as u64),
+        ),
+    ])
+}
+
+async fn trade_params(
+    Path(pool_name): Path<String>,
+    common: CommonParams,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<HashMap<String, Value>>>, DeepBookError> {
+    let pool_id = state.reader.get_pool_id_by_name(&pool_name).await?;
+    let (start_time, end_time) = governance_time_range(&common);
+    let limit = common
+        .limit
+        .unwrap_or(TRADE_PARAMS_DEFAULT_LIMIT)
+        .clamp(1, TRADE_PARAMS_MAX_LIMIT);
+
+    let updates = state
+        .reader
+        .get_trade_params(&pool_id, start_time, end_time, limit)
+        .await?;
+
+    Ok(Json(updates.into_iter().map(trade_params_entry).collect()))
+}
+
+async fn latest_trade_params(
+    Path(pool_name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<HashMap<String, Value>>, DeepBookError> {
+    let pool_id = state.reader.get_pool_id_by_name(&pool_name).await?;
+
+    let latest = state
+        .reader
+        .get_trade_params(&pool_id, 0, i64::MAX, 1)
+        .await?
+        .pop()
+        .ok_or_else(|| {
+            DeepBookError::NotFound(format!("No trade params updates for pool {}", pool_name))
+        })?;
+
+    Ok(Json(trade_params_entry(latest)))
+}
+
+async fn conversion_rate(
+    Path(pool_name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<HashMap<String, Value>>, DeepBookError> {
+    let pool = state.reader.pool_by_name(&pool_name).await?;
+
+    let (reference_pool_id, conversion_rate, timestamp) = state
+        .reader
+        .get_latest_pool_price(&pool.pool_id)
+        .await?
+        .ok_or_else(|| {
+            DeepBookError::NotFound(format!("No DEEP price points for pool {}", pool_name))
+        })?;
+    let reference = state.reader.pool_by_id(&reference_pool_id).await?;
+
+    let (is_base_conversion, deep_decimals) =
+        deep_price_side(&pool, &reference).ok_or_else(|| {
+            DeepBookError::InternalError(format!(
+                "Reference pool {} shares no asset with pool {}",
+                reference.pool_name, pool_name
+            ))
+        })?;
+    let (asset_symbol, asset_decimals) = if is_base_conversion {
+        (pool.base_asset_symbol, pool.base_asset_decimals)
+    } else {
+        (pool.quote_asset_symbol, pool.quote_asset_decimals)
+    };
+    // the rate is DEEP per unit of the asset, scaled like a price of asset/DEEP
+    let factor = price_scaling_factor(asset_decimals, deep_decimals)?;
+
+    Ok(Json(HashMap::from([
+        ("pool_name".to_string(), Value::from(pool_name)),
+        (
+            "reference_pool".to_string(),
+            Value::from(reference.pool_name),
+        ),
+        ("asset".to_string(), Value::from(asset_symbol)),
+        (
+            "is_base_conversion".to_string(),
+            Value::from(is_base_conversion),
+        ),
+        (
+            "conversion_rate".to_string(),
+            Value::from(conversion_rate as f64 / factor),
+        ),
+        ("timestamp".to_string(), Value::from(timestamp)),
+    ])))
+}
+
+async fn stakes(
+    Path(pool_name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<HashMap<String, Value>>>, DeepBookError> {
+    let pool_id = state.reader.get_pool_id_by_name(&pool_name).await?;
+    let (start_time, end_time) = governance_time_range(&common);
+
+    let stakes = state
+        .reader
+        .get_stakes(
+            &pool_id,
+            params.epoch()?,
+            start_time,
+            end_time,
+            common.limit.unwrap_or(1),
+        )
+        .await?;
+
+    let data = stakes
+        .into_iter()
+        .map(|stake| {
+            HashMap::from([
+                ("digest".to_string(), Value::from(stake.digest)),
+                (
+                    "balance_manager_id".to_string(),
+                    Value::from(stake.balance_manager_id),
+                ),
+                ("epoch".to_string(), Value::from(stake.epoch)),
+                ("amount".to_string(), Value::from(stake.amount)),
+                ("stake".to_string(), Value::from(stake.stake)),
+                (
+                    "timestamp".to_string(),
+                    Value::from(stake.checkpoint_timestamp_ms as u64),
+                ),
+            ])
+        })
+        .collect();
+
+    Ok(Json(data))
+}
+
+/// Normalizes coin type address, so `0x2::sui::SUI` equals `0000...0002::sui::SUI`
+pub fn normalize_coin_type(coin_type: &str) -> String {
+    let coin_type = coin_type.trim_start_matches("0x");
+    match coin_type.split_once("::") {
+        Some((address, rest)) => format!("{}::{}", address.trim_start_matches('0'), rest),
+        None => coin_type.to_string(),
+    }
+}
+
+/// Body of `/recent_trades` and messages of the `/latest_trades` websocket, oldest trade first
+pub fn latest_trades_json(trades: &[Value]) -> Option<String> {
+    serde_json::to_string(trades).ok()
+}
+
+/// Latest trades of `fills` in `/recent_trades` order, `fills` are newest first
+pub fn recent_trades_values(fills: &[OrderFill]) -> Result<Vec<Value>, DeepBookError> {
+    fills
+        .iter()
+        .rev()
+        .map(|fill| serde_json::to_value(LatestTrade::from(fill)).map_err(DeepBookError::from))
+        .collect()
+}
+
+/// Latest trades from the database in the shape of the `/latest_trades` websocket, so
+/// clients can load history before subscribing. Trades of one checkpoint may be ordered
+/// differently than the websocket sends them.
+async fn recent_trades(
+    Path(pool_name): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, DeepBookError> {
+    let pool_id = state.reader.get_pool_id_by_name(&pool_name).await?;
+    let fills = state
+        .reader
+        .get_order_fills(
+            &pool_id,
+            naive_datetime_from_millis(0)?,
+            naive_datetime_from_millis(now_millis()?)?,
+            state.config.latest_trades_size as i64,
+            SortOrder::Desc,
+            None,
+        )
+        .await?;
+
+    let body = latest_trades_json(&recent_trades_values(&fills)?).ok_or_else(|| {
+        DeepBookError::InternalError("Failed serializing recent trades".to_string())
+    })?;
+    Ok((
+        [(axum::http::header::CONTENT_TYPE, "application/json")],
+        body,
+    ))
+}
+
+async fn trades(
+    Path(pool_name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, DeepBookError> {
+    let envelope = wants_envelope(&params)?;
+    // Fetch the pool to map its name to ID, decimals and tick/lot sizes
+    let pool = state.reader.pool_by_name(&pool_name).await?;
+    // Parse start_time and end_time
+    let end_time = common.end_time_ms();
+    let start_time = common
+        .start_time_ms()
+        .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
+
+    // Parse limit (default to 1 if not provided)
+    let limit = common.limit.unwrap_or(1);
+    if limit > state.config.max_aggregation_rows {
+        return Err(DeepBookError::BadRequest(format!(
+            "limit must be at most {}",
+            state.config.max_aggregation_rows
+        )));
+    }
+
+    // Parse optional filters for balance managers
+    let maker_balance_manager_filter = params.get("maker_balance_manager_id").cloned();
+    let taker_balance_manager_filter = params.get("taker_balance_manager_id").cloned();
+    let exclude_self_trades = common.exclude_self_trades.unwrap_or(false);
+
+    let base_decimals = pool.base_asset_decimals as u8;
+    let quote_decimals = pool.quote_asset_decimals as u8;
+    let (tick_size, lot_size) = tick_and_lot_sizes(&state, pool.tick_size, pool.lot_size);
+
+    let trades = state
+        .reader
+        .get_orders(
+            pool_name,
+            pool.pool_id,
+            start_time,
+            end_time,
+            limit,
+            maker_balance_manager_filter,
+            taker_balance_manager_filter,
+            exclude_self_trades,
+            PageCursor::from_params(&params)?,
+        )
+        .await?;
+
+    // Conversion factors for decimals
+    let base_factor = (10u64).pow(base_decimals as u32);
+    let quote_factor = (10u64).pow(quote_decimals as u32);
+    let price_factor = price_scaling_factor(base_decimals.into(), quote_decimals.into())?;
+
+    // Map trades to JSON format
+    let trade_data = trades
+        .into_iter()
+        .map(
+            |(
+                maker_order_id,
+                taker_order_id,
+                price,
+                base_quantity,
+                quote_quantity,
+                timestamp,
+                taker_is_bid,
+                maker_balance_manager_id,
+                taker_balance_manager_id,
+                event_digest,
+            )| {
+                let trade_id = calculate_trade_id(&maker_order_id, &taker_order_id).unwrap_or(0);
+                let trade_type = if taker_is_bid { "buy" } else { "sell" };
+
+                HashMap::from([
+                    ("event_digest".to_string(), Value::from(event_digest)),
+                    ("trade_id".to_string(), Value::from(trade_id.to_string())),
+                    ("maker_order_id".to_string(), Value::from(maker_order_id)),
+                    ("taker_order_id".to_string(), Value::from(taker_order_id)),
+                    (
+                        "maker_balance_manager_id".to_string(),
+                        Value::from(maker_balance_manager_id),
+                    ),
+                    (
+                        "taker_balance_manager_id".to_string(),
+                        Value::from(taker_balance_manager_id),
                     ),
                     (
                         "price".to_string(),
-                        Value::from((price as f64) / (price_factor as f64)),
+                        Value::from(to_readable(price, price_factor, tick_size)),
                     ),
                     (
                         "base_volume".to_string(),
-                        Value::from((base_quantity as f64) / (base_factor as f64)),
+                        Value::from(to_readable(base_quantity, base_factor as f64, lot_size)),
                     ),
                     (
                         "quote_volume".to_string(),
@@ -956,17 +2539,17 @@ async fn trades(
         )
         .collect();
 
-    Ok(Json(trade_data))
+    Ok(page_response(trade_data, envelope, limit, "timestamp"))
 }
 
 async fn trade_count(
-    Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<i64>, DeepBookError> {
     // Parse start_time and end_time
-    let end_time = params.end_time();
-    let start_time = params
-        .start_time() // Convert to milliseconds
+    let end_time = common.end_time_ms();
+    let start_time = common
+        .start_time_ms()
         .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
 
     let query = schema::order_fills::table.select(count_star()).filter(
@@ -980,7 +2563,13 @@ async fn trade_count(
     Ok(Json(result))
 }
 
-fn calculate_trade_id(maker_id: &str, taker_id: &str) -> Result<u128, DeepBookError> {
+/// FNV-1a 128 parameters, the trade id must not depend on the std hasher which may change
+const FNV_128_OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+const FNV_128_PRIME: u128 = 0x0000000001000000000000000000013b;
+
+/// Trade id of a fill, FNV-1a 128 hash of the big endian maker and taker order ids.
+/// Fills with a different maker or taker order, or with the two swapped, get different ids.
+pub fn calculate_trade_id(maker_id: &str, taker_id: &str) -> Result<u128, DeepBookError> {
     // Parse maker_id and taker_id as u128
     let maker_id = maker_id
         .parse::<u128>()
@@ -989,12 +2578,29 @@ fn calculate_trade_id(maker_id: &str, taker_id: &str) -> Result<u128, DeepBookEr
         .parse::<u128>()
         .map_err(|_| DeepBookError::InternalError("Invalid taker_id".to_string()))?;
 
-    // Ignore the most significant bit for both IDs
-    let maker_id = maker_id & !(1 << 127);
-    let taker_id = taker_id & !(1 << 127);
+    let hash = maker_id
+        .to_be_bytes()
+        .into_iter()
+        .chain(taker_id.to_be_bytes())
+        .fold(FNV_128_OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u128).wrapping_mul(FNV_128_PRIME)
+        });
+    Ok(hash)
+}
 
-    // Return the sum of the modified IDs as the trade_id
-    Ok(maker_id + taker_id)
+/// Placeholder of the package id in `asset_url_template`
+pub const PACKAGE_ID_PLACEHOLDER: &str = "{package_id}";
+
+/// Stored contract URL of an asset, or the template filled with its package id when none is stored
+pub fn contract_address_url(
+    stored: Option<String>,
+    package_id: Option<&str>,
+    template: Option<&str>,
+) -> Option<String> {
+    stored.or_else(|| {
+        let (template, package_id) = template.zip(package_id)?;
+        Some(template.replace(PACKAGE_ID_PLACEHOLDER, package_id))
+    })
 }
 
 pub async fn assets(
@@ -1006,21 +2612,36 @@ pub async fn assets(
         schema::assets::ucid,
         schema::assets::package_address_url,
         schema::assets::package_id,
+        schema::assets::can_deposit,
+        schema::assets::can_withdraw,
     ));
-    let assets: Vec<(String, String, Option<i32>, Option<String>, Option<String>)> =
-        state.reader.results(query).await.map_err(|err| {
-            DeepBookError::InternalError(format!("Failed to query assets: {}", err))
-        })?;
+    #[allow(clippy::type_complexity)]
+    let assets: Vec<(
+        String,
+        String,
+        Option<i32>,
+        Option<String>,
+        Option<String>,
+        bool,
+        bool,
+    )> = state
+        .reader
+        .results(query)
+        .await
+        .map_err(|err| DeepBookError::Db(format!("Failed to query assets: {}", err)))?;
     let mut response = HashMap::new();
 
-    for (symbol, name, ucid, package_address_url, package_id) in assets {
+    for (symbol, name, ucid, package_address_url, package_id, can_deposit, can_withdraw) in assets {
         let mut asset_info = HashMap::new();
         asset_info.insert("name".to_string(), Value::String(name));
         asset_info.insert(
             "can_withdraw".to_string(),
-            Value::String("true".to_string()),
+            Value::String(can_withdraw.to_string()),
+        );
+        asset_info.insert(
+            "can_deposit".to_string(),
+            Value::String(can_deposit.to_string()),
         );
-        asset_info.insert("can_deposit".to_string(), Value::String("true".to_string()));
 
         if let Some(ucid) = ucid {
             asset_info.insert(
@@ -1028,8 +2649,12 @@ pub async fn assets(
                 Value::String(ucid.to_string()),
             );
         }
-        if let Some(addresses) = package_address_url {
-            asset_info.insert("contractAddressUrl".to_string(), Value::String(addresses));
+        if let Some(url) = contract_address_url(
+            package_address_url,
+            package_id.as_deref(),
+            state.config.asset_url_template.as_deref(),
+        ) {
+            asset_info.insert("contractAddressUrl".to_string(), Value::String(url));
         }
 
         if let Some(addresses) = package_id {
@@ -1042,86 +2667,248 @@ pub async fn assets(
     Ok(Json(response))
 }
 
+/// Ticks from mid to fetch for the `depth` and `level` query values of `/orderbook` and
+/// `/orderbook_imbalance`. `depth` counts price levels of both sides: `0` or `full` is the
+/// whole book up to `max_depth` levels, `2` up to `max_depth` is that many levels. `1` is
+/// rejected since it can't cover both sides, and so is more than `max_depth`. `level=1` only
+/// fetches the best bid and ask, no `depth` is the whole book.
+pub fn parse_depth(
+    depth: Option<&str>,
+    level: Option<&str>,
+    max_depth: u64,
+) -> Result<u64, DeepBookError> {
+    let max_depth = max_depth.max(2);
+    let depth =
+        match depth {
+            None | Some("full") => max_depth,
+            Some(depth) => match depth.parse::<u64>() {
+                Ok(0) => max_depth,
+                Ok(1) => return Err(DeepBookError::BadRequest(
+                    "Depth cannot be 1. Use a value greater than 1 or 0 for the entire orderbook"
+                        .to_string(),
+                )),
+                Ok(depth) if depth > max_depth => {
+                    return Err(DeepBookError::BadRequest(format!(
+                        "Depth must be at most {max_depth}"
+                    )))
+                }
+                Ok(depth) => depth,
+                Err(_) => {
+                    return Err(DeepBookError::BadRequest(
+                        "Depth must be a non-negative integer or full".to_string(),
+                    ))
+                }
+            },
+        };
+
+    match level.map(|v| v.parse::<u64>()) {
+        None | Some(Ok(2)) => Ok(depth / 2),
+        Some(Ok(1)) => Ok(1),
+        Some(Ok(_)) => Err(DeepBookError::BadRequest(
+            "Level must be 1 or 2".to_string(),
+        )),
+        Some(Err(_)) => Err(DeepBookError::BadRequest(
+            "Level must be an integer between 1 and 2".to_string(),
+        )),
+    }
+}
+
 /// Level2 data for all pools
 async fn orderbook(
     Path(pool_name): Path<String>,
     Query(params): Query<HashMap<String, String>>,
-    State((state, rpc_url)): State<(Arc<AppState>, Url)>,
+    State(state): State<Arc<AppState>>,
 ) -> Result<Json<HashMap<String, Value>>, DeepBookError> {
-    let depth = params
-        .get("depth")
-        .map(|v| v.parse::<u64>())
+    let ticks_from_mid = parse_depth(
+        params.get("depth").map(String::as_str),
+        params.get("level").map(String::as_str),
+        state.config.max_orderbook_depth,
+    )?;
+
+    let group = params
+        .get("group")
+        .map(|v| v.parse::<f64>())
         .transpose()
-        .map_err(|_| {
-            DeepBookError::InternalError("Depth must be a non-negative integer".to_string())
-        })?
-        .map(|depth| if depth == 0 { 200 } else { depth });
-
-    if let Some(depth) = depth {
-        if depth == 1 {
-            return Err(DeepBookError::InternalError(
-                "Depth cannot be 1. Use a value greater than 1 or 0 for the entire orderbook"
-                    .to_string(),
+        .map_err(|_| DeepBookError::BadRequest("Group must be a positive number".to_string()))?;
+
+    if let Some(group) = group {
+        if !(group > 0.0 && group.is_finite()) {
+            return Err(DeepBookError::BadRequest(
+                "Group must be a positive number".to_string(),
             ));
         }
     }
 
-    let level = params
-        .get("level")
-        .map(|v| v.parse::<u64>())
-        .transpose()
-        .map_err(|_| {
-            DeepBookError::InternalError("Level must be an integer between 1 and 2".to_string())
-        })?;
+    let Pool {
+        pool_id,
+        base_asset_id,
+        base_asset_decimals: base_decimals,
+        quote_asset_id,
+        quote_asset_decimals: quote_decimals,
+        tick_size,
+        lot_size,
+        ..
+    } = state.reader.pool_by_name(&pool_name).await?;
+    let base_decimals = base_decimals as u8;
+    let quote_decimals = quote_decimals as u8;
+    let (tick_size, lot_size) = tick_and_lot_sizes(&state, tick_size, lot_size);
+
+    let ticks = level2_ticks(
+        &state,
+        &pool_name,
+        &pool_id,
+        &base_asset_id,
+        &quote_asset_id,
+        ticks_from_mid,
+    )
+    .await?;
+
+    let mut result = HashMap::new();
+    result.insert(
+        "timestamp".to_string(),
+        Value::from(ticks.timestamp.to_string()),
+    );
+
+    let price_factor = price_scaling_factor(base_decimals.into(), quote_decimals.into())?;
+    let quantity_factor = (10u64).pow(base_decimals.into());
+
+    let mut bid_levels: Vec<(u64, u64)> = ticks
+        .bid_prices
+        .into_iter()
+        .zip(ticks.bid_quantities)
+        .take(ticks_from_mid as usize)
+        .collect();
+    let mut ask_levels: Vec<(u64, u64)> = ticks
+        .ask_prices
+        .into_iter()
+        .zip(ticks.ask_quantities)
+        .take(ticks_from_mid as usize)
+        .collect();
 
-    if let Some(level) = level {
-        if !(1..=2).contains(&level) {
-            return Err(DeepBookError::InternalError(
-                "Level must be 1 or 2".to_string(),
+    if let Some(group) = group {
+        // Group is in quote units per base, bin on the onchain integer prices
+        let group = (group * price_factor).round() as u64;
+        if group == 0 {
+            return Err(DeepBookError::BadRequest(
+                "Group is smaller than the price precision of the pool".to_string(),
             ));
         }
+        bid_levels = group_price_levels(&bid_levels, group, true);
+        ask_levels = group_price_levels(&ask_levels, group, false);
     }
 
-    let ticks_from_mid = match (depth, level) {
-        (Some(_), Some(1)) => 1u64, // Depth + Level 1 → Best bid and ask
-        (Some(depth), Some(2)) | (Some(depth), None) => depth / 2, // Depth + Level 2 → Use depth
-        (None, Some(1)) => 1u64,    // Only Level 1 → Best bid and ask
-        (None, Some(2)) | (None, None) => 100u64, // Level 2 or default → 100 ticks
-        _ => 100u64,                // Fallback to default
+    let to_value = |(price, quantity): (u64, u64)| {
+        Value::Array(vec![
+            Value::from(to_readable(price as i64, price_factor, tick_size).to_string()),
+            Value::from(to_readable(quantity as i64, quantity_factor as f64, lot_size).to_string()),
+        ])
     };
 
-    // Fetch the pool data from the `pools` table
-    let query = schema::pools::table
-        .filter(schema::pools::pool_name.eq(pool_name.clone()))
-        .select((
-            schema::pools::pool_id,
-            schema::pools::base_asset_id,
-            schema::pools::base_asset_decimals,
-            schema::pools::quote_asset_id,
-            schema::pools::quote_asset_decimals,
-        ));
-    let pool_data: (String, String, i16, String, i16) = state.reader.first(query).await?;
-    let (pool_id, base_asset_id, base_decimals, quote_asset_id, quote_decimals) = pool_data;
-    let base_decimals = base_decimals as u8;
-    let quote_decimals = quote_decimals as u8;
+    let bids: Vec<Value> = bid_levels.into_iter().map(to_value).collect();
+    result.insert("bids".to_string(), Value::Array(bids));
+
+    let asks: Vec<Value> = ask_levels.into_iter().map(to_value).collect();
+    result.insert("asks".to_string(), Value::Array(asks));
+
+    Ok(Json(result))
+}
+
+/// Level2 ticks of a pool read from the chain, `timestamp` is when they were read
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Level2Ticks {
+    pub timestamp: i64,
+    pub bid_prices: Vec<u64>,
+    pub bid_quantities: Vec<u64>,
+    pub ask_prices: Vec<u64>,
+    pub ask_quantities: Vec<u64>,
+}
+
+impl Level2Ticks {
+    /// Cached ticks older than `ttl` are refetched even if Redis still holds them
+    pub fn is_fresh(&self, now_ms: i64, ttl: Duration) -> bool {
+        now_ms - self.timestamp < ttl.as_millis() as i64
+    }
+}
+
+/// Redis key of cached level2 ticks of a pool
+pub fn level2_cache_key(pool_name: &str, ticks_from_mid: u64) -> String {
+    format!("orderbook_rpc::{}::{}", pool_name, ticks_from_mid)
+}
+
+/// Level2 ticks of a pool, served from Redis while younger than the configured TTL so
+/// bursts of orderbook requests share one dev-inspect call. Cache failures fall back to RPC.
+async fn level2_ticks(
+    state: &AppState,
+    pool_name: &str,
+    pool_id: &str,
+    base_asset_id: &str,
+    quote_asset_id: &str,
+    ticks_from_mid: u64,
+) -> Result<Level2Ticks, DeepBookError> {
+    let ttl = Duration::from_millis(state.config.orderbook_rpc_cache_ttl_ms);
+    if ttl.is_zero() {
+        return fetch_level2_ticks(
+            &*state.sui_client().await?,
+            pool_name,
+            pool_id,
+            base_asset_id,
+            quote_asset_id,
+            ticks_from_mid,
+        )
+        .await;
+    }
+
+    let key = level2_cache_key(pool_name, ticks_from_mid);
+    match state.reader.cache.get::<Level2Ticks>(&key).await {
+        Ok(Some(ticks)) if ticks.is_fresh(now_millis()?, ttl) => return Ok(ticks),
+        Ok(_) => {}
+        Err(e) => println!("Failed reading cached orderbook {}: {:?}", key, e),
+    }
+
+    let ticks = fetch_level2_ticks(
+        &*state.sui_client().await?,
+        pool_name,
+        pool_id,
+        base_asset_id,
+        quote_asset_id,
+        ticks_from_mid,
+    )
+    .await?;
+    if let Err(e) = state.reader.cache.set_with_ttl(&key, &ticks, ttl).await {
+        println!("Failed caching orderbook {}: {:?}", key, e);
+    }
+
+    Ok(ticks)
+}
+
+fn now_millis() -> Result<i64, DeepBookError> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| DeepBookError::InternalError("System time error".to_string()))?
+        .as_millis() as i64)
+}
 
-    let pool_address = ObjectID::from_hex_literal(&pool_id)?;
+async fn fetch_level2_ticks(
+    sui_client: &SuiClient,
+    pool_name: &str,
+    pool_id: &str,
+    base_asset_id: &str,
+    quote_asset_id: &str,
+    ticks_from_mid: u64,
+) -> Result<Level2Ticks, DeepBookError> {
+    let pool_address = ObjectID::from_hex_literal(pool_id)?;
 
-    let sui_client = SuiClientBuilder::default().build(rpc_url.as_str()).await?;
     let mut ptb = ProgrammableTransactionBuilder::new();
 
-    let pool_object: SuiObjectResponse = sui_client
-        .read_api()
-        .get_object_with_options(pool_address, SuiObjectDataOptions::full_content())
-        .await?;
-    let pool_data: &SuiObjectData =
-        pool_object
-            .data
-            .as_ref()
-            .ok_or(DeepBookError::InternalError(format!(
-                "Missing data in pool object response for '{}'",
-                pool_name
-            )))?;
+    let pool_object: SuiObjectResponse = retry_rpc(|| {
+        sui_client
+            .read_api()
+            .get_object_with_options(pool_address, SuiObjectDataOptions::full_content())
+    })
+    .await?;
+    let pool_data: &SuiObjectData = pool_object.data.as_ref().ok_or(DeepBookError::Rpc(
+        format!("Missing data in pool object response for '{}'", pool_name),
+    ))?;
     let pool_object_ref: ObjectRef = (pool_data.object_id, pool_data.version, pool_data.digest);
 
     let pool_input = CallArg::Object(ObjectArg::ImmOrOwnedObject(pool_object_ref));
@@ -1135,17 +2922,15 @@ async fn orderbook(
     let sui_clock_object_id = ObjectID::from_hex_literal(
         "0x0000000000000000000000000000000000000000000000000000000000000006",
     )?;
-    let sui_clock_object: SuiObjectResponse = sui_client
-        .read_api()
-        .get_object_with_options(sui_clock_object_id, SuiObjectDataOptions::full_content())
-        .await?;
-    let clock_data: &SuiObjectData =
-        sui_clock_object
-            .data
-            .as_ref()
-            .ok_or(DeepBookError::InternalError(
-                "Missing data in clock object response".to_string(),
-            ))?;
+    let sui_clock_object: SuiObjectResponse = retry_rpc(|| {
+        sui_client
+            .read_api()
+            .get_object_with_options(sui_clock_object_id, SuiObjectDataOptions::full_content())
+    })
+    .await?;
+    let clock_data: &SuiObjectData = sui_clock_object.data.as_ref().ok_or(DeepBookError::Rpc(
+        "Missing data in clock object response".to_string(),
+    ))?;
 
     let sui_clock_object_ref: ObjectRef =
         (clock_data.object_id, clock_data.version, clock_data.digest);
@@ -1153,8 +2938,8 @@ async fn orderbook(
     let clock_input = CallArg::Object(ObjectArg::ImmOrOwnedObject(sui_clock_object_ref));
     ptb.input(clock_input)?;
 
-    let base_coin_type = parse_type_input(&base_asset_id)?;
-    let quote_coin_type = parse_type_input(&quote_asset_id)?;
+    let base_coin_type = parse_type_input(base_asset_id)?;
+    let quote_coin_type = parse_type_input(quote_asset_id)?;
 
     let package = ObjectID::from_hex_literal(DEEPBOOK_PACKAGE_ID)
         .map_err(|e| DeepBookError::InternalError(format!("Invalid pool ID: {}", e)))?;
@@ -1172,135 +2957,194 @@ async fn orderbook(
     let builder = ptb.finish();
     let tx = TransactionKind::ProgrammableTransaction(builder);
 
-    let result = sui_client
-        .read_api()
-        .dev_inspect_transaction_block(SuiAddress::default(), tx, None, None, None)
-        .await?;
+    let result = retry_rpc(|| {
+        sui_client.read_api().dev_inspect_transaction_block(
+            SuiAddress::default(),
+            tx.clone(),
+            None,
+            None,
+            None,
+        )
+    })
+    .await?;
 
-    let mut binding = result.results.ok_or(DeepBookError::InternalError(
+    let mut binding = result.results.ok_or(DeepBookError::Rpc(
         "No results from dev_inspect_transaction_block".to_string(),
     ))?;
     let bid_prices = &binding
         .first_mut()
-        .ok_or(DeepBookError::InternalError(
+        .ok_or(DeepBookError::Rpc(
             "No return values for bid prices".to_string(),
         ))?
         .return_values
         .first_mut()
-        .ok_or(DeepBookError::InternalError(
-            "No bid price data found".to_string(),
-        ))?
+        .ok_or(DeepBookError::Rpc("No bid price data found".to_string()))?
         .0;
-    let bid_parsed_prices: Vec<u64> = bcs::from_bytes(bid_prices).map_err(|_| {
-        DeepBookError::InternalError("Failed to deserialize bid prices".to_string())
-    })?;
+    let bid_parsed_prices: Vec<u64> = bcs::from_bytes(bid_prices)
+        .map_err(|_| DeepBookError::Deserialize("Failed to deserialize bid prices".to_string()))?;
     let bid_quantities = &binding
         .first_mut()
-        .ok_or(DeepBookError::InternalError(
+        .ok_or(DeepBookError::Rpc(
             "No return values for bid quantities".to_string(),
         ))?
         .return_values
         .get(1)
-        .ok_or(DeepBookError::InternalError(
-            "No bid quantity data found".to_string(),
-        ))?
+        .ok_or(DeepBookError::Rpc("No bid quantity data found".to_string()))?
         .0;
     let bid_parsed_quantities: Vec<u64> = bcs::from_bytes(bid_quantities).map_err(|_| {
-        DeepBookError::InternalError("Failed to deserialize bid quantities".to_string())
+        DeepBookError::Deserialize("Failed to deserialize bid quantities".to_string())
     })?;
 
     let ask_prices = &binding
         .first_mut()
-        .ok_or(DeepBookError::InternalError(
+        .ok_or(DeepBookError::Rpc(
             "No return values for ask prices".to_string(),
         ))?
         .return_values
         .get(2)
-        .ok_or(DeepBookError::InternalError(
-            "No ask price data found".to_string(),
-        ))?
+        .ok_or(DeepBookError::Rpc("No ask price data found".to_string()))?
         .0;
-    let ask_parsed_prices: Vec<u64> = bcs::from_bytes(ask_prices).map_err(|_| {
-        DeepBookError::InternalError("Failed to deserialize ask prices".to_string())
-    })?;
+    let ask_parsed_prices: Vec<u64> = bcs::from_bytes(ask_prices)
+        .map_err(|_| DeepBookError::Deserialize("Failed to deserialize ask prices".to_string()))?;
     let ask_quantities = &binding
         .first_mut()
-        .ok_or(DeepBookError::InternalError(
+        .ok_or(DeepBookError::Rpc(
             "No return values for ask quantities".to_string(),
         ))?
         .return_values
         .get(3)
-        .ok_or(DeepBookError::InternalError(
-            "No ask quantity data found".to_string(),
-        ))?
+        .ok_or(DeepBookError::Rpc("No ask quantity data found".to_string()))?
         .0;
     let ask_parsed_quantities: Vec<u64> = bcs::from_bytes(ask_quantities).map_err(|_| {
-        DeepBookError::InternalError("Failed to deserialize ask quantities".to_string())
+        DeepBookError::Deserialize("Failed to deserialize ask quantities".to_string())
     })?;
 
-    let mut result = HashMap::new();
+    Ok(Level2Ticks {
+        timestamp: now_millis()?,
+        bid_prices: bid_parsed_prices,
+        bid_quantities: bid_parsed_quantities,
+        ask_prices: ask_parsed_prices,
+        ask_quantities: ask_parsed_quantities,
+    })
+}
 
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map_err(|_| DeepBookError::InternalError("System time error".to_string()))?
-        .as_millis() as i64;
-    result.insert("timestamp".to_string(), Value::from(timestamp.to_string()));
+/// Steps readable prices and sizes of the pool are rounded to, `None` when rounding is disabled
+fn tick_and_lot_sizes(
+    state: &AppState,
+    tick_size: i32,
+    lot_size: i32,
+) -> (Option<i64>, Option<i64>) {
+    if state.config.round_to_ticks {
+        (Some(tick_size as i64), Some(lot_size as i64))
+    } else {
+        (None, None)
+    }
+}
 
-    let bids: Vec<Value> = bid_parsed_prices
-        .into_iter()
-        .zip(bid_parsed_quantities.into_iter())
-        .take(ticks_from_mid as usize)
-        .map(|(price, quantity)| {
-            let price_factor = (10u64).pow((9 - base_decimals + quote_decimals).into());
-            let quantity_factor = (10u64).pow(base_decimals.into());
-            Value::Array(vec![
-                Value::from(((price as f64) / (price_factor as f64)).to_string()),
-                Value::from(((quantity as f64) / (quantity_factor as f64)).to_string()),
-            ])
-        })
-        .collect();
-    result.insert("bids".to_string(), Value::Array(bids));
+/// Level2 data of several pools, `pool_names` is comma-separated and the other parameters
+/// are applied to every pool like in `/orderbook/:pool_name`
+async fn orderbooks(
+    Query(params): Query<HashMap<String, String>>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<HashMap<String, HashMap<String, Value>>>, DeepBookError> {
+    let pool_names = params
+        .get("pool_names")
+        .ok_or_else(|| DeepBookError::BadRequest("Missing pool_names".to_string()))?;
+    let pool_names = parse_pool_names(pool_names, ORDERBOOKS_MAX_POOLS)?;
+
+    let mut pool_params = params.clone();
+    pool_params.remove("pool_names");
+
+    let orderbook_futures = pool_names.iter().map(|pool_name| {
+        orderbook(
+            Path(pool_name.clone()),
+            Query(pool_params.clone()),
+            State(state.clone()),
+        )
+    });
+    let orderbook_results = join_all(orderbook_futures).await;
 
-    let asks: Vec<Value> = ask_parsed_prices
-        .into_iter()
-        .zip(ask_parsed_quantities.into_iter())
-        .take(ticks_from_mid as usize)
-        .map(|(price, quantity)| {
-            let price_factor = (10u64).pow((9 - base_decimals + quote_decimals).into());
-            let quantity_factor = (10u64).pow(base_decimals.into());
-            Value::Array(vec![
-                Value::from(((price as f64) / (price_factor as f64)).to_string()),
-                Value::from(((quantity as f64) / (quantity_factor as f64)).to_string()),
-            ])
-        })
-        .collect();
-    result.insert("asks".to_string(), Value::Array(asks));
+    let mut response = HashMap::new();
+    for (pool_name, result) in pool_names.into_iter().zip(orderbook_results) {
+        let Json(book) = result?;
+        response.insert(pool_name, book);
+    }
 
-    Ok(Json(result))
+    Ok(Json(response))
+}
+
+/// Splits comma-separated pool names, dropping blanks and duplicates while keeping order.
+/// Fails when no name is left or there are more than `max_pools`.
+pub fn parse_pool_names(raw: &str, max_pools: usize) -> Result<Vec<String>, DeepBookError> {
+    let mut pool_names: Vec<String> = Vec::new();
+    for name in raw
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        if !pool_names.iter().any(|existing| existing == name) {
+            pool_names.push(name.to_string());
+        }
+    }
+
+    if pool_names.is_empty() {
+        return Err(DeepBookError::BadRequest(
+            "No pool names provided".to_string(),
+        ));
+    }
+    if pool_names.len() > max_pools {
+        return Err(DeepBookError::BadRequest(format!(
+            "At most {} pools can be requested at once",
+            max_pools
+        )));
+    }
+
+    Ok(pool_names)
+}
+
+/// Merges price levels into bins of `group` price units, summing quantities. Bids are
+/// binned down and asks up, so grouped sides never cross. Levels keep their order.
+pub fn group_price_levels(levels: &[(u64, u64)], group: u64, is_bid: bool) -> Vec<(u64, u64)> {
+    let mut grouped: Vec<(u64, u64)> = Vec::new();
+    for &(price, quantity) in levels {
+        let bin = if is_bid {
+            price / group * group
+        } else {
+            price.div_ceil(group) * group
+        };
+        match grouped.last_mut() {
+            Some((last_bin, last_quantity)) if *last_bin == bin => *last_quantity += quantity,
+            _ => grouped.push((bin, quantity)),
+        }
+    }
+    grouped
+}
+
+/// Latest checkpoint of the chain
+async fn chain_tip(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<CheckpointDigest>, DeepBookError> {
+    let sui_client = state.sui_client().await?;
+    Ok(Json(CheckpointDigest::get_latest(&*sui_client).await?))
 }
 
 /// DEEP total supply
-async fn deep_supply(
-    State((_, rpc_url)): State<(Arc<AppState>, Url)>,
-) -> Result<Json<u64>, DeepBookError> {
-    let sui_client = SuiClientBuilder::default().build(rpc_url.as_str()).await?;
+async fn deep_supply(State(state): State<Arc<AppState>>) -> Result<Json<u64>, DeepBookError> {
+    let sui_client = state.sui_client().await?;
     let mut ptb = ProgrammableTransactionBuilder::new();
 
     let deep_treasury_object_id = ObjectID::from_hex_literal(DEEP_TREASURY_ID)?;
-    let deep_treasury_object: SuiObjectResponse = sui_client
-        .read_api()
-        .get_object_with_options(
+    let deep_treasury_object: SuiObjectResponse = retry_rpc(|| {
+        sui_client.read_api().get_object_with_options(
             deep_treasury_object_id,
             SuiObjectDataOptions::full_content(),
         )
-        .await?;
-    let deep_treasury_data: &SuiObjectData =
-        deep_treasury_object
-            .data
-            .as_ref()
-            .ok_or(DeepBookError::InternalError(
-                "Incorrect Treasury ID".to_string(),
-            ))?;
+    })
+    .await?;
+    let deep_treasury_data: &SuiObjectData = deep_treasury_object
+        .data
+        .as_ref()
+        .ok_or(DeepBookError::Rpc("Incorrect Treasury ID".to_string()))?;
 
     let deep_treasury_ref: ObjectRef = (
         deep_treasury_data.object_id,
@@ -1328,29 +3172,33 @@ async fn deep_supply(
     let builder = ptb.finish();
     let tx = TransactionKind::ProgrammableTransaction(builder);
 
-    let result = sui_client
-        .read_api()
-        .dev_inspect_transaction_block(SuiAddress::default(), tx, None, None, None)
-        .await?;
+    let result = retry_rpc(|| {
+        sui_client.read_api().dev_inspect_transaction_block(
+            SuiAddress::default(),
+            tx.clone(),
+            None,
+            None,
+            None,
+        )
+    })
+    .await?;
 
-    let mut binding = result.results.ok_or(DeepBookError::InternalError(
+    let mut binding = result.results.ok_or(DeepBookError::Rpc(
         "No results from dev_inspect_transaction_block".to_string(),
     ))?;
 
     let total_supply = &binding
         .first_mut()
-        .ok_or(DeepBookError::InternalError(
+        .ok_or(DeepBookError::Rpc(
             "No return values for total supply".to_string(),
         ))?
         .return_values
         .first_mut()
-        .ok_or(DeepBookError::InternalError(
-            "No total supply data found".to_string(),
-        ))?
+        .ok_or(DeepBookError::Rpc("No total supply data found".to_string()))?
         .0;
 
     let total_supply_value: u64 = bcs::from_bytes(total_supply).map_err(|_| {
-        DeepBookError::InternalError("Failed to deserialize total supply".to_string())
+        DeepBookError::Deserialize("Failed to deserialize total supply".to_string())
     })?;
 
     Ok(Json(total_supply_value))
@@ -1393,96 +3241,128 @@ async fn get_net_deposits(
     Ok(Json(net_deposits))
 }
 
+/// Direction of time ordered results
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    /// `asc` or `desc` of the `order` query parameter, newest first when missing
+    pub fn parse(order: Option<&str>) -> Result<Self, DeepBookError> {
+        match order {
+            None | Some("desc") => Ok(SortOrder::Desc),
+            Some("asc") => Ok(SortOrder::Asc),
+            Some(order) => Err(DeepBookError::BadRequest(format!(
+                "Invalid order: {}, use asc or desc",
+                order
+            ))),
+        }
+    }
+}
+
+/// Fills of the pool in the time range ordered by time, at most `limit` of them
 pub async fn get_order_fills(
     Path(pool_name): Path<String>,
     Query(params): Query<HashMap<String, String>>,
+    common: CommonParams,
+    headers: HeaderMap,
     State(state): State<Arc<AppState>>,
-) -> Result<Json<Vec<HashMap<String, Value>>>, DeepBookError> {
-    let pool_id = match state.reader.get_pool_id_by_name(&pool_name.as_str()).await {
-        Err(_) => {
-            return Err(DeepBookError::InternalError(
-                "No valid pool names provided".to_string(),
-            ));
-        }
-        Ok(v) => v,
-    };
+) -> Result<Response, DeepBookError> {
+    let csv = wants_csv(&params, &headers)?;
+    let envelope = wants_envelope(&params)?;
+    let pool_id = state.reader.get_pool_id_by_name(&pool_name).await?;
     // Parse start_time and end_time from query parameters (in seconds) and convert to milliseconds
-    let end_time = params.end_time();
-    let start_time = params
-        .start_time() // Convert to milliseconds
+    let end_time = common.end_time_ms();
+    let start_time = common
+        .start_time_ms()
         .unwrap_or_else(|| end_time - 24 * 60 * 60 * 1000);
 
+    let limit = common
+        .limit
+        .unwrap_or(ORDER_FILLS_DEFAULT_LIMIT)
+        .clamp(1, ORDER_FILLS_MAX_LIMIT);
+    let order = SortOrder::parse(params.get("order").map(String::as_str))?;
+
     let result: Vec<OrderFill> = state
         .reader
-        .results(
-            schema::order_fills::table
-                .select(OrderFill::as_select())
-                .filter(schema::order_fills::timestamp.between(
-                    naive_datetime_from_millis(start_time)?,
-                    naive_datetime_from_millis(end_time)?,
-                ))
-                .filter(schema::order_fills::pool_id.eq(pool_id)),
+        .get_order_fills(
+            &pool_id,
+            naive_datetime_from_millis(start_time)?,
+            naive_datetime_from_millis(end_time)?,
+            limit,
+            order,
+            PageCursor::from_params(&params)?,
         )
         .await?;
 
-    Ok(Json(
-        result
-            .into_iter()
-            .map(|fill| {
-                let mut map = HashMap::new();
-                map.insert("event_digest".into(), Value::String(fill.event_digest));
-                map.insert("digest".into(), Value::String(fill.digest));
-                map.insert("sender".into(), Value::String(fill.sender));
-                map.insert("checkpoint".into(), Value::from(fill.checkpoint));
-                map.insert(
-                    "checkpoint_timestamp_ms".into(),
-                    Value::from(fill.checkpoint_timestamp_ms),
-                );
-                map.insert(
-                    "timestamp".into(),
-                    Value::from(((fill.checkpoint_timestamp_ms as f64) / 1000.0).round() as i64),
-                );
-                map.insert("package".into(), Value::String(fill.package));
-                map.insert("pool_id".into(), Value::String(fill.pool_id));
-                map.insert("maker_order_id".into(), Value::String(fill.maker_order_id));
-                map.insert("taker_order_id".into(), Value::String(fill.taker_order_id));
-                map.insert(
-                    "maker_client_order_id".into(),
-                    Value::from(fill.maker_client_order_id),
-                );
-                map.insert(
-                    "taker_client_order_id".into(),
-                    Value::from(fill.taker_client_order_id),
-                );
-                map.insert("price".into(), Value::from(fill.price));
-                map.insert("taker_fee".into(), Value::from(fill.taker_fee));
-                map.insert(
-                    "taker_fee_is_deep".into(),
-                    Value::from(fill.taker_fee_is_deep),
-                );
-                map.insert("maker_fee".into(), Value::from(fill.maker_fee));
-                map.insert(
-                    "maker_fee_is_deep".into(),
-                    Value::from(fill.maker_fee_is_deep),
-                );
-                map.insert("taker_is_bid".into(), Value::from(fill.taker_is_bid));
-                map.insert("base_quantity".into(), Value::from(fill.base_quantity));
-                map.insert("quote_quantity".into(), Value::from(fill.quote_quantity));
-                map.insert(
-                    "maker_balance_manager_id".into(),
-                    Value::String(fill.maker_balance_manager_id),
-                );
-                map.insert(
-                    "taker_balance_manager_id".into(),
-                    Value::String(fill.taker_balance_manager_id),
-                );
-                map.insert(
-                    "onchain_timestamp".into(),
-                    Value::from(fill.onchain_timestamp),
-                );
-                map
-            })
-            .collect(),
+    let rows = result
+        .into_iter()
+        .map(|fill| {
+            let mut map = HashMap::new();
+            map.insert("event_digest".into(), Value::String(fill.event_digest));
+            map.insert("digest".into(), Value::String(fill.digest));
+            map.insert("sender".into(), Value::String(fill.sender));
+            map.insert("checkpoint".into(), Value::from(fill.checkpoint));
+            map.insert(
+                "checkpoint_timestamp_ms".into(),
+                Value::from(fill.checkpoint_timestamp_ms),
+            );
+            map.insert(
+                "timestamp".into(),
+                Value::from(((fill.checkpoint_timestamp_ms as f64) / 1000.0).round() as i64),
+            );
+            map.insert("package".into(), Value::String(fill.package));
+            map.insert("pool_id".into(), Value::String(fill.pool_id));
+            map.insert("maker_order_id".into(), Value::String(fill.maker_order_id));
+            map.insert("taker_order_id".into(), Value::String(fill.taker_order_id));
+            map.insert(
+                "maker_client_order_id".into(),
+                Value::from(fill.maker_client_order_id),
+            );
+            map.insert(
+                "taker_client_order_id".into(),
+                Value::from(fill.taker_client_order_id),
+            );
+            map.insert("price".into(), Value::from(fill.price));
+            map.insert("taker_fee".into(), Value::from(fill.taker_fee));
+            map.insert(
+                "taker_fee_is_deep".into(),
+                Value::from(fill.taker_fee_is_deep),
+            );
+            map.insert("maker_fee".into(), Value::from(fill.maker_fee));
+            map.insert(
+                "maker_fee_is_deep".into(),
+                Value::from(fill.maker_fee_is_deep),
+            );
+            map.insert("taker_is_bid".into(), Value::from(fill.taker_is_bid));
+            map.insert("base_quantity".into(), Value::from(fill.base_quantity));
+            map.insert("quote_quantity".into(), Value::from(fill.quote_quantity));
+            map.insert(
+                "maker_balance_manager_id".into(),
+                Value::String(fill.maker_balance_manager_id),
+            );
+            map.insert(
+                "taker_balance_manager_id".into(),
+                Value::String(fill.taker_balance_manager_id),
+            );
+            map.insert(
+                "onchain_timestamp".into(),
+                Value::from(fill.onchain_timestamp),
+            );
+            map
+        })
+        .collect();
+
+    if csv {
+        return Ok(rows_response(rows, true));
+    }
+    Ok(page_response(
+        rows,
+        envelope,
+        limit,
+        "checkpoint_timestamp_ms",
     ))
 }
 
@@ -1496,38 +3376,73 @@ pub fn parse_type_input(type_str: &str) -> Result<TypeInput, DeepBookError> {
 async fn orderbook_ws(
     ws: WebSocketUpgrade,
     Path(pool_name): Path<String>,
-    State(state): State<(Arc<AppState>, Url)>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    ws.protocols(WS_PROTOCOLS)
+        .on_upgrade(move |socket| handle_orderbook_socket(socket, pool_name, state.clone()))
+}
+
+async fn orderbook_diff_ws(
+    ws: WebSocketUpgrade,
+    Path(pool_name): Path<String>,
+    State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_orderbook_socket(socket, pool_name, state.0.clone()))
+    ws.protocols(WS_PROTOCOLS)
+        .on_upgrade(move |socket| handle_orderbook_diff_socket(socket, pool_name, state.clone()))
 }
 
 async fn latest_trades_ws(
     ws: WebSocketUpgrade,
     Path(pool_name): Path<String>,
-    State(state): State<(Arc<AppState>, Url)>,
+    State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_latest_trades_socket(socket, pool_name, state.0.clone()))
+    ws.protocols(WS_PROTOCOLS)
+        .on_upgrade(move |socket| handle_latest_trades_socket(socket, pool_name, state.clone()))
 }
 
 async fn orderbook_bests_ws(
     ws: WebSocketUpgrade,
     Path(pool_name): Path<String>,
-    State(state): State<(Arc<AppState>, Url)>,
+    State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_bests_socket(socket, pool_name, state.0.clone()))
+    ws.protocols(WS_PROTOCOLS)
+        .on_upgrade(move |socket| handle_bests_socket(socket, pool_name, state.clone()))
 }
 
 async fn orderbook_spread_ws(
     ws: WebSocketUpgrade,
     Path(pool_name): Path<String>,
-    State(state): State<(Arc<AppState>, Url)>,
+    State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_spread_socket(socket, pool_name, state.0.clone()))
+    ws.protocols(WS_PROTOCOLS)
+        .on_upgrade(move |socket| handle_spread_socket(socket, pool_name, state.clone()))
+}
+
+async fn ohlcv_ws(
+    ws: WebSocketUpgrade,
+    Path(pool_name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, DeepBookError> {
+    let (timeframe, bucket_ms) = parse_ohlcv_interval(params.get("interval").map(String::as_str))?;
+    let pool = state.reader.pool_by_name(&pool_name).await?;
+    let scale = OhlcvScale::new(
+        pool.base_asset_decimals as u8,
+        pool.quote_asset_decimals as u8,
+    )?;
+    Ok(ws
+        .protocols(WS_PROTOCOLS)
+        .on_upgrade(move |socket| {
+            handle_ohlcv_socket(socket, pool, scale, timeframe, bucket_ms, state.clone())
+        })
+        .into_response())
 }
 
 async fn handle_orderbook_socket(mut socket: WebSocket, pool_name: String, state: Arc<AppState>) {
     // Redis key that stores the order‑book JSON
     let redis_key = format!("orderbook::{}", pool_name);
+    let sent = ws_sent_counter(&state, &pool_name, WsStream::Orderbook).await;
+    let version = WsVersion::negotiated(socket.protocol());
 
     // Clone the async cache and extract the underlying Redis client
     let cache = state.reader.cache.clone();
@@ -1556,26 +3471,49 @@ async fn handle_orderbook_socket(mut socket: WebSocket, pool_name: String, state
     // Send initial snapshot if present
     let mut last_sent = fetch_latest().await;
     if let Some(snapshot) = &last_sent {
-        let _ = socket.send(Message::Text(snapshot.clone())).await;
+        send_counted(
+            &mut socket,
+            version.message(WsStream::Orderbook, snapshot),
+            &sent,
+        )
+        .await;
     }
 
     // Stream of Redis events
     let mut redis_stream = pubsub.on_message();
+    let mut heartbeat = ws_heartbeat(&state);
+    let shutdown = state.ws_shutdown.child_token();
 
     loop {
         tokio::select! {
+            // Server is shutting down
+            _ = shutdown.cancelled() => {
+                close_for_shutdown(&mut socket).await;
+                break;
+            }
             // Client closed WebSocket
             maybe_msg = socket.recv().fuse() => {
                 if maybe_msg.is_none() {
                     break;
                 }
+                heartbeat.received();
+            }
+            _ = heartbeat.tick() => {
+                if !heartbeat.ping(&mut socket).await {
+                    break;
+                }
             }
             // Redis published an event
             Some(_msg) = redis_stream.next() => {
                 if let Some(current) = fetch_latest().await {
                     if Some(&current) != last_sent.as_ref() {
                         last_sent = Some(current.clone());
-                        let _ = socket.send(Message::Text(current)).await;
+                        send_counted(
+                            &mut socket,
+                            version.message(WsStream::Orderbook, &current),
+                            &sent,
+                        )
+                        .await;
                     }
                 }
             }
@@ -1583,9 +3521,108 @@ async fn handle_orderbook_socket(mut socket: WebSocket, pool_name: String, state
     }
 }
 
+/// Full book followed by the diffs the orderbook service publishes in diff mode. Clients get
+/// a new full book when diffs were missed, so applying every message in order keeps their book
+/// in sync.
+async fn handle_orderbook_diff_socket(
+    mut socket: WebSocket,
+    pool_name: String,
+    state: Arc<AppState>,
+) {
+    let redis_key = orderbook_diff_book_key(&pool_name);
+    let sent = ws_sent_counter(&state, &pool_name, WsStream::OrderbookDiff).await;
+    let version = WsVersion::negotiated(socket.protocol());
+
+    let cache = state.reader.cache.clone();
+    let Ok(mut pubsub) = cache.client.get_async_pubsub().await else {
+        return;
+    };
+    // subscribe before reading the full book, so no diff after it is missed
+    if pubsub
+        .subscribe(orderbook_diff_channel(&pool_name))
+        .await
+        .is_err()
+    {
+        return;
+    }
+
+    let mut cursor = send_diff_snapshot(&mut socket, &cache, &redis_key, version, &sent).await;
+    let mut redis_stream = pubsub.on_message();
+    let mut heartbeat = ws_heartbeat(&state);
+    let shutdown = state.ws_shutdown.child_token();
+
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => {
+                close_for_shutdown(&mut socket).await;
+                break;
+            }
+            maybe_msg = socket.recv().fuse() => {
+                if maybe_msg.is_none() {
+                    break;
+                }
+                heartbeat.received();
+            }
+            _ = heartbeat.tick() => {
+                if !heartbeat.ping(&mut socket).await {
+                    break;
+                }
+            }
+            Some(msg) = redis_stream.next() => {
+                let Ok(diff) = cache.decode::<OrderbookDiff>(msg.get_payload_bytes()) else {
+                    continue;
+                };
+                let step = cursor.map_or(DiffStep::Resnapshot, |cursor| cursor.step(&diff));
+                if step == DiffStep::Resnapshot {
+                    cursor =
+                        send_diff_snapshot(&mut socket, &cache, &redis_key, version, &sent).await;
+                }
+                // the new full book may already contain the diff
+                let Some(current) = cursor else {
+                    continue;
+                };
+                if current.step(&diff) == DiffStep::Send {
+                    cursor = Some(DiffCursor {
+                        epoch: diff.epoch,
+                        seq: diff.seq,
+                    });
+                    send_counted(
+                        &mut socket,
+                        version.message(WsStream::OrderbookDiff, &diff_message(&diff).to_string()),
+                        &sent,
+                    )
+                    .await;
+                }
+            }
+        }
+    }
+}
+
+/// Sends the stored full book of a diff websocket, returns its position in the diff sequence
+async fn send_diff_snapshot(
+    socket: &mut WebSocket,
+    cache: &AsyncCache,
+    redis_key: &str,
+    version: WsVersion,
+    sent: &IntCounter,
+) -> Option<DiffCursor> {
+    let snapshot = cache.get::<Value>(redis_key).await.ok().flatten()?;
+    let cursor = DiffCursor::of_snapshot(&snapshot)?;
+    let message = snapshot_message(snapshot).to_string();
+    send_counted(
+        socket,
+        version.message(WsStream::OrderbookDiff, &message),
+        sent,
+    )
+    .await;
+    Some(cursor)
+}
+
 async fn handle_bests_socket(mut socket: WebSocket, pool_name: String, state: Arc<AppState>) {
     // Redis key that stores the order‑book JSON
     let redis_key = format!("orderbook::{}", pool_name);
+    let sent = ws_sent_counter(&state, &pool_name, WsStream::Bests).await;
+    let version = WsVersion::negotiated(socket.protocol());
 
     // // Clone the async cache and extract the underlying Redis client
     let cache = state.reader.cache.clone();
@@ -1616,19 +3653,37 @@ async fn handle_bests_socket(mut socket: WebSocket, pool_name: String, state: Ar
     let stringified = serde_json::to_string(&bests);
 
     if let Ok(message) = stringified {
-        let _ = socket.send(Message::Text(message)).await;
+        send_counted(
+            &mut socket,
+            version.message(WsStream::Bests, &message),
+            &sent,
+        )
+        .await;
     };
 
     // Stream of Redis events
     let mut redis_stream = pubsub.on_message();
+    let mut heartbeat = ws_heartbeat(&state);
+    let shutdown = state.ws_shutdown.child_token();
 
     loop {
         tokio::select! {
+            // Server is shutting down
+            _ = shutdown.cancelled() => {
+                close_for_shutdown(&mut socket).await;
+                break;
+            }
             // Client closed WebSocket
             maybe_msg = socket.recv().fuse() => {
                 if maybe_msg.is_none() {
                     break;
                 }
+                heartbeat.received();
+            }
+            _ = heartbeat.tick() => {
+                if !heartbeat.ping(&mut socket).await {
+                    break;
+                }
             }
             // Redis published an event
             Some(_msg) = redis_stream.next() => {
@@ -1640,7 +3695,12 @@ async fn handle_bests_socket(mut socket: WebSocket, pool_name: String, state: Ar
                         let stringified = serde_json::to_string(&bests);
 
                         if let Ok(message) = stringified {
-                            let _ = socket.send(Message::Text(message)).await;
+                            send_counted(
+                                &mut socket,
+                                version.message(WsStream::Bests, &message),
+                                &sent,
+                            )
+                            .await;
                         }
                     }
                 }
@@ -1656,6 +3716,8 @@ async fn handle_latest_trades_socket(
 ) {
     // Redis key that stores the order‑book JSON
     let redis_key = format!("latest_trades::{}", pool_name);
+    let sent = ws_sent_counter(&state, &pool_name, WsStream::Trades).await;
+    let version = WsVersion::negotiated(socket.protocol());
 
     // Clone the async cache and extract the underlying Redis client
     let cache = state.reader.cache.clone();
@@ -1672,6 +3734,8 @@ async fn handle_latest_trades_socket(
         .expect("Failed to subscribe to key‑space");
 
     let mut redis_stream = pubsub.on_message();
+    let mut heartbeat = ws_heartbeat(&state);
+    let shutdown = state.ws_shutdown.child_token();
 
     // Helper to fetch the full JSON array from Redis
     let fetch_latest = || async {
@@ -1680,24 +3744,39 @@ async fn handle_latest_trades_socket(
             .await
             .ok()
             .flatten()
-            .map(|array| serde_json::to_string(&array).ok())
-            .flatten()
+            .and_then(|array| latest_trades_json(&array))
     };
 
     // Send initial array if present
     let mut last_sent = fetch_latest().await;
     if let Some(snapshot) = &last_sent {
-        let _ = socket.send(Message::Text(snapshot.clone())).await;
+        send_counted(
+            &mut socket,
+            version.message(WsStream::Trades, snapshot),
+            &sent,
+        )
+        .await;
     }
 
     // Main loop: respond to Redis events or client disconnect
     loop {
         tokio::select! {
+            // Server is shutting down
+            _ = shutdown.cancelled() => {
+                close_for_shutdown(&mut socket).await;
+                break;
+            }
             // Client closed WebSocket
             maybe_msg = socket.recv() => {
                 if maybe_msg.is_none() {
                     break;
                 }
+                heartbeat.received();
+            }
+            _ = heartbeat.tick() => {
+                if !heartbeat.ping(&mut socket).await {
+                    break;
+                }
             }
 
             // Redis published a change event
@@ -1705,7 +3784,12 @@ async fn handle_latest_trades_socket(
                 if let Some(current) = fetch_latest().await {
                     if Some(&current) != last_sent.as_ref() {
                         last_sent = Some(current.clone());
-                        let _ = socket.send(Message::Text(current)).await;
+                        send_counted(
+                            &mut socket,
+                            version.message(WsStream::Trades, &current),
+                            &sent,
+                        )
+                        .await;
                     }
                 }
             }
@@ -1716,6 +3800,8 @@ async fn handle_latest_trades_socket(
 async fn handle_spread_socket(mut socket: WebSocket, pool_name: String, state: Arc<AppState>) {
     // Redis key that stores the order‑book JSON
     let redis_key = format!("orderbook::{}", pool_name);
+    let sent = ws_sent_counter(&state, &pool_name, WsStream::Spread).await;
+    let version = WsVersion::negotiated(socket.protocol());
 
     // // Clone the async cache and extract the underlying Redis client
     let cache = state.reader.cache.clone();
@@ -1748,19 +3834,37 @@ async fn handle_spread_socket(mut socket: WebSocket, pool_name: String, state: A
     let stringified = serde_json::to_string(&spread);
 
     if let Ok(message) = stringified {
-        let _ = socket.send(Message::Text(message)).await;
+        send_counted(
+            &mut socket,
+            version.message(WsStream::Spread, &message),
+            &sent,
+        )
+        .await;
     };
 
     // Stream of Redis events
     let mut redis_stream = pubsub.on_message();
+    let mut heartbeat = ws_heartbeat(&state);
+    let shutdown = state.ws_shutdown.child_token();
 
     loop {
         tokio::select! {
+            // Server is shutting down
+            _ = shutdown.cancelled() => {
+                close_for_shutdown(&mut socket).await;
+                break;
+            }
             // Client closed WebSocket
             maybe_msg = socket.recv().fuse() => {
                 if maybe_msg.is_none() {
                     break;
                 }
+                heartbeat.received();
+            }
+            _ = heartbeat.tick() => {
+                if !heartbeat.ping(&mut socket).await {
+                    break;
+                }
             }
             // Redis published an event
             Some(_msg) = redis_stream.next() => {
@@ -1773,7 +3877,12 @@ async fn handle_spread_socket(mut socket: WebSocket, pool_name: String, state: A
                         let stringified = serde_json::to_string(&spread);
 
                         if let Ok(message) = stringified {
-                            let _ = socket.send(Message::Text(message)).await;
+                            send_counted(
+                                &mut socket,
+                                version.message(WsStream::Spread, &message),
+                                &sent,
+                            )
+                            .await;
                         }
                     }
                 }
@@ -1782,6 +3891,250 @@ async fn handle_spread_socket(mut socket: WebSocket, pool_name: String, state: A
     }
 }
 
+/// Candles of the pool's buckets that closed by `closed_by_ms` and started after `after_ms`,
+/// oldest first. Looks back from `from_ms`.
+async fn load_completed_candles(
+    state: &AppState,
+    pool_id: &str,
+    timeframe: &str,
+    bucket_ms: i64,
+    from_ms: i64,
+    after_ms: Option<i64>,
+    closed_by_ms: i64,
+) -> Result<Vec<OHLCV>, DeepBookError> {
+    let rows = load_ohlcv(
+        state,
+        pool_id,
+        timeframe,
+        naive_datetime_from_millis(from_ms)?,
+        naive_datetime_from_millis(closed_by_ms)?,
+    )
+    .await?;
+    Ok(completed_candles(rows, after_ms, closed_by_ms, bucket_ms))
+}
+
+/// Time by which the candles are complete, see [`candles_closed_by`]
+async fn ohlcv_closed_by(state: &AppState) -> Result<Option<i64>, DeepBookError> {
+    let indexed = state
+        .reader
+        .get_watermark_timestamp_ms(OHLCV_WS_PIPELINE)
+        .await?;
+    Ok(candles_closed_by(now_millis()?, indexed))
+}
+
+/// Reads the candles of the feed's buckets once they closed and their fills are indexed and
+/// broadcasts them, until no socket streams the feed. Buckets without trades have no candle.
+async fn poll_ohlcv_feed(
+    state: Arc<AppState>,
+    key: FeedKey,
+    bucket_ms: i64,
+    sender: tokio::sync::broadcast::Sender<CandleBatch>,
+    closed_by: Option<i64>,
+) {
+    let (pool_id, timeframe) = key.clone();
+    let shutdown = state.ws_shutdown.child_token();
+    // start of the last bucket read, the first socket loaded the ones closed by `closed_by`
+    let mut done = closed_by.map(|closed_by| bucket_start_ms(closed_by, bucket_ms) - bucket_ms);
+
+    loop {
+        let now = now_millis().unwrap_or_default();
+        let wait = match done {
+            // a bucket is pending once it closed, until its fills are indexed
+            Some(done) => Duration::from_millis((done + 2 * bucket_ms - now).max(0) as u64),
+            None => Duration::ZERO,
+        } + OHLCV_WS_SETTLE;
+
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = tokio::time::sleep(wait) => {}
+        }
+        if state.ohlcv_feeds.close_if_unused(&key) {
+            break;
+        }
+
+        let closed_by = match ohlcv_closed_by(&state).await {
+            Ok(Some(closed_by)) => closed_by,
+            Ok(None) => continue,
+            Err(e) => {
+                println!(
+                    "Failed reading the {} watermark: {:?}",
+                    OHLCV_WS_PIPELINE, e
+                );
+                continue;
+            }
+        };
+        let closed = bucket_start_ms(closed_by, bucket_ms) - bucket_ms;
+        let Some(after) = done else {
+            done = Some(closed);
+            continue;
+        };
+        if closed <= after {
+            continue;
+        }
+
+        match load_completed_candles(
+            &state,
+            &pool_id,
+            timeframe,
+            bucket_ms,
+            after + bucket_ms,
+            Some(after),
+            closed_by,
+        )
+        .await
+        {
+            Ok(candles) => {
+                done = Some(closed);
+                if !candles.is_empty() {
+                    // no receivers is fine, the feed is closed on the next poll
+                    let _ = sender.send(Arc::new(candles));
+                }
+            }
+            Err(e) => println!("Failed loading candles of {}: {:?}", pool_id, e),
+        }
+    }
+}
+
+/// Sends the last completed candles on connect, then each candle once its bucket closes and its
+/// fills are indexed. Buckets without trades have no candle and are skipped.
+async fn handle_ohlcv_socket(
+    mut socket: WebSocket,
+    pool: Pool,
+    scale: OhlcvScale,
+    timeframe: &'static str,
+    bucket_ms: i64,
+    state: Arc<AppState>,
+) {
+    let sent = ws_sent_counter(&state, &pool.pool_name, WsStream::Ohlcv).await;
+    let version = WsVersion::negotiated(socket.protocol());
+
+    // subscribed before the first candles are read, so none completing meanwhile is missed
+    let key: FeedKey = (pool.pool_id.clone(), timeframe);
+    let (mut feed, new_feed) = state.ohlcv_feeds.subscribe(key.clone());
+    let closed_by = ohlcv_closed_by(&state).await;
+    if let Some(sender) = new_feed {
+        let closed_by = closed_by.as_ref().ok().copied().flatten();
+        tokio::spawn(poll_ohlcv_feed(
+            state.clone(),
+            key,
+            bucket_ms,
+            sender,
+            closed_by,
+        ));
+    }
+
+    let mut last_bucket = None;
+    let initial = match closed_by {
+        Ok(Some(closed_by)) => {
+            load_completed_candles(
+                &state,
+                &pool.pool_id,
+                timeframe,
+                bucket_ms,
+                closed_by - OHLCV_WS_INITIAL_CANDLES * bucket_ms,
+                None,
+                closed_by,
+            )
+            .await
+        }
+        Ok(None) => Ok(vec![]),
+        Err(e) => Err(e),
+    };
+    match initial {
+        Ok(candles) => {
+            last_bucket = candles
+                .last()
+                .map(|candle| candle.bucket.and_utc().timestamp_millis());
+            let candles: Vec<_> = candles
+                .into_iter()
+                .map(|candle| scale.candle(candle, timeframe))
+                .collect();
+            if let Ok(message) = serde_json::to_string(&candles) {
+                send_counted(
+                    &mut socket,
+                    version.message(WsStream::Ohlcv, &message),
+                    &sent,
+                )
+                .await;
+            }
+        }
+        Err(e) => println!("Failed loading candles of {}: {:?}", pool.pool_name, e),
+    }
+
+    let mut heartbeat = ws_heartbeat(&state);
+    let shutdown = state.ws_shutdown.child_token();
+
+    loop {
+        tokio::select! {
+            // Server is shutting down
+            _ = shutdown.cancelled() => {
+                close_for_shutdown(&mut socket).await;
+                break;
+            }
+            // Client closed WebSocket
+            maybe_msg = socket.recv().fuse() => {
+                if maybe_msg.is_none() {
+                    break;
+                }
+                heartbeat.received();
+            }
+            _ = heartbeat.tick() => {
+                if !heartbeat.ping(&mut socket).await {
+                    break;
+                }
+            }
+            // Candles completed, ones already sent on connect are skipped
+            batch = feed.recv() => {
+                let candles = match batch {
+                    Ok(candles) => candles,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(missed)) => {
+                        println!("OHLCV socket of {} missed {} candle batches", pool.pool_name, missed);
+                        continue;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+                for candle in candles.iter() {
+                    let start = candle.bucket.and_utc().timestamp_millis();
+                    if last_bucket.is_some_and(|last| start <= last) {
+                        continue;
+                    }
+                    last_bucket = Some(start);
+                    let candle = scale.candle(candle.clone(), timeframe);
+                    if let Ok(message) = serde_json::to_string(&candle) {
+                        send_counted(&mut socket, version.message(WsStream::Ohlcv, &message), &sent)
+                            .await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Tells the client the server is going away, so it reconnects to another instance
+pub async fn close_for_shutdown<S>(socket: &mut S)
+where
+    S: Sink<Message> + Unpin,
+{
+    let _ = socket
+        .send(Message::Close(Some(CloseFrame {
+            code: close_code::AWAY,
+            reason: "server shutting down".into(),
+        })))
+        .await;
+}
+
+/// Heartbeat every websocket handler selects on next to its streams. Pings keep a socket open
+/// while its stream is quiet, and a client missing a pong has its socket closed.
+fn ws_heartbeat(state: &AppState) -> Heartbeat {
+    Heartbeat::new(Duration::from_secs(state.config.ws_heartbeat_interval_secs))
+}
+
+/// Message counter of a websocket connection, labeled by pool only for known pools
+async fn ws_sent_counter(state: &AppState, pool_name: &str, stream: WsStream) -> IntCounter {
+    let known_pool = state.reader.get_pool_id_by_name(pool_name).await.is_ok();
+    state.ws_metrics.sent_counter(pool_name, known_pool, stream)
+}
+
 fn get_spread_from_bests(bests: Option<HashMap<String, HashMap<String, f64>>>) -> Option<f64> {
     let map = bests?;
 
@@ -1833,63 +4186,46 @@ fn parse_orderbook_from_redis(value: Value) -> Option<HashMap<String, Vec<HashMa
     ]))
 }
 
+/// Endpoint specific parameters, the shared ones are parsed by [`CommonParams`]
 pub trait ParameterUtil {
-    fn start_time(&self) -> Option<i64>;
-    fn end_time(&self) -> i64;
-    fn volume_in_base(&self) -> bool;
-
-    fn limit(&self) -> i64;
-    fn days(&self) -> i64;
-    fn ohlcv_timeframe(&self) -> &str;
+    fn days(&self) -> Result<i64, DeepBookError>;
+    fn epoch(&self) -> Result<Option<i64>, DeepBookError>;
+    fn ohlcv_timeframe(&self) -> Option<&str>;
 }
 
 impl ParameterUtil for HashMap<String, String> {
-    fn start_time(&self) -> Option<i64> {
-        self.get("start_time")
-            .and_then(|v| v.parse::<i64>().ok())
-            .map(|t| t * 1000) // Convert
-    }
-
-    fn end_time(&self) -> i64 {
-        self.get("end_time")
-            .and_then(|v| v.parse::<i64>().ok())
-            .map(|t| t * 1000) // Convert to milliseconds
-            .unwrap_or_else(|| {
-                SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as i64
+    /// Positive number of days that fits a duration in milliseconds, defaults to 1
+    fn days(&self) -> Result<i64, DeepBookError> {
+        let Some(days) = self.get("days") else {
+            return Ok(1);
+        };
+        days.parse::<i64>()
+            .ok()
+            .filter(|days| *days > 0)
+            .filter(|days| days.checked_mul(24 * 60 * 60 * 1000).is_some())
+            .ok_or_else(|| {
+                DeepBookError::BadRequest(format!("Invalid days: {}, use a positive integer", days))
             })
     }
 
-    fn volume_in_base(&self) -> bool {
-        self.get("volume_in_base")
-            .map(|v| v == "true")
-            .unwrap_or_default()
-    }
-
-    fn limit(&self) -> i64 {
-        self.get("limit")
-            .and_then(|v| v.parse::<i64>().ok())
-            .unwrap_or(1)
-    }
-    // defaults to 1
-    fn days(&self) -> i64 {
-        self.get("days")
-            .and_then(|v| v.parse::<i64>().ok())
-            .unwrap_or(1)
+    fn epoch(&self) -> Result<Option<i64>, DeepBookError> {
+        self.get("epoch")
+            .map(|epoch| {
+                epoch
+                    .parse::<i64>()
+                    .map_err(|_| DeepBookError::BadRequest(format!("Invalid epoch: {}", epoch)))
+            })
+            .transpose()
     }
 
-    /// Defaults to 1m
-    fn ohlcv_timeframe(&self) -> &str {
-        self.get("timeframe").map(String::as_str).unwrap_or("1m")
+    /// None lets the server pick timeframe from the requested range
+    fn ohlcv_timeframe(&self) -> Option<&str> {
+        self.get("timeframe").map(String::as_str)
     }
 }
 
 pub fn naive_datetime_from_millis(millis: i64) -> Result<NaiveDateTime, DeepBookError> {
     Ok(chrono::DateTime::from_timestamp_millis(millis)
-        .ok_or(DeepBookError::InternalError(
-            "Invalid timestamp".to_string(),
-        ))?
+        .ok_or(DeepBookError::BadRequest("Invalid timestamp".to_string()))?
         .naive_utc())
 }
@@ -0,0 +1,10 @@
+//! Bucket boundaries of millisecond timestamps, matching TimescaleDB `time_bucket` and the
+//! `date_bin` of the raw OHLCV query.
+
+/// 2000-01-03, the origin TimescaleDB `time_bucket` uses for intervals without months
+pub const TIME_BUCKET_ORIGIN_MS: i64 = 946_857_600_000;
+
+/// Start of the bucket `timestamp_ms` falls into
+pub fn bucket_start_ms(timestamp_ms: i64, bucket_ms: i64) -> i64 {
+    (timestamp_ms - TIME_BUCKET_ORIGIN_MS).div_euclid(bucket_ms) * bucket_ms + TIME_BUCKET_ORIGIN_MS
+}
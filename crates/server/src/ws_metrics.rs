@@ -0,0 +1,71 @@
+use axum::extract::ws::Message;
+use futures::{Sink, SinkExt};
+use prometheus::{register_int_counter_vec_with_registry, IntCounter, IntCounterVec, Registry};
+
+/// Pool label of connections to pools missing from the pools table, keeps the label
+/// set bounded since pool names come from the request path
+pub const UNKNOWN_POOL_LABEL: &str = "unknown";
+
+/// Kind of data a websocket streams
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WsStream {
+    Orderbook,
+    OrderbookDiff,
+    Bests,
+    Spread,
+    Trades,
+    Ohlcv,
+}
+
+impl WsStream {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WsStream::Orderbook => "orderbook",
+            WsStream::OrderbookDiff => "orderbook_diff",
+            WsStream::Bests => "bests",
+            WsStream::Spread => "spread",
+            WsStream::Trades => "trades",
+            WsStream::Ohlcv => "ohlcv",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct WsMetrics {
+    pub messages_sent: IntCounterVec,
+}
+
+impl WsMetrics {
+    pub fn new(registry: &Registry) -> Self {
+        Self {
+            messages_sent: register_int_counter_vec_with_registry!(
+                "ws_messages_sent",
+                "Number of websocket messages sent, by pool and stream",
+                &["pool", "stream"],
+                registry
+            )
+            .unwrap(),
+        }
+    }
+
+    /// Counter of one connection, `known_pool` is false for pools not in the pools table
+    pub fn sent_counter(&self, pool_name: &str, known_pool: bool, stream: WsStream) -> IntCounter {
+        let pool = if known_pool {
+            pool_name
+        } else {
+            UNKNOWN_POOL_LABEL
+        };
+        self.messages_sent
+            .with_label_values(&[pool, stream.as_str()])
+    }
+}
+
+/// Sends a text message, counting it when it was handed to the client
+pub async fn send_counted<S>(socket: &mut S, message: String, sent: &IntCounter)
+where
+    S: Sink<Message> + Unpin,
+{
+    if socket.send(Message::Text(message)).await.is_ok() {
+        sent.inc();
+    }
+}
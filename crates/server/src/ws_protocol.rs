@@ -0,0 +1,40 @@
+use axum::http::HeaderValue;
+
+use crate::ws_metrics::WsStream;
+
+/// Websocket subprotocol of versioned messages, `Sec-WebSocket-Protocol: deeplook.v1`
+pub const WS_PROTOCOL_V1: &str = "deeplook.v1";
+
+/// Subprotocols the server negotiates, newest first
+pub const WS_PROTOCOLS: [&str; 1] = [WS_PROTOCOL_V1];
+
+/// Message schema of a websocket connection, picked at upgrade from the requested subprotocols
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WsVersion {
+    /// No subprotocol requested, payloads are sent as they are for existing clients
+    Unversioned,
+    /// Payloads are wrapped as `{"v": 1, "type": <stream>, "data": <payload>}`
+    V1,
+}
+
+impl WsVersion {
+    /// Version of the subprotocol the server selected during the upgrade
+    pub fn negotiated(protocol: Option<&HeaderValue>) -> Self {
+        match protocol.and_then(|p| p.to_str().ok()) {
+            Some(WS_PROTOCOL_V1) => WsVersion::V1,
+            _ => WsVersion::Unversioned,
+        }
+    }
+
+    /// Message of `payload` in this version, `payload` must be JSON
+    pub fn message(&self, stream: WsStream, payload: &str) -> String {
+        match self {
+            WsVersion::Unversioned => payload.to_string(),
+            WsVersion::V1 => format!(
+                r#"{{"v":1,"type":"{}","data":{}}}"#,
+                stream.as_str(),
+                payload
+            ),
+        }
+    }
+}
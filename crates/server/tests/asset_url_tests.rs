@@ -0,0 +1,27 @@
+use deeplook_server::server::contract_address_url;
+
+const TEMPLATE: &str = "https://suiscan.xyz/mainnet/object/{package_id}";
+
+#[test]
+fn template_is_used_without_stored_url() {
+    assert_eq!(
+        contract_address_url(None, Some("0xdeeb"), Some(TEMPLATE)),
+        Some("https://suiscan.xyz/mainnet/object/0xdeeb".to_string())
+    );
+}
+
+#[test]
+fn stored_url_wins_over_template() {
+    let stored = "https://explorer.example/0xdeeb".to_string();
+
+    assert_eq!(
+        contract_address_url(Some(stored.clone()), Some("0xdeeb"), Some(TEMPLATE)),
+        Some(stored)
+    );
+}
+
+#[test]
+fn no_url_without_template_or_package_id() {
+    assert_eq!(contract_address_url(None, Some("0xdeeb"), None), None);
+    assert_eq!(contract_address_url(None, None, Some(TEMPLATE)), None);
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use bigdecimal::BigDecimal;
+use deeplook_server::server::{asset_flows, AssetFlow, AssetFlowSum};
+
+const SUI: &str = "0000000000000000000000000000000000000000000000000000000000000002::sui::SUI";
+const USDC: &str = "dba34672e30cb065b1f93e3ab55318768fd6fef66c15942c9f7cb846e2f900e7::usdc::USDC";
+
+fn row(asset: &str, amount: i64, deposit: bool) -> AssetFlowSum {
+    AssetFlowSum {
+        asset: asset.to_string(),
+        amount: BigDecimal::from(amount),
+        deposit,
+    }
+}
+
+fn decimals() -> HashMap<String, i16> {
+    HashMap::from([
+        ("2::sui::SUI".to_string(), 9),
+        (
+            "dba34672e30cb065b1f93e3ab55318768fd6fef66c15942c9f7cb846e2f900e7::usdc::USDC"
+                .to_string(),
+            6,
+        ),
+    ])
+}
+
+#[test]
+fn flows_are_netted_and_scaled_per_asset() {
+    let flows = asset_flows(
+        &[
+            row(SUI, 5_000_000_000, true),
+            row(SUI, 1_500_000_000, false),
+            row(USDC, 20_000_000, true),
+        ],
+        &decimals(),
+    );
+
+    assert_eq!(
+        flows,
+        HashMap::from([
+            (
+                format!("0x{SUI}"),
+                AssetFlow {
+                    deposited: 5.0,
+                    withdrawn: 1.5,
+                    net: 3.5,
+                },
+            ),
+            (
+                format!("0x{USDC}"),
+                AssetFlow {
+                    deposited: 20.0,
+                    withdrawn: 0.0,
+                    net: 20.0,
+                },
+            ),
+        ])
+    );
+}
+
+#[test]
+fn withdrawals_only_give_negative_net() {
+    let flows = asset_flows(&[row(SUI, 2_000_000_000, false)], &decimals());
+
+    assert_eq!(flows[&format!("0x{SUI}")].net, -2.0);
+}
+
+#[test]
+fn assets_without_decimals_are_left_out() {
+    let flows = asset_flows(
+        &[row("0x5::coin::X", 42, true), row(SUI, 1_000_000_000, true)],
+        &decimals(),
+    );
+
+    assert_eq!(flows.len(), 1);
+    assert_eq!(flows[&format!("0x{SUI}")].deposited, 1.0);
+}
+
+#[test]
+fn sums_over_the_i64_range_are_scaled() {
+    // two deposits of i64::MAX each, summed as NUMERIC
+    let amount = BigDecimal::from(i64::MAX) * BigDecimal::from(2);
+    let flows = asset_flows(
+        &[AssetFlowSum {
+            asset: SUI.to_string(),
+            amount,
+            deposit: true,
+        }],
+        &decimals(),
+    );
+
+    assert_eq!(
+        flows[&format!("0x{SUI}")].deposited,
+        2.0 * i64::MAX as f64 / 1e9
+    );
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use std::collections::HashMap;
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use deeplook_schema::models::OrderFill;
+use deeplook_schema::schema;
+use diesel::{ExpressionMethods, PgConnection, RunQueryDsl};
+use tower::ServiceExt;
+
+const POOL_ID: &str = "0xinterval_volume_test_pool";
+const POOL_NAME: &str = "INTERVAL_TEST_USDC";
+const BALANCE_MANAGER: &str = "0xinterval_test_manager";
+const HOUR_MS: i64 = 60 * 60 * 1000;
+// 2025-01-01T00:00:00Z
+const START_MS: i64 = 1_735_689_600_000;
+
+fn fill(i: i64, timestamp_ms: i64, base_quantity: i64, maker: &str, taker: &str) -> OrderFill {
+    OrderFill {
+        base_quantity,
+        quote_quantity: base_quantity,
+        maker_balance_manager_id: maker.to_string(),
+        taker_balance_manager_id: taker.to_string(),
+        ..common::fill(POOL_ID, i, timestamp_ms)
+    }
+}
+
+fn cleanup(conn: &mut PgConnection) {
+    diesel::delete(schema::order_fills::table)
+        .filter(schema::order_fills::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+    diesel::delete(schema::pools::table)
+        .filter(schema::pools::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn interval_volumes_cover_only_their_window() {
+    let mut conn = common::connect();
+    cleanup(&mut conn);
+
+    // one fill of the manager per hour, a fill on the boundary between the last two
+    // hours and a fill of another manager
+    let fills = vec![
+        fill(0, START_MS + 10 * 60 * 1000, 2, BALANCE_MANAGER, "0xother"),
+        fill(
+            1,
+            START_MS + HOUR_MS + 20 * 60 * 1000,
+            5,
+            "0xother",
+            BALANCE_MANAGER,
+        ),
+        fill(2, START_MS + 2 * HOUR_MS, 11, BALANCE_MANAGER, "0xother"),
+        fill(
+            3,
+            START_MS + 2 * HOUR_MS + 30 * 60 * 1000,
+            13,
+            "0xother",
+            "0xanother",
+        ),
+    ];
+    diesel::insert_into(schema::pools::table)
+        .values(&common::pool(POOL_ID, POOL_NAME))
+        .execute(&mut conn)
+        .unwrap();
+    diesel::insert_into(schema::order_fills::table)
+        .values(&fills)
+        .execute(&mut conn)
+        .unwrap();
+
+    let router = common::router(common::config()).await;
+
+    let uri = format!(
+        "/historical_volume_by_balance_manager_id_with_interval/{POOL_NAME}/{BALANCE_MANAGER}?start_time={}&end_time={}&interval=3600&volume_in_base=true",
+        START_MS / 1000,
+        (START_MS + 3 * HOUR_MS) / 1000
+    );
+    let response = router
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let volumes: HashMap<String, HashMap<String, Vec<i64>>> =
+        serde_json::from_slice(&body).unwrap();
+
+    cleanup(&mut conn);
+
+    let bucket = |hour: i64| {
+        let start = (START_MS + hour * HOUR_MS) / 1000;
+        volumes[&format!("[{}, {}]", start, start + HOUR_MS / 1000)][POOL_NAME].clone()
+    };
+    assert_eq!(volumes.len(), 3);
+    // [maker volume, taker volume]
+    assert_eq!(bucket(0), vec![2, 0]);
+    assert_eq!(bucket(1), vec![0, 5]);
+    assert_eq!(bucket(2), vec![11, 0]);
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use deeplook_schema::models::OrderFill;
+use deeplook_schema::schema;
+use deeplook_server::server::BALANCE_MANAGERS_QUERY;
+use diesel::sql_types::{BigInt, Text};
+use diesel::{sql_query, PgConnection, QueryableByName, RunQueryDsl};
+
+const POOL_ID: &str = "0xbalance_managers_test_pool";
+
+#[derive(QueryableByName)]
+struct Row {
+    #[diesel(sql_type = Text)]
+    balance_manager_id: String,
+}
+
+fn fill(i: i64, maker: &str, taker: &str) -> OrderFill {
+    OrderFill {
+        price: 1,
+        maker_balance_manager_id: maker.to_string(),
+        taker_balance_manager_id: taker.to_string(),
+        ..common::fill(POOL_ID, i, 1_735_689_600_000 + i * 1000)
+    }
+}
+
+fn load(conn: &mut PgConnection, after: &str, limit: i64) -> Vec<String> {
+    sql_query(BALANCE_MANAGERS_QUERY)
+        .bind::<Text, _>(POOL_ID)
+        .bind::<BigInt, _>(0)
+        .bind::<BigInt, _>(i64::MAX)
+        .bind::<Text, _>(after)
+        .bind::<BigInt, _>(limit)
+        .load::<Row>(conn)
+        .unwrap()
+        .into_iter()
+        .map(|row| row.balance_manager_id)
+        .collect()
+}
+
+#[test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+fn managers_on_both_sides_are_listed_once() {
+    let mut conn = common::connect();
+
+    conn.test_transaction::<_, diesel::result::Error, _>(|conn| {
+        let fills = vec![
+            fill(0, "0xa", "0xb"),
+            fill(1, "0xb", "0xc"),
+            fill(2, "0xc", "0xa"),
+            fill(3, "0xa", "0xb"),
+        ];
+        diesel::insert_into(schema::order_fills::table)
+            .values(&fills)
+            .execute(conn)?;
+
+        assert_eq!(load(conn, "", 10), vec!["0xa", "0xb", "0xc"]);
+
+        // pages continue after the last id of the previous page
+        assert_eq!(load(conn, "", 2), vec!["0xa", "0xb"]);
+        assert_eq!(load(conn, "0xb", 2), vec!["0xc"]);
+        Ok(())
+    });
+}
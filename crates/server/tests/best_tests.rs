@@ -0,0 +1,29 @@
+use deeplook_server::server::{best_quote, BestQuote};
+use serde_json::json;
+
+#[test]
+fn best_levels_of_unsorted_book() {
+    let orderbook = json!({
+        "asks": [{"price": 1.3, "size": 5.0}, {"price": 1.1, "size": 2.0}],
+        "bids": [{"price": 0.9, "size": 4.0}, {"price": 1.0, "size": 3.0}],
+    });
+
+    let quote = best_quote(orderbook).unwrap();
+
+    assert_eq!(quote.bid_price, 1.0);
+    assert_eq!(quote.bid_size, 3.0);
+    assert_eq!(quote.ask_price, 1.1);
+    assert_eq!(quote.ask_size, 2.0);
+    assert!((quote.spread - 0.1).abs() < 1e-9);
+    assert!((quote.mid - 1.05).abs() < 1e-9);
+}
+
+#[test]
+fn empty_side_has_no_quote() {
+    let orderbook = json!({
+        "asks": [],
+        "bids": [{"price": 1.0, "size": 3.0}],
+    });
+
+    assert_eq!(best_quote(orderbook), None::<BestQuote>);
+}
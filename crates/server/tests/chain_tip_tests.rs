@@ -0,0 +1,62 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use deeplook_server::server::{make_router, CHAIN_TIP_PATH};
+use deeplook_utils::checkpoint::{CheckpointDigest, StubChain};
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn chain_tip_comes_from_the_shared_digest() {
+    let latest = CheckpointDigest::get_latest(&StubChain { latest: 42 })
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::to_value(&latest).unwrap(),
+        serde_json::json!({
+            "epoch": "0",
+            "sequenceNumber": "42",
+            "timestampMs": "1735689642000",
+        })
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB and network access, set DATABASE_URL"]
+async fn chain_tip_serves_the_latest_checkpoint() {
+    common::connect();
+    let state = common::state(common::REDIS_URL, common::config()).await;
+    let router = make_router(Arc::new(state));
+
+    let response = router
+        .oneshot(Request::get(CHAIN_TIP_PATH).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let tip: CheckpointDigest = serde_json::from_slice(&body).unwrap();
+    assert!(tip.sequence_number > 0);
+    assert!(tip.timestamp_ms > 1_735_689_600_000);
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn chain_tip_fails_without_a_full_node() {
+    common::connect();
+    let state = common::state_with_rpc(
+        common::REDIS_URL,
+        common::UNREACHABLE_RPC_URL,
+        common::config(),
+    )
+    .await;
+    let router = make_router(Arc::new(state));
+
+    let response = router
+        .oneshot(Request::get(CHAIN_TIP_PATH).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert!(response.status().is_server_error());
+}
@@ -0,0 +1,123 @@
+//! Fixtures shared by the integration tests, declared with `mod common;` where needed. Tests
+//! override the fields they check with struct update syntax.
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use axum::Router;
+use chrono::DateTime;
+use deeplook_schema::models::{OrderFill, Pool};
+use deeplook_schema::MIGRATIONS;
+use deeplook_server::config::ServerConfig;
+use deeplook_server::server::{make_router, AppState, SUI_MAINNET_URL};
+use diesel::{Connection, PgConnection};
+use diesel_migrations::MigrationHarness;
+use prometheus::Registry;
+use sui_pg_db::DbArgs;
+use url::Url;
+
+pub const REDIS_URL: &str = "redis://localhost:6379";
+
+/// Nothing listens here, for tests of an unreachable Redis
+pub const UNREACHABLE_REDIS_URL: &str = "redis://127.0.0.1:1";
+
+/// Nothing listens here, for tests of an unreachable full node
+pub const UNREACHABLE_RPC_URL: &str = "http://127.0.0.1:1";
+
+/// Pool with the assets and decimals of SUI_USDC
+pub fn pool(pool_id: &str, pool_name: &str) -> Pool {
+    Pool {
+        pool_id: pool_id.to_string(),
+        pool_name: pool_name.to_string(),
+        base_asset_id: "0x2::sui::SUI".to_string(),
+        base_asset_decimals: 9,
+        base_asset_symbol: "SUI".to_string(),
+        base_asset_name: "Sui".to_string(),
+        quote_asset_id: "0xusdc::usdc::USDC".to_string(),
+        quote_asset_decimals: 6,
+        quote_asset_symbol: "USDC".to_string(),
+        quote_asset_name: "USDC".to_string(),
+        min_size: 1,
+        lot_size: 1,
+        tick_size: 1,
+    }
+}
+
+/// `i`th fill of the pool at checkpoint `i`, a buy of 1 at price 1_000_000 between
+/// `0xmaker` and `0xtaker`
+pub fn fill(pool_id: &str, i: i64, timestamp_ms: i64) -> OrderFill {
+    OrderFill {
+        event_digest: format!("{pool_id}_{i}"),
+        digest: format!("digest_{i}"),
+        sender: "0x0".to_string(),
+        checkpoint: i,
+        checkpoint_timestamp_ms: timestamp_ms,
+        timestamp: DateTime::from_timestamp_millis(timestamp_ms)
+            .unwrap()
+            .naive_utc(),
+        package: "0x0".to_string(),
+        pool_id: pool_id.to_string(),
+        maker_order_id: i.to_string(),
+        taker_order_id: (i + 1).to_string(),
+        maker_client_order_id: 0,
+        taker_client_order_id: 0,
+        price: 1_000_000,
+        taker_fee: 0,
+        taker_fee_is_deep: false,
+        maker_fee: 0,
+        maker_fee_is_deep: false,
+        taker_is_bid: true,
+        base_quantity: 1,
+        quote_quantity: 1,
+        maker_balance_manager_id: "0xmaker".to_string(),
+        taker_balance_manager_id: "0xtaker".to_string(),
+        onchain_timestamp: timestamp_ms,
+    }
+}
+
+/// Default config without background pings, caches of onchain books, the concurrency limit
+/// and query timeouts
+pub fn config() -> ServerConfig {
+    ServerConfig {
+        orderbook_rpc_cache_ttl_ms: 0,
+        ws_heartbeat_interval_secs: 0,
+        redis_ping_interval_secs: 0,
+        heavy_route_concurrency: 0,
+        db_query_timeout_ms: 0,
+        ..Default::default()
+    }
+}
+
+pub fn database_url() -> String {
+    std::env::var("DATABASE_URL").expect("DATABASE_URL must be set")
+}
+
+/// Connection to `DATABASE_URL` with the migrations applied
+pub fn connect() -> PgConnection {
+    let mut conn = PgConnection::establish(&database_url()).unwrap();
+    conn.run_pending_migrations(MIGRATIONS).unwrap();
+    conn
+}
+
+pub async fn state(redis_url: &str, config: ServerConfig) -> AppState {
+    state_with_rpc(redis_url, SUI_MAINNET_URL, config).await
+}
+
+pub async fn state_with_rpc(redis_url: &str, rpc_url: &str, config: ServerConfig) -> AppState {
+    AppState::new(
+        Url::parse(&database_url()).unwrap(),
+        DbArgs::default(),
+        &Registry::new(),
+        Url::parse(redis_url).unwrap(),
+        Url::parse(rpc_url).unwrap(),
+        config,
+    )
+    .await
+    .unwrap()
+}
+
+/// Router of a server using `DATABASE_URL` and Redis at [`REDIS_URL`]
+pub async fn router(config: ServerConfig) -> Router {
+    let state = state(REDIS_URL, config).await;
+    make_router(Arc::new(state))
+}
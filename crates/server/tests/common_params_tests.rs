@@ -0,0 +1,90 @@
+use axum::extract::FromRequestParts;
+use axum::http::Request;
+use deeplook_server::error::DeepBookError;
+use deeplook_server::params::CommonParams;
+use deeplook_server::server::ParameterUtil;
+use std::collections::HashMap;
+
+async fn extract(uri: &str) -> Result<CommonParams, DeepBookError> {
+    let (mut parts, _) = Request::builder().uri(uri).body(()).unwrap().into_parts();
+    CommonParams::from_request_parts(&mut parts, &()).await
+}
+
+#[tokio::test]
+async fn parses_shared_parameters() {
+    let params =
+        extract("/trades/SUI_USDC?start_time=100&end_time=200&limit=5&volume_in_base=true")
+            .await
+            .unwrap();
+
+    assert_eq!(params.start_time_ms(), Some(100_000));
+    assert_eq!(params.end_time_ms(), 200_000);
+    assert_eq!(params.limit, Some(5));
+    assert_eq!(params.volume_in_base, Some(true));
+}
+
+#[tokio::test]
+async fn parses_exclude_self_trades() {
+    let params = extract("/trades/SUI_USDC?exclude_self_trades=true")
+        .await
+        .unwrap();
+    assert_eq!(params.exclude_self_trades, Some(true));
+
+    let params = extract("/trades/SUI_USDC").await.unwrap();
+    assert_eq!(params.exclude_self_trades, None);
+}
+
+#[tokio::test]
+async fn missing_parameters_are_none() {
+    let params = extract("/trades/SUI_USDC").await.unwrap();
+
+    assert_eq!(params, CommonParams::default());
+    assert!(params.end_time_ms() > 0);
+}
+
+#[tokio::test]
+async fn endpoint_parameters_are_ignored() {
+    let params = extract("/order_fills/SUI_USDC?order=asc&format=csv&limit=10")
+        .await
+        .unwrap();
+
+    assert_eq!(params.limit, Some(10));
+}
+
+#[tokio::test]
+async fn malformed_values_are_bad_requests() {
+    for uri in [
+        "/trades/SUI_USDC?limit=ten",
+        "/trades/SUI_USDC?start_time=yesterday",
+        "/trades/SUI_USDC?volume_in_base=yes",
+        "/trades/SUI_USDC?exclude_self_trades=1",
+        "/trades/SUI_USDC?start_time=9223372036854775807",
+        "/trades/SUI_USDC?end_time=-9223372036854775807",
+    ] {
+        let err = extract(uri).await.unwrap_err();
+        assert!(matches!(err, DeepBookError::BadRequest(_)), "{}", uri);
+    }
+}
+
+fn endpoint_params(name: &str, value: &str) -> HashMap<String, String> {
+    HashMap::from([(name.to_string(), value.to_string())])
+}
+
+#[test]
+fn days_and_epoch_are_parsed_strictly() {
+    assert_eq!(HashMap::<String, String>::new().days().unwrap(), 1);
+    assert_eq!(endpoint_params("days", "7").days().unwrap(), 7);
+    for days in ["0", "-1", "week", "9223372036854775807"] {
+        assert!(matches!(
+            endpoint_params("days", days).days(),
+            Err(DeepBookError::BadRequest(_))
+        ));
+    }
+
+    assert_eq!(HashMap::<String, String>::new().epoch().unwrap(), None);
+    assert_eq!(endpoint_params("epoch", "812").epoch().unwrap(), Some(812));
+    assert!(matches!(
+        endpoint_params("epoch", "latest").epoch(),
+        Err(DeepBookError::BadRequest(_))
+    ));
+}
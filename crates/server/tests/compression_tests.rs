@@ -0,0 +1,86 @@
+mod common;
+
+use axum::body::{to_bytes, Body};
+use axum::http::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
+use axum::http::{Request, StatusCode};
+use axum::routing::get;
+use axum::{Json, Router};
+use deeplook_server::server::compression_layer;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+/// `/summary` shaped payload of 100 pools, about 33 KB of JSON
+fn summary_payload() -> Value {
+    let rows: Vec<Value> = (0..100)
+        .map(|i| {
+            let price = 1.0 + i as f64 * 0.137;
+            json!({
+                "trading_pairs": format!("TOKEN{i}_USDC"),
+                "base_currency": format!("TOKEN{i}"),
+                "quote_currency": "USDC",
+                "last_price": price,
+                "lowest_price_24h": price * 0.93,
+                "highest_price_24h": price * 1.07,
+                "base_volume": 1_000_000.0 / price,
+                "quote_volume": 1_000_000.0 + i as f64,
+                "price_change_percent_24h": i as f64 * 0.01 - 0.5,
+                "highest_bid": price * 0.999,
+                "lowest_ask": price * 1.001,
+            })
+        })
+        .collect();
+    Value::from(rows)
+}
+
+async fn body_size(
+    router: Router,
+    uri: &str,
+    accept_encoding: Option<&str>,
+) -> (usize, Option<String>) {
+    let mut request = Request::builder().uri(uri);
+    if let Some(encoding) = accept_encoding {
+        request = request.header(ACCEPT_ENCODING, encoding);
+    }
+    let response = router
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let encoding = response
+        .headers()
+        .get(CONTENT_ENCODING)
+        .map(|value| value.to_str().unwrap().to_string());
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (body.len(), encoding)
+}
+
+#[tokio::test]
+async fn summary_payload_is_gzipped_when_accepted() {
+    let router = Router::new()
+        .route("/summary", get(|| async { Json(summary_payload()) }))
+        .layer(compression_layer());
+
+    let (plain, encoding) = body_size(router.clone(), "/summary", None).await;
+    assert_eq!(encoding, None);
+    assert!((30_000..36_000).contains(&plain), "{plain} bytes plain");
+
+    let (gzipped, encoding) = body_size(router, "/summary", Some("gzip")).await;
+    assert_eq!(encoding.as_deref(), Some("gzip"));
+
+    // about 5.4 KB, the repeated keys compress well
+    assert!(gzipped * 4 < plain, "{gzipped} of {plain} bytes gzipped");
+}
+
+#[tokio::test]
+#[ignore = "requires an indexed database and RPC, set DATABASE_URL"]
+async fn summary_is_compressed_by_the_router() {
+    let router = common::router(common::config()).await;
+
+    let (plain, _) = body_size(router.clone(), "/summary", None).await;
+    let (compressed, encoding) = body_size(router, "/summary", Some("br, gzip")).await;
+    assert!(encoding.is_some());
+    assert!(
+        compressed * 2 < plain,
+        "{compressed} of {plain} bytes {encoding:?}"
+    );
+}
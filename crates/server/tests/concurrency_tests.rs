@@ -0,0 +1,53 @@
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::middleware::from_fn_with_state;
+use axum::routing::get;
+use axum::Router;
+use deeplook_server::concurrency::{limit_concurrency, ConcurrencyLimit};
+use tower::ServiceExt;
+
+fn router(limit: ConcurrencyLimit) -> Router {
+    let heavy = Router::new()
+        .route("/trades", get(|| async { "trades" }))
+        .route_layer(from_fn_with_state(limit, limit_concurrency));
+    Router::new()
+        .route("/get_pools", get(|| async { "pools" }))
+        .merge(heavy)
+}
+
+async fn status(router: &Router, uri: &str) -> StatusCode {
+    router
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn saturated_limit_rejects_heavy_routes_only() {
+    let limit = ConcurrencyLimit::new(1);
+    let router = router(limit.clone());
+
+    // a request in flight holds the only permit
+    let in_flight = limit.try_acquire().unwrap();
+    assert!(in_flight.is_some());
+
+    assert_eq!(
+        status(&router, "/trades").await,
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+    assert_eq!(status(&router, "/get_pools").await, StatusCode::OK);
+
+    drop(in_flight);
+    assert_eq!(status(&router, "/trades").await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn zero_disables_the_limit() {
+    let limit = ConcurrencyLimit::new(0);
+    let router = router(limit.clone());
+
+    assert!(limit.try_acquire().unwrap().is_none());
+    assert_eq!(status(&router, "/trades").await, StatusCode::OK);
+}
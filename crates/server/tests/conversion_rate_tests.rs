@@ -0,0 +1,58 @@
+mod common;
+
+use deeplook_schema::models::Pool;
+use deeplook_server::server::{deep_price_side, price_scaling_factor};
+
+const DEEP: &str = "0xdeep::deep::DEEP";
+const SUI: &str = "0x2::sui::SUI";
+const USDC: &str = "0xusdc::usdc::USDC";
+const WAL: &str = "0xwal::wal::WAL";
+
+fn pool(name: &str, base: (&str, i16), quote: (&str, i16)) -> Pool {
+    Pool {
+        base_asset_id: base.0.to_string(),
+        base_asset_decimals: base.1,
+        base_asset_symbol: name.split('_').next().unwrap().to_string(),
+        quote_asset_id: quote.0.to_string(),
+        quote_asset_decimals: quote.1,
+        quote_asset_symbol: name.split('_').nth(1).unwrap().to_string(),
+        ..common::pool(&format!("0x{}", name.to_lowercase()), name)
+    }
+}
+
+#[test]
+fn reference_quote_matches_target_base() {
+    let target = pool("SUI_USDC", (SUI, 9), (USDC, 6));
+    let reference = pool("DEEP_SUI", (DEEP, 6), (SUI, 9));
+    assert_eq!(deep_price_side(&target, &reference), Some((true, 6)));
+}
+
+#[test]
+fn reference_quote_matches_target_quote() {
+    let target = pool("WAL_USDC", (WAL, 9), (USDC, 6));
+    let reference = pool("DEEP_USDC", (DEEP, 6), (USDC, 6));
+    assert_eq!(deep_price_side(&target, &reference), Some((false, 6)));
+}
+
+#[test]
+fn deep_can_be_reference_quote() {
+    let target = pool("SUI_USDC", (SUI, 9), (USDC, 6));
+    let reference = pool("SUI_DEEP", (SUI, 9), (DEEP, 6));
+    assert_eq!(deep_price_side(&target, &reference), Some((true, 6)));
+}
+
+#[test]
+fn unrelated_reference_has_no_side() {
+    let target = pool("WAL_SUI", (WAL, 9), (SUI, 9));
+    let reference = pool("DEEP_USDC", (DEEP, 6), (USDC, 6));
+    assert_eq!(deep_price_side(&target, &reference), None);
+}
+
+#[test]
+fn rate_is_deep_per_asset_unit() {
+    // 32.23 DEEP per SUI and 6.63 DEEP per USDC
+    let per_sui = 32_226_877f64 / price_scaling_factor(9, 6).unwrap();
+    let per_usdc = 6_631_959_412f64 / price_scaling_factor(6, 6).unwrap();
+    assert!((per_sui - 32.226877).abs() < 1e-9);
+    assert!((per_usdc - 6.631959412).abs() < 1e-9);
+}
@@ -0,0 +1,54 @@
+use axum::body::Body;
+use axum::http::header::{ACCESS_CONTROL_ALLOW_ORIGIN, ORIGIN};
+use axum::http::{HeaderValue, Request};
+use axum::routing::get;
+use axum::Router;
+use deeplook_server::server::cors_layer;
+use tower::ServiceExt;
+
+async fn allowed_origin(allowed: &[&'static str], origin: &'static str) -> Option<HeaderValue> {
+    let allowed: Vec<HeaderValue> = allowed
+        .iter()
+        .map(|origin| HeaderValue::from_static(origin))
+        .collect();
+    let router = Router::new()
+        .route("/", get(|| async { "ok" }))
+        .layer(cors_layer(&allowed));
+
+    let response = router
+        .oneshot(
+            Request::get("/")
+                .header(ORIGIN, origin)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    response.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).cloned()
+}
+
+#[tokio::test]
+async fn any_origin_is_allowed_without_allowlist() {
+    assert_eq!(
+        allowed_origin(&[], "https://anyone.example.com").await,
+        Some(HeaderValue::from_static("*"))
+    );
+}
+
+#[tokio::test]
+async fn listed_origin_is_allowed() {
+    let allowed = ["https://app.example.com", "https://beta.example.com"];
+    assert_eq!(
+        allowed_origin(&allowed, "https://beta.example.com").await,
+        Some(HeaderValue::from_static("https://beta.example.com"))
+    );
+}
+
+#[tokio::test]
+async fn unlisted_origin_is_not_allowed() {
+    let allowed = ["https://app.example.com"];
+    assert_eq!(
+        allowed_origin(&allowed, "https://evil.example.com").await,
+        None
+    );
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use axum::http::{header, HeaderMap, HeaderValue};
+use deeplook_server::csv::{to_csv, wants_csv};
+use serde_json::{json, Value};
+
+fn row(values: Value) -> HashMap<String, Value> {
+    serde_json::from_value(values).unwrap()
+}
+
+fn params(format: Option<&str>) -> HashMap<String, String> {
+    format
+        .map(|format| HashMap::from([("format".to_string(), format.to_string())]))
+        .unwrap_or_default()
+}
+
+fn accept(value: &'static str) -> HeaderMap {
+    HeaderMap::from_iter([(header::ACCEPT, HeaderValue::from_static(value))])
+}
+
+#[test]
+fn header_has_sorted_columns() {
+    let rows = vec![
+        row(json!({"price": 1.5, "digest": "a", "is_bid": true})),
+        row(json!({"price": 2, "digest": "b", "is_bid": false})),
+    ];
+
+    assert_eq!(
+        to_csv(&rows),
+        "digest,is_bid,price\r\na,true,1.5\r\nb,false,2\r\n"
+    );
+}
+
+#[test]
+fn missing_and_null_values_are_empty() {
+    let rows = vec![row(json!({"a": 1, "b": null})), row(json!({"b": "x"}))];
+
+    assert_eq!(to_csv(&rows), "a,b\r\n1,\r\n,x\r\n");
+}
+
+#[test]
+fn special_characters_are_quoted() {
+    let rows = vec![row(
+        json!({"note": "a,b", "quote": "say \"hi\"", "plain": "c"}),
+    )];
+
+    assert_eq!(
+        to_csv(&rows),
+        "note,plain,quote\r\n\"a,b\",c,\"say \"\"hi\"\"\"\r\n"
+    );
+}
+
+#[test]
+fn no_rows_give_empty_header() {
+    assert_eq!(to_csv(&[]), "\r\n");
+}
+
+#[test]
+fn format_parameter_selects_output() {
+    assert!(wants_csv(&params(Some("csv")), &HeaderMap::new()).unwrap());
+    assert!(!wants_csv(&params(Some("json")), &accept("text/csv")).unwrap());
+    assert!(wants_csv(&params(Some("xml")), &HeaderMap::new()).is_err());
+}
+
+#[test]
+fn accept_header_selects_csv() {
+    assert!(!wants_csv(&params(None), &HeaderMap::new()).unwrap());
+    assert!(!wants_csv(&params(None), &accept("application/json")).unwrap());
+    assert!(wants_csv(&params(None), &accept("text/csv")).unwrap());
+    assert!(wants_csv(
+        &params(None),
+        &accept("application/json;q=0.5, text/csv; charset=utf-8")
+    )
+    .unwrap());
+}
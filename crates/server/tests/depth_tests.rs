@@ -0,0 +1,58 @@
+use deeplook_server::error::DeepBookError;
+use deeplook_server::server::parse_depth;
+
+fn ticks(depth: Option<&str>, level: Option<&str>) -> Result<u64, DeepBookError> {
+    parse_depth(depth, level, 200)
+}
+
+#[test]
+fn zero_full_and_missing_depth_are_the_whole_book() {
+    assert_eq!(ticks(None, None).unwrap(), 100);
+    assert_eq!(ticks(Some("0"), None).unwrap(), 100);
+    assert_eq!(ticks(Some("full"), None).unwrap(), 100);
+    assert_eq!(parse_depth(Some("full"), None, 50).unwrap(), 25);
+}
+
+#[test]
+fn depth_counts_levels_of_both_sides_up_to_the_max() {
+    assert_eq!(ticks(Some("2"), None).unwrap(), 1);
+    assert_eq!(ticks(Some("20"), Some("2")).unwrap(), 10);
+    assert_eq!(ticks(Some("200"), None).unwrap(), 100);
+}
+
+#[test]
+fn depth_over_the_max_is_rejected() {
+    assert!(matches!(
+        ticks(Some("201"), None),
+        Err(DeepBookError::BadRequest(_))
+    ));
+}
+
+#[test]
+fn depth_of_one_is_rejected() {
+    assert!(matches!(
+        ticks(Some("1"), None),
+        Err(DeepBookError::BadRequest(_))
+    ));
+}
+
+#[test]
+fn invalid_depth_and_level_are_rejected() {
+    for (depth, level) in [
+        (Some("-2"), None),
+        (Some("all"), None),
+        (None, Some("3")),
+        (None, Some("two")),
+    ] {
+        assert!(matches!(
+            ticks(depth, level),
+            Err(DeepBookError::BadRequest(_))
+        ));
+    }
+}
+
+#[test]
+fn level_one_is_the_best_bid_and_ask() {
+    assert_eq!(ticks(None, Some("1")).unwrap(), 1);
+    assert_eq!(ticks(Some("50"), Some("1")).unwrap(), 1);
+}
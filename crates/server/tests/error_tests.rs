@@ -0,0 +1,57 @@
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use deeplook_server::error::{DeepBookError, ErrorCode};
+
+#[test]
+fn diesel_errors_become_db_errors() {
+    let err = DeepBookError::from(anyhow::Error::new(diesel::result::Error::NotFound));
+
+    assert!(matches!(err, DeepBookError::Db(_)));
+    assert_eq!(err.code(), "db_error");
+}
+
+#[test]
+fn full_node_errors_become_rpc_errors() {
+    let err = DeepBookError::from(sui_sdk::error::Error::DataError("no object".to_string()));
+
+    assert!(matches!(err, DeepBookError::Rpc(_)));
+    assert_eq!(
+        err.clone().into_response().status(),
+        StatusCode::BAD_GATEWAY
+    );
+}
+
+#[test]
+fn decoding_errors_become_deserialize_errors() {
+    let json = serde_json::from_str::<u64>("\"x\"").unwrap_err();
+    assert!(matches!(
+        DeepBookError::from(json),
+        DeepBookError::Deserialize(_)
+    ));
+
+    let bcs = bcs::from_bytes::<u64>(&[1, 2]).unwrap_err();
+    assert!(matches!(
+        DeepBookError::from(bcs),
+        DeepBookError::Deserialize(_)
+    ));
+}
+
+#[test]
+fn redis_errors_become_cache_errors() {
+    let redis = redis::RedisError::from((redis::ErrorKind::IoError, "connection refused"));
+    let err = DeepBookError::from(redis);
+
+    assert!(matches!(err, DeepBookError::Cache(_)));
+    assert_eq!(err.code(), "cache_error");
+}
+
+#[test]
+fn response_carries_error_code() {
+    let response = DeepBookError::Cache("redis down".to_string()).into_response();
+
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(
+        response.extensions().get::<ErrorCode>(),
+        Some(&ErrorCode("cache_error"))
+    );
+}
@@ -0,0 +1,51 @@
+use bigdecimal::BigDecimal;
+use deeplook_server::aggregations::{split_fees, FeeSplit};
+use serde_json::json;
+
+fn raw(value: i64) -> Option<BigDecimal> {
+    Some(BigDecimal::from(value))
+}
+
+fn split(deep: i64, base: i64, quote: i64) -> FeeSplit {
+    FeeSplit {
+        deep: BigDecimal::from(deep),
+        base: BigDecimal::from(base),
+        quote: BigDecimal::from(quote),
+    }
+}
+
+#[test]
+fn deep_fees_are_split_from_input_asset_fees() {
+    let (taker, maker) = split_fees(vec![
+        // buying takers pay quote, the asks they fill pay base
+        (true, false, false, raw(300), raw(100)),
+        (true, true, true, raw(50), raw(20)),
+        // selling takers pay base, the bids they fill pay quote
+        (false, false, false, raw(7), raw(3)),
+        (false, true, false, raw(40), raw(9)),
+    ]);
+
+    assert_eq!(taker, split(90, 7, 300));
+    assert_eq!(maker, split(20, 100, 12));
+}
+
+#[test]
+fn no_fills_have_no_fees() {
+    let (taker, maker) = split_fees(vec![]);
+    assert_eq!(taker, FeeSplit::default());
+    assert_eq!(maker, FeeSplit::default());
+
+    let (taker, _) = split_fees(vec![(true, false, false, None, None)]);
+    assert_eq!(taker, split(0, 0, 0));
+}
+
+#[test]
+fn fees_are_scaled_by_asset_decimals() {
+    // DEEP has 6 decimals, SUI 9 and USDC 6
+    let fees = split(1_500_000, 2_000_000_000, 250_000);
+
+    assert_eq!(
+        fees.to_json(9, 6),
+        json!({"deep": 1.5, "base": 2.0, "quote": 0.25})
+    );
+}
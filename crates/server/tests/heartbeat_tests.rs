@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use axum::extract::ws::Message;
+use deeplook_server::heartbeat::Heartbeat;
+use futures::channel::mpsc;
+use futures::StreamExt;
+
+const PERIOD: Duration = Duration::from_millis(20);
+
+#[tokio::test]
+async fn pings_after_each_period() {
+    let mut heartbeat = Heartbeat::new(PERIOD);
+    let (mut tx, mut rx) = mpsc::unbounded::<Message>();
+
+    let started = tokio::time::Instant::now();
+    heartbeat.tick().await;
+    assert!(started.elapsed() >= PERIOD);
+    assert!(heartbeat.ping(&mut tx).await);
+    assert_eq!(rx.next().await, Some(Message::Ping(vec![])));
+
+    heartbeat.received();
+    heartbeat.tick().await;
+    assert!(heartbeat.ping(&mut tx).await);
+}
+
+#[tokio::test]
+async fn unanswered_ping_ends_connection() {
+    let mut heartbeat = Heartbeat::new(PERIOD);
+    let (mut tx, _rx) = mpsc::unbounded::<Message>();
+
+    assert!(heartbeat.ping(&mut tx).await);
+    assert!(!heartbeat.ping(&mut tx).await);
+}
+
+#[tokio::test]
+async fn failed_ping_ends_connection() {
+    let mut heartbeat = Heartbeat::new(PERIOD);
+    let (mut tx, rx) = mpsc::unbounded::<Message>();
+    drop(rx);
+
+    assert!(!heartbeat.ping(&mut tx).await);
+}
+
+#[tokio::test]
+async fn zero_period_disables_heartbeat() {
+    let mut heartbeat = Heartbeat::new(Duration::ZERO);
+
+    let ticked = tokio::time::timeout(PERIOD * 3, heartbeat.tick()).await;
+    assert!(ticked.is_err());
+}
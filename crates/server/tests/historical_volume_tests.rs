@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+use deeplook_server::error::DeepBookError;
+use deeplook_server::server::resolve_pool_names;
+
+fn pool_name_to_id() -> HashMap<String, String> {
+    HashMap::from([
+        ("SUI_USDC".to_string(), "0x01".to_string()),
+        ("DEEP_SUI".to_string(), "0x02".to_string()),
+    ])
+}
+
+#[test]
+fn lenient_mode_drops_unknown_names() {
+    let ids = resolve_pool_names("SUI_USDC,SUI_USCD,DEEP_SUI", &pool_name_to_id(), false).unwrap();
+
+    assert_eq!(ids, vec!["0x01".to_string(), "0x02".to_string()]);
+}
+
+#[test]
+fn strict_mode_reports_unknown_names() {
+    let err =
+        resolve_pool_names("SUI_USDC,SUI_USCD,DEEP_SUI", &pool_name_to_id(), true).unwrap_err();
+
+    match err {
+        DeepBookError::BadRequest(message) => {
+            assert_eq!(message, "Unknown pool names: SUI_USCD");
+        }
+        other => panic!("expected BadRequest, got {:?}", other),
+    }
+}
+
+#[test]
+fn strict_mode_accepts_known_names() {
+    let ids = resolve_pool_names("DEEP_SUI", &pool_name_to_id(), true).unwrap();
+
+    assert_eq!(ids, vec!["0x02".to_string()]);
+}
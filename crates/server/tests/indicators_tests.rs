@@ -0,0 +1,54 @@
+use deeplook_server::aggregations::{moving_average, IndicatorPoint, MovingAverage};
+
+fn closes(values: &[f64]) -> Vec<(i64, f64)> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, close)| (i as i64 * 60, *close))
+        .collect()
+}
+
+fn values(points: &[IndicatorPoint]) -> Vec<f64> {
+    points.iter().map(|point| point.value).collect()
+}
+
+#[test]
+fn sma_averages_the_last_period_closes() {
+    let points = moving_average(&closes(&[1.0, 2.0, 3.0, 4.0, 5.0]), 3, MovingAverage::Sma);
+
+    assert_eq!(values(&points), vec![2.0, 3.0, 4.0]);
+    assert_eq!(points[0].timestamp, 120);
+    assert_eq!(points[2].timestamp, 240);
+}
+
+#[test]
+fn ema_starts_from_the_sma() {
+    let points = moving_average(&closes(&[2.0, 4.0, 6.0, 10.0]), 3, MovingAverage::Ema);
+
+    // alpha = 2 / (3 + 1) = 0.5
+    assert_eq!(values(&points), vec![4.0, 7.0]);
+    assert_eq!(points[1].timestamp, 180);
+}
+
+#[test]
+fn short_history_gives_no_points() {
+    assert!(moving_average(&closes(&[1.0, 2.0]), 3, MovingAverage::Sma).is_empty());
+    assert!(moving_average(&closes(&[]), 1, MovingAverage::Ema).is_empty());
+}
+
+#[test]
+fn period_of_one_returns_the_closes() {
+    let points = moving_average(&closes(&[1.5, 2.5]), 1, MovingAverage::Ema);
+
+    assert_eq!(values(&points), vec![1.5, 2.5]);
+}
+
+#[test]
+fn type_defaults_to_sma() {
+    assert_eq!(MovingAverage::parse(None).unwrap(), MovingAverage::Sma);
+    assert_eq!(
+        MovingAverage::parse(Some("ema")).unwrap(),
+        MovingAverage::Ema
+    );
+    assert!(MovingAverage::parse(Some("wma")).is_err());
+}
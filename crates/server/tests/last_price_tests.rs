@@ -0,0 +1,118 @@
+mod common;
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use deeplook_schema::models::OrderFill;
+use deeplook_schema::schema;
+use diesel::{ExpressionMethods, PgConnection, RunQueryDsl};
+use tower::ServiceExt;
+
+const POOL_ID: &str = "0xlast_price_test_pool";
+const POOL_NAME: &str = "LAST_PRICE_TEST_USDC";
+// 2025-01-01T00:00:00Z
+const START_MS: i64 = 1_735_689_600_000;
+
+fn fill(i: i64, timestamp_ms: i64, price: i64) -> OrderFill {
+    OrderFill {
+        price,
+        ..common::fill(POOL_ID, i, timestamp_ms)
+    }
+}
+
+fn cleanup(conn: &mut PgConnection) {
+    diesel::delete(schema::order_fills::table)
+        .filter(schema::order_fills::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+    diesel::delete(schema::pools::table)
+        .filter(schema::pools::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+}
+
+async fn last_price(router: &Router) -> Option<f64> {
+    let response = router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/last_price/{POOL_NAME}"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn last_price_is_the_latest_fill() {
+    let mut conn = common::connect();
+    cleanup(&mut conn);
+
+    diesel::insert_into(schema::pools::table)
+        .values(&common::pool(POOL_ID, POOL_NAME))
+        .execute(&mut conn)
+        .unwrap();
+
+    let router = common::router(common::config()).await;
+
+    let before_trades = last_price(&router).await;
+
+    // inserted out of order, the latest fill is the second one
+    let fills = vec![
+        fill(0, START_MS, 3_000_000),
+        fill(1, START_MS + 2_000, 3_500_000),
+        fill(2, START_MS + 1_000, 3_200_000),
+    ];
+    diesel::insert_into(schema::order_fills::table)
+        .values(&fills)
+        .execute(&mut conn)
+        .unwrap();
+
+    let after_trades = last_price(&router).await;
+
+    cleanup(&mut conn);
+
+    assert_eq!(before_trades, None);
+    assert_eq!(after_trades, Some(3.5));
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn last_price_is_the_last_fill_of_a_sweep() {
+    let mut conn = common::connect();
+    cleanup(&mut conn);
+
+    diesel::insert_into(schema::pools::table)
+        .values(&common::pool(POOL_ID, POOL_NAME))
+        .execute(&mut conn)
+        .unwrap();
+
+    // one taker sweeping twelve levels, every fill in the same checkpoint and millisecond. The
+    // last event has index 11, which sorts before 9 as text.
+    let sweep = |index: i64| OrderFill {
+        event_digest: format!("sweep_digest{index}"),
+        digest: "sweep_digest".to_string(),
+        checkpoint: 7,
+        ..fill(index, START_MS, 3_000_000 + index * 100_000)
+    };
+    let fills: Vec<_> = [5, 11, 0, 9, 3, 10, 1, 8, 2, 7, 4, 6]
+        .into_iter()
+        .map(sweep)
+        .collect();
+    diesel::insert_into(schema::order_fills::table)
+        .values(&fills)
+        .execute(&mut conn)
+        .unwrap();
+
+    let router = common::router(common::config()).await;
+    let price = last_price(&router).await;
+
+    cleanup(&mut conn);
+
+    assert_eq!(price, Some(4.1));
+}
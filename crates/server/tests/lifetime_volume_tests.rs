@@ -0,0 +1,111 @@
+mod common;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use chrono::Utc;
+use deeplook_schema::models::OrderFill;
+use deeplook_schema::schema;
+use deeplook_server::aggregations::{
+    lifetime_volume_cache_key, LifetimeVolume, ToDecimalFloat64, LIFETIME_VOLUME_RAW_QUERY,
+};
+use deeplook_server::config::ServerConfig;
+use deeplook_server::server::make_router;
+use diesel::sql_types::Text;
+use diesel::{sql_query, ExpressionMethods, PgConnection, RunQueryDsl};
+use tower::ServiceExt;
+
+const POOL_ID: &str = "0xlifetime_volume_test_pool";
+const POOL_NAME: &str = "LIFETIME_VOLUME_TEST_USDC";
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+fn fill(i: i64, timestamp_ms: i64, base_quantity: i64, quote_quantity: i64) -> OrderFill {
+    OrderFill {
+        base_quantity,
+        quote_quantity,
+        ..common::fill(POOL_ID, i, timestamp_ms)
+    }
+}
+
+fn cleanup(conn: &mut PgConnection) {
+    diesel::delete(schema::order_fills::table)
+        .filter(schema::order_fills::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+    diesel::delete(schema::pools::table)
+        .filter(schema::pools::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn lifetime_volume_matches_fill_total() {
+    let mut conn = common::connect();
+    cleanup(&mut conn);
+
+    let now = Utc::now().timestamp_millis();
+    let fills = vec![
+        fill(0, now - DAY_MS / 12, 1_000_000_000, 2_500_000),
+        fill(1, now - 40 * DAY_MS, 2_000_000_000, 5_000_000),
+        fill(2, now - 400 * DAY_MS, 4_000_000_000, 3_000_000),
+    ];
+    diesel::insert_into(schema::pools::table)
+        .values(common::pool(POOL_ID, POOL_NAME))
+        .execute(&mut conn)
+        .unwrap();
+    diesel::insert_into(schema::order_fills::table)
+        .values(&fills)
+        .execute(&mut conn)
+        .unwrap();
+
+    let direct = sql_query(LIFETIME_VOLUME_RAW_QUERY)
+        .bind::<Text, _>(POOL_ID)
+        .load::<LifetimeVolume>(&mut conn)
+        .unwrap()
+        .pop()
+        .unwrap();
+
+    let config = ServerConfig {
+        ohlcv_from_raw: true,
+        ..common::config()
+    };
+    let state = common::state(common::REDIS_URL, config).await;
+    // a volume cached by an earlier run would hide the query
+    let _ = state
+        .reader
+        .cache
+        .del(&lifetime_volume_cache_key(POOL_NAME))
+        .await;
+    let router = make_router(Arc::new(state));
+
+    let response = router
+        .oneshot(
+            Request::builder()
+                .uri(format!("/pool/{POOL_NAME}/lifetime_volume"))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let volume: HashMap<String, f64> = serde_json::from_slice(&body).unwrap();
+
+    cleanup(&mut conn);
+
+    assert_eq!(
+        volume["base_volume"],
+        direct.base_volume.to_decimal_f64(9).unwrap()
+    );
+    assert_eq!(
+        volume["quote_volume"],
+        direct.quote_volume.to_decimal_f64(6).unwrap()
+    );
+    let base_total: i64 = fills.iter().map(|fill| fill.base_quantity).sum();
+    let quote_total: i64 = fills.iter().map(|fill| fill.quote_quantity).sum();
+    assert_eq!(volume["base_volume"], base_total as f64 / 1e9);
+    assert_eq!(volume["quote_volume"], quote_total as f64 / 1e6);
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use chrono::{DateTime, NaiveDateTime};
+use deeplook_schema::models::{OrderFill, OHLCV};
+use deeplook_schema::schema;
+use deeplook_server::aggregations::{OHLCV_TIMEFRAMES, RAW_OHLCV_QUERY};
+use diesel::sql_types::{BigInt, Text, Timestamp};
+use diesel::{sql_query, PgConnection, RunQueryDsl};
+
+const POOL_ID: &str = "0xraw_ohlcv_test_pool";
+
+// Same aggregation as the ohlcv caggs, bucket filtered like the cagg endpoints
+const CAGG_OHLCV_QUERY: &str = r#"
+SELECT * FROM (
+    SELECT
+        time_bucket($1 * INTERVAL '1 second', timestamp) AS bucket,
+        pool_id,
+        FIRST(price, timestamp) AS open,
+        MAX(price)              AS high,
+        MIN(price)              AS low,
+        LAST(price, timestamp)  AS close,
+        SUM(base_quantity)      AS volume_base,
+        SUM(quote_quantity)     AS volume_quote
+    FROM order_fills
+    WHERE pool_id = $2
+    GROUP BY bucket, pool_id
+) ohlcv
+WHERE bucket BETWEEN $3 AND $4
+ORDER BY bucket
+"#;
+
+fn fill(i: i64, timestamp_ms: i64, price: i64, base_quantity: i64) -> OrderFill {
+    OrderFill {
+        price,
+        taker_is_bid: i % 2 == 0,
+        base_quantity,
+        quote_quantity: base_quantity * price,
+        ..common::fill(POOL_ID, i, timestamp_ms)
+    }
+}
+
+fn millis(ms: i64) -> NaiveDateTime {
+    DateTime::from_timestamp_millis(ms).unwrap().naive_utc()
+}
+
+fn load(
+    conn: &mut PgConnection,
+    query: &str,
+    bucket_seconds: i64,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+) -> Vec<OHLCV> {
+    sql_query(query)
+        .bind::<BigInt, _>(bucket_seconds)
+        .bind::<Text, _>(POOL_ID)
+        .bind::<Timestamp, _>(start)
+        .bind::<Timestamp, _>(end)
+        .load(conn)
+        .unwrap()
+}
+
+#[test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+fn raw_ohlcv_matches_cagg_aggregation() {
+    let mut conn = common::connect();
+
+    conn.test_transaction::<_, diesel::result::Error, _>(|conn| {
+        // 2025-01-01T00:00:00Z, fills spread over ~10 hours with irregular gaps
+        let start_ms = 1_735_689_600_000;
+        let fills: Vec<OrderFill> = (0..500)
+            .map(|i| {
+                let timestamp_ms = start_ms + i * 73_331 + (i % 7) * 1_009;
+                let price = 1_000_000 + (i * 7_919) % 50_000;
+                fill(i, timestamp_ms, price, 1 + i % 13)
+            })
+            .collect();
+        diesel::insert_into(schema::order_fills::table)
+            .values(&fills)
+            .execute(conn)?;
+
+        // range starting mid-bucket to cover partial buckets at both ends
+        let start = millis(start_ms + 17 * 60 * 1000 + 30_000);
+        let end = millis(start_ms + 8 * 60 * 60 * 1000 + 5_000);
+
+        for (label, bucket_ms) in OHLCV_TIMEFRAMES {
+            let raw = load(conn, RAW_OHLCV_QUERY, bucket_ms / 1000, start, end);
+            let cagg = load(conn, CAGG_OHLCV_QUERY, bucket_ms / 1000, start, end);
+            assert!(!raw.is_empty(), "no {label} candles");
+            assert_eq!(raw, cagg, "{label} candles differ");
+        }
+        Ok(())
+    });
+}
@@ -0,0 +1,66 @@
+use deeplook_server::aggregations::{
+    parse_ohlcv_fields, select_ohlcv_timeframe, OHLCV_MAX_POINTS, OHLCV_TIMEFRAMES,
+};
+
+const HOUR_MS: i64 = 60 * 60 * 1000;
+const DAY_MS: i64 = 24 * HOUR_MS;
+
+#[test]
+fn default_range_uses_finest_timeframe() {
+    assert_eq!(select_ohlcv_timeframe(0, DAY_MS, OHLCV_MAX_POINTS), "1m");
+}
+
+#[test]
+fn timeframe_fits_max_points() {
+    assert_eq!(
+        select_ohlcv_timeframe(0, 7 * DAY_MS, OHLCV_MAX_POINTS),
+        "15m"
+    );
+    assert_eq!(
+        select_ohlcv_timeframe(0, 60 * DAY_MS, OHLCV_MAX_POINTS),
+        "1h"
+    );
+    assert_eq!(
+        select_ohlcv_timeframe(0, 200 * DAY_MS, OHLCV_MAX_POINTS),
+        "4h"
+    );
+
+    for days in [1, 7, 30, 60, 200] {
+        let label = select_ohlcv_timeframe(0, days * DAY_MS, OHLCV_MAX_POINTS);
+        let (_, bucket_ms) = OHLCV_TIMEFRAMES.iter().find(|(l, _)| *l == label).unwrap();
+        assert!(days * DAY_MS / bucket_ms <= OHLCV_MAX_POINTS);
+    }
+}
+
+#[test]
+fn max_points_is_respected() {
+    assert_eq!(select_ohlcv_timeframe(0, DAY_MS, 24), "1h");
+    assert_eq!(select_ohlcv_timeframe(0, DAY_MS, 6), "4h");
+}
+
+#[test]
+fn too_long_range_falls_back_to_coarsest() {
+    assert_eq!(
+        select_ohlcv_timeframe(0, 10_000 * DAY_MS, OHLCV_MAX_POINTS),
+        "4h"
+    );
+}
+
+#[test]
+fn missing_fields_keep_all() {
+    assert_eq!(parse_ohlcv_fields(None).unwrap(), None);
+}
+
+#[test]
+fn fields_are_split_on_commas() {
+    assert_eq!(
+        parse_ohlcv_fields(Some("timestamp, close,volume_quote")).unwrap(),
+        Some(vec!["timestamp", "close", "volume_quote"])
+    );
+}
+
+#[test]
+fn unknown_fields_are_rejected() {
+    assert!(parse_ohlcv_fields(Some("timestamp,vwap")).is_err());
+    assert!(parse_ohlcv_fields(Some("")).is_err());
+}
@@ -0,0 +1,127 @@
+use bigdecimal::BigDecimal;
+use chrono::DateTime;
+use deeplook_schema::models::OHLCV;
+use deeplook_server::aggregations::{
+    candles_closed_by, completed_candles, parse_ohlcv_interval, OhlcvScale,
+};
+use deeplook_server::ohlcv_feed::OhlcvFeeds;
+use serde_json::Value;
+
+const MINUTE_MS: i64 = 60 * 1000;
+
+fn candle(start_ms: i64, close: i64) -> OHLCV {
+    OHLCV {
+        bucket: DateTime::from_timestamp_millis(start_ms)
+            .unwrap()
+            .naive_utc(),
+        pool_id: "0xpool".to_string(),
+        open: close,
+        high: close,
+        low: close,
+        close,
+        volume_base: BigDecimal::from(2_000_000_000),
+        volume_quote: BigDecimal::from(7_000_000),
+    }
+}
+
+fn starts(candles: &[OHLCV]) -> Vec<i64> {
+    candles
+        .iter()
+        .map(|candle| candle.bucket.and_utc().timestamp_millis())
+        .collect()
+}
+
+// volumes are decimal strings
+fn volume(value: &Value) -> f64 {
+    value.as_str().unwrap().parse().unwrap()
+}
+
+#[test]
+fn interval_defaults_to_one_minute() {
+    assert_eq!(parse_ohlcv_interval(None).unwrap(), ("1m", MINUTE_MS));
+    assert_eq!(
+        parse_ohlcv_interval(Some("4h")).unwrap(),
+        ("4h", 4 * 60 * MINUTE_MS)
+    );
+    assert!(parse_ohlcv_interval(Some("5m")).is_err());
+}
+
+#[test]
+fn only_closed_buckets_are_completed() {
+    let now = 10 * MINUTE_MS + 5_000;
+    let rows = vec![
+        candle(10 * MINUTE_MS, 4),
+        candle(8 * MINUTE_MS, 2),
+        candle(9 * MINUTE_MS, 3),
+    ];
+
+    assert_eq!(
+        starts(&completed_candles(rows, None, now, MINUTE_MS)),
+        vec![8 * MINUTE_MS, 9 * MINUTE_MS]
+    );
+}
+
+#[test]
+fn sent_buckets_are_skipped() {
+    let now = 11 * MINUTE_MS;
+    let rows = vec![candle(9 * MINUTE_MS, 3), candle(10 * MINUTE_MS, 4)];
+
+    assert_eq!(
+        starts(&completed_candles(
+            rows,
+            Some(9 * MINUTE_MS),
+            now,
+            MINUTE_MS
+        )),
+        vec![10 * MINUTE_MS]
+    );
+}
+
+#[test]
+fn lagging_index_holds_back_the_closed_bucket() {
+    let now = 11 * MINUTE_MS + 5_000;
+    // fills are indexed only up to the middle of the 10th minute
+    let closed_by = candles_closed_by(now, Some(10 * MINUTE_MS + 30_000)).unwrap();
+    let rows = vec![candle(9 * MINUTE_MS, 3), candle(10 * MINUTE_MS, 4)];
+
+    assert_eq!(
+        starts(&completed_candles(rows, None, closed_by, MINUTE_MS)),
+        vec![9 * MINUTE_MS]
+    );
+    assert_eq!(candles_closed_by(now, Some(12 * MINUTE_MS)), Some(now));
+    assert_eq!(candles_closed_by(now, None), None);
+}
+
+#[test]
+fn sockets_of_a_pool_and_interval_share_one_feed() {
+    let feeds = OhlcvFeeds::default();
+    let key = ("0xpool".to_string(), "1m");
+
+    let (first, sender) = feeds.subscribe(key.clone());
+    assert!(sender.is_some());
+    let (second, sender) = feeds.subscribe(key.clone());
+    assert!(sender.is_none());
+    let (_other, sender) = feeds.subscribe(("0xpool".to_string(), "5m"));
+    assert!(sender.is_some());
+
+    assert!(!feeds.close_if_unused(&key));
+    drop(first);
+    assert!(!feeds.close_if_unused(&key));
+    drop(second);
+    assert!(feeds.close_if_unused(&key));
+    // the next socket starts a new poller
+    assert!(feeds.subscribe(key).1.is_some());
+}
+
+#[test]
+fn candles_are_scaled_like_ohlcv() {
+    // SUI_USDC
+    let scale = OhlcvScale::new(9, 6).unwrap();
+    let candle = scale.candle(candle(MINUTE_MS, 3_500_000), "1m");
+
+    assert_eq!(candle["timestamp"], Value::from(60));
+    assert_eq!(candle["close"], Value::from(3.5));
+    assert_eq!(volume(&candle["volume_base"]), 2.0);
+    assert_eq!(volume(&candle["volume_quote"]), 7.0);
+    assert_eq!(candle["interval"], Value::from("1m"));
+}
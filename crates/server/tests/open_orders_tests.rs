@@ -0,0 +1,175 @@
+mod common;
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use chrono::DateTime;
+use deeplook_schema::models::{OrderUpdate, OrderUpdateStatus};
+use deeplook_schema::schema;
+use deeplook_server::server::{filter_open_orders, LatestOrderUpdate};
+use diesel::{ExpressionMethods, PgConnection, RunQueryDsl};
+use serde_json::Value;
+use tower::ServiceExt;
+
+const POOL_ID: &str = "0xopen_orders_test_pool";
+const POOL_NAME: &str = "OPEN_ORDERS_TEST_USDC";
+const BALANCE_MANAGER: &str = "0xopen_orders_test_manager";
+// 2025-01-01T00:00:00Z
+const START_MS: i64 = 1_735_689_600_000;
+
+fn update(order_id: &str, checkpoint: i64, quantity: i64, status: &str) -> LatestOrderUpdate {
+    LatestOrderUpdate {
+        order_id: order_id.to_string(),
+        price: 1_000,
+        original_quantity: quantity,
+        quantity,
+        filled_quantity: 0,
+        checkpoint,
+        checkpoint_timestamp_ms: checkpoint * 1000,
+        is_bid: true,
+        balance_manager_id: "0xmanager".to_string(),
+        status: status.to_string(),
+    }
+}
+
+fn order_ids(open: &[(LatestOrderUpdate, i64)]) -> Vec<&str> {
+    open.iter()
+        .map(|(update, _)| update.order_id.as_str())
+        .collect()
+}
+
+#[test]
+fn canceled_and_expired_orders_are_not_open() {
+    // the reader returns the latest update per order, "1" was placed then canceled
+    let latest = vec![
+        update("1", 2, 100, "Canceled"),
+        update("2", 1, 100, "Placed"),
+        update("3", 3, 100, "Expired"),
+        update("4", 4, 100, "Modified"),
+    ];
+
+    let open = filter_open_orders(latest, &[]);
+
+    assert_eq!(order_ids(&open), vec!["4", "2"]);
+    assert_eq!(open[0].1, 100);
+}
+
+#[test]
+fn maker_fills_reduce_remaining_quantity() {
+    let latest = vec![update("1", 1, 100, "Placed"), update("2", 1, 100, "Placed")];
+    let fills = vec![
+        ("1".to_string(), 2, 40),
+        ("2".to_string(), 2, 60),
+        ("2".to_string(), 3, 40),
+    ];
+
+    let open = filter_open_orders(latest, &fills);
+
+    assert_eq!(order_ids(&open), vec!["1"]);
+    assert_eq!(open[0].1, 60);
+}
+
+#[test]
+fn fills_before_modification_are_in_its_filled_quantity() {
+    let mut modified = update("1", 5, 100, "Modified");
+    modified.filled_quantity = 30;
+    let fills = vec![("1".to_string(), 3, 30), ("1".to_string(), 6, 20)];
+
+    let open = filter_open_orders(vec![modified], &fills);
+
+    assert_eq!(open[0].1, 50);
+}
+
+fn stored_update(
+    event: &str,
+    order_id: &str,
+    timestamp_ms: i64,
+    status: OrderUpdateStatus,
+) -> OrderUpdate {
+    OrderUpdate {
+        event_digest: format!("open_orders_test_{event}"),
+        digest: format!("digest_{event}"),
+        sender: "0x0".to_string(),
+        checkpoint: (timestamp_ms - START_MS) / 1000,
+        checkpoint_timestamp_ms: timestamp_ms,
+        timestamp: DateTime::from_timestamp_millis(timestamp_ms)
+            .unwrap()
+            .naive_utc(),
+        package: "0x0".to_string(),
+        status,
+        pool_id: POOL_ID.to_string(),
+        order_id: order_id.to_string(),
+        client_order_id: 0,
+        price: 1_000_000,
+        is_bid: true,
+        original_quantity: 1_000_000_000,
+        quantity: 1_000_000_000,
+        filled_quantity: 0,
+        onchain_timestamp: timestamp_ms,
+        trader: "0xtrader".to_string(),
+        balance_manager_id: BALANCE_MANAGER.to_string(),
+    }
+}
+
+fn cleanup(conn: &mut PgConnection) {
+    diesel::delete(schema::order_updates::table)
+        .filter(schema::order_updates::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+    diesel::delete(schema::pools::table)
+        .filter(schema::pools::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn same_transaction_updates_resolve_to_the_later_status() {
+    let mut conn = common::connect();
+    cleanup(&mut conn);
+
+    diesel::insert_into(schema::pools::table)
+        .values(&common::pool(POOL_ID, POOL_NAME))
+        .execute(&mut conn)
+        .unwrap();
+    let placed_at = START_MS + 1000;
+    let updates = vec![
+        // placed and canceled in one transaction
+        stored_update("1", "1", placed_at, OrderUpdateStatus::Placed),
+        stored_update("2", "1", placed_at, OrderUpdateStatus::Canceled),
+        // placed and modified in one transaction
+        stored_update("3", "2", placed_at, OrderUpdateStatus::Modified),
+        stored_update("4", "2", placed_at, OrderUpdateStatus::Placed),
+        // placed before the searched window
+        stored_update("5", "3", START_MS, OrderUpdateStatus::Placed),
+    ];
+    diesel::insert_into(schema::order_updates::table)
+        .values(&updates)
+        .execute(&mut conn)
+        .unwrap();
+
+    let response = common::router(common::config())
+        .await
+        .oneshot(
+            Request::builder()
+                .uri(format!(
+                    "/open_orders/{POOL_NAME}?balance_manager_id={BALANCE_MANAGER}\
+                     &start_time={}&end_time={}",
+                    placed_at / 1000,
+                    placed_at / 1000 + 60
+                ))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+    cleanup(&mut conn);
+
+    assert_eq!(status, StatusCode::OK);
+    let open: Vec<Value> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(open.len(), 1);
+    assert_eq!(open[0]["order_id"], "2");
+    assert_eq!(open[0]["status"], "Modified");
+}
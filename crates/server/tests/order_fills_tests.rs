@@ -0,0 +1,17 @@
+use deeplook_server::error::DeepBookError;
+use deeplook_server::server::SortOrder;
+
+#[test]
+fn fills_are_newest_first_by_default() {
+    assert_eq!(SortOrder::parse(None).unwrap(), SortOrder::Desc);
+    assert_eq!(SortOrder::parse(Some("desc")).unwrap(), SortOrder::Desc);
+    assert_eq!(SortOrder::parse(Some("asc")).unwrap(), SortOrder::Asc);
+}
+
+#[test]
+fn unknown_order_is_rejected() {
+    assert!(matches!(
+        SortOrder::parse(Some("newest")),
+        Err(DeepBookError::BadRequest(_))
+    ));
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use chrono::DateTime;
+use deeplook_schema::models::{OrderUpdate, OrderUpdateStatus};
+use deeplook_schema::schema;
+use diesel::{ExpressionMethods, RunQueryDsl};
+
+const POOL_ID: &str = "0xorder_updates_trader_test_pool";
+const TRADER: &str = "0xorder_updates_test_trader";
+const BALANCE_MANAGER: &str = "0xorder_updates_test_manager";
+// 2025-01-01T00:00:00Z
+const START_MS: i64 = 1_735_689_600_000;
+
+fn update(
+    i: i64,
+    trader: &str,
+    balance_manager_id: &str,
+    status: OrderUpdateStatus,
+) -> OrderUpdate {
+    let timestamp_ms = START_MS + i * 1000;
+    OrderUpdate {
+        event_digest: format!("order_updates_trader_test_{i}"),
+        digest: format!("digest_{i}"),
+        sender: trader.to_string(),
+        checkpoint: i,
+        checkpoint_timestamp_ms: timestamp_ms,
+        timestamp: DateTime::from_timestamp_millis(timestamp_ms)
+            .unwrap()
+            .naive_utc(),
+        package: "0x0".to_string(),
+        status,
+        pool_id: POOL_ID.to_string(),
+        order_id: i.to_string(),
+        client_order_id: 0,
+        price: 1_000_000,
+        is_bid: true,
+        original_quantity: 10,
+        quantity: 10,
+        filled_quantity: 0,
+        onchain_timestamp: timestamp_ms,
+        trader: trader.to_string(),
+        balance_manager_id: balance_manager_id.to_string(),
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn trader_filter_combines_with_other_filters() {
+    let mut conn = common::connect();
+    diesel::delete(schema::order_updates::table)
+        .filter(schema::order_updates::pool_id.eq(POOL_ID))
+        .execute(&mut conn)
+        .unwrap();
+
+    // the trader places through two balance managers and cancels one order,
+    // another address places through the same manager
+    let updates = vec![
+        update(0, TRADER, BALANCE_MANAGER, OrderUpdateStatus::Placed),
+        update(1, TRADER, "0xother_manager", OrderUpdateStatus::Placed),
+        update(2, TRADER, BALANCE_MANAGER, OrderUpdateStatus::Canceled),
+        update(
+            3,
+            "0xother_trader",
+            BALANCE_MANAGER,
+            OrderUpdateStatus::Placed,
+        ),
+    ];
+    diesel::insert_into(schema::order_updates::table)
+        .values(&updates)
+        .execute(&mut conn)
+        .unwrap();
+
+    let state = common::state(common::REDIS_URL, common::config()).await;
+
+    let order_ids = |rows: Vec<(
+        String,
+        i64,
+        i64,
+        i64,
+        i64,
+        i64,
+        bool,
+        String,
+        String,
+        String,
+    )>| { rows.into_iter().map(|row| row.0).collect::<Vec<String>>() };
+    let end_ms = START_MS + 60 * 1000;
+
+    let by_trader = state
+        .reader
+        .get_order_updates(
+            POOL_ID.to_string(),
+            START_MS,
+            end_ms,
+            100,
+            None,
+            None,
+            Some(TRADER.to_string()),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(order_ids(by_trader), vec!["2", "1", "0"]);
+
+    let by_trader_and_manager = state
+        .reader
+        .get_order_updates(
+            POOL_ID.to_string(),
+            START_MS,
+            end_ms,
+            100,
+            Some(BALANCE_MANAGER.to_string()),
+            None,
+            Some(TRADER.to_string()),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(order_ids(by_trader_and_manager), vec!["2", "0"]);
+
+    let placed_by_trader = state
+        .reader
+        .get_order_updates(
+            POOL_ID.to_string(),
+            START_MS,
+            end_ms,
+            100,
+            Some(BALANCE_MANAGER.to_string()),
+            Some("Placed".to_string()),
+            Some(TRADER.to_string()),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(order_ids(placed_by_trader), vec!["0"]);
+
+    diesel::delete(schema::order_updates::table)
+        .filter(schema::order_updates::pool_id.eq(POOL_ID))
+        .execute(&mut conn)
+        .unwrap();
+}
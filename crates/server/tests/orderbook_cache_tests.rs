@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use deeplook_server::server::{level2_cache_key, Level2Ticks};
+
+fn ticks(timestamp: i64) -> Level2Ticks {
+    Level2Ticks {
+        timestamp,
+        bid_prices: vec![3_456_000, 3_455_000],
+        bid_quantities: vec![100_000_000, 200_000_000],
+        ask_prices: vec![3_457_000],
+        ask_quantities: vec![300_000_000],
+    }
+}
+
+#[test]
+fn cache_key_includes_pool_and_ticks() {
+    assert_eq!(
+        level2_cache_key("SUI_USDC", 100),
+        "orderbook_rpc::SUI_USDC::100"
+    );
+    assert_ne!(
+        level2_cache_key("SUI_USDC", 1),
+        level2_cache_key("SUI_USDC", 100)
+    );
+}
+
+#[test]
+fn ticks_are_fresh_within_ttl() {
+    let ttl = Duration::from_millis(1000);
+    let cached = ticks(10_000);
+    assert!(cached.is_fresh(10_000, ttl));
+    assert!(cached.is_fresh(10_999, ttl));
+    assert!(!cached.is_fresh(11_000, ttl));
+}
+
+#[test]
+fn cached_ticks_keep_their_timestamp() {
+    let cached = ticks(10_000);
+    let json = serde_json::to_string(&cached).unwrap();
+    let restored: Level2Ticks = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, cached);
+}
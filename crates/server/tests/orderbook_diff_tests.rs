@@ -0,0 +1,88 @@
+use deeplook_server::orderbook_diff::{
+    diff_message, orderbook_diff_book_key, orderbook_diff_channel, snapshot_message, DiffCursor,
+    DiffStep, LevelChange, OrderbookDiff,
+};
+use serde_json::json;
+
+const EPOCH: i64 = 1_750_000_000_000;
+
+fn diff(epoch: i64, seq: u64) -> OrderbookDiff {
+    OrderbookDiff {
+        epoch,
+        seq,
+        asks: vec![LevelChange {
+            price: 3.51,
+            new_size: 0.0,
+        }],
+        bids: vec![],
+    }
+}
+
+#[test]
+fn channel_is_per_pool() {
+    assert_eq!(
+        orderbook_diff_channel("SUI_USDC"),
+        "orderbook_diff::SUI_USDC"
+    );
+    assert_eq!(
+        orderbook_diff_book_key("SUI_USDC"),
+        "orderbook_diff_book::SUI_USDC"
+    );
+}
+
+#[test]
+fn cursor_of_snapshot_needs_epoch_and_seq() {
+    let snapshot = json!({"asks": [], "bids": [], "epoch": EPOCH, "seq": 7});
+    assert_eq!(
+        DiffCursor::of_snapshot(&snapshot),
+        Some(DiffCursor {
+            epoch: EPOCH,
+            seq: 7
+        })
+    );
+    // books stored without diffs
+    assert_eq!(
+        DiffCursor::of_snapshot(&json!({"asks": [], "bids": []})),
+        None
+    );
+}
+
+#[test]
+fn next_diff_is_sent_older_ones_skipped() {
+    let cursor = DiffCursor {
+        epoch: EPOCH,
+        seq: 7,
+    };
+    assert_eq!(cursor.step(&diff(EPOCH, 8)), DiffStep::Send);
+    assert_eq!(cursor.step(&diff(EPOCH, 7)), DiffStep::Skip);
+    assert_eq!(cursor.step(&diff(EPOCH, 3)), DiffStep::Skip);
+}
+
+#[test]
+fn gaps_and_restarts_need_a_new_snapshot() {
+    let cursor = DiffCursor {
+        epoch: EPOCH,
+        seq: 7,
+    };
+    assert_eq!(cursor.step(&diff(EPOCH, 9)), DiffStep::Resnapshot);
+    assert_eq!(cursor.step(&diff(EPOCH + 1, 1)), DiffStep::Resnapshot);
+}
+
+#[test]
+fn messages_are_tagged_with_their_kind() {
+    let snapshot = snapshot_message(json!({"asks": [], "bids": [], "epoch": EPOCH, "seq": 7}));
+    assert_eq!(snapshot["kind"], "snapshot");
+    assert_eq!(snapshot["seq"], 7);
+
+    let message = diff_message(&diff(EPOCH, 8));
+    assert_eq!(
+        message,
+        json!({
+            "kind": "diff",
+            "epoch": EPOCH,
+            "seq": 8,
+            "asks": [{"price": 3.51, "new_size": 0.0}],
+            "bids": [],
+        })
+    );
+}
@@ -0,0 +1,25 @@
+use deeplook_server::server::group_price_levels;
+
+#[test]
+fn bids_are_binned_down() {
+    let bids = [(1_009, 1), (1_005, 2), (1_000, 3), (999, 4), (990, 5)];
+    assert_eq!(
+        group_price_levels(&bids, 10, true),
+        vec![(1_000, 6), (990, 9)]
+    );
+}
+
+#[test]
+fn asks_are_binned_up() {
+    let asks = [(1_010, 1), (1_011, 2), (1_020, 3), (1_021, 4)];
+    assert_eq!(
+        group_price_levels(&asks, 10, false),
+        vec![(1_010, 1), (1_020, 5), (1_030, 4)]
+    );
+}
+
+#[test]
+fn unit_group_keeps_levels() {
+    let asks = [(1, 1), (2, 2), (3, 3)];
+    assert_eq!(group_price_levels(&asks, 1, false), asks.to_vec());
+}
@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+use deeplook_server::server::{checkpoint_range, snapshot_levels};
+use serde_json::json;
+
+#[test]
+fn bids_are_sorted_from_highest_price() {
+    let bids = json!({"100": 5, "120": 1, "90": 3});
+
+    assert_eq!(
+        snapshot_levels(&bids, true).unwrap(),
+        vec![(120, 1), (100, 5), (90, 3)]
+    );
+}
+
+#[test]
+fn asks_are_sorted_from_lowest_price_without_empty_levels() {
+    let asks = json!({"130": 2, "125": 0, "140": 4});
+
+    assert_eq!(
+        snapshot_levels(&asks, false).unwrap(),
+        vec![(130, 2), (140, 4)]
+    );
+}
+
+#[test]
+fn invalid_price_is_an_error() {
+    assert!(snapshot_levels(&json!({"abc": 1}), false).is_err());
+}
+
+fn range_params(from: &str, to: &str) -> HashMap<String, String> {
+    HashMap::from([
+        ("from_checkpoint".to_string(), from.to_string()),
+        ("to_checkpoint".to_string(), to.to_string()),
+    ])
+}
+
+#[test]
+fn checkpoint_range_is_inclusive() {
+    assert_eq!(
+        checkpoint_range(&range_params("10", "20")).unwrap(),
+        (10, 20)
+    );
+    assert_eq!(
+        checkpoint_range(&range_params("10", "10")).unwrap(),
+        (10, 10)
+    );
+}
+
+#[test]
+fn reversed_or_invalid_range_is_an_error() {
+    assert!(checkpoint_range(&range_params("20", "10")).is_err());
+    assert!(checkpoint_range(&range_params("0", "10")).is_err());
+    assert!(checkpoint_range(&range_params("a", "10")).is_err());
+    assert!(checkpoint_range(&HashMap::new()).is_err());
+}
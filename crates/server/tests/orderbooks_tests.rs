@@ -0,0 +1,34 @@
+use deeplook_server::error::DeepBookError;
+use deeplook_server::server::{parse_pool_names, ORDERBOOKS_MAX_POOLS};
+
+#[test]
+fn pool_names_are_trimmed_and_deduplicated() {
+    let pool_names =
+        parse_pool_names(" SUI_USDC,DEEP_SUI,,SUI_USDC ,", ORDERBOOKS_MAX_POOLS).unwrap();
+    assert_eq!(pool_names, vec!["SUI_USDC", "DEEP_SUI"]);
+}
+
+#[test]
+fn empty_pool_names_are_rejected() {
+    assert!(matches!(
+        parse_pool_names(" , ", ORDERBOOKS_MAX_POOLS),
+        Err(DeepBookError::BadRequest(_))
+    ));
+}
+
+#[test]
+fn pool_count_is_capped() {
+    let names: Vec<String> = (0..=ORDERBOOKS_MAX_POOLS)
+        .map(|i| format!("POOL_{i}"))
+        .collect();
+    assert!(matches!(
+        parse_pool_names(&names.join(","), ORDERBOOKS_MAX_POOLS),
+        Err(DeepBookError::BadRequest(_))
+    ));
+    assert_eq!(
+        parse_pool_names(&names[1..].join(","), ORDERBOOKS_MAX_POOLS)
+            .unwrap()
+            .len(),
+        ORDERBOOKS_MAX_POOLS
+    );
+}
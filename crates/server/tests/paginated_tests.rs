@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use deeplook_server::paginated::{page_cursor, wants_envelope, PageCursor, Paginated};
+use serde_json::{json, Value};
+
+fn row(timestamp_ms: i64) -> HashMap<String, Value> {
+    HashMap::from([
+        ("timestamp".to_string(), Value::from(timestamp_ms)),
+        (
+            "event_digest".to_string(),
+            Value::from(format!("digest{timestamp_ms}0")),
+        ),
+    ])
+}
+
+fn params(envelope: Option<&str>) -> HashMap<String, String> {
+    envelope
+        .map(|envelope| HashMap::from([("envelope".to_string(), envelope.to_string())]))
+        .unwrap_or_default()
+}
+
+fn page(rows: Vec<HashMap<String, Value>>, limit: i64) -> Paginated<HashMap<String, Value>> {
+    Paginated::new(rows, limit, |row| page_cursor(row, "timestamp"))
+}
+
+#[test]
+fn full_page_has_cursor_of_last_row() {
+    let page = page(vec![row(1_750_000_002_500), row(1_750_000_001_200)], 2);
+
+    assert_eq!(page.count, 2);
+    assert_eq!(
+        page.next_cursor.as_deref(),
+        Some("1750000001200:digest17500000012000")
+    );
+}
+
+#[test]
+fn short_page_has_no_cursor() {
+    let short = page(vec![row(1_750_000_002_500)], 2);
+
+    assert_eq!(short.count, 1);
+    assert_eq!(short.next_cursor, None);
+    assert_eq!(page(vec![], 2).next_cursor, None);
+}
+
+#[test]
+fn serializes_as_envelope() {
+    let page = page(vec![row(1_000)], 1);
+
+    assert_eq!(
+        serde_json::to_value(&page).unwrap(),
+        json!({
+            "data": [{"timestamp": 1000, "event_digest": "digest10000"}],
+            "next_cursor": "1000:digest10000",
+            "count": 1
+        })
+    );
+}
+
+#[test]
+fn row_without_timestamp_or_digest_has_no_cursor() {
+    let price = HashMap::from([("price".to_string(), Value::from(1.5))]);
+    assert_eq!(page_cursor(&price, "timestamp"), None);
+
+    let mut no_digest = row(1_000);
+    no_digest.remove("event_digest");
+    assert_eq!(page_cursor(&no_digest, "timestamp"), None);
+}
+
+#[test]
+fn cursor_round_trips() {
+    let cursor = PageCursor {
+        timestamp_ms: 1_750_000_001_200,
+        event_digest: "9hKq2Xz3".to_string(),
+    };
+
+    assert_eq!(PageCursor::decode(&cursor.encode()).unwrap(), cursor);
+}
+
+#[test]
+fn malformed_cursor_is_rejected() {
+    for cursor in ["", "1750000001", "1750000001:", "soon:9hKq2Xz3"] {
+        assert!(PageCursor::decode(cursor).is_err(), "{cursor}");
+    }
+
+    let params = HashMap::from([("cursor".to_string(), "soon".to_string())]);
+    assert!(PageCursor::from_params(&params).is_err());
+    assert_eq!(PageCursor::from_params(&HashMap::new()).unwrap(), None);
+}
+
+#[test]
+fn envelope_parameter_is_strict() {
+    assert!(!wants_envelope(&params(None)).unwrap());
+    assert!(!wants_envelope(&params(Some("false"))).unwrap());
+    assert!(wants_envelope(&params(Some("true"))).unwrap());
+    assert!(wants_envelope(&params(Some("yes"))).is_err());
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use deeplook_schema::schema;
+use diesel::{ExpressionMethods, PgConnection, RunQueryDsl};
+use serde_json::Value;
+use tower::ServiceExt;
+
+const POOL_ID: &str = "0xpaging_test_pool";
+const POOL_NAME: &str = "PAGING_TEST_USDC";
+// 2025-01-01T00:00:00Z
+const START_MS: i64 = 1_735_689_600_000;
+
+fn cleanup(conn: &mut PgConnection) {
+    diesel::delete(schema::order_fills::table)
+        .filter(schema::order_fills::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+    diesel::delete(schema::pools::table)
+        .filter(schema::pools::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+}
+
+async fn get_json(router: &Router, uri: &str) -> Value {
+    let response = router
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+/// `event_digest`s of every page of `uri`, following `next_cursor` until it's missing
+async fn all_pages(router: &Router, uri: &str) -> Vec<String> {
+    let mut digests = Vec::new();
+    let mut cursor: Option<String> = None;
+    for _ in 0..10 {
+        let page_uri = match &cursor {
+            Some(cursor) => format!("{uri}&cursor={cursor}"),
+            None => uri.to_string(),
+        };
+        let page = get_json(router, &page_uri).await;
+        digests.extend(
+            page["data"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|row| row["event_digest"].as_str().unwrap().to_string()),
+        );
+        cursor = page["next_cursor"].as_str().map(str::to_string);
+        if cursor.is_none() {
+            return digests;
+        }
+    }
+    panic!("{uri} kept returning a next_cursor");
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn pages_of_one_second_continue_after_the_cursor() {
+    let mut conn = common::connect();
+    cleanup(&mut conn);
+
+    diesel::insert_into(schema::pools::table)
+        .values(common::pool(POOL_ID, POOL_NAME))
+        .execute(&mut conn)
+        .unwrap();
+    // three fills in the same millisecond, more than a page of one
+    let fills: Vec<_> = (0..3)
+        .map(|i| common::fill(POOL_ID, i, START_MS + 500))
+        .collect();
+    diesel::insert_into(schema::order_fills::table)
+        .values(&fills)
+        .execute(&mut conn)
+        .unwrap();
+
+    let router = common::router(common::config()).await;
+    let window = format!(
+        "start_time={}&end_time={}",
+        START_MS / 1000,
+        START_MS / 1000 + 1
+    );
+
+    let trades = all_pages(
+        &router,
+        &format!("/trades/{POOL_NAME}?limit=1&envelope=true&{window}"),
+    )
+    .await;
+    let fills_asc = all_pages(
+        &router,
+        &format!("/order_fills/{POOL_NAME}?limit=1&envelope=true&order=asc&{window}"),
+    )
+    .await;
+
+    cleanup(&mut conn);
+
+    let digest = |i: i64| format!("{POOL_ID}_{i}");
+    assert_eq!(trades, vec![digest(2), digest(1), digest(0)]);
+    assert_eq!(fills_asc, vec![digest(0), digest(1), digest(2)]);
+}
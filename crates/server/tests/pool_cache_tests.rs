@@ -0,0 +1,112 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use deeplook_server::pool_cache::{needs_reload, PoolCache, PoolSet, POOL_CACHE_MISS_RELOAD};
+
+const TTL: Duration = Duration::from_secs(60);
+
+#[test]
+fn pools_are_found_by_name_and_id() {
+    let pools = PoolSet::new(vec![
+        common::pool("0x1", "SUI_USDC"),
+        common::pool("0x2", "DEEP_SUI"),
+    ]);
+
+    assert_eq!(pools.by_name("DEEP_SUI").unwrap().pool_id, "0x2");
+    assert_eq!(pools.by_id("0x1").unwrap().pool_name, "SUI_USDC");
+    assert!(pools.by_name("WAL_USDC").is_none());
+    assert_eq!(pools.pools().len(), 2);
+}
+
+#[test]
+fn pools_are_reloaded_once_stale() {
+    let loaded_at = Instant::now();
+
+    assert!(needs_reload(None, loaded_at, TTL, false));
+    assert!(!needs_reload(
+        Some(loaded_at),
+        loaded_at + TTL / 2,
+        TTL,
+        false
+    ));
+    assert!(needs_reload(Some(loaded_at), loaded_at + TTL, TTL, false));
+}
+
+#[test]
+fn misses_reload_after_a_short_interval() {
+    let loaded_at = Instant::now();
+
+    assert!(!needs_reload(Some(loaded_at), loaded_at, TTL, true));
+    assert!(needs_reload(
+        Some(loaded_at),
+        loaded_at + POOL_CACHE_MISS_RELOAD,
+        TTL,
+        true
+    ));
+}
+
+#[test]
+fn zero_ttl_disables_cache() {
+    let loaded_at = Instant::now();
+    assert!(needs_reload(
+        Some(loaded_at),
+        loaded_at,
+        Duration::ZERO,
+        false
+    ));
+
+    let cache = PoolCache::new(Duration::ZERO);
+    cache.store(
+        PoolSet::new(vec![common::pool("0x1", "SUI_USDC")]),
+        loaded_at,
+    );
+    assert!(cache.get(loaded_at, false).is_none());
+}
+
+#[test]
+fn stored_pools_are_shared_until_stale() {
+    let loaded_at = Instant::now();
+    let cache = PoolCache::new(TTL);
+    assert!(cache.get(loaded_at, false).is_none());
+
+    cache.store(
+        PoolSet::new(vec![common::pool("0x1", "SUI_USDC")]),
+        loaded_at,
+    );
+    let clone = cache.clone();
+
+    assert!(clone
+        .get(loaded_at, false)
+        .unwrap()
+        .by_name("SUI_USDC")
+        .is_some());
+    assert!(clone.get(loaded_at + TTL, false).is_none());
+}
+
+#[tokio::test]
+async fn concurrent_misses_share_one_load() {
+    let cache = PoolCache::new(TTL);
+    let loads = AtomicUsize::new(0);
+    let now = Instant::now();
+
+    let load = || {
+        let cache = cache.clone();
+        let loads = &loads;
+        async move {
+            cache
+                .get_or_load(now, true, || async {
+                    loads.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok::<_, ()>(PoolSet::new(vec![common::pool("0x1", "SUI_USDC")]))
+                })
+                .await
+                .unwrap()
+        }
+    };
+    let sets = futures::future::join_all((0..8).map(|_| load())).await;
+
+    assert_eq!(loads.load(Ordering::SeqCst), 1);
+    assert!(sets.iter().all(|pools| pools.by_name("SUI_USDC").is_some()));
+}
@@ -0,0 +1,29 @@
+use deeplook_server::error::DeepBookError;
+use deeplook_server::server::{price_exponent, price_scaling_factor};
+
+#[test]
+fn factor_follows_pool_decimals() {
+    // SUI_USDC
+    assert_eq!(price_scaling_factor(9, 6).unwrap(), 1_000_000.0);
+    // DEEP_SUI
+    assert_eq!(price_scaling_factor(6, 9).unwrap(), 1_000_000_000_000.0);
+    assert_eq!(price_scaling_factor(18, 9).unwrap(), 1.0);
+}
+
+#[test]
+fn negative_exponent_is_an_error() {
+    let err = price_scaling_factor(18, 6).unwrap_err();
+    assert!(matches!(err, DeepBookError::InternalError(_)));
+    assert!(err.message().contains("base 18"));
+}
+
+#[test]
+fn decimals_must_fit_price_scaling() {
+    assert_eq!(price_exponent(9, 6), Some(6));
+    assert_eq!(price_exponent(6, 9), Some(12));
+    // 10^(9 - 19 + 0) is not an integer factor
+    assert_eq!(price_exponent(19, 0), None);
+    // 10^20 doesn't fit u64
+    assert_eq!(price_exponent(0, 11), None);
+    assert_eq!(price_exponent(20, 19), None);
+}
@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use deeplook_server::error::{DeepBookError, QueryTimeout};
+
+#[test]
+fn query_timeout_becomes_timeout_error() {
+    let err = DeepBookError::from(anyhow::Error::new(QueryTimeout(Duration::from_millis(
+        1500,
+    ))));
+
+    assert!(matches!(err, DeepBookError::Timeout(_)));
+    assert_eq!(err.code(), "timeout");
+    assert_eq!(err.message(), "Database query timed out after 1500ms");
+}
+
+#[test]
+fn timeout_is_a_gateway_timeout() {
+    let response = DeepBookError::Timeout("slow".to_string()).into_response();
+    assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+}
+
+#[test]
+fn other_errors_stay_internal() {
+    let err = DeepBookError::from(anyhow::anyhow!("connection reset"));
+    assert!(matches!(err, DeepBookError::InternalError(_)));
+}
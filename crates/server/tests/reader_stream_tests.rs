@@ -0,0 +1,75 @@
+mod common;
+
+use deeplook_server::server::AppState;
+use diesel::sql_types::BigInt;
+use diesel::{sql_query, QueryableByName};
+use futures::StreamExt;
+
+const ROWS: i64 = 100_000;
+
+#[derive(QueryableByName)]
+struct Row {
+    #[diesel(sql_type = BigInt)]
+    n: i64,
+}
+
+async fn state() -> AppState {
+    common::state(common::REDIS_URL, common::config()).await
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn stream_yields_every_row_in_order() {
+    let state = state().await;
+    let query = sql_query(format!(
+        "SELECT generate_series(1, {ROWS})::BIGINT AS n ORDER BY n"
+    ));
+
+    // rows are counted as they come, never collected
+    let (count, last) = state
+        .reader
+        .stream::<_, Row>(query)
+        .fold((0i64, 0i64), |(count, last), row| async move {
+            let row = row.unwrap();
+            assert_eq!(row.n, last + 1);
+            (count + 1, row.n)
+        })
+        .await;
+
+    assert_eq!(count, ROWS);
+    assert_eq!(last, ROWS);
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn dropped_stream_stops_reading() {
+    let state = state().await;
+    let query = sql_query(format!("SELECT generate_series(1, {ROWS})::BIGINT AS n"));
+
+    let mut rows = state.reader.stream::<_, Row>(query);
+    let first = rows.next().await.unwrap().unwrap();
+    assert_eq!(first.n, 1);
+    drop(rows);
+
+    // the reader task gives its connection back, the next query doesn't wait for it
+    let query = sql_query("SELECT 1::BIGINT AS n");
+    let rows: Vec<Row> = state
+        .reader
+        .stream::<_, Row>(query)
+        .map(|row| row.unwrap())
+        .collect()
+        .await;
+    assert_eq!(rows.len(), 1);
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn query_error_ends_the_stream() {
+    let state = state().await;
+    let query = sql_query("SELECT n FROM missing_stream_test_table");
+
+    let rows: Vec<_> = state.reader.stream::<_, Row>(query).collect().await;
+
+    assert_eq!(rows.len(), 1);
+    assert!(rows[0].is_err());
+}
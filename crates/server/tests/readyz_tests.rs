@@ -0,0 +1,30 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use deeplook_server::server::{make_router, READYZ_PATH};
+use serde_json::Value;
+use tower::ServiceExt;
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn unreachable_redis_is_not_ready_but_alive() {
+    common::connect();
+
+    let state = common::state(common::UNREACHABLE_REDIS_URL, common::config()).await;
+    let router = make_router(Arc::new(state));
+
+    let request = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+    let response = router.clone().oneshot(request("/")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = router.oneshot(request(READYZ_PATH)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let checks: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(checks["database"], "ok");
+    assert_ne!(checks["redis"], "ok");
+}
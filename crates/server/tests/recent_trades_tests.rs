@@ -0,0 +1,45 @@
+mod common;
+
+use deeplook_schema::models::{LatestTrade, OrderFill};
+use deeplook_server::server::{latest_trades_json, recent_trades_values};
+use serde_json::Value;
+
+fn fill(checkpoint: i64, price: i64) -> OrderFill {
+    OrderFill {
+        price,
+        base_quantity: 5,
+        quote_quantity: 5 * price,
+        ..common::fill("0xpool", checkpoint, 1_735_689_600_000 + checkpoint * 1000)
+    }
+}
+
+/// What the websocket sends: the orderbook service pushes trades as JSON strings, the
+/// server reads them back as values
+fn websocket_message(fills: &[OrderFill]) -> String {
+    let stored: Vec<Value> = fills
+        .iter()
+        .map(|fill| serde_json::to_string(&LatestTrade::from(fill)).unwrap())
+        .map(|json| serde_json::from_str(&json).unwrap())
+        .collect();
+    latest_trades_json(&stored).unwrap()
+}
+
+#[test]
+fn rest_and_websocket_payloads_are_identical() {
+    let oldest_first = vec![fill(1, 1_000), fill(2, 1_100)];
+    let newest_first: Vec<OrderFill> = vec![fill(2, 1_100), fill(1, 1_000)];
+
+    let rest = latest_trades_json(&recent_trades_values(&newest_first).unwrap()).unwrap();
+
+    assert_eq!(rest, websocket_message(&oldest_first));
+}
+
+#[test]
+fn trades_are_oldest_first() {
+    let values = recent_trades_values(&[fill(2, 1_100), fill(1, 1_000)]).unwrap();
+    let checkpoints: Vec<i64> = values
+        .iter()
+        .map(|trade| trade["checkpoint"].as_i64().unwrap())
+        .collect();
+    assert_eq!(checkpoints, vec![1, 2]);
+}
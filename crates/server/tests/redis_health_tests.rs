@@ -0,0 +1,51 @@
+use deeplook_server::redis_health::RedisHealth;
+use deeplook_utils::cache::AsyncCache;
+use prometheus::Registry;
+use url::Url;
+
+// nothing listens on port 1
+const UNREACHABLE_REDIS: &str = "redis://127.0.0.1:1";
+
+#[tokio::test]
+async fn unreachable_redis_is_down() {
+    let health = RedisHealth::new(&Registry::new());
+    let cache = AsyncCache::new(Url::parse(UNREACHABLE_REDIS).unwrap());
+
+    assert!(!health.check(&cache).await);
+    assert_eq!(health.up.get(), 0);
+    assert_eq!(health.ping_latency.get_sample_count(), 0);
+}
+
+#[test]
+fn only_changes_are_reported() {
+    let health = RedisHealth::new(&Registry::new());
+
+    assert!(health.record(false));
+    assert!(!health.record(false));
+    assert_eq!(health.up.get(), 0);
+    assert!(health.record(true));
+    assert!(!health.record(true));
+    assert_eq!(health.up.get(), 1);
+    // clones share the last result
+    assert!(health.clone().record(false));
+    assert!(!health.record(false));
+}
+
+#[tokio::test]
+#[ignore = "requires a running Redis, set REDIS_URL"]
+async fn gauge_flips_on_outage() {
+    let redis_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    let health = RedisHealth::new(&Registry::new());
+    let live = AsyncCache::new(Url::parse(&redis_url).unwrap());
+    let down = AsyncCache::new(Url::parse(UNREACHABLE_REDIS).unwrap());
+
+    assert!(health.check(&live).await);
+    assert_eq!(health.up.get(), 1);
+    assert_eq!(health.ping_latency.get_sample_count(), 1);
+
+    assert!(!health.check(&down).await);
+    assert_eq!(health.up.get(), 0);
+
+    assert!(health.check(&live).await);
+    assert_eq!(health.up.get(), 1);
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use deeplook_schema::schema;
+use deeplook_server::aggregations::{capped_query, enforce_row_cap};
+use deeplook_server::config::ServerConfig;
+use deeplook_server::error::DeepBookError;
+use diesel::sql_types::BigInt;
+use diesel::{
+    sql_query, Connection, ExpressionMethods, PgConnection, QueryableByName, RunQueryDsl,
+};
+use tower::ServiceExt;
+
+const POOL_ID: &str = "0xrow_cap_test_pool";
+const POOL_NAME: &str = "ROW_CAP_TEST_USDC";
+// 2025-01-01T00:00:00Z
+const START_MS: i64 = 1_735_689_600_000;
+
+#[derive(QueryableByName)]
+struct Row {
+    #[diesel(sql_type = BigInt)]
+    n: i64,
+}
+
+#[test]
+fn rows_over_the_cap_fail() {
+    assert_eq!(enforce_row_cap(vec![1, 2, 3], 3).unwrap(), vec![1, 2, 3]);
+    assert!(matches!(
+        enforce_row_cap(vec![1, 2, 3, 4], 3),
+        Err(DeepBookError::BadRequest(_))
+    ));
+    assert!(enforce_row_cap(Vec::<i64>::new(), 0).is_ok());
+}
+
+#[test]
+fn capped_query_fetches_one_row_over_the_cap() {
+    assert_eq!(
+        capped_query("\n SELECT n FROM seeded_rows\n ", 10),
+        "SELECT * FROM (SELECT n FROM seeded_rows) capped LIMIT 11"
+    );
+}
+
+#[test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+fn cap_triggers_on_oversized_view() {
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let mut conn = PgConnection::establish(&database_url).unwrap();
+
+    conn.test_transaction::<_, diesel::result::Error, _>(|conn| {
+        sql_query(
+            "CREATE TEMP VIEW seeded_rows AS \
+             SELECT n::BIGINT AS n FROM generate_series(1, 11) n",
+        )
+        .execute(conn)?;
+
+        let load = |conn: &mut PgConnection, max_rows: i64| {
+            sql_query(capped_query("SELECT n FROM seeded_rows", max_rows)).load::<Row>(conn)
+        };
+
+        let rows = load(conn, 10)?;
+        assert_eq!(rows.len(), 11);
+        assert!(enforce_row_cap(rows, 10).is_err());
+
+        let rows = enforce_row_cap(load(conn, 11)?, 11).unwrap();
+        assert_eq!(rows.iter().map(|row| row.n).sum::<i64>(), 66);
+        Ok(())
+    });
+}
+
+fn cleanup(conn: &mut PgConnection) {
+    diesel::delete(schema::order_fills::table)
+        .filter(schema::order_fills::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+    diesel::delete(schema::pools::table)
+        .filter(schema::pools::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+}
+
+async fn status(router: &Router, uri: String) -> StatusCode {
+    router
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn ranges_over_the_cap_are_rejected() {
+    let mut conn = common::connect();
+    cleanup(&mut conn);
+
+    diesel::insert_into(schema::pools::table)
+        .values(&common::pool(POOL_ID, POOL_NAME))
+        .execute(&mut conn)
+        .unwrap();
+    // one fill in each of three minutes, so three 1m candles
+    let fills: Vec<_> = (0..3)
+        .map(|i| common::fill(POOL_ID, i, START_MS + i * 60_000))
+        .collect();
+    diesel::insert_into(schema::order_fills::table)
+        .values(&fills)
+        .execute(&mut conn)
+        .unwrap();
+
+    let config = |max_aggregation_rows| ServerConfig {
+        ohlcv_from_raw: true,
+        max_aggregation_rows,
+        ..common::config()
+    };
+    let ohlcv = format!(
+        "/ohlcv/{POOL_NAME}?timeframe=1m&start_time={}&end_time={}",
+        START_MS / 1000,
+        START_MS / 1000 + 180
+    );
+    let trades = format!(
+        "/trades/{POOL_NAME}?limit=3&start_time={}&end_time={}",
+        START_MS / 1000,
+        START_MS / 1000 + 180
+    );
+
+    let capped = common::router(config(2)).await;
+    let capped_ohlcv = status(&capped, ohlcv.clone()).await;
+    let capped_trades = status(&capped, trades.clone()).await;
+
+    let fitting = common::router(config(3)).await;
+    let fitting_ohlcv = status(&fitting, ohlcv).await;
+    let fitting_trades = status(&fitting, trades).await;
+
+    cleanup(&mut conn);
+
+    assert_eq!(capped_ohlcv, StatusCode::BAD_REQUEST);
+    assert_eq!(capped_trades, StatusCode::BAD_REQUEST);
+    assert_eq!(fitting_ohlcv, StatusCode::OK);
+    assert_eq!(fitting_trades, StatusCode::OK);
+}
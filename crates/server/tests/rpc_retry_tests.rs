@@ -0,0 +1,92 @@
+use std::cell::Cell;
+use std::sync::Arc;
+use std::time::Duration;
+
+use deeplook_server::rpc_retry::{is_retryable_client_error, with_retry, RetryPolicy, RPC_RETRY};
+use jsonrpsee::core::ClientError;
+
+const FAST: RetryPolicy = RetryPolicy {
+    attempts: 3,
+    base_delay: Duration::from_millis(1),
+};
+
+fn transient(e: &&str) -> bool {
+    *e == "transient"
+}
+
+#[test]
+fn delay_doubles_with_every_retry() {
+    assert_eq!(RPC_RETRY.backoff_delay(1, 0.0), Duration::from_millis(100));
+    assert_eq!(RPC_RETRY.backoff_delay(2, 0.0), Duration::from_millis(200));
+    assert_eq!(RPC_RETRY.backoff_delay(3, 0.0), Duration::from_millis(400));
+}
+
+#[test]
+fn jitter_shortens_delay_by_up_to_half() {
+    assert_eq!(RPC_RETRY.backoff_delay(2, 0.5), Duration::from_millis(150));
+    assert_eq!(RPC_RETRY.backoff_delay(2, 1.0), Duration::from_millis(100));
+}
+
+#[tokio::test]
+async fn transient_errors_are_retried() {
+    let calls = Cell::new(0);
+    let result = with_retry(FAST, transient, || {
+        calls.set(calls.get() + 1);
+        let call = calls.get();
+        async move {
+            if call < 3 {
+                Err("transient")
+            } else {
+                Ok(call)
+            }
+        }
+    })
+    .await;
+
+    assert_eq!(result, Ok(3));
+}
+
+#[tokio::test]
+async fn last_error_is_returned_when_attempts_run_out() {
+    let calls = Cell::new(0);
+    let result: Result<(), _> = with_retry(FAST, transient, || {
+        calls.set(calls.get() + 1);
+        async { Err("transient") }
+    })
+    .await;
+
+    assert_eq!(result, Err("transient"));
+    assert_eq!(calls.get(), 3);
+}
+
+#[tokio::test]
+async fn other_errors_fail_immediately() {
+    let calls = Cell::new(0);
+    let result: Result<(), _> = with_retry(FAST, transient, || {
+        calls.set(calls.get() + 1);
+        async { Err("object not found") }
+    })
+    .await;
+
+    assert_eq!(result, Err("object not found"));
+    assert_eq!(calls.get(), 1);
+}
+
+#[test]
+fn only_transport_timeout_and_restart_errors_are_retryable() {
+    assert!(is_retryable_client_error(&ClientError::Transport(
+        "connection reset".into()
+    )));
+    assert!(is_retryable_client_error(&ClientError::RequestTimeout));
+    assert!(is_retryable_client_error(&ClientError::RestartNeeded(
+        Arc::new(ClientError::RequestTimeout)
+    )));
+
+    assert!(!is_retryable_client_error(&ClientError::Custom(
+        "invalid params".to_string()
+    )));
+    assert!(!is_retryable_client_error(
+        &ClientError::InvalidSubscriptionId
+    ));
+    assert!(!is_retryable_client_error(&ClientError::HttpNotImplemented));
+}
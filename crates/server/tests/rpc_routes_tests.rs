@@ -0,0 +1,39 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use deeplook_server::config::ServerConfig;
+use deeplook_server::server::{make_router, CHAIN_TIP_PATH, DEEP_SUPPLY_PATH};
+use tower::ServiceExt;
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn disabled_rpc_routes_are_not_served() {
+    common::connect();
+
+    let config = ServerConfig {
+        disable_rpc_routes: true,
+        ..common::config()
+    };
+    // the router must not need the full node
+    let state =
+        common::state_with_rpc(common::REDIS_URL, common::UNREACHABLE_RPC_URL, config).await;
+    let router = make_router(Arc::new(state));
+
+    for path in [DEEP_SUPPLY_PATH, CHAIN_TIP_PATH, "/orderbook/SUI_USDC"] {
+        let response = router
+            .clone()
+            .oneshot(Request::get(path).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND, "{}", path);
+    }
+
+    let response = router
+        .oneshot(Request::get("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use std::collections::HashMap;
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use deeplook_schema::models::OrderFill;
+use deeplook_schema::schema;
+use diesel::{ExpressionMethods, PgConnection, RunQueryDsl};
+use serde_json::Value;
+use tower::ServiceExt;
+
+const POOL_ID: &str = "0xself_trade_test_pool";
+const POOL_NAME: &str = "SELF_TRADE_TEST_USDC";
+const BALANCE_MANAGER: &str = "0xself_trade_test_manager";
+// 2025-01-01T00:00:00Z
+const START_MS: i64 = 1_735_689_600_000;
+
+fn fill(i: i64, base_quantity: i64, maker: &str, taker: &str) -> OrderFill {
+    OrderFill {
+        base_quantity,
+        quote_quantity: base_quantity,
+        maker_balance_manager_id: maker.to_string(),
+        taker_balance_manager_id: taker.to_string(),
+        ..common::fill(POOL_ID, i, START_MS + i * 1000)
+    }
+}
+
+fn cleanup(conn: &mut PgConnection) {
+    diesel::delete(schema::order_fills::table)
+        .filter(schema::order_fills::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+    diesel::delete(schema::pools::table)
+        .filter(schema::pools::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+}
+
+async fn get_json(router: &Router, uri: &str) -> Value {
+    let response = router
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn self_trades_are_left_out_of_trades_and_volumes() {
+    let mut conn = common::connect();
+    cleanup(&mut conn);
+
+    diesel::insert_into(schema::pools::table)
+        .values(common::pool(POOL_ID, POOL_NAME))
+        .execute(&mut conn)
+        .unwrap();
+    // the manager makes against another manager, then trades with itself
+    let fills = vec![
+        fill(0, 2, BALANCE_MANAGER, "0xother"),
+        fill(1, 5, "0xother", BALANCE_MANAGER),
+        fill(2, 11, BALANCE_MANAGER, BALANCE_MANAGER),
+    ];
+    diesel::insert_into(schema::order_fills::table)
+        .values(&fills)
+        .execute(&mut conn)
+        .unwrap();
+
+    let router = common::router(common::config()).await;
+    let window = format!(
+        "start_time={}&end_time={}&volume_in_base=true",
+        START_MS / 1000,
+        START_MS / 1000 + 60
+    );
+
+    let mut responses = HashMap::new();
+    for exclude in [false, true] {
+        let params = format!("{window}&exclude_self_trades={exclude}");
+        let trades = get_json(&router, &format!("/trades/{POOL_NAME}?{params}")).await;
+        let volume = get_json(&router, &format!("/historical_volume/{POOL_NAME}?{params}")).await;
+        let by_manager = get_json(
+            &router,
+            &format!(
+                "/historical_volume_by_balance_manager_id/{POOL_NAME}/{BALANCE_MANAGER}?{params}"
+            ),
+        )
+        .await;
+        responses.insert(exclude, (trades, volume, by_manager));
+    }
+
+    cleanup(&mut conn);
+
+    let digests = |trades: &Value| -> Vec<String> {
+        trades
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|trade| trade["event_digest"].as_str().unwrap().to_string())
+            .collect()
+    };
+    let digest = |i: i64| format!("{POOL_ID}_{i}");
+
+    let (trades, volume, by_manager) = &responses[&false];
+    assert_eq!(digests(trades), vec![digest(2), digest(1), digest(0)]);
+    assert_eq!(volume[POOL_NAME], 18);
+    // [maker volume, taker volume], a self-trade counts on both sides
+    assert_eq!(by_manager[POOL_NAME], serde_json::json!([13, 16]));
+
+    let (trades, volume, by_manager) = &responses[&true];
+    assert_eq!(digests(trades), vec![digest(1), digest(0)]);
+    assert_eq!(volume[POOL_NAME], 7);
+    assert_eq!(by_manager[POOL_NAME], serde_json::json!([2, 5]));
+}
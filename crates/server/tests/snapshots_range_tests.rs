@@ -0,0 +1,74 @@
+mod common;
+
+use chrono::Utc;
+use deeplook_schema::models::OrderbookSnapshot;
+use deeplook_schema::schema::orderbook_snapshots;
+use diesel::{ExpressionMethods, RunQueryDsl};
+use serde_json::json;
+
+const POOL_ID: &str = "0xsnapshots_range_test_pool";
+const OTHER_POOL_ID: &str = "0xsnapshots_range_other_pool";
+
+fn snapshot(pool_id: &str, checkpoint: i64) -> OrderbookSnapshot {
+    OrderbookSnapshot {
+        checkpoint,
+        pool_id: pool_id.to_string(),
+        asks: json!({ "2000": 1 }),
+        bids: json!({ "1000": 1 }),
+        timestamp: Utc::now().naive_utc(),
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn range_returns_snapshots_between_checkpoints_in_order() {
+    let mut conn = common::connect();
+    diesel::delete(orderbook_snapshots::table)
+        .filter(orderbook_snapshots::pool_id.eq_any([POOL_ID, OTHER_POOL_ID]))
+        .execute(&mut conn)
+        .unwrap();
+    diesel::insert_into(orderbook_snapshots::table)
+        .values(&vec![
+            snapshot(POOL_ID, 300),
+            snapshot(POOL_ID, 100),
+            snapshot(POOL_ID, 200),
+            snapshot(OTHER_POOL_ID, 200),
+        ])
+        .execute(&mut conn)
+        .unwrap();
+
+    let state = common::state(common::REDIS_URL, common::config()).await;
+    let checkpoints = |snapshots: Vec<OrderbookSnapshot>| -> Vec<i64> {
+        snapshots
+            .iter()
+            .map(|snapshot| snapshot.checkpoint)
+            .collect()
+    };
+
+    let in_range = state
+        .reader
+        .get_snapshots_range(POOL_ID, 150, 300, 10)
+        .await
+        .unwrap();
+    assert_eq!(checkpoints(in_range), vec![200, 300]);
+
+    let past_the_end = state
+        .reader
+        .get_snapshots_range(POOL_ID, 301, 400, 10)
+        .await
+        .unwrap();
+    assert!(past_the_end.is_empty());
+
+    // one row past the cap is loaded so the caller can reject the range
+    let capped = state
+        .reader
+        .get_snapshots_range(POOL_ID, 0, 300, 1)
+        .await
+        .unwrap();
+    assert_eq!(checkpoints(capped), vec![100, 200]);
+
+    diesel::delete(orderbook_snapshots::table)
+        .filter(orderbook_snapshots::pool_id.eq_any([POOL_ID, OTHER_POOL_ID]))
+        .execute(&mut conn)
+        .unwrap();
+}
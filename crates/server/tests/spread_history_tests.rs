@@ -0,0 +1,85 @@
+use chrono::{DateTime, NaiveDateTime};
+use deeplook_schema::models::OrderbookSnapshot;
+use deeplook_schema::{schema, MIGRATIONS};
+use deeplook_server::server::SPREAD_HISTORY_QUERY;
+use diesel::sql_types::{BigInt, Nullable, Text, Timestamp};
+use diesel::{sql_query, Connection, PgConnection, QueryableByName, RunQueryDsl};
+use diesel_migrations::MigrationHarness;
+use serde_json::json;
+
+const POOL_ID: &str = "0xspread_history_test_pool";
+const START_MS: i64 = 1_735_689_600_000;
+
+#[derive(QueryableByName, Debug, PartialEq)]
+struct Row {
+    #[diesel(sql_type = BigInt)]
+    checkpoint: i64,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    best_bid: Option<i64>,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    best_ask: Option<i64>,
+}
+
+fn timestamp(ms: i64) -> NaiveDateTime {
+    DateTime::from_timestamp_millis(ms).unwrap().naive_utc()
+}
+
+fn snapshot(
+    checkpoint: i64,
+    asks: serde_json::Value,
+    bids: serde_json::Value,
+) -> OrderbookSnapshot {
+    OrderbookSnapshot {
+        checkpoint,
+        pool_id: POOL_ID.to_string(),
+        asks,
+        bids,
+        timestamp: timestamp(START_MS + checkpoint * 1000),
+    }
+}
+
+#[test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+fn bests_are_taken_from_each_snapshot() {
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let mut conn = PgConnection::establish(&database_url).unwrap();
+    conn.run_pending_migrations(MIGRATIONS).unwrap();
+
+    conn.test_transaction::<_, diesel::result::Error, _>(|conn| {
+        let snapshots = vec![
+            // emptied 1050 ask level is ignored
+            snapshot(
+                10,
+                json!({"1100": 5, "1050": 0, "1200": 3}),
+                json!({"1000": 2, "990": 1}),
+            ),
+            snapshot(20, json!({"1300": 1}), json!({})),
+        ];
+        diesel::insert_into(schema::orderbook_snapshots::table)
+            .values(&snapshots)
+            .execute(conn)?;
+
+        let rows = sql_query(SPREAD_HISTORY_QUERY)
+            .bind::<Text, _>(POOL_ID)
+            .bind::<Timestamp, _>(timestamp(START_MS))
+            .bind::<Timestamp, _>(timestamp(START_MS + 60 * 1000))
+            .load::<Row>(conn)?;
+
+        assert_eq!(
+            rows,
+            vec![
+                Row {
+                    checkpoint: 10,
+                    best_bid: Some(1000),
+                    best_ask: Some(1100),
+                },
+                Row {
+                    checkpoint: 20,
+                    best_bid: None,
+                    best_ask: Some(1300),
+                },
+            ]
+        );
+        Ok(())
+    });
+}
@@ -0,0 +1,32 @@
+use deeplook_server::server::{pipeline_statuses, PipelineStatus};
+
+#[test]
+fn lag_is_time_since_the_newest_checkpoint() {
+    let now_ms = 1_750_000_060_500;
+    let statuses = pipeline_statuses(
+        vec![
+            ("order_fill".to_string(), 100, 1_750_000_000_000),
+            ("order_update".to_string(), 101, 1_750_000_061_000),
+        ],
+        now_ms,
+    );
+
+    assert_eq!(
+        statuses,
+        vec![
+            PipelineStatus {
+                pipeline: "order_fill".to_string(),
+                checkpoint: 100,
+                timestamp_ms: 1_750_000_000_000,
+                lag_seconds: 60,
+            },
+            // clock skew, the watermark is ahead of now
+            PipelineStatus {
+                pipeline: "order_update".to_string(),
+                checkpoint: 101,
+                timestamp_ms: 1_750_000_061_000,
+                lag_seconds: 0,
+            },
+        ]
+    );
+}
@@ -0,0 +1,29 @@
+mod common;
+
+use std::sync::Arc;
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn unreachable_rpc_fails_without_caching_the_failure() {
+    common::connect();
+    let state = common::state_with_rpc(
+        common::UNREACHABLE_REDIS_URL,
+        common::UNREACHABLE_RPC_URL,
+        common::config(),
+    )
+    .await;
+
+    assert!(state.sui_client().await.is_err());
+    assert!(state.sui_client().await.is_err());
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB and network access, set DATABASE_URL"]
+async fn requests_share_one_client() {
+    common::connect();
+    let state = common::state(common::UNREACHABLE_REDIS_URL, common::config()).await;
+
+    let first = state.sui_client().await.unwrap();
+    let second = state.sui_client().await.unwrap();
+    assert!(Arc::ptr_eq(&first, &second));
+}
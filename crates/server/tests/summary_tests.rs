@@ -0,0 +1,105 @@
+mod common;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use deeplook_schema::models::OrderFill;
+use deeplook_schema::schema;
+use deeplook_utils::cache::Cache;
+use diesel::{ExpressionMethods, PgConnection, RunQueryDsl};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use url::Url;
+
+const POOL_ID: &str = "0xsummary_test_pool";
+const POOL_NAME: &str = "SUMMARY_TEST_USDC";
+const HOUR_MS: i64 = 60 * 60 * 1000;
+
+fn fill(i: i64, timestamp_ms: i64, price: i64) -> OrderFill {
+    OrderFill {
+        price,
+        base_quantity: 1_000_000_000,
+        quote_quantity: price,
+        ..common::fill(POOL_ID, i, timestamp_ms)
+    }
+}
+
+fn cleanup(conn: &mut PgConnection, cache: &mut Cache) {
+    diesel::delete(schema::order_fills::table)
+        .filter(schema::order_fills::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+    diesel::delete(schema::pools::table)
+        .filter(schema::pools::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+    cache.del(&format!("orderbook::{POOL_NAME}")).unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB and Redis, set DATABASE_URL"]
+async fn summary_combines_ticker_price_change_and_live_bests() {
+    let mut conn = common::connect();
+    let mut cache = Cache::new(Url::parse(common::REDIS_URL).unwrap());
+    cleanup(&mut conn, &mut cache);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64;
+    diesel::insert_into(schema::pools::table)
+        .values(common::pool(POOL_ID, POOL_NAME))
+        .execute(&mut conn)
+        .unwrap();
+    // a day earlier at 2, then two trades of 1 SUI in the last day ending at 4
+    let fills = vec![
+        fill(0, now - 30 * HOUR_MS, 2_000_000),
+        fill(1, now - HOUR_MS, 3_000_000),
+        fill(2, now - HOUR_MS / 2, 4_000_000),
+    ];
+    diesel::insert_into(schema::order_fills::table)
+        .values(&fills)
+        .execute(&mut conn)
+        .unwrap();
+    // the live book is read instead of the onchain one
+    cache
+        .set(
+            &format!("orderbook::{POOL_NAME}"),
+            &json!({
+                "asks": [{"price": 4.2, "size": 1.0}],
+                "bids": [{"price": 3.9, "size": 2.0}],
+            }),
+        )
+        .unwrap();
+
+    let router = common::router(common::config()).await;
+    let response = router
+        .oneshot(
+            Request::builder()
+                .uri("/summary")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+    cleanup(&mut conn, &mut cache);
+
+    assert_eq!(status, StatusCode::OK);
+    let summaries: Vec<Value> = serde_json::from_slice(&body).unwrap();
+    let summary = summaries
+        .iter()
+        .find(|summary| summary["trading_pairs"] == POOL_NAME)
+        .unwrap();
+    assert_eq!(summary["base_currency"], "SUI");
+    assert_eq!(summary["quote_currency"], "USDC");
+    assert_eq!(summary["last_price"], json!(4.0));
+    assert_eq!(summary["has_trades"], json!(true));
+    assert_eq!(summary["base_volume"], json!(2.0));
+    assert_eq!(summary["price_change_percent_24h"], json!(100.0));
+    assert_eq!(summary["highest_bid"], json!(3.9));
+    assert_eq!(summary["lowest_ask"], json!(4.2));
+}
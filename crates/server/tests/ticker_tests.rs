@@ -0,0 +1,102 @@
+mod common;
+
+use bigdecimal::BigDecimal;
+use deeplook_schema::models::Pool;
+use deeplook_server::server::{
+    is_pool_frozen, normalize_coin_type, split_last_prices, ticker_entry, volumes_by_pool_name,
+};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+fn pool() -> Pool {
+    common::pool("0xpool", "SUI_USDC")
+}
+
+#[test]
+fn no_trades_has_null_last_price() {
+    let entry = ticker_entry(&pool(), None, 0, 0, false).unwrap();
+    assert_eq!(entry["last_price"], Value::Null);
+    assert_eq!(entry["has_trades"], Value::Bool(false));
+    assert_eq!(entry["base_volume"], Value::from(0.0));
+    assert_eq!(entry["isFrozen"], Value::from(0));
+}
+
+#[test]
+fn trades_have_scaled_last_price() {
+    let entry = ticker_entry(&pool(), Some(3_500_000), 2_000_000_000, 7_000_000, false).unwrap();
+    assert_eq!(entry["last_price"], Value::from(3.5));
+    assert_eq!(entry["has_trades"], Value::Bool(true));
+    assert_eq!(entry["base_volume"], Value::from(2.0));
+    assert_eq!(entry["quote_volume"], Value::from(7.0));
+}
+
+#[test]
+fn frozen_pool_reports_is_frozen() {
+    let frozen_assets = HashSet::from([normalize_coin_type(
+        "0x0000000000000000000000000000000000000000000000000000000000000002::sui::SUI",
+    )]);
+    let is_frozen = is_pool_frozen(&pool(), &frozen_assets);
+    assert!(is_frozen);
+
+    let entry = ticker_entry(&pool(), None, 0, 0, is_frozen).unwrap();
+    assert_eq!(entry["isFrozen"], Value::from(1));
+}
+
+#[test]
+fn pool_without_frozen_assets_is_active() {
+    let frozen_assets = HashSet::from([normalize_coin_type("0xdeep::deep::DEEP")]);
+    assert!(!is_pool_frozen(&pool(), &frozen_assets));
+}
+
+#[test]
+fn pools_without_a_cagg_bucket_fall_back_to_raw_fills() {
+    let pool_ids = ["0xa".to_string(), "0xb".to_string(), "0xc".to_string()];
+    let (prices, mut missing) =
+        split_last_prices(vec![("0xb".to_string(), 42)], vec![], pool_ids.iter());
+    missing.sort();
+
+    assert_eq!(prices, HashMap::from([("0xb".to_string(), 42)]));
+    assert_eq!(missing, vec!["0xa".to_string(), "0xc".to_string()]);
+}
+
+#[test]
+fn fills_after_the_newest_bucket_replace_cagg_closes() {
+    let pool_ids = ["0xa".to_string(), "0xb".to_string()];
+    let (prices, missing) = split_last_prices(
+        vec![("0xa".to_string(), 42), ("0xb".to_string(), 7)],
+        vec![("0xa".to_string(), 43)],
+        pool_ids.iter(),
+    );
+
+    assert_eq!(
+        prices,
+        HashMap::from([("0xa".to_string(), 43), ("0xb".to_string(), 7)])
+    );
+    assert!(missing.is_empty());
+}
+
+#[test]
+fn volume_sums_are_keyed_by_pool_name() {
+    let sums = vec![
+        (
+            "0xpool".to_string(),
+            Some(BigDecimal::from(2_000_000_000)),
+            Some(BigDecimal::from(7_000_000)),
+        ),
+        ("0xunknown".to_string(), Some(BigDecimal::from(1)), None),
+    ];
+
+    assert_eq!(
+        volumes_by_pool_name(&[pool()], sums),
+        HashMap::from([("SUI_USDC".to_string(), (2_000_000_000, 7_000_000))])
+    );
+}
+
+#[test]
+fn missing_volume_sums_are_zero() {
+    let sums = vec![("0xpool".to_string(), None, None)];
+    assert_eq!(
+        volumes_by_pool_name(&[pool()], sums),
+        HashMap::from([("SUI_USDC".to_string(), (0, 0))])
+    );
+}
@@ -0,0 +1,66 @@
+use chrono::{DateTime, NaiveDateTime};
+use deeplook_server::time_bucket::bucket_start_ms;
+use diesel::sql_types::{BigInt, Timestamp};
+use diesel::{sql_query, Connection, PgConnection, QueryableByName, RunQueryDsl};
+
+const MINUTE_MS: i64 = 60 * 1000;
+const HOUR_MS: i64 = 60 * MINUTE_MS;
+// 2025-06-19 22:00:00 UTC
+const HOUR_START: i64 = 1_750_370_400_000;
+
+#[test]
+fn one_hour_bucket_boundaries() {
+    assert_eq!(bucket_start_ms(HOUR_START, HOUR_MS), HOUR_START);
+    assert_eq!(bucket_start_ms(HOUR_START + 1, HOUR_MS), HOUR_START);
+    assert_eq!(
+        bucket_start_ms(HOUR_START + HOUR_MS - 1, HOUR_MS),
+        HOUR_START
+    );
+    assert_eq!(
+        bucket_start_ms(HOUR_START + HOUR_MS, HOUR_MS),
+        HOUR_START + HOUR_MS
+    );
+}
+
+#[test]
+fn minute_buckets_are_aligned_to_epoch() {
+    assert_eq!(bucket_start_ms(125_000, MINUTE_MS), 120_000);
+    assert_eq!(bucket_start_ms(120_000, MINUTE_MS), 120_000);
+}
+
+#[derive(QueryableByName)]
+struct Bucket {
+    #[diesel(sql_type = Timestamp)]
+    bucket: NaiveDateTime,
+}
+
+#[test]
+#[ignore = "requires PostgreSQL, set DATABASE_URL"]
+fn buckets_match_raw_ohlcv_bucketing() {
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let mut conn = PgConnection::establish(&database_url).unwrap();
+
+    let timestamps = [
+        HOUR_START,
+        HOUR_START + HOUR_MS - 1,
+        HOUR_START + HOUR_MS,
+        HOUR_START + 2 * HOUR_MS + 1,
+    ];
+    for bucket_ms in [MINUTE_MS, 15 * MINUTE_MS, HOUR_MS, 4 * HOUR_MS] {
+        for timestamp in timestamps {
+            // the bucketing of `RAW_OHLCV_QUERY`
+            let bucket: Bucket = sql_query(
+                "SELECT date_bin($1 * INTERVAL '1 millisecond', \
+                 to_timestamp($2 / 1000.0) AT TIME ZONE 'UTC', TIMESTAMP '2000-01-03') AS bucket",
+            )
+            .bind::<BigInt, _>(bucket_ms)
+            .bind::<BigInt, _>(timestamp)
+            .get_result(&mut conn)
+            .unwrap();
+            let start = DateTime::from_timestamp_millis(bucket_start_ms(timestamp, bucket_ms))
+                .unwrap()
+                .naive_utc();
+            assert_eq!(bucket.bucket, start, "{timestamp} in {bucket_ms}ms buckets");
+        }
+    }
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use bigdecimal::BigDecimal;
+use deeplook_schema::models::OrderFill;
+use deeplook_schema::schema;
+use deeplook_server::server::{parse_window_ms, TOP_TRADERS_QUERY};
+use diesel::sql_types::{BigInt, Numeric, Text};
+use diesel::{sql_query, PgConnection, QueryableByName, RunQueryDsl};
+
+const POOL_ID: &str = "0xtop_traders_test_pool";
+const START_MS: i64 = 1_735_689_600_000;
+
+#[derive(QueryableByName)]
+struct Row {
+    #[diesel(sql_type = Text)]
+    balance_manager_id: String,
+    #[diesel(sql_type = Numeric)]
+    volume: BigDecimal,
+}
+
+fn fill(i: i64, maker: &str, taker: &str, base_quantity: i64) -> OrderFill {
+    OrderFill {
+        price: 1,
+        base_quantity,
+        quote_quantity: base_quantity,
+        maker_balance_manager_id: maker.to_string(),
+        taker_balance_manager_id: taker.to_string(),
+        ..common::fill(POOL_ID, i, START_MS + i * 1000)
+    }
+}
+
+fn load(conn: &mut PgConnection, end_time: i64, limit: i64) -> Vec<(String, BigDecimal)> {
+    sql_query(TOP_TRADERS_QUERY)
+        .bind::<Text, _>(POOL_ID)
+        .bind::<BigInt, _>(START_MS)
+        .bind::<BigInt, _>(end_time)
+        .bind::<BigInt, _>(limit)
+        .load::<Row>(conn)
+        .unwrap()
+        .into_iter()
+        .map(|row| (row.balance_manager_id, row.volume))
+        .collect()
+}
+
+#[test]
+fn windows_are_parsed_to_milliseconds() {
+    assert_eq!(parse_window_ms("15m"), Some(15 * 60 * 1000));
+    assert_eq!(parse_window_ms("24h"), Some(24 * 60 * 60 * 1000));
+    assert_eq!(parse_window_ms("7d"), Some(7 * 24 * 60 * 60 * 1000));
+    assert_eq!(parse_window_ms("0h"), None);
+    assert_eq!(parse_window_ms("h"), None);
+    assert_eq!(parse_window_ms("1w"), None);
+    assert_eq!(parse_window_ms(""), None);
+}
+
+#[test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+fn managers_are_ranked_by_maker_and_taker_volume() {
+    let mut conn = common::connect();
+
+    conn.test_transaction::<_, diesel::result::Error, _>(|conn| {
+        let fills = vec![
+            fill(0, "0xa", "0xb", 10),
+            fill(1, "0xb", "0xc", 5),
+            fill(2, "0xc", "0xa", 1),
+            // outside of the window
+            fill(100, "0xc", "0xc", 1000),
+        ];
+        diesel::insert_into(schema::order_fills::table)
+            .values(&fills)
+            .execute(conn)?;
+
+        // 0xb: 10 as taker + 5 as maker, 0xa: 10 as maker + 1 as taker
+        let end_time = START_MS + 10 * 1000;
+        assert_eq!(
+            load(conn, end_time, 10),
+            vec![
+                ("0xb".to_string(), BigDecimal::from(15)),
+                ("0xa".to_string(), BigDecimal::from(11)),
+                ("0xc".to_string(), BigDecimal::from(6)),
+            ]
+        );
+        assert_eq!(load(conn, end_time, 1).len(), 1);
+        Ok(())
+    });
+}
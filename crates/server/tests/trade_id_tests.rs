@@ -0,0 +1,38 @@
+use deeplook_server::server::calculate_trade_id;
+
+#[test]
+fn fills_with_equal_id_sums_get_different_ids() {
+    // 1 + 4 == 2 + 3, the ids used to be the sum of the order ids
+    let first = calculate_trade_id("1", "4").unwrap();
+    let second = calculate_trade_id("2", "3").unwrap();
+
+    assert_ne!(first, second);
+}
+
+#[test]
+fn swapped_maker_and_taker_get_different_ids() {
+    assert_ne!(
+        calculate_trade_id("1", "4").unwrap(),
+        calculate_trade_id("4", "1").unwrap()
+    );
+}
+
+#[test]
+fn trade_id_is_stable() {
+    // bid order id, the top bit is part of the hash
+    let maker_id = "170141183460469231731687303715884105729";
+
+    assert_eq!(
+        calculate_trade_id(maker_id, "42").unwrap(),
+        77389407012196330012093301256029347540
+    );
+    assert_eq!(
+        calculate_trade_id("1", "4").unwrap(),
+        147270073996687984807393310301029772198
+    );
+}
+
+#[test]
+fn invalid_order_id_is_rejected() {
+    assert!(calculate_trade_id("0xabc", "4").is_err());
+}
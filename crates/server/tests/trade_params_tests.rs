@@ -0,0 +1,100 @@
+mod common;
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use chrono::DateTime;
+use deeplook_schema::models::TradeParamsUpdate;
+use deeplook_schema::schema;
+use diesel::{ExpressionMethods, PgConnection, RunQueryDsl};
+use serde_json::Value;
+use tower::ServiceExt;
+
+const POOL_ID: &str = "0xtrade_params_test_pool";
+const POOL_NAME: &str = "TRADE_PARAMS_TEST_USDC";
+const EPOCH_MS: i64 = 24 * 60 * 60 * 1000;
+
+fn update(epoch: i64, taker_fee: i64, maker_fee: i64) -> TradeParamsUpdate {
+    let timestamp_ms = 1_750_000_000_000 + epoch * EPOCH_MS;
+    TradeParamsUpdate {
+        event_digest: format!("trade_params_test_{epoch}"),
+        digest: format!("digest_{epoch}"),
+        sender: "0x0".to_string(),
+        checkpoint: epoch,
+        checkpoint_timestamp_ms: timestamp_ms,
+        timestamp: DateTime::from_timestamp_millis(timestamp_ms)
+            .unwrap()
+            .naive_utc(),
+        package: "0x0".to_string(),
+        pool_id: POOL_ID.to_string(),
+        taker_fee,
+        maker_fee,
+        stake_required: 100_000_000,
+        epoch: Some(epoch),
+    }
+}
+
+fn cleanup(conn: &mut PgConnection) {
+    diesel::delete(schema::trade_params_update::table)
+        .filter(schema::trade_params_update::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+    diesel::delete(schema::pools::table)
+        .filter(schema::pools::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+}
+
+async fn get_json(router: &Router, uri: &str) -> Value {
+    let response = router
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn trade_params_history_is_newest_first() {
+    let mut conn = common::connect();
+    cleanup(&mut conn);
+
+    diesel::insert_into(schema::pools::table)
+        .values(common::pool(POOL_ID, POOL_NAME))
+        .execute(&mut conn)
+        .unwrap();
+    diesel::insert_into(schema::trade_params_update::table)
+        .values(vec![
+            update(700, 1_000_000, 500_000),
+            update(702, 800_000, 400_000),
+            update(701, 900_000, 450_000),
+        ])
+        .execute(&mut conn)
+        .unwrap();
+
+    let router = common::router(common::config()).await;
+
+    let history = get_json(&router, &format!("/trade_params/{POOL_NAME}")).await;
+    let limited = get_json(&router, &format!("/trade_params/{POOL_NAME}?limit=2")).await;
+    let latest = get_json(&router, &format!("/trade_params/{POOL_NAME}/latest")).await;
+
+    cleanup(&mut conn);
+
+    let epochs: Vec<i64> = history
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["epoch"].as_i64().unwrap())
+        .collect();
+    assert_eq!(epochs, vec![702, 701, 700]);
+    assert_eq!(limited.as_array().unwrap().len(), 2);
+
+    assert_eq!(latest["epoch"], 702);
+    assert_eq!(latest["taker_fee"], 800_000);
+    assert_eq!(latest["maker_fee"], 400_000);
+    assert_eq!(latest["stake_required"], 100_000_000);
+    assert_eq!(latest, history[0]);
+}
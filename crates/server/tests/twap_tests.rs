@@ -0,0 +1,43 @@
+use deeplook_server::aggregations::{time_weighted_average, TWAP_BUCKET_MS};
+
+const MINUTE: i64 = 60 * 1000;
+
+#[test]
+fn empty_range_is_none() {
+    assert_eq!(
+        time_weighted_average(&[], TWAP_BUCKET_MS, 10 * MINUTE),
+        None
+    );
+}
+
+#[test]
+fn single_close_is_the_average() {
+    assert_eq!(
+        time_weighted_average(&[(0, 7.0)], TWAP_BUCKET_MS, MINUTE / 2),
+        Some(7.0)
+    );
+}
+
+#[test]
+fn closes_weighted_by_duration_not_count() {
+    // 1.0 holds for 3 minutes until the next trade, 4.0 for the last minute
+    let closes = [(0, 1.0), (3 * MINUTE, 4.0)];
+    assert_eq!(
+        time_weighted_average(&closes, TWAP_BUCKET_MS, 4 * MINUTE),
+        Some(1.75)
+    );
+}
+
+#[test]
+fn last_close_holds_until_end_time() {
+    let closes = [(0, 2.0), (MINUTE, 5.0)];
+    assert_eq!(
+        time_weighted_average(&closes, TWAP_BUCKET_MS, 2 * MINUTE),
+        Some(3.5)
+    );
+    // 2.0 for one minute, 5.0 for three
+    assert_eq!(
+        time_weighted_average(&closes, TWAP_BUCKET_MS, 4 * MINUTE),
+        Some(4.25)
+    );
+}
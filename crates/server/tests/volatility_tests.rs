@@ -0,0 +1,63 @@
+use deeplook_server::aggregations::{
+    annualization_factor, annualized_volatility, realized_volatility,
+};
+
+const MINUTE_MS: i64 = 60 * 1000;
+
+#[test]
+fn volatility_is_std_dev_of_log_returns() {
+    // +10%, -10%, +10%
+    let volatility = realized_volatility(&[100.0, 110.0, 99.0, 108.9]).unwrap();
+    assert!((volatility - 0.115_857_3).abs() < 1e-6);
+}
+
+#[test]
+fn steady_growth_has_no_volatility() {
+    let volatility = realized_volatility(&[1.0, 2.0, 4.0, 8.0]).unwrap();
+    assert!(volatility.abs() < 1e-12);
+}
+
+#[test]
+fn fewer_than_two_returns_have_no_volatility() {
+    assert_eq!(realized_volatility(&[]), None);
+    assert_eq!(realized_volatility(&[1.0]), None);
+    assert_eq!(realized_volatility(&[1.0, 2.0]), None);
+}
+
+#[test]
+fn annualization_counts_buckets_per_year() {
+    assert!((annualization_factor(24 * 60 * 60 * 1000) - 365f64.sqrt()).abs() < 1e-9);
+    assert!((annualization_factor(60 * 1000) - 724.983).abs() < 0.001);
+}
+
+#[test]
+fn annualized_volatility_of_regular_closes_uses_the_bucket() {
+    let prices = [100.0, 110.0, 99.0, 108.9];
+    let closes: Vec<(i64, f64)> = prices
+        .iter()
+        .enumerate()
+        .map(|(i, price)| (i as i64 * MINUTE_MS, *price))
+        .collect();
+
+    let expected = realized_volatility(&prices).unwrap() * annualization_factor(MINUTE_MS);
+    assert!((annualized_volatility(&closes).unwrap() - expected).abs() < 1e-6);
+}
+
+#[test]
+fn returns_across_empty_buckets_are_scaled_by_their_gap() {
+    // the same moves as one bucket apart, the middle one four buckets apart
+    let spaced = [
+        (0, 100.0),
+        (MINUTE_MS, 110.0),
+        (5 * MINUTE_MS, 99.0),
+        (6 * MINUTE_MS, 108.9),
+    ];
+    let regular = [
+        (0, 100.0),
+        (MINUTE_MS, 110.0),
+        (2 * MINUTE_MS, 99.0),
+        (3 * MINUTE_MS, 108.9),
+    ];
+
+    assert!(annualized_volatility(&spaced).unwrap() < annualized_volatility(&regular).unwrap());
+}
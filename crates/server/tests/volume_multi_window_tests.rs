@@ -0,0 +1,109 @@
+mod common;
+
+use std::collections::HashMap;
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use chrono::Utc;
+use deeplook_schema::models::OrderFill;
+use deeplook_schema::schema;
+use deeplook_server::aggregations::{
+    ToDecimalFloat64, VolumeWindowed, VOLUME_MULTI_WINDOW_RAW_QUERY,
+};
+use deeplook_server::config::ServerConfig;
+use diesel::sql_types::Text;
+use diesel::{sql_query, ExpressionMethods, PgConnection, RunQueryDsl};
+use tower::ServiceExt;
+
+const POOL_ID: &str = "0xvolume_multi_window_test_pool";
+const POOL_NAME: &str = "VOLUME_WINDOW_TEST_USDC";
+const EMPTY_POOL_ID: &str = "0xvolume_multi_window_empty_pool";
+const EMPTY_POOL_NAME: &str = "VOLUME_WINDOW_EMPTY_USDC";
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+fn fill(i: i64, timestamp_ms: i64, base_quantity: i64) -> OrderFill {
+    OrderFill {
+        base_quantity,
+        quote_quantity: base_quantity,
+        ..common::fill(POOL_ID, i, timestamp_ms)
+    }
+}
+
+fn cleanup(conn: &mut PgConnection) {
+    diesel::delete(schema::order_fills::table)
+        .filter(schema::order_fills::pool_id.eq(POOL_ID))
+        .execute(conn)
+        .unwrap();
+    diesel::delete(schema::pools::table)
+        .filter(schema::pools::pool_id.eq_any([POOL_ID, EMPTY_POOL_ID]))
+        .execute(conn)
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TimescaleDB, set DATABASE_URL"]
+async fn volumes_match_direct_query() {
+    let mut conn = common::connect();
+    cleanup(&mut conn);
+
+    let now = Utc::now().timestamp_millis();
+    let fills = vec![
+        fill(0, now - DAY_MS / 12, 1_000_000_000),
+        fill(1, now - 3 * DAY_MS, 2_000_000_000),
+        fill(2, now - 10 * DAY_MS, 4_000_000_000),
+        fill(3, now - 40 * DAY_MS, 8_000_000_000),
+    ];
+    diesel::insert_into(schema::pools::table)
+        .values(&vec![
+            common::pool(POOL_ID, POOL_NAME),
+            common::pool(EMPTY_POOL_ID, EMPTY_POOL_NAME),
+        ])
+        .execute(&mut conn)
+        .unwrap();
+    diesel::insert_into(schema::order_fills::table)
+        .values(&fills)
+        .execute(&mut conn)
+        .unwrap();
+
+    let direct = sql_query(VOLUME_MULTI_WINDOW_RAW_QUERY)
+        .bind::<Text, _>(POOL_ID)
+        .load::<VolumeWindowed>(&mut conn)
+        .unwrap()
+        .pop()
+        .unwrap();
+
+    let config = ServerConfig {
+        ohlcv_from_raw: true,
+        ..common::config()
+    };
+    let router = common::router(config).await;
+
+    let mut volumes = HashMap::new();
+    for pool_name in [POOL_NAME, EMPTY_POOL_NAME] {
+        let response = router
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/volume_multi_window/{pool_name}"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let pool_volumes: HashMap<String, f64> = serde_json::from_slice(&body).unwrap();
+        volumes.insert(pool_name, pool_volumes);
+    }
+
+    cleanup(&mut conn);
+
+    let volume = &volumes[POOL_NAME];
+    assert_eq!(volume["1d"], direct.volume_1d.to_decimal_f64(9).unwrap());
+    assert_eq!(volume["7d"], direct.volume_7d.to_decimal_f64(9).unwrap());
+    assert_eq!(volume["30d"], direct.volume_30d.to_decimal_f64(9).unwrap());
+    assert_eq!((volume["1d"], volume["7d"], volume["30d"]), (1.0, 3.0, 7.0));
+
+    let empty = &volumes[EMPTY_POOL_NAME];
+    assert_eq!((empty["1d"], empty["7d"], empty["30d"]), (0.0, 0.0, 0.0));
+}
@@ -0,0 +1,78 @@
+use axum::{
+    body::Body,
+    extract::WebSocketUpgrade,
+    http::{header, Request, StatusCode},
+    middleware::from_fn_with_state,
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use deeplook_server::auth::{ws_auth, WsAuth};
+use tower::ServiceExt;
+
+const TOKEN: &str = "secret";
+
+async fn ws_handler(ws: WebSocketUpgrade) -> impl IntoResponse {
+    ws.on_upgrade(|_socket| async {})
+}
+
+fn router(auth: WsAuth) -> Router {
+    Router::new()
+        .route("/ws", get(ws_handler))
+        .route_layer(from_fn_with_state(auth, ws_auth))
+}
+
+fn upgrade_request(uri: &str) -> Request<Body> {
+    Request::builder()
+        .uri(uri)
+        .header(header::CONNECTION, "upgrade")
+        .header(header::UPGRADE, "websocket")
+        .header(header::SEC_WEBSOCKET_VERSION, "13")
+        .header(header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==")
+        .body(Body::empty())
+        .unwrap()
+}
+
+async fn status(auth: WsAuth, req: Request<Body>) -> StatusCode {
+    router(auth).oneshot(req).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn missing_token_rejected_on_upgrade() {
+    let auth = WsAuth::new([TOKEN.to_string()]);
+    assert_eq!(
+        status(auth, upgrade_request("/ws")).await,
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[tokio::test]
+async fn invalid_token_rejected_on_upgrade() {
+    let auth = WsAuth::new([TOKEN.to_string()]);
+    assert_eq!(
+        status(auth, upgrade_request("/ws?token=wrong")).await,
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[tokio::test]
+async fn valid_token_passes_auth() {
+    let auth = WsAuth::new([TOKEN.to_string()]);
+    assert_ne!(
+        status(auth.clone(), upgrade_request("/ws?token=secret")).await,
+        StatusCode::UNAUTHORIZED
+    );
+
+    let mut req = upgrade_request("/ws");
+    req.headers_mut()
+        .insert(header::AUTHORIZATION, "Bearer secret".parse().unwrap());
+    assert_ne!(status(auth, req).await, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn auth_disabled_by_default() {
+    assert_ne!(
+        status(WsAuth::default(), upgrade_request("/ws")).await,
+        StatusCode::UNAUTHORIZED
+    );
+}
@@ -0,0 +1,69 @@
+use axum::extract::ws::Message;
+use deeplook_server::ws_metrics::{send_counted, WsMetrics, WsStream, UNKNOWN_POOL_LABEL};
+use futures::channel::mpsc;
+use futures::StreamExt;
+use prometheus::Registry;
+
+fn sent_count(registry: &Registry, pool: &str, stream: &str) -> Option<f64> {
+    registry
+        .gather()
+        .iter()
+        .filter(|family| family.get_name() == "ws_messages_sent")
+        .flat_map(|family| family.get_metric())
+        .find(|metric| {
+            let labels = metric.get_label();
+            labels
+                .iter()
+                .any(|l| l.get_name() == "pool" && l.get_value() == pool)
+                && labels
+                    .iter()
+                    .any(|l| l.get_name() == "stream" && l.get_value() == stream)
+        })
+        .map(|metric| metric.get_counter().get_value())
+}
+
+#[tokio::test]
+async fn sending_bumps_labeled_counter() {
+    let registry = Registry::new();
+    let metrics = WsMetrics::new(&registry);
+    let sent = metrics.sent_counter("SUI_USDC", true, WsStream::Bests);
+    let (mut tx, mut rx) = mpsc::unbounded::<Message>();
+
+    send_counted(&mut tx, "first".to_string(), &sent).await;
+    send_counted(&mut tx, "second".to_string(), &sent).await;
+
+    assert_eq!(rx.next().await, Some(Message::Text("first".to_string())));
+    assert_eq!(sent_count(&registry, "SUI_USDC", "bests"), Some(2.0));
+    assert_eq!(sent_count(&registry, "SUI_USDC", "orderbook"), None);
+}
+
+#[tokio::test]
+async fn failed_send_is_not_counted() {
+    let registry = Registry::new();
+    let metrics = WsMetrics::new(&registry);
+    let sent = metrics.sent_counter("SUI_USDC", true, WsStream::Trades);
+    let (mut tx, rx) = mpsc::unbounded::<Message>();
+    drop(rx);
+
+    send_counted(&mut tx, "lost".to_string(), &sent).await;
+
+    assert_eq!(sent_count(&registry, "SUI_USDC", "trades"), Some(0.0));
+}
+
+#[tokio::test]
+async fn unknown_pools_share_one_label() {
+    let registry = Registry::new();
+    let metrics = WsMetrics::new(&registry);
+    let (mut tx, _rx) = mpsc::unbounded::<Message>();
+
+    for pool in ["NOT_A_POOL", "ANOTHER_ONE"] {
+        let sent = metrics.sent_counter(pool, false, WsStream::Spread);
+        send_counted(&mut tx, "spread".to_string(), &sent).await;
+    }
+
+    assert_eq!(
+        sent_count(&registry, UNKNOWN_POOL_LABEL, "spread"),
+        Some(2.0)
+    );
+    assert_eq!(sent_count(&registry, "NOT_A_POOL", "spread"), None);
+}
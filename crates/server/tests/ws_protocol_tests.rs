@@ -0,0 +1,43 @@
+use axum::http::HeaderValue;
+use deeplook_server::ws_metrics::WsStream;
+use deeplook_server::ws_protocol::{WsVersion, WS_PROTOCOL_V1};
+use serde_json::{json, Value};
+
+#[test]
+fn v1_is_negotiated_only_when_selected() {
+    assert_eq!(
+        WsVersion::negotiated(Some(&HeaderValue::from_static(WS_PROTOCOL_V1))),
+        WsVersion::V1
+    );
+    assert_eq!(WsVersion::negotiated(None), WsVersion::Unversioned);
+}
+
+#[test]
+fn v1_messages_carry_version_and_type() {
+    let payload = r#"{"bids":[["1.5","10"]],"asks":[]}"#;
+    for (stream, kind) in [
+        (WsStream::Orderbook, "orderbook"),
+        (WsStream::Bests, "bests"),
+        (WsStream::Spread, "spread"),
+        (WsStream::Trades, "trades"),
+        (WsStream::Ohlcv, "ohlcv"),
+    ] {
+        let message: Value = serde_json::from_str(&WsVersion::V1.message(stream, payload)).unwrap();
+        assert_eq!(
+            message,
+            json!({
+                "v": 1,
+                "type": kind,
+                "data": { "bids": [["1.5", "10"]], "asks": [] },
+            })
+        );
+    }
+}
+
+#[test]
+fn unversioned_messages_are_the_bare_payload() {
+    assert_eq!(
+        WsVersion::Unversioned.message(WsStream::Spread, "0.25"),
+        "0.25"
+    );
+}
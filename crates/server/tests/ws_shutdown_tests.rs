@@ -0,0 +1,27 @@
+use axum::extract::ws::{close_code, CloseFrame, Message};
+use deeplook_server::server::close_for_shutdown;
+use futures::channel::mpsc;
+use futures::StreamExt;
+
+#[tokio::test]
+async fn shutdown_sends_going_away_close() {
+    let (mut tx, mut rx) = mpsc::unbounded::<Message>();
+
+    close_for_shutdown(&mut tx).await;
+
+    assert_eq!(
+        rx.next().await,
+        Some(Message::Close(Some(CloseFrame {
+            code: close_code::AWAY,
+            reason: "server shutting down".into(),
+        })))
+    );
+}
+
+#[tokio::test]
+async fn shutdown_ignores_closed_sockets() {
+    let (mut tx, rx) = mpsc::unbounded::<Message>();
+    drop(rx);
+
+    close_for_shutdown(&mut tx).await;
+}
@@ -1,3 +1,7 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
 use redis::AsyncCommands;
 
 use redis::{Connection, RedisError};
@@ -7,7 +11,59 @@ use url::Url;
 
 use redis::Commands;
 
-const LATEST_TRADE_SIZE: usize = 100;
+/// Default number of latest trades kept per pool
+pub const LATEST_TRADE_SIZE: usize = 100;
+
+/// Encoding of values stored in Redis, writers and readers of a key must use the same one
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Codec {
+    /// Readable in `redis-cli`, compatible with values stored by older versions
+    #[default]
+    Json,
+    /// Smaller and faster to parse, structs keep their field names
+    MessagePack,
+}
+
+impl Codec {
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, CacheError> {
+        match self {
+            Codec::Json => serde_json::to_vec(value).map_err(CacheError::Serialization),
+            Codec::MessagePack => {
+                rmp_serde::to_vec_named(value).map_err(CacheError::MessagePackSerialization)
+            }
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CacheError> {
+        match self {
+            Codec::Json => serde_json::from_slice(bytes).map_err(CacheError::DeSerialization),
+            Codec::MessagePack => {
+                rmp_serde::from_slice(bytes).map_err(CacheError::MessagePackDeSerialization)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Codec::Json => write!(f, "json"),
+            Codec::MessagePack => write!(f, "msgpack"),
+        }
+    }
+}
+
+impl FromStr for Codec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Codec::Json),
+            "msgpack" => Ok(Codec::MessagePack),
+            _ => Err(format!("unknown codec {}, use json or msgpack", s)),
+        }
+    }
+}
 
 impl Clone for Cache {
     fn clone(&self) -> Self {
@@ -20,7 +76,8 @@ impl Clone for Cache {
         Cache {
             _connection_string: self._connection_string.clone(),
             redis_connection,
-            latest_trades_size: LATEST_TRADE_SIZE,
+            latest_trades_size: self.latest_trades_size,
+            codec: self.codec,
         }
     }
 }
@@ -29,17 +86,34 @@ pub struct Cache {
     _connection_string: Url,
     redis_connection: Connection,
     latest_trades_size: usize,
+    codec: Codec,
 }
 
 #[derive(Debug)]
 pub enum CacheError {
     Serialization(Error),
     DeSerialization(Error),
+    MessagePackSerialization(rmp_serde::encode::Error),
+    MessagePackDeSerialization(rmp_serde::decode::Error),
     Redis(RedisError),
 }
 
 impl Cache {
     pub fn new(connection_string: Url) -> Self {
+        Self::with_trade_size(connection_string, LATEST_TRADE_SIZE)
+    }
+
+    /// Creates cache that keeps at most `latest_trades_size` items in pushed lists
+    pub fn with_trade_size(connection_string: Url, latest_trades_size: usize) -> Self {
+        Self::with_options(connection_string, latest_trades_size, Codec::Json)
+    }
+
+    /// Creates cache that stores values encoded with `codec`
+    pub fn with_codec(connection_string: Url, codec: Codec) -> Self {
+        Self::with_options(connection_string, LATEST_TRADE_SIZE, codec)
+    }
+
+    pub fn with_options(connection_string: Url, latest_trades_size: usize, codec: Codec) -> Self {
         let client =
             redis::Client::open(connection_string.clone()).expect("Failed creating Redis client");
         let redis_connection = client
@@ -49,20 +123,16 @@ impl Cache {
         Cache {
             _connection_string: connection_string,
             redis_connection,
-            latest_trades_size: LATEST_TRADE_SIZE,
+            latest_trades_size,
+            codec,
         }
     }
 
     pub fn set<T: Serialize>(&mut self, key: &str, value: &T) -> Result<(), CacheError> {
-        let serialized = match serde_json::to_string(value) {
-            Ok(v) => v,
-            Err(e) => {
-                return Err(CacheError::Serialization(e));
-            }
-        };
+        let serialized = self.codec.encode(value)?;
         if let Err(e) = self
             .redis_connection
-            .set::<&str, String, ()>(key, serialized)
+            .set::<&str, Vec<u8>, ()>(key, serialized)
         {
             return Err(CacheError::Redis(e));
         }
@@ -70,7 +140,7 @@ impl Cache {
     }
 
     pub fn get<T: DeserializeOwned>(&mut self, key: &str) -> Result<Option<T>, CacheError> {
-        let val: Option<String> = match self.redis_connection.get::<&str, Option<String>>(key) {
+        let val: Option<Vec<u8>> = match self.redis_connection.get::<&str, Option<Vec<u8>>>(key) {
             Ok(v) => v,
             Err(e) => {
                 return Err(CacheError::Redis(e));
@@ -84,16 +154,15 @@ impl Cache {
             }
         };
 
-        let deserialized =
-            serde_json::from_str(&val).map_err(|e| CacheError::DeSerialization(e))?;
+        let deserialized = self.codec.decode(&val)?;
         Ok(Some(deserialized))
     }
 
     pub fn push<T: Serialize>(&mut self, key: &str, value: &T) -> Result<(), CacheError> {
-        let serialized = serde_json::to_string(value).map_err(CacheError::Serialization)?;
+        let serialized = self.codec.encode(value)?;
 
         self.redis_connection
-            .rpush::<&str, String, ()>(key, serialized)
+            .rpush::<&str, Vec<u8>, ()>(key, serialized)
             .map_err(CacheError::Redis)?;
 
         self.redis_connection
@@ -103,6 +172,24 @@ impl Cache {
         Ok(())
     }
 
+    /// Publishes `value` encoded with the codec to subscribers of `channel`
+    pub fn publish<T: Serialize>(&mut self, channel: &str, value: &T) -> Result<(), CacheError> {
+        let serialized = self.codec.encode(value)?;
+        self.redis_connection
+            .publish::<&str, Vec<u8>, ()>(channel, serialized)
+            .map_err(CacheError::Redis)
+    }
+
+    /// Removes the key, returns whether it existed
+    pub fn del(&mut self, key: &str) -> Result<bool, CacheError> {
+        let removed: usize = self.redis_connection.del(key).map_err(CacheError::Redis)?;
+        Ok(removed > 0)
+    }
+
+    pub fn exists(&mut self, key: &str) -> Result<bool, CacheError> {
+        self.redis_connection.exists(key).map_err(CacheError::Redis)
+    }
+
     pub fn delete_by_prefixes(&mut self, prefixes: &[&str]) -> Result<usize, CacheError> {
         let mut deleted = 0usize;
 
@@ -129,13 +216,38 @@ impl Cache {
 #[derive(Clone)]
 pub struct AsyncCache {
     pub client: redis::Client,
+    latest_trades_size: usize,
+    codec: Codec,
 }
 
 impl AsyncCache {
     pub fn new(redis_url: Url) -> Self {
+        Self::with_trade_size(redis_url, LATEST_TRADE_SIZE)
+    }
+
+    /// Creates cache that keeps and reads at most `latest_trades_size` items in lists
+    pub fn with_trade_size(redis_url: Url, latest_trades_size: usize) -> Self {
+        Self::with_options(redis_url, latest_trades_size, Codec::Json)
+    }
+
+    /// Creates cache that reads and stores values encoded with `codec`
+    pub fn with_codec(redis_url: Url, codec: Codec) -> Self {
+        Self::with_options(redis_url, LATEST_TRADE_SIZE, codec)
+    }
+
+    pub fn with_options(redis_url: Url, latest_trades_size: usize, codec: Codec) -> Self {
         let client =
             redis::Client::open(redis_url).expect("Failed creating Redis client for AsyncCache");
-        Self { client }
+        Self {
+            client,
+            latest_trades_size,
+            codec,
+        }
+    }
+
+    /// Decodes a payload written with the codec, e.g. a message of a subscribed channel
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, CacheError> {
+        self.codec.decode(bytes)
     }
 
     pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, CacheError> {
@@ -144,26 +256,118 @@ impl AsyncCache {
             .get_multiplexed_async_connection()
             .await
             .map_err(CacheError::Redis)?;
-        let val: Option<String> = conn.get(key).await.map_err(CacheError::Redis)?;
+        let val: Option<Vec<u8>> = conn.get(key).await.map_err(CacheError::Redis)?;
 
-        if let Some(json) = val {
-            let deserialized = serde_json::from_str(&json).map_err(CacheError::DeSerialization)?;
+        if let Some(bytes) = val {
+            let deserialized = self.codec.decode(&bytes)?;
             Ok(Some(deserialized))
         } else {
             Ok(None)
         }
     }
 
+    /// Values of `keys` read with one `MGET`, aligned with `keys`
+    pub async fn mget<T: DeserializeOwned>(
+        &self,
+        keys: &[&str],
+    ) -> Result<Vec<Option<T>>, CacheError> {
+        // MGET without keys is a Redis error
+        if keys.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(CacheError::Redis)?;
+        let values: Vec<Option<Vec<u8>>> = conn.mget(keys).await.map_err(CacheError::Redis)?;
+
+        values
+            .into_iter()
+            .map(|value| value.map(|bytes| self.codec.decode(&bytes)).transpose())
+            .collect()
+    }
+
     pub async fn set<T: Serialize>(&self, key: &str, value: &T) -> Result<(), CacheError> {
-        let json = serde_json::to_string(value).map_err(CacheError::Serialization)?;
+        let encoded = self.codec.encode(value)?;
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(CacheError::Redis)?;
+        conn.set(key, encoded).await.map_err(CacheError::Redis)
+    }
+
+    /// Sets the value, Redis drops it after `ttl`
+    pub async fn set_with_ttl<T: Serialize>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> Result<(), CacheError> {
+        let encoded = self.codec.encode(value)?;
         let mut conn = self
             .client
             .get_multiplexed_async_connection()
             .await
             .map_err(CacheError::Redis)?;
-        conn.set(key, json).await.map_err(CacheError::Redis)
+        conn.pset_ex(key, encoded, ttl.as_millis() as u64)
+            .await
+            .map_err(CacheError::Redis)
     }
 
+    pub async fn push<T: Serialize>(&self, key: &str, value: &T) -> Result<(), CacheError> {
+        let encoded = self.codec.encode(value)?;
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(CacheError::Redis)?;
+
+        conn.rpush::<&str, Vec<u8>, ()>(key, encoded)
+            .await
+            .map_err(CacheError::Redis)?;
+
+        conn.ltrim::<&str, ()>(key, -(self.latest_trades_size as isize), -1)
+            .await
+            .map_err(CacheError::Redis)?;
+
+        Ok(())
+    }
+
+    pub async fn ping(&self) -> Result<(), CacheError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(CacheError::Redis)?;
+        redis::cmd("PING")
+            .query_async::<()>(&mut conn)
+            .await
+            .map_err(CacheError::Redis)
+    }
+
+    /// Removes the key, returns whether it existed
+    pub async fn del(&self, key: &str) -> Result<bool, CacheError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(CacheError::Redis)?;
+        let removed: usize = conn.del(key).await.map_err(CacheError::Redis)?;
+        Ok(removed > 0)
+    }
+
+    pub async fn exists(&self, key: &str) -> Result<bool, CacheError> {
+        let mut conn = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(CacheError::Redis)?;
+        conn.exists(key).await.map_err(CacheError::Redis)
+    }
+
+    /// Reads at most `latest_trades_size` newest items of the list
     pub async fn get_array<T: DeserializeOwned>(
         &self,
         key: &str,
@@ -174,7 +378,10 @@ impl AsyncCache {
             .await
             .map_err(CacheError::Redis)?;
 
-        let items: Vec<String> = conn.lrange(key, 0, -1).await.map_err(CacheError::Redis)?;
+        let items: Vec<Vec<u8>> = conn
+            .lrange(key, -(self.latest_trades_size as isize), -1)
+            .await
+            .map_err(CacheError::Redis)?;
 
         if items.is_empty() {
             return Ok(None);
@@ -182,7 +389,7 @@ impl AsyncCache {
 
         let mut result = Vec::with_capacity(items.len());
         for item in items {
-            let value: T = serde_json::from_str(&item).map_err(CacheError::DeSerialization)?;
+            let value: T = self.codec.decode(&item)?;
             result.push(value);
         }
 
@@ -0,0 +1,107 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use sui_sdk::{SuiClient, rpc_types::CheckpointId};
+use sui_types::{
+    committee::EpochId,
+    messages_checkpoint::{CheckpointSequenceNumber, CheckpointTimestamp},
+    sui_serde::BigInt,
+};
+
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointDigest {
+    #[serde_as(as = "BigInt<u64>")]
+    pub epoch: EpochId,
+    #[serde_as(as = "BigInt<u64>")]
+    pub sequence_number: CheckpointSequenceNumber,
+    #[serde_as(as = "BigInt<u64>")]
+    pub timestamp_ms: CheckpointTimestamp,
+}
+
+/// Where checkpoints are read from, implemented by `SuiClient` and by stubs in tests
+#[async_trait]
+pub trait CheckpointSource: Send + Sync {
+    async fn latest_sequence_number(&self) -> Result<CheckpointSequenceNumber, anyhow::Error>;
+
+    async fn checkpoint(
+        &self,
+        n: CheckpointSequenceNumber,
+    ) -> Result<CheckpointDigest, anyhow::Error>;
+}
+
+#[async_trait]
+impl CheckpointSource for SuiClient {
+    async fn latest_sequence_number(&self) -> Result<CheckpointSequenceNumber, anyhow::Error> {
+        Ok(self
+            .read_api()
+            .get_latest_checkpoint_sequence_number()
+            .await?)
+    }
+
+    async fn checkpoint(
+        &self,
+        n: CheckpointSequenceNumber,
+    ) -> Result<CheckpointDigest, anyhow::Error> {
+        let checkpoint: sui_sdk::rpc_types::Checkpoint = self
+            .read_api()
+            .get_checkpoint(CheckpointId::SequenceNumber(n))
+            .await?;
+
+        Ok(CheckpointDigest {
+            epoch: checkpoint.epoch,
+            sequence_number: checkpoint.sequence_number,
+            timestamp_ms: checkpoint.timestamp_ms,
+        })
+    }
+}
+
+impl CheckpointDigest {
+    pub async fn from_sequence_number<C: CheckpointSource + ?Sized>(
+        source: &C,
+        n: CheckpointSequenceNumber,
+    ) -> Result<Self, anyhow::Error> {
+        source.checkpoint(n).await
+    }
+
+    pub async fn get_latest<C: CheckpointSource + ?Sized>(
+        source: &C,
+    ) -> Result<Self, anyhow::Error> {
+        let latest_checkpoint = source.latest_sequence_number().await?;
+        source.checkpoint(latest_checkpoint).await
+    }
+
+    pub async fn get_sequence_number<C: CheckpointSource + ?Sized>(
+        source: &C,
+    ) -> Result<CheckpointSequenceNumber, anyhow::Error> {
+        source.latest_sequence_number().await
+    }
+}
+
+/// Chain with checkpoints `0..=latest`, one per second from 2025-01-01 and 100 per epoch, for
+/// tests of code reading checkpoints
+pub struct StubChain {
+    pub latest: CheckpointSequenceNumber,
+}
+
+#[async_trait]
+impl CheckpointSource for StubChain {
+    async fn latest_sequence_number(&self) -> Result<CheckpointSequenceNumber, anyhow::Error> {
+        Ok(self.latest)
+    }
+
+    async fn checkpoint(
+        &self,
+        n: CheckpointSequenceNumber,
+    ) -> Result<CheckpointDigest, anyhow::Error> {
+        if n > self.latest {
+            anyhow::bail!("checkpoint {} not found", n);
+        }
+        Ok(CheckpointDigest {
+            epoch: n / 100,
+            sequence_number: n,
+            timestamp_ms: 1_735_689_600_000 + n * 1000,
+        })
+    }
+}
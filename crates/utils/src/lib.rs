@@ -1,2 +1,4 @@
 pub mod cache;
+pub mod checkpoint;
 pub mod logging;
+pub mod rounding;
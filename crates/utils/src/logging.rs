@@ -1,11 +1,31 @@
-/// Configures tracing library
+use tracing::Level;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::filter::LevelFilter;
+
+/// Configures tracing library, `INFO` and above in compact format unless `RUST_LOG` is set
 pub fn setup_logging() {
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::INFO)
-        .compact()
+    setup_logging_with(Level::INFO, false);
+}
+
+/// Configures tracing library to log `level` and above, `json` prints one JSON object per
+/// line for log aggregation. Directives in `RUST_LOG`, e.g. `info,deeplook_orderbook=debug`,
+/// take precedence over `level`.
+pub fn setup_logging_with(level: Level, json: bool) {
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(log_filter(level))
         .with_file(false)
         .with_line_number(false)
-        .with_thread_ids(false)
-        .with_target(false)
-        .init();
+        .with_thread_ids(false);
+    if json {
+        builder.json().with_target(true).init();
+    } else {
+        builder.compact().with_target(false).init();
+    }
+}
+
+/// Filter of `RUST_LOG` directives, `level` for targets without a directive
+pub fn log_filter(level: Level) -> EnvFilter {
+    EnvFilter::builder()
+        .with_default_directive(LevelFilter::from_level(level).into())
+        .from_env_lossy()
 }
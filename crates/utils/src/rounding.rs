@@ -0,0 +1,23 @@
+//! Snapping of onchain integer prices and sizes to the pool's tick and lot sizes.
+
+/// Rounds `raw` to the nearest multiple of `step`, halves round up.
+/// Steps below 2 leave the value unchanged.
+pub fn round_to_step(raw: i64, step: i64) -> i64 {
+    if step < 2 {
+        return raw;
+    }
+    let remainder = raw.rem_euclid(step);
+    let floor = raw - remainder;
+    if remainder * 2 >= step {
+        floor + step
+    } else {
+        floor
+    }
+}
+
+/// Human readable value of `raw` in units of `factor`, snapped to `step` first when given.
+/// A snapped value has no more decimals than the step, so it prints without float noise.
+pub fn to_readable(raw: i64, factor: f64, step: Option<i64>) -> f64 {
+    let raw = step.map_or(raw, |step| round_to_step(raw, step));
+    raw as f64 / factor
+}
@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+use deeplook_utils::cache::{AsyncCache, Cache};
+use redis::AsyncCommands;
+use url::Url;
+
+const TRADES_SIZE: usize = 5;
+
+fn redis_url() -> Url {
+    let url = std::env::var("REDIS_URL").unwrap_or("redis://localhost:6379".to_string());
+    Url::parse(&url).expect("Invalid REDIS_URL")
+}
+
+#[tokio::test]
+#[ignore = "requires a running Redis, set REDIS_URL"]
+async fn async_push_keeps_latest_trades_size() -> Result<(), anyhow::Error> {
+    let key = "test::async_push_keeps_latest_trades_size";
+    let cache = AsyncCache::with_trade_size(redis_url(), TRADES_SIZE);
+    let mut conn = cache.client.get_multiplexed_async_connection().await?;
+    conn.del::<_, ()>(key).await?;
+
+    for i in 0..TRADES_SIZE + 10 {
+        cache
+            .push(key, &i)
+            .await
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    }
+
+    let len: usize = conn.llen(key).await?;
+    assert_eq!(len, TRADES_SIZE);
+
+    let items: Vec<usize> = cache
+        .get_array(key)
+        .await
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?
+        .unwrap_or_default();
+    assert_eq!(items, (10..TRADES_SIZE + 10).collect::<Vec<_>>());
+
+    conn.del::<_, ()>(key).await?;
+    Ok(())
+}
+
+#[test]
+#[ignore = "requires a running Redis, set REDIS_URL"]
+fn push_keeps_latest_trades_size() -> Result<(), anyhow::Error> {
+    let key = "test::push_keeps_latest_trades_size";
+    let mut cache = Cache::with_trade_size(redis_url(), TRADES_SIZE);
+    cache
+        .delete_by_prefixes(&[key])
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    for i in 0..TRADES_SIZE + 10 {
+        cache.push(key, &i).map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    }
+
+    let mut conn = redis::Client::open(redis_url())?.get_connection()?;
+    let len: usize = redis::cmd("LLEN").arg(key).query(&mut conn)?;
+    assert_eq!(len, TRADES_SIZE);
+
+    cache
+        .delete_by_prefixes(&[key])
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    Ok(())
+}
+
+#[test]
+#[ignore = "requires a running Redis, set REDIS_URL"]
+fn del_reports_whether_key_existed() -> Result<(), anyhow::Error> {
+    let key = "test::del_reports_whether_key_existed";
+    let mut cache = Cache::new(redis_url());
+    cache.set(key, &1).map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    assert!(cache.exists(key).map_err(|e| anyhow::anyhow!("{e:?}"))?);
+    assert!(cache.del(key).map_err(|e| anyhow::anyhow!("{e:?}"))?);
+    assert!(!cache.exists(key).map_err(|e| anyhow::anyhow!("{e:?}"))?);
+    assert!(!cache.del(key).map_err(|e| anyhow::anyhow!("{e:?}"))?);
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "requires a running Redis, set REDIS_URL"]
+async fn async_del_reports_whether_key_existed() -> Result<(), anyhow::Error> {
+    let key = "test::async_del_reports_whether_key_existed";
+    let cache = AsyncCache::new(redis_url());
+    cache
+        .set(key, &1)
+        .await
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    assert!(
+        cache
+            .exists(key)
+            .await
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?
+    );
+    assert!(cache.del(key).await.map_err(|e| anyhow::anyhow!("{e:?}"))?);
+    assert!(
+        !cache
+            .exists(key)
+            .await
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?
+    );
+    assert!(!cache.del(key).await.map_err(|e| anyhow::anyhow!("{e:?}"))?);
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "requires a running Redis, set REDIS_URL"]
+async fn set_with_ttl_expires() -> Result<(), anyhow::Error> {
+    let key = "test::set_with_ttl_expires";
+    let cache = AsyncCache::new(redis_url());
+    cache
+        .set_with_ttl(key, &1, Duration::from_millis(100))
+        .await
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    let cached: Option<i32> = cache.get(key).await.map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_eq!(cached, Some(1));
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(
+        !cache
+            .exists(key)
+            .await
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?
+    );
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "requires a running Redis, set REDIS_URL"]
+async fn mget_aligns_with_keys() -> Result<(), anyhow::Error> {
+    let keys = [
+        "test::mget_first",
+        "test::mget_missing",
+        "test::mget_second",
+    ];
+    let cache = AsyncCache::new(redis_url());
+    for key in keys {
+        cache.del(key).await.map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    }
+    cache
+        .set(keys[0], &1)
+        .await
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    cache
+        .set(keys[2], &3)
+        .await
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+    let values: Vec<Option<i32>> = cache
+        .mget(&keys)
+        .await
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_eq!(values, vec![Some(1), None, Some(3)]);
+
+    let single: Vec<Option<i32>> = cache
+        .mget(&keys[2..])
+        .await
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert_eq!(single, vec![Some(3)]);
+
+    let none: Vec<Option<i32>> = cache
+        .mget(&[])
+        .await
+        .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    assert!(none.is_empty());
+
+    for key in keys {
+        cache.del(key).await.map_err(|e| anyhow::anyhow!("{e:?}"))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use deeplook_utils::checkpoint::{CheckpointDigest, StubChain};
+
+#[tokio::test]
+async fn digests_are_read_from_the_source() {
+    let chain = StubChain { latest: 250 };
+
+    assert_eq!(
+        CheckpointDigest::get_sequence_number(&chain).await.unwrap(),
+        250
+    );
+    assert_eq!(
+        CheckpointDigest::get_latest(&chain).await.unwrap(),
+        CheckpointDigest {
+            epoch: 2,
+            sequence_number: 250,
+            timestamp_ms: 1_735_689_850_000,
+        }
+    );
+    assert_eq!(
+        CheckpointDigest::from_sequence_number(&chain, 10)
+            .await
+            .unwrap()
+            .timestamp_ms,
+        1_735_689_610_000
+    );
+    assert!(
+        CheckpointDigest::from_sequence_number(&chain, 251)
+            .await
+            .is_err()
+    );
+}
+
+#[test]
+fn digest_serializes_numbers_as_strings() {
+    let digest = CheckpointDigest {
+        epoch: 2,
+        sequence_number: 250,
+        timestamp_ms: 1_735_689_850_000,
+    };
+
+    assert_eq!(
+        serde_json::to_value(&digest).unwrap(),
+        serde_json::json!({
+            "epoch": "2",
+            "sequenceNumber": "250",
+            "timestampMs": "1735689850000",
+        })
+    );
+}
@@ -0,0 +1,68 @@
+use std::str::FromStr;
+
+use deeplook_utils::cache::Codec;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Level {
+    price: f64,
+    quantity: f64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Book {
+    timestamp: String,
+    bids: Vec<Level>,
+    asks: Vec<Level>,
+}
+
+fn book() -> Book {
+    Book {
+        timestamp: "1700000000000".to_string(),
+        bids: vec![Level {
+            price: 1.5,
+            quantity: 10.0,
+        }],
+        asks: vec![],
+    }
+}
+
+#[test]
+fn codecs_roundtrip_values() {
+    for codec in [Codec::Json, Codec::MessagePack] {
+        let bytes = codec.encode(&book()).unwrap();
+        let decoded: Book = codec.decode(&bytes).unwrap();
+        assert_eq!(decoded, book(), "{codec}");
+    }
+}
+
+#[test]
+fn msgpack_keeps_field_names() {
+    // the ws handlers forward decoded values as JSON, so both codecs must give the same shape
+    let bytes = Codec::MessagePack.encode(&book()).unwrap();
+    let value: Value = Codec::MessagePack.decode(&bytes).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "timestamp": "1700000000000",
+            "bids": [{ "price": 1.5, "quantity": 10.0 }],
+            "asks": [],
+        })
+    );
+}
+
+#[test]
+fn decoding_with_the_other_codec_fails() {
+    let bytes = Codec::MessagePack.encode(&book()).unwrap();
+    assert!(Codec::Json.decode::<Book>(&bytes).is_err());
+}
+
+#[test]
+fn codec_parses_its_display() {
+    assert_eq!(Codec::default(), Codec::Json);
+    for codec in [Codec::Json, Codec::MessagePack] {
+        assert_eq!(Codec::from_str(&codec.to_string()).unwrap(), codec);
+    }
+    assert!(Codec::from_str("bincode").is_err());
+}
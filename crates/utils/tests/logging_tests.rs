@@ -0,0 +1,21 @@
+use deeplook_utils::logging::log_filter;
+use tracing::Level;
+use tracing_subscriber::filter::LevelFilter;
+
+// one test, both cases change the same process wide variable
+#[test]
+fn rust_log_overrides_default_level() {
+    unsafe { std::env::remove_var("RUST_LOG") };
+    assert_eq!(
+        log_filter(Level::WARN).max_level_hint(),
+        Some(LevelFilter::WARN)
+    );
+
+    unsafe { std::env::set_var("RUST_LOG", "info,deeplook_orderbook=trace") };
+    assert_eq!(
+        log_filter(Level::WARN).max_level_hint(),
+        Some(LevelFilter::TRACE)
+    );
+
+    unsafe { std::env::remove_var("RUST_LOG") };
+}
@@ -0,0 +1,32 @@
+use deeplook_utils::rounding::{round_to_step, to_readable};
+
+// SUI_USDC: 9 base and 6 quote decimals, tick 1000 and lot 100000000
+const PRICE_FACTOR: f64 = 1_000_000.0;
+const SIZE_FACTOR: f64 = 1_000_000_000.0;
+const TICK_SIZE: i64 = 1000;
+const LOT_SIZE: i64 = 100_000_000;
+
+#[test]
+fn values_snap_to_nearest_step() {
+    assert_eq!(round_to_step(3_456_400, TICK_SIZE), 3_456_000);
+    assert_eq!(round_to_step(3_456_500, TICK_SIZE), 3_457_000);
+    assert_eq!(round_to_step(3_456_000, TICK_SIZE), 3_456_000);
+    assert_eq!(round_to_step(0, TICK_SIZE), 0);
+    assert_eq!(round_to_step(1234, 1), 1234);
+    assert_eq!(round_to_step(1234, 0), 1234);
+}
+
+#[test]
+fn readable_values_align_to_tick_and_lot() {
+    let price = to_readable(3_456_789, PRICE_FACTOR, Some(TICK_SIZE));
+    assert_eq!(price, 3.457);
+    assert_eq!(price.to_string(), "3.457");
+    let tick = TICK_SIZE as f64 / PRICE_FACTOR as f64;
+    assert_eq!((price / tick).round() * tick, price);
+
+    let size = to_readable(12_345_678_901, SIZE_FACTOR, Some(LOT_SIZE));
+    assert_eq!(size, 12.3);
+    assert_eq!(size.to_string(), "12.3");
+
+    assert_eq!(to_readable(3_456_789, PRICE_FACTOR, None), 3.456789);
+}